@@ -4,10 +4,11 @@ use glam::{Affine3A, Quat, Vec3};
 use openxr_sys as xr;
 use paste::paste;
 use slotmap::{DefaultKey, Key, KeyData, SlotMap};
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::ffi::{c_char, CStr, CString};
 use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
     mpsc, Arc, LazyLock, Mutex, MutexGuard, OnceLock, RwLock, Weak,
 };
 
@@ -45,6 +46,23 @@ pub fn set_action_state(action: xr::Action, state: ActionState, hand: UserPath)
     action.active.store(true, Ordering::Relaxed);
 }
 
+/// Returns the most recent haptic pulse applied to the given action for the given hand, clearing
+/// it in the process.
+pub fn take_haptic_pulse(action: xr::Action, hand: UserPath) -> Option<HapticPulse> {
+    let action = action.to_handle().unwrap();
+    match hand {
+        UserPath::LeftHand => action.haptics.left.take(),
+        UserPath::RightHand => action.haptics.right.take(),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HapticPulse {
+    pub duration: xr::Duration,
+    pub frequency: f32,
+    pub amplitude: f32,
+}
+
 pub fn deactivate_action(action: xr::Action) {
     let action = action.to_handle().unwrap();
     action.active.store(false, Ordering::Relaxed);
@@ -97,6 +115,12 @@ pub fn set_aim(session: xr::Session, path: UserPath, pose: xr::Posef) {
     get_hand_data(path, &session).aim_pose.store(pose);
 }
 
+/// Sets the location flags reported for the hand's pose actions (fully tracked by default).
+pub fn set_location_flags(session: xr::Session, path: UserPath, flags: xr::SpaceLocationFlags) {
+    let session = session.to_handle().unwrap();
+    get_hand_data(path, &session).location_flags.store(flags);
+}
+
 #[track_caller]
 pub fn get_suggested_bindings(action: xr::Action, profile: xr::Path) -> Vec<String> {
     let action = xr::Action::to_handle(action).unwrap();
@@ -117,11 +141,153 @@ pub fn get_suggested_bindings(action: xr::Action, profile: xr::Path) -> Vec<Stri
         .collect()
 }
 
+/// Makes the runtime bind `action` to `sources` (full input paths, e.g.
+/// `/user/hand/right/input/a/click`), instead of the sources suggested for the current interaction
+/// profiles, as if the user rebound it.
+pub fn set_bound_sources(action: xr::Action, sources: &[&str]) {
+    let action = xr::Action::to_handle(action).unwrap();
+    let instance = action.instance.upgrade().unwrap();
+    let sources = sources
+        .iter()
+        .map(|s| instance.get_or_create_path(s))
+        .collect();
+    *action.bound.lock().unwrap() = Some(sources);
+}
+
 pub fn session_frame_state(session: xr::Session) -> FrameState {
     let session = session.to_handle().unwrap();
     session.frame_state.load()
 }
 
+/// A composition layer submitted in xrEndFrame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SubmittedLayer {
+    pub ty: xr::StructureType,
+    /// From XR_KHR_composition_layer_color_scale_bias, if chained
+    pub color_scale: Option<xr::Color4f>,
+    /// From XR_KHR_composition_layer_color_scale_bias, if chained
+    pub color_bias: Option<xr::Color4f>,
+    /// Size of quad layers
+    pub size: Option<xr::Extent2Df>,
+    /// Eyes quad layers are shown to
+    pub eye_visibility: Option<xr::EyeVisibility>,
+    /// Part of the swapchain image shown by quad layers
+    pub image_rect: Option<xr::Rect2Di>,
+    /// Poses of the left and right views of projection layers
+    pub view_poses: Option<[xr::Posef; 2]>,
+}
+
+/// Returns the layers submitted in the most recent xrEndFrame call.
+pub fn last_frame_layers(session: xr::Session) -> Vec<SubmittedLayer> {
+    let session = session.to_handle().unwrap();
+    session.last_frame_layers.lock().unwrap().clone()
+}
+
+/// Returns the action sets passed to the most recent xrSyncActions call, in order.
+pub fn last_synced_action_sets(session: xr::Session) -> Vec<xr::ActionSet> {
+    let session = session.to_handle().unwrap();
+    session.last_synced_sets.lock().unwrap().clone()
+}
+
+thread_local! {
+    static VIEW_VELOCITY: Cell<(xr::Vector3f, xr::Vector3f)> = Cell::default();
+    static VIEW_POSE: Cell<xr::Posef> = const { Cell::new(xr::Posef::IDENTITY) };
+    static API_LAYERS: RefCell<Vec<CString>> = RefCell::default();
+    static EXTRA_EXTENSIONS: RefCell<Vec<&'static [u8]>> = RefCell::default();
+    static ACTION_CHANGE_TIME: Cell<i64> = const { Cell::new(0) };
+}
+
+/// Sets the linear and angular velocity reported when locating the view space on this thread.
+pub fn set_view_velocity(linear: xr::Vector3f, angular: xr::Vector3f) {
+    VIEW_VELOCITY.set((linear, angular));
+}
+
+/// Sets the pose of the view space in the local space on this thread.
+pub fn set_view_pose(pose: xr::Posef) {
+    VIEW_POSE.set(pose);
+}
+
+/// Adds an API layer to the ones reported by xrEnumerateApiLayerProperties on this thread.
+pub fn add_api_layer(name: &CStr) {
+    API_LAYERS.with_borrow_mut(|layers| layers.push(name.into()));
+}
+
+/// Sets the last change time reported for action states that change in later xrSyncActions calls
+/// on this thread.
+pub fn set_action_change_time(time: xr::Time) {
+    ACTION_CHANGE_TIME.set(time.as_nanos());
+}
+
+/// Adds an extension to the ones reported by xrEnumerateInstanceExtensionProperties on this
+/// thread. `name` is the extension's `*_EXTENSION_NAME` constant.
+pub fn add_instance_extension(name: &'static [u8]) {
+    EXTRA_EXTENSIONS.with_borrow_mut(|extensions| extensions.push(name));
+}
+
+/// Moves the session to the given state, queueing a session state changed event.
+pub fn set_session_state(session: xr::Session, state: xr::SessionState) {
+    let s = session.to_handle().unwrap();
+    s.state.store(state);
+    send_event(
+        &s.event_sender,
+        xr::EventDataSessionStateChanged {
+            ty: xr::EventDataSessionStateChanged::TYPE,
+            next: std::ptr::null(),
+            session,
+            state,
+            time: xr::Time::from_nanos(0),
+        },
+        None,
+    );
+}
+
+/// Sets the shouldRender value reported by later xrWaitFrame calls.
+pub fn set_should_render(session: xr::Session, should_render: bool) {
+    let s = session.to_handle().unwrap();
+    s.should_render.store(should_render, Ordering::Relaxed);
+}
+
+/// Sets the refresh rate reported by xrGetDisplayRefreshRateFB.
+pub fn set_display_refresh_rate(session: xr::Session, rate: f32) {
+    let s = session.to_handle().unwrap();
+    s.display_refresh_rate.store(rate);
+}
+
+/// Sets the bounds reported by xrGetReferenceSpaceBoundsRect for the stage space, or makes them
+/// unavailable if `None`.
+pub fn set_stage_bounds(session: xr::Session, bounds: Option<xr::Extent2Df>) {
+    let s = session.to_handle().unwrap();
+    s.stage_bounds.store(bounds);
+}
+
+/// Makes swapchain creation fail for swapchains with more samples than `count`.
+pub fn set_max_swapchain_sample_count(session: xr::Session, count: u32) {
+    let s = session.to_handle().unwrap();
+    s.max_swapchain_sample_count.store(count, Ordering::Relaxed);
+}
+
+/// Returns the create flags of every swapchain created on the session, oldest first.
+pub fn swapchain_create_flags(session: xr::Session) -> Vec<xr::SwapchainCreateFlags> {
+    let session = session.to_handle().unwrap();
+    session.swapchain_create_flags.lock().unwrap().clone()
+}
+
+/// Returns the number of frames ended with xrEndFrame so far.
+pub fn ended_frame_count(session: xr::Session) -> u32 {
+    let session = session.to_handle().unwrap();
+    session.ended_frames.load(Ordering::Relaxed)
+}
+
+/// Returns the number of action spaces created on the instance's sessions that haven't been
+/// destroyed yet.
+pub fn live_action_space_count(instance: xr::Instance) -> usize {
+    let instance = instance.to_handle().unwrap();
+    Space::instances()
+        .values()
+        .filter(|space| std::ptr::eq(space.instance.as_ptr(), Arc::as_ptr(&instance)))
+        .count()
+}
+
 macro_rules! fn_unimplemented_impl {
     ($($param:ident),+) => {
         fn_unimplemented_impl!($($param),+  -> []);
@@ -199,7 +365,7 @@ pub extern "system" fn get_instance_proc_addr(
 
     if instance == xr::Instance::NULL {
         unsafe {
-            *function = get_fn!([CreateInstance, EnumerateInstanceExtensionProperties, (EnumerateApiLayerProperties)]
+            *function = get_fn!([CreateInstance, EnumerateInstanceExtensionProperties, EnumerateApiLayerProperties]
                 other => {
                     println!("unknown func without instance: {other:?}");
                     return xr::Result::ERROR_HANDLE_INVALID;
@@ -215,7 +381,7 @@ pub extern "system" fn get_instance_proc_addr(
                 CreateInstance,
                 DestroyInstance,
                 (EnumerateInstanceExtensionProperties),
-                (EnumerateApiLayerProperties),
+                EnumerateApiLayerProperties,
                 GetVulkanInstanceExtensionsKHR,
                 GetVulkanDeviceExtensionsKHR,
                 GetVulkanGraphicsDeviceKHR,
@@ -247,20 +413,20 @@ pub extern "system" fn get_instance_proc_addr(
                 (EnumerateViewConfigurations),
                 (EnumerateEnvironmentBlendModes),
                 (GetViewConfigurationProperties),
-                (EnumerateViewConfigurationViews),
+                EnumerateViewConfigurationViews,
                 BeginFrame,
                 EndFrame,
                 WaitFrame,
-                (ApplyHapticFeedback),
+                ApplyHapticFeedback,
                 (StopHapticFeedback),
                 (PollEvent),
                 StringToPath,
                 PathToString,
-                (GetReferenceSpaceBoundsRect),
+                GetReferenceSpaceBoundsRect,
                 GetActionStateBoolean,
                 GetActionStateFloat,
                 GetActionStateVector2f,
-                (GetActionStatePose),
+                GetActionStatePose,
                 CreateActionSet,
                 DestroyActionSet,
                 CreateAction,
@@ -269,8 +435,12 @@ pub extern "system" fn get_instance_proc_addr(
                 AttachSessionActionSets,
                 GetCurrentInteractionProfile,
                 SyncActions,
-                (EnumerateBoundSourcesForAction),
-                (GetInputSourceLocalizedName)
+                EnumerateBoundSourcesForAction,
+                (GetInputSourceLocalizedName),
+                (EnumerateDisplayRefreshRatesFB),
+                GetDisplayRefreshRateFB,
+                (RequestDisplayRefreshRateFB),
+                ConvertTimespecTimeToTimeKHR
                 ]
 
                 other => {
@@ -291,24 +461,70 @@ extern "system" fn enumerate_instance_extension_properties(
     properties: *mut xr::ExtensionProperties,
 ) -> xr::Result {
     assert!(layer_name.is_null());
-    unsafe { *property_count_output = 1 };
+    let extensions: Vec<&[u8]> = [
+        xr::KHR_VULKAN_ENABLE_EXTENSION_NAME,
+        xr::FB_DISPLAY_REFRESH_RATE_EXTENSION_NAME,
+        xr::EXT_HAND_INTERACTION_EXTENSION_NAME,
+    ]
+    .into_iter()
+    .chain(EXTRA_EXTENSIONS.with_borrow(|extra| extra.clone()))
+    .collect();
+    unsafe { *property_count_output = extensions.len() as u32 };
     if property_capacity_input > 0 {
+        if (property_capacity_input as usize) < extensions.len() {
+            return xr::Result::ERROR_SIZE_INSUFFICIENT;
+        }
         let props =
             unsafe { std::slice::from_raw_parts_mut(properties, property_capacity_input as usize) };
-        props[0] = xr::ExtensionProperties {
-            ty: xr::ExtensionProperties::TYPE,
-            next: std::ptr::null_mut(),
-            extension_name: [0 as c_char; xr::MAX_EXTENSION_NAME_SIZE],
-            extension_version: 1,
-        };
-        let name = xr::KHR_VULKAN_ENABLE_EXTENSION_NAME;
-        let name =
-            unsafe { std::slice::from_raw_parts(name.as_ptr() as *const c_char, name.len()) };
-        props[0].extension_name[..name.len()].copy_from_slice(name);
+        for (prop, name) in props.iter_mut().zip(extensions) {
+            *prop = xr::ExtensionProperties {
+                ty: xr::ExtensionProperties::TYPE,
+                next: std::ptr::null_mut(),
+                extension_name: [0 as c_char; xr::MAX_EXTENSION_NAME_SIZE],
+                extension_version: 1,
+            };
+            let name =
+                unsafe { std::slice::from_raw_parts(name.as_ptr() as *const c_char, name.len()) };
+            prop.extension_name[..name.len()].copy_from_slice(name);
+        }
     }
     xr::Result::SUCCESS
 }
 
+extern "system" fn enumerate_api_layer_properties(
+    property_capacity_input: u32,
+    property_count_output: *mut u32,
+    properties: *mut xr::ApiLayerProperties,
+) -> xr::Result {
+    API_LAYERS.with_borrow(|layers| {
+        unsafe { *property_count_output = layers.len() as u32 };
+        if property_capacity_input == 0 {
+            return xr::Result::SUCCESS;
+        }
+        if (property_capacity_input as usize) < layers.len() {
+            return xr::Result::ERROR_SIZE_INSUFFICIENT;
+        }
+
+        let props =
+            unsafe { std::slice::from_raw_parts_mut(properties, property_capacity_input as usize) };
+        for (prop, name) in props.iter_mut().zip(layers) {
+            *prop = xr::ApiLayerProperties {
+                ty: xr::ApiLayerProperties::TYPE,
+                next: std::ptr::null_mut(),
+                layer_name: [0 as c_char; xr::MAX_API_LAYER_NAME_SIZE],
+                spec_version: xr::CURRENT_API_VERSION,
+                layer_version: 1,
+                description: [0 as c_char; xr::MAX_API_LAYER_DESCRIPTION_SIZE],
+            };
+            let name = name.to_bytes();
+            let name =
+                unsafe { std::slice::from_raw_parts(name.as_ptr() as *const c_char, name.len()) };
+            prop.layer_name[..name.len()].copy_from_slice(name);
+        }
+        xr::Result::SUCCESS
+    })
+}
+
 trait Handle: 'static {
     type XrType: XrType;
     fn instances() -> MutexGuard<'static, SlotMap<DefaultKey, Arc<Self>>>;
@@ -388,6 +604,20 @@ impl Instance {
         }
     }
 
+    fn get_or_create_path(&self, s: &str) -> xr::Path {
+        let mut string_to_path = self.string_to_path.lock().unwrap();
+        let key = match string_to_path.get(s) {
+            Some(p) => *p,
+            None => {
+                let mut paths = self.paths.lock().unwrap();
+                let key = paths.insert(s.to_string());
+                string_to_path.insert(s.to_string(), key);
+                key
+            }
+        };
+        xr::Path::from_raw(key.data().as_ffi())
+    }
+
     fn get_user_path(&self, path: xr::Path) -> Result<Option<UserPath>, ()> {
         Ok(self
             .get_path_value(path)?
@@ -400,6 +630,7 @@ struct HandData {
     profile: AtomicCell<xr::Path>,
     grip_pose: AtomicCell<xr::Posef>,
     aim_pose: AtomicCell<xr::Posef>,
+    location_flags: AtomicCell<xr::SpaceLocationFlags>,
 }
 
 impl Default for HandData {
@@ -409,6 +640,7 @@ impl Default for HandData {
             profile: Default::default(),
             grip_pose: xr::Posef::IDENTITY.into(),
             aim_pose: xr::Posef::IDENTITY.into(),
+            location_flags: (*LOCATION_FLAGS_TRACKED).into(),
         }
     }
 }
@@ -424,7 +656,14 @@ struct Session {
     state: AtomicCell<xr::SessionState>,
     state_synced: AtomicBool,
     should_render: AtomicBool,
+    display_refresh_rate: AtomicCell<f32>,
     frame_state: AtomicCell<FrameState>,
+    last_frame_layers: Mutex<Vec<SubmittedLayer>>,
+    swapchain_create_flags: Mutex<Vec<xr::SwapchainCreateFlags>>,
+    max_swapchain_sample_count: AtomicU32,
+    ended_frames: AtomicU32,
+    stage_bounds: AtomicCell<Option<xr::Extent2Df>>,
+    last_synced_sets: Mutex<Vec<xr::ActionSet>>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -507,6 +746,8 @@ static LOCATION_FLAGS_TRACKED: LazyLock<xr::SpaceLocationFlags> = LazyLock::new(
 struct Space {
     hand: Option<UserPath>,
     offset: xr::Posef,
+    /// Kept separately from the session, so spaces that outlive it can still be counted.
+    instance: Weak<Instance>,
     session: Weak<Session>,
     action: Weak<Action>,
 }
@@ -552,17 +793,13 @@ impl Space {
             .upgrade()
             .ok_or(xr::Result::ERROR_SESSION_LOST)?;
 
-        let binding = bindings
-            .iter()
-            .copied()
-            .find_map(|p| {
-                let val = instance.get_path_value(p).unwrap().unwrap();
-                val.starts_with(hand_path).then_some(val)
-            })
-            .expect(&format!(
-                "expected binding for space for action {:?}",
-                action.name
-            ));
+        // No binding for this hand - no data
+        let Some(binding) = bindings.iter().copied().find_map(|p| {
+            let val = instance.get_path_value(p).unwrap().unwrap();
+            val.starts_with(hand_path).then_some(val)
+        }) else {
+            return Ok(default());
+        };
 
         let pose = match binding.strip_prefix(hand.to_path()).unwrap() {
             "/input/grip/pose" => hand_data.grip_pose.load(),
@@ -581,7 +818,7 @@ impl Space {
         Ok(xr::SpaceLocation {
             ty: xr::SpaceLocation::TYPE,
             next: std::ptr::null_mut(),
-            location_flags: *LOCATION_FLAGS_TRACKED,
+            location_flags: hand_data.location_flags.load(),
             pose: ret,
         })
     }
@@ -612,6 +849,9 @@ struct Action {
     state: LeftRight<AtomicCell<ActionStateData>>,
     pending_state: AtomicCell<LeftRight<Option<ActionState>>>,
     suggested: Mutex<HashMap<xr::Path, Vec<xr::Path>>>,
+    /// Sources set with [`set_bound_sources`], which replace the suggested ones.
+    bound: Mutex<Option<Vec<xr::Path>>>,
+    haptics: LeftRight<AtomicCell<Option<HapticPulse>>>,
 }
 
 impl Action {
@@ -633,6 +873,7 @@ struct LeftRight<T> {
 struct ActionStateData {
     state: ActionState,
     changed: bool,
+    last_change_time: xr::Time,
 }
 
 struct Swapchain {
@@ -712,7 +953,14 @@ extern "system" fn create_session(
         state: xr::SessionState::READY.into(),
         state_synced: true.into(),
         should_render: false.into(),
+        display_refresh_rate: 90.0.into(),
         frame_state: FrameState::Ended.into(),
+        last_frame_layers: Default::default(),
+        swapchain_create_flags: Default::default(),
+        max_swapchain_sample_count: u32::MAX.into(),
+        ended_frames: 0.into(),
+        stage_bounds: None.into(),
+        last_synced_sets: Default::default(),
     });
 
     let tx = sess.event_sender.clone();
@@ -849,6 +1097,7 @@ extern "system" fn create_action(
     let data = ActionStateData {
         state,
         changed: false,
+        last_change_time: xr::Time::from_nanos(0),
     };
     let a = Arc::new(Action {
         instance: set.instance.clone(),
@@ -868,6 +1117,8 @@ extern "system" fn create_action(
         },
         pending_state: Default::default(),
         suggested: Mutex::default(),
+        bound: Mutex::default(),
+        haptics: Default::default(),
     });
 
     set.pending_actions.write().unwrap().push(a.clone());
@@ -902,6 +1153,7 @@ extern "system" fn create_action_space(
     let s = Arc::new(Space {
         hand,
         offset: info.pose_in_action_space,
+        instance: Arc::downgrade(&instance),
         session: Arc::downgrade(&session),
         action: Arc::downgrade(&action),
     });
@@ -949,6 +1201,9 @@ static VIEW: LazyLock<xr::Space> = LazyLock::new(|| xr::Space::from_raw(1));
 static LOCAL: LazyLock<xr::Space> = LazyLock::new(|| xr::Space::from_raw(2));
 static STAGE: LazyLock<xr::Space> = LazyLock::new(|| xr::Space::from_raw(3));
 
+/// How far above the stage space's origin the local space's origin is.
+pub const LOCAL_HEIGHT_ABOVE_STAGE: f32 = 1.5;
+
 extern "system" fn create_reference_space(
     _: xr::Session,
     create_info: *const xr::ReferenceSpaceCreateInfo,
@@ -997,18 +1252,7 @@ extern "system" fn string_to_path(
 ) -> xr::Result {
     let instance = get_handle!(instance);
     let s = unsafe { CStr::from_ptr(string) }.to_str().unwrap();
-    let mut string_to_path = instance.string_to_path.lock().unwrap();
-    let key = match string_to_path.get(s) {
-        Some(p) => *p,
-        None => {
-            let mut paths = instance.paths.lock().unwrap();
-            let key = paths.insert(s.to_string());
-            string_to_path.insert(s.to_string(), key);
-            key
-        }
-    };
-
-    unsafe { path.write(xr::Path::from_raw(key.data().as_ffi())) };
+    unsafe { path.write(instance.get_or_create_path(s)) };
 
     xr::Result::SUCCESS
 }
@@ -1148,6 +1392,7 @@ extern "system" fn sync_actions(
             (*info).count_active_action_sets as _,
         )
     };
+    *session.last_synced_sets.lock().unwrap() = sets.iter().map(|set| set.action_set).collect();
     for set in sets {
         if !attached.contains(&set.action_set) {
             return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED;
@@ -1170,6 +1415,7 @@ extern "system" fn sync_actions(
                     if d.state != new_state {
                         d.changed = true;
                         d.state = new_state;
+                        d.last_change_time = xr::Time::from_nanos(ACTION_CHANGE_TIME.get());
                     }
                 }
                 state.store(d);
@@ -1229,6 +1475,7 @@ extern "system" fn get_action_state_boolean(
         if active {
             state.current_state = b.into();
             state.changed_since_last_sync = hand_state.changed.into();
+            state.last_change_time = hand_state.last_change_time;
         }
         state.is_active = active.into();
     }
@@ -1264,6 +1511,7 @@ extern "system" fn get_action_state_float(
         let active = action.active.load(Ordering::Relaxed);
         if active {
             state.current_state = f;
+            state.last_change_time = hand_state.last_change_time;
         }
         state.is_active = active.into();
     }
@@ -1300,6 +1548,7 @@ extern "system" fn get_action_state_vector2f(
         let active = action.active.load(Ordering::Relaxed);
         if active {
             state.current_state = xr::Vector2f { x, y };
+            state.last_change_time = hand_state.last_change_time;
         }
         state.is_active = active.into();
     }
@@ -1307,6 +1556,75 @@ extern "system" fn get_action_state_vector2f(
     xr::Result::SUCCESS
 }
 
+extern "system" fn get_action_state_pose(
+    session: xr::Session,
+    info: *const xr::ActionStateGetInfo,
+    state: *mut xr::ActionStatePose,
+) -> xr::Result {
+    unsafe {
+        state.write(xr::ActionStatePose {
+            ty: xr::ActionStatePose::TYPE,
+            next: std::ptr::null_mut(),
+            is_active: false.into(),
+        });
+    }
+    let session = get_handle!(session);
+    let Some((set, action)) = get_action_if_attached(&session, info) else {
+        return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED;
+    };
+
+    let info = unsafe { info.as_ref().unwrap() };
+    let instance = session.instance.upgrade().unwrap();
+    let hand_state = action.get_hand_state(&instance, info.subaction_path);
+    let ActionState::Pose = hand_state.state else {
+        return xr::Result::ERROR_ACTION_TYPE_MISMATCH;
+    };
+    let state = unsafe { state.as_mut().unwrap() };
+    if set.active.load(Ordering::Relaxed) {
+        state.is_active = action.active.load(Ordering::Relaxed).into();
+    }
+    xr::Result::SUCCESS
+}
+
+extern "system" fn apply_haptic_feedback(
+    session: xr::Session,
+    info: *const xr::HapticActionInfo,
+    feedback: *const xr::HapticBaseHeader,
+) -> xr::Result {
+    let session = get_handle!(session);
+    let Some(instance) = session.instance.upgrade() else {
+        return xr::Result::ERROR_INSTANCE_LOST;
+    };
+    let info = unsafe { info.as_ref() }.unwrap();
+    let action = get_handle!(info.action);
+    if !matches!(action.state.left.load().state, ActionState::Haptic) {
+        return xr::Result::ERROR_ACTION_TYPE_MISMATCH;
+    }
+
+    let feedback = unsafe { feedback.as_ref() }.unwrap();
+    if feedback.ty != xr::HapticVibration::TYPE {
+        return xr::Result::ERROR_VALIDATION_FAILURE;
+    }
+    let vibration = unsafe { &*(feedback as *const _ as *const xr::HapticVibration) };
+    let pulse = Some(HapticPulse {
+        duration: vibration.duration,
+        frequency: vibration.frequency,
+        amplitude: vibration.amplitude,
+    });
+
+    match instance.get_user_path(info.subaction_path) {
+        Ok(Some(UserPath::LeftHand)) => action.haptics.left.store(pulse),
+        Ok(Some(UserPath::RightHand)) => action.haptics.right.store(pulse),
+        Ok(None) => {
+            action.haptics.left.store(pulse);
+            action.haptics.right.store(pulse);
+        }
+        Err(_) => return xr::Result::ERROR_PATH_INVALID,
+    }
+
+    xr::Result::SUCCESS
+}
+
 extern "system" fn get_current_interaction_profile(
     session: xr::Session,
     user_path: xr::Path,
@@ -1336,19 +1654,128 @@ extern "system" fn get_current_interaction_profile(
     xr::Result::SUCCESS
 }
 
+extern "system" fn get_reference_space_bounds_rect(
+    session: xr::Session,
+    ty: xr::ReferenceSpaceType,
+    bounds: *mut xr::Extent2Df,
+) -> xr::Result {
+    let session = get_handle!(session);
+    let stage_bounds = match ty {
+        xr::ReferenceSpaceType::STAGE => session.stage_bounds.load(),
+        xr::ReferenceSpaceType::VIEW | xr::ReferenceSpaceType::LOCAL => None,
+        _ => return xr::Result::ERROR_REFERENCE_SPACE_UNSUPPORTED,
+    };
+
+    unsafe {
+        bounds.write(stage_bounds.unwrap_or(xr::Extent2Df {
+            width: 0.0,
+            height: 0.0,
+        }));
+    }
+    match stage_bounds {
+        Some(_) => xr::Result::SUCCESS,
+        None => xr::Result::SPACE_BOUNDS_UNAVAILABLE,
+    }
+}
+
+extern "system" fn enumerate_bound_sources_for_action(
+    session: xr::Session,
+    info: *const xr::BoundSourcesForActionEnumerateInfo,
+    capacity: u32,
+    output: *mut u32,
+    sources: *mut xr::Path,
+) -> xr::Result {
+    let session = get_handle!(session);
+    let Some(instance) = session.instance.upgrade() else {
+        return xr::Result::ERROR_INSTANCE_LOST;
+    };
+    let Some(action) = xr::Action::to_handle(unsafe { (*info).action }) else {
+        return xr::Result::ERROR_HANDLE_INVALID;
+    };
+    if session.attached_sets.get().is_none() {
+        return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED;
+    }
+
+    // Sources suggested for the current interaction profile of each hand, unless a test rebound
+    // the action.
+    let instance = &instance;
+    let suggested = action.suggested.lock().unwrap();
+    let rebound = action.bound.lock().unwrap().clone();
+    let bound: Vec<xr::Path> = rebound.unwrap_or_else(|| {
+        [UserPath::LeftHand, UserPath::RightHand]
+            .into_iter()
+            .flat_map(|hand| {
+                let profile = get_hand_data(hand, &session).profile.load();
+                suggested
+                    .get(&profile)
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(move |path| {
+                        instance
+                            .get_path_value(*path)
+                            .ok()
+                            .flatten()
+                            .is_some_and(|value| value.starts_with(hand.to_path()))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    });
+
+    unsafe {
+        output.write(bound.len() as u32);
+    }
+    if !bound.is_empty() && capacity as usize >= bound.len() {
+        let sources = unsafe { std::slice::from_raw_parts_mut(sources, capacity as usize) };
+        sources[..bound.len()].copy_from_slice(&bound);
+    }
+
+    xr::Result::SUCCESS
+}
+
 extern "system" fn locate_space(
     space: xr::Space,
     base_space: xr::Space,
-    _time: xr::Time,
+    time: xr::Time,
     location: *mut xr::SpaceLocation,
 ) -> xr::Result {
-    assert!(
-        base_space != *STAGE && base_space != *VIEW,
-        "stage/view locate unimplemented"
-    );
-    assert_ne!(space, *LOCAL);
+    assert!(base_space != *VIEW, "view locate unimplemented");
+
+    // The reference spaces only differ by the stage being lowered to the floor.
+    if [*LOCAL, *STAGE].contains(&space) {
+        assert!(
+            [*LOCAL, *STAGE].contains(&base_space),
+            "reference space locate unimplemented"
+        );
+        let y = match (space == *LOCAL, base_space == *LOCAL) {
+            (true, false) => LOCAL_HEIGHT_ABOVE_STAGE,
+            (false, true) => -LOCAL_HEIGHT_ABOVE_STAGE,
+            _ => 0.0,
+        };
+        let location = unsafe { location.as_mut() }.unwrap();
+        location.location_flags = *LOCATION_FLAGS_TRACKED;
+        location.pose = xr::Posef {
+            orientation: xr::Quaternionf::IDENTITY,
+            position: xr::Vector3f { x: 0.0, y, z: 0.0 },
+        };
+        return xr::Result::SUCCESS;
+    }
+
+    // The stage is the local space, lowered to the floor.
+    if base_space == *STAGE {
+        let ret = locate_space(space, *LOCAL, time, location);
+        let location = unsafe { location.as_mut() }.unwrap();
+        if ret == xr::Result::SUCCESS
+            && location
+                .location_flags
+                .contains(xr::SpaceLocationFlags::POSITION_VALID)
+        {
+            location.pose.position.y += LOCAL_HEIGHT_ABOVE_STAGE;
+        }
+        return ret;
+    }
 
-    let space = get_handle!(space);
     let next = unsafe { *&raw mut (*location).next };
     let mut out_loc = xr::SpaceLocation {
         ty: xr::SpaceLocation::TYPE,
@@ -1362,17 +1789,35 @@ extern "system" fn locate_space(
         unsafe {
             if *&raw mut (*header).ty == xr::SpaceVelocity::TYPE {
                 let velo = next as *mut xr::SpaceVelocity;
+                let (velocity_flags, (linear_velocity, angular_velocity)) = if space == *VIEW {
+                    (
+                        xr::SpaceVelocityFlags::LINEAR_VALID
+                            | xr::SpaceVelocityFlags::ANGULAR_VALID,
+                        VIEW_VELOCITY.get(),
+                    )
+                } else {
+                    (xr::SpaceVelocityFlags::EMPTY, Default::default())
+                };
                 velo.write(xr::SpaceVelocity {
                     ty: xr::SpaceVelocity::TYPE,
                     next: *&raw mut (*velo).next,
-                    velocity_flags: xr::SpaceVelocityFlags::EMPTY,
-                    linear_velocity: Default::default(),
-                    angular_velocity: Default::default(),
+                    velocity_flags,
+                    linear_velocity,
+                    angular_velocity,
                 });
                 out_loc.next = velo as _;
             }
         }
     }
+
+    if space == *VIEW && base_space == *LOCAL {
+        out_loc.location_flags = *LOCATION_FLAGS_TRACKED;
+        out_loc.pose = VIEW_POSE.get();
+        unsafe { location.write(out_loc) }
+        return xr::Result::SUCCESS;
+    }
+
+    let space = get_handle!(space);
     if base_space == *LOCAL {
         match space.get_pose_relative_to_local() {
             Ok(loc) => {
@@ -1393,9 +1838,11 @@ extern "system" fn locate_space(
         };
 
         if base_loc.location_flags.contains(*LOCATION_FLAGS_TRACKED)
-            && target_loc.location_flags.contains(*LOCATION_FLAGS_TRACKED)
+            && target_loc
+                .location_flags
+                .contains(xr::SpaceLocationFlags::ORIENTATION_VALID)
         {
-            out_loc.location_flags = *LOCATION_FLAGS_TRACKED;
+            out_loc.location_flags = target_loc.location_flags;
             let base_mat = pose_to_mat(base_loc.pose);
             let target_mat = pose_to_mat(target_loc.pose);
 
@@ -1409,10 +1856,11 @@ extern "system" fn locate_space(
     xr::Result::SUCCESS
 }
 extern "system" fn create_swapchain(
-    _session: xr::Session,
+    session: xr::Session,
     info: *const xr::SwapchainCreateInfo,
     swapchain: *mut xr::Swapchain,
 ) -> xr::Result {
+    let session = get_handle!(session);
     let info = unsafe { info.as_ref() }.unwrap();
     if info.width == 0 || info.height == 0 {
         return xr::Result::ERROR_VALIDATION_FAILURE;
@@ -1420,6 +1868,14 @@ extern "system" fn create_swapchain(
     if info.format != 0 {
         return xr::Result::ERROR_SWAPCHAIN_FORMAT_UNSUPPORTED;
     }
+    if info.sample_count > session.max_swapchain_sample_count.load(Ordering::Relaxed) {
+        return xr::Result::ERROR_FEATURE_UNSUPPORTED;
+    }
+    session
+        .swapchain_create_flags
+        .lock()
+        .unwrap()
+        .push(info.create_flags);
     let swap = Arc::new(Swapchain {
         image_acquired: false.into(),
     });
@@ -1439,12 +1895,42 @@ extern "system" fn enumerate_swapchain_formats(
     output: *mut u32,
     formats: *mut i64,
 ) -> xr::Result {
+    const FORMATS: [i64; 2] = [0, 2];
     unsafe {
-        output.write(1);
+        output.write(FORMATS.len() as u32);
     }
-    if capacity >= 1 {
+    if capacity as usize >= FORMATS.len() {
         let formats = unsafe { std::slice::from_raw_parts_mut(formats, capacity as usize) };
-        formats[0] = 0;
+        formats[..FORMATS.len()].copy_from_slice(&FORMATS);
+    }
+
+    xr::Result::SUCCESS
+}
+
+extern "system" fn enumerate_view_configuration_views(
+    _instance: xr::Instance,
+    _system_id: xr::SystemId,
+    _ty: xr::ViewConfigurationType,
+    capacity: u32,
+    output: *mut u32,
+    views: *mut xr::ViewConfigurationView,
+) -> xr::Result {
+    const VIEW: xr::ViewConfigurationView = xr::ViewConfigurationView {
+        ty: xr::ViewConfigurationView::TYPE,
+        next: std::ptr::null_mut(),
+        recommended_image_rect_width: 2016,
+        max_image_rect_width: 4032,
+        recommended_image_rect_height: 2240,
+        max_image_rect_height: 4480,
+        recommended_swapchain_sample_count: 1,
+        max_swapchain_sample_count: 1,
+    };
+    unsafe {
+        output.write(2);
+    }
+    if capacity >= 2 {
+        let views = unsafe { std::slice::from_raw_parts_mut(views, capacity as usize) };
+        views[..2].fill(VIEW);
     }
 
     xr::Result::SUCCESS
@@ -1527,11 +2013,52 @@ extern "system" fn begin_frame(
     xr::Result::SUCCESS
 }
 
-extern "system" fn end_frame(session: xr::Session, _info: *const xr::FrameEndInfo) -> xr::Result {
+extern "system" fn end_frame(session: xr::Session, info: *const xr::FrameEndInfo) -> xr::Result {
     let session = get_handle!(session);
     if let Err(e) = transition_frame_state(&session.frame_state, FrameState::Ended) {
         return e;
     }
+
+    let info = unsafe { info.as_ref() }.unwrap();
+    let layers = if info.layer_count > 0 {
+        unsafe { std::slice::from_raw_parts(info.layers, info.layer_count as usize) }
+    } else {
+        &[]
+    };
+    *session.last_frame_layers.lock().unwrap() = layers
+        .iter()
+        .map(|layer| {
+            let layer = unsafe { &**layer };
+            let mut color_scale = None;
+            let mut color_bias = None;
+            let mut next = layer.next as *const xr::BaseInStructure;
+            while let Some(item) = unsafe { next.as_ref() } {
+                if item.ty == xr::CompositionLayerColorScaleBiasKHR::TYPE {
+                    let bias = unsafe { &*(next as *const xr::CompositionLayerColorScaleBiasKHR) };
+                    color_scale = Some(bias.color_scale);
+                    color_bias = Some(bias.color_bias);
+                }
+                next = item.next;
+            }
+            let quad = (layer.ty == xr::CompositionLayerQuad::TYPE)
+                .then(|| unsafe { &*(layer as *const _ as *const xr::CompositionLayerQuad) });
+            let projection = (layer.ty == xr::CompositionLayerProjection::TYPE)
+                .then(|| unsafe { &*(layer as *const _ as *const xr::CompositionLayerProjection) });
+            SubmittedLayer {
+                ty: layer.ty,
+                color_scale,
+                color_bias,
+                size: quad.map(|quad| quad.size),
+                eye_visibility: quad.map(|quad| quad.eye_visibility),
+                image_rect: quad.map(|quad| quad.sub_image.image_rect),
+                view_poses: projection.filter(|p| p.view_count >= 2).map(|p| {
+                    let views = unsafe { std::slice::from_raw_parts(p.views, 2) };
+                    [views[0].pose, views[1].pose]
+                }),
+            }
+        })
+        .collect();
+    session.ended_frames.fetch_add(1, Ordering::Relaxed);
     if session.state.load() == xr::SessionState::READY {
         session.synchronized();
     }
@@ -1580,6 +2107,27 @@ extern "system" fn locate_views(
     xr::Result::SUCCESS
 }
 
+extern "system" fn get_display_refresh_rate_f_b(
+    session: xr::Session,
+    rate: *mut f32,
+) -> xr::Result {
+    let session = get_handle!(session);
+    unsafe { rate.write(session.display_refresh_rate.load()) };
+    xr::Result::SUCCESS
+}
+
+extern "system" fn convert_timespec_time_to_time_k_h_r(
+    _instance: xr::Instance,
+    timespec_time: *const xr::platform::timespec,
+    time: *mut xr::Time,
+) -> xr::Result {
+    // XrTime is just the timespec in nanoseconds.
+    let timespec_time = unsafe { &*timespec_time };
+    let nanos = timespec_time.tv_sec as i64 * 1_000_000_000 + timespec_time.tv_nsec as i64;
+    unsafe { time.write(xr::Time::from_nanos(nanos)) };
+    xr::Result::SUCCESS
+}
+
 fn pose_to_mat(
     xr::Posef {
         position: p,
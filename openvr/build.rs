@@ -804,6 +804,10 @@ fn process_and_versionify_types(tokens: TokenStream) -> String {
 
         items
     });
+    let property_types = match unversioned.get("ETrackedDeviceProperty") {
+        Some((syn::Item::Enum(props), _)) => property_types(props),
+        _ => panic!("ETrackedDeviceProperty wasn't generated"),
+    };
     let unversioned = unversioned.into_iter().flat_map(|(name, (item, parent))| {
         let mut items = vec![item];
 
@@ -851,6 +855,7 @@ fn process_and_versionify_types(tokens: TokenStream) -> String {
             pub mod vr {
                 use super::#vr_uses;
                 #(#unversioned)*
+                #property_types
                 #(#versioned)*
             }
         }
@@ -863,6 +868,38 @@ fn process_and_versionify_types(tokens: TokenStream) -> String {
     })
 }
 
+/// Generates `ETrackedDeviceProperty::value_type`, from the type each property's name ends with
+/// (e.g. `DisplayFrequency_Float`).
+fn property_types(props: &syn::ItemEnum) -> syn::Item {
+    let arms = props.variants.iter().map(|variant| -> syn::Arm {
+        let ident = &variant.ident;
+        let name = ident.to_string();
+        let ty = match name.rsplit('_').next().unwrap() {
+            suffix @ ("Bool" | "Float" | "Int32" | "Uint64" | "String" | "Matrix34" | "Vector3"
+            | "Array") => Some(format_ident!("{suffix}")),
+            // Also covers the ends of binary property ranges, i.e. DisplayHiddenArea_Binary_Start
+            _ if name.contains("_Binary") => Some(format_ident!("Binary")),
+            _ => None,
+        };
+        match ty {
+            Some(ty) => parse_quote! { Self::#ident => Some(crate::TrackedPropertyType::#ty), },
+            None => parse_quote! { Self::#ident => None, },
+        }
+    });
+
+    parse_quote! {
+        impl ETrackedDeviceProperty {
+            /// The type of the property's value, or `None` for values that aren't properties,
+            /// like `Invalid`.
+            pub const fn value_type(self) -> Option<crate::TrackedPropertyType> {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+}
+
 struct GeneratedInterfaceData {
     /// Generated interface trait
     gen_trait: syn::ItemTrait,
@@ -6,9 +6,8 @@ pub fn space_relation_to_openvr_pose(
     location: xr::SpaceLocation,
     velocity: xr::SpaceVelocity,
 ) -> TrackedDevicePose_t {
-    if !location.location_flags.contains(
-        xr::SpaceLocationFlags::POSITION_VALID | xr::SpaceLocationFlags::ORIENTATION_VALID,
-    ) {
+    let flags = location.location_flags;
+    if !flags.contains(xr::SpaceLocationFlags::ORIENTATION_VALID) {
         return TrackedDevicePose_t {
             bPoseIsValid: false,
             bDeviceIsConnected: false,
@@ -29,11 +28,23 @@ pub fn space_relation_to_openvr_pose(
         .contains(xr::SpaceVelocityFlags::ANGULAR_VALID)
         .then(|| velocity.angular_velocity.into());
 
+    // An orientation without a position is still usable (e.g. while the position is lost), but
+    // the tracking result tells the app it's missing.
+    let tracking_result = if !flags.contains(xr::SpaceLocationFlags::POSITION_VALID) {
+        ETrackingResult::Fallback_RotationOnly
+    } else if !flags.contains(
+        xr::SpaceLocationFlags::POSITION_TRACKED | xr::SpaceLocationFlags::ORIENTATION_TRACKED,
+    ) {
+        ETrackingResult::Running_OutOfRange
+    } else {
+        ETrackingResult::Running_OK
+    };
+
     TrackedDevicePose_t {
         mDeviceToAbsoluteTracking: location,
         vVelocity: linear_velo.unwrap_or_default(),
         vAngularVelocity: angular_velo.unwrap_or_default(),
-        eTrackingResult: ETrackingResult::Running_OK,
+        eTrackingResult: tracking_result,
         bPoseIsValid: true,
         bDeviceIsConnected: true,
     }
@@ -47,6 +47,20 @@ pub trait InterfaceImpl: Sync + Send + 'static {
     fn get_version(version: &CStr) -> Option<InterfaceGetter<Self>>;
 }
 
+/// The type of a tracked device property's value, which its name ends with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrackedPropertyType {
+    Bool,
+    Float,
+    Int32,
+    Uint64,
+    String,
+    Matrix34,
+    Vector3,
+    Array,
+    Binary,
+}
+
 impl Default for ETrackingResult {
     fn default() -> Self {
         Self::Uninitialized
@@ -0,0 +1,20 @@
+#version 450
+layout(set = 0, binding = 0) uniform sampler2D eye;
+layout(location = 0) in vec2 texCoord;
+layout(location = 0) out vec4 color;
+layout(push_constant, std430) uniform pc {
+	layout(offset = 16) float gamma;
+	float brightness;
+	// The texture holds sRGB encoded values but is sampled as linear
+	uint decodeSrgb;
+};
+
+vec3 srgbToLinear(vec3 c) {
+	return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), greaterThan(c, vec3(0.04045)));
+}
+
+void main() {
+	vec4 texel = texture(eye, texCoord);
+	vec3 rgb = decodeSrgb != 0 ? srgbToLinear(texel.rgb) : texel.rgb;
+	color = vec4(pow(rgb, vec3(1.0 / gamma)) * (1.0 + brightness), texel.a);
+}
@@ -14,6 +14,7 @@ pub fn compile(out_dir: &str) -> Vec<PathBuf> {
     let shaders = [
         ("overlay.vert", "vert_overlay.spv"),
         ("overlay.frag", "frag_overlay.spv"),
+        ("color_adjust.frag", "frag_color_adjust.spv"),
     ];
 
     let cache_path = PathBuf::from(out_dir).join("shader_cache.json");
@@ -1,5 +1,6 @@
+use log::warn;
 use openvr as vr;
-use std::ffi::c_char;
+use std::ffi::{c_char, CStr};
 
 #[derive(Default, macros::InterfaceImpl)]
 #[interface = "IVRApplications"]
@@ -118,8 +119,11 @@ impl vr::IVRApplications007_Interface for Applications {
     ) -> vr::EVRApplicationError {
         todo!()
     }
-    fn LaunchApplication(&self, _: *const c_char) -> vr::EVRApplicationError {
-        todo!()
+    fn LaunchApplication(&self, app_key: *const c_char) -> vr::EVRApplicationError {
+        let app_key = (!app_key.is_null()).then(|| unsafe { CStr::from_ptr(app_key) });
+        // We have no knowledge of installed SteamVR applications, so we can't launch anything.
+        warn!("Application requested to launch {app_key:?}, but launching applications is unsupported");
+        vr::EVRApplicationError::LaunchFailed
     }
     fn GetApplicationKeyByProcessId(
         &self,
@@ -149,3 +153,22 @@ impl vr::IVRApplications007_Interface for Applications {
         vr::EVRApplicationError::None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vr::IVRApplications007_Interface;
+
+    #[test]
+    fn launch_application_fails_gracefully() {
+        let apps = Applications::default();
+        assert_eq!(
+            apps.LaunchApplication(c"steam.app.12345".as_ptr()),
+            vr::EVRApplicationError::LaunchFailed
+        );
+        assert_eq!(
+            apps.LaunchApplication(std::ptr::null()),
+            vr::EVRApplicationError::LaunchFailed
+        );
+    }
+}
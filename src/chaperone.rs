@@ -1,5 +1,8 @@
-use crate::openxr_data::RealOpenXrData;
+use crate::{openxr_data::RealOpenXrData, xr_call};
+use log::warn;
 use openvr as vr;
+use openxr as xr;
+use std::ffi::c_char;
 use std::sync::Arc;
 
 #[derive(macros::InterfaceImpl)]
@@ -46,17 +49,27 @@ impl vr::IVRChaperone004_Interface for Chaperone {
         todo!()
     }
     fn GetPlayAreaRect(&self, rect: *mut vr::HmdQuad_t) -> bool {
-        crate::warn_unimplemented!("GetPlayAreaRect");
-        unsafe {
-            *rect = Default::default();
+        let Some(rect) = (unsafe { rect.as_mut() }) else {
+            return false;
+        };
+        match stage_bounds(&self.openxr) {
+            Some(bounds) => {
+                *rect = stage_bounds_quad(bounds);
+                true
+            }
+            None => {
+                *rect = Default::default();
+                false
+            }
         }
-        false
     }
     fn GetPlayAreaSize(&self, size_x: *mut f32, size_z: *mut f32) -> bool {
-        crate::warn_unimplemented!("GetPlayAreaSize");
+        // Games tend to assume there's a play area, so a minimal one stands in without bounds.
+        let bounds = stage_bounds(&self.openxr);
+        let (width, depth) = bounds.map_or((1.0, 1.0), |b| (b.width, b.height));
         unsafe {
-            *size_x = 1.0;
-            *size_z = 1.0;
+            *size_x = width;
+            *size_z = depth;
         };
         true
     }
@@ -64,3 +77,246 @@ impl vr::IVRChaperone004_Interface for Chaperone {
         vr::ChaperoneCalibrationState::OK
     }
 }
+
+/// The size of the stage, if the runtime knows it.
+fn stage_bounds(openxr: &RealOpenXrData) -> Option<xr::Extent2Df> {
+    xr_call!(
+        "xrGetReferenceSpaceBoundsRect",
+        openxr
+            .session_data
+            .get()
+            .session
+            .reference_space_bounds_rect(xr::ReferenceSpaceType::STAGE)
+    )
+    .inspect_err(|e| warn!("Failed to get stage bounds: {e}"))
+    .ok()
+    .flatten()
+}
+
+/// A quad on the floor around the stage bounds, centered on the standing origin.
+fn stage_bounds_quad(bounds: xr::Extent2Df) -> vr::HmdQuad_t {
+    let x = bounds.width / 2.0;
+    let z = bounds.height / 2.0;
+    let corner = |x, z| vr::HmdVector3_t { v: [x, 0.0, z] };
+    vr::HmdQuad_t {
+        vCorners: [corner(-x, z), corner(x, z), corner(x, -z), corner(-x, -z)],
+    }
+}
+
+#[derive(macros::InterfaceImpl)]
+#[interface = "IVRChaperoneSetup"]
+#[versions(006)]
+pub struct ChaperoneSetup {
+    vtables: Vtables,
+    openxr: Arc<RealOpenXrData>,
+}
+
+impl ChaperoneSetup {
+    pub fn new(openxr: Arc<RealOpenXrData>) -> Self {
+        Self {
+            vtables: Default::default(),
+            openxr,
+        }
+    }
+}
+
+impl vr::IVRChaperoneSetup006_Interface for ChaperoneSetup {
+    fn CommitWorkingCopy(&self, _: vr::EChaperoneConfigFile) -> bool {
+        crate::warn_unimplemented!("CommitWorkingCopy");
+        false
+    }
+    fn RevertWorkingCopy(&self) {
+        crate::warn_unimplemented!("RevertWorkingCopy");
+    }
+    fn GetWorkingPlayAreaSize(&self, _: *mut f32, _: *mut f32) -> bool {
+        crate::warn_unimplemented!("GetWorkingPlayAreaSize");
+        false
+    }
+    fn GetWorkingPlayAreaRect(&self, _: *mut vr::HmdQuad_t) -> bool {
+        crate::warn_unimplemented!("GetWorkingPlayAreaRect");
+        false
+    }
+    fn GetWorkingCollisionBoundsInfo(&self, _: *mut vr::HmdQuad_t, count: *mut u32) -> bool {
+        crate::warn_unimplemented!("GetWorkingCollisionBoundsInfo");
+        if let Some(count) = unsafe { count.as_mut() } {
+            *count = 0;
+        }
+        false
+    }
+    fn GetLiveCollisionBoundsInfo(&self, quads: *mut vr::HmdQuad_t, count: *mut u32) -> bool {
+        let Some(count) = (unsafe { count.as_mut() }) else {
+            return false;
+        };
+        // The runtime only reports the bounds as a rectangle, so that's the only quad.
+        let Some(bounds) = stage_bounds(&self.openxr) else {
+            *count = 0;
+            return false;
+        };
+
+        let capacity = *count;
+        *count = 1;
+        if quads.is_null() {
+            return true;
+        }
+        if capacity < 1 {
+            return false;
+        }
+        unsafe { quads.write(stage_bounds_quad(bounds)) };
+        true
+    }
+    fn GetWorkingSeatedZeroPoseToRawTrackingPose(&self, _: *mut vr::HmdMatrix34_t) -> bool {
+        crate::warn_unimplemented!("GetWorkingSeatedZeroPoseToRawTrackingPose");
+        false
+    }
+    fn GetWorkingStandingZeroPoseToRawTrackingPose(&self, _: *mut vr::HmdMatrix34_t) -> bool {
+        crate::warn_unimplemented!("GetWorkingStandingZeroPoseToRawTrackingPose");
+        false
+    }
+    fn SetWorkingPlayAreaSize(&self, _: f32, _: f32) {
+        crate::warn_unimplemented!("SetWorkingPlayAreaSize");
+    }
+    fn SetWorkingCollisionBoundsInfo(&self, _: *mut vr::HmdQuad_t, _: u32) {
+        crate::warn_unimplemented!("SetWorkingCollisionBoundsInfo");
+    }
+    fn SetWorkingPerimeter(&self, _: *mut vr::HmdVector2_t, _: u32) {
+        crate::warn_unimplemented!("SetWorkingPerimeter");
+    }
+    fn SetWorkingSeatedZeroPoseToRawTrackingPose(&self, _: *const vr::HmdMatrix34_t) {
+        crate::warn_unimplemented!("SetWorkingSeatedZeroPoseToRawTrackingPose");
+    }
+    fn SetWorkingStandingZeroPoseToRawTrackingPose(&self, _: *const vr::HmdMatrix34_t) {
+        crate::warn_unimplemented!("SetWorkingStandingZeroPoseToRawTrackingPose");
+    }
+    fn ReloadFromDisk(&self, _: vr::EChaperoneConfigFile) {
+        crate::warn_unimplemented!("ReloadFromDisk");
+    }
+    fn GetLiveSeatedZeroPoseToRawTrackingPose(&self, pose: *mut vr::HmdMatrix34_t) -> bool {
+        let Some(pose) = (unsafe { pose.as_mut() }) else {
+            return false;
+        };
+        // Raw tracking is the same as standing, since that's all xrizer tracks in.
+        let session = self.openxr.session_data.get();
+        let location = xr_call!(
+            "xrLocateSpace",
+            session
+                .get_space_for_origin(vr::ETrackingUniverseOrigin::Seated)
+                .locate(
+                    session.get_space_for_origin(vr::ETrackingUniverseOrigin::Standing),
+                    self.openxr.display_time.get(),
+                )
+        );
+        match location {
+            Ok(location)
+                if location.location_flags.contains(
+                    xr::SpaceLocationFlags::POSITION_VALID
+                        | xr::SpaceLocationFlags::ORIENTATION_VALID,
+                ) =>
+            {
+                *pose = location.pose.into();
+                true
+            }
+            Ok(_) => false,
+            Err(e) => {
+                warn!("Failed to locate seated space: {e}");
+                false
+            }
+        }
+    }
+    fn ExportLiveToBuffer(&self, _: *mut c_char, _: *mut u32) -> bool {
+        crate::warn_unimplemented!("ExportLiveToBuffer");
+        false
+    }
+    fn ImportFromBufferToWorking(&self, _: *const c_char, _: u32) -> bool {
+        crate::warn_unimplemented!("ImportFromBufferToWorking");
+        false
+    }
+    fn ShowWorkingSetPreview(&self) {
+        crate::warn_unimplemented!("ShowWorkingSetPreview");
+    }
+    fn HideWorkingSetPreview(&self) {
+        crate::warn_unimplemented!("HideWorkingSetPreview");
+    }
+    fn RoomSetupStarting(&self) {
+        crate::warn_unimplemented!("RoomSetupStarting");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vr::{IVRChaperone004_Interface, IVRChaperoneSetup006_Interface};
+
+    #[test]
+    fn live_collision_bounds() {
+        let xr = RealOpenXrData::new_fake();
+        let setup = ChaperoneSetup::new(xr.clone());
+        let session = xr.session_data.get().session.as_raw();
+
+        let mut count = 5;
+        assert!(!setup.GetLiveCollisionBoundsInfo(std::ptr::null_mut(), &mut count));
+        assert_eq!(count, 0);
+
+        fakexr::set_stage_bounds(
+            session,
+            Some(xr::Extent2Df {
+                width: 3.0,
+                height: 2.0,
+            }),
+        );
+        assert!(setup.GetLiveCollisionBoundsInfo(std::ptr::null_mut(), &mut count));
+        assert_eq!(count, 1);
+
+        let mut quads = [vr::HmdQuad_t::default(); 2];
+        count = quads.len() as u32;
+        assert!(setup.GetLiveCollisionBoundsInfo(quads.as_mut_ptr(), &mut count));
+        assert_eq!(count, 1);
+        let corners = quads[0].vCorners.map(|corner| corner.v);
+        assert_eq!(
+            corners,
+            [
+                [-1.5, 0.0, 1.0],
+                [1.5, 0.0, 1.0],
+                [1.5, 0.0, -1.0],
+                [-1.5, 0.0, -1.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn live_seated_zero_pose() {
+        let xr = RealOpenXrData::new_fake();
+        let setup = ChaperoneSetup::new(xr.clone());
+
+        let mut pose = vr::HmdMatrix34_t::default();
+        assert!(setup.GetLiveSeatedZeroPoseToRawTrackingPose(&mut pose));
+        // The seated origin is at head height above the floor.
+        assert_eq!(pose.m[1][3], fakexr::LOCAL_HEIGHT_ABOVE_STAGE);
+        assert_eq!(pose.m[0][3], 0.0);
+        assert_eq!(pose.m[2][3], 0.0);
+    }
+
+    #[test]
+    fn play_area() {
+        let xr = RealOpenXrData::new_fake();
+        let chaperone = Chaperone::new(xr.clone());
+        let session = xr.session_data.get().session.as_raw();
+
+        let mut rect = vr::HmdQuad_t::default();
+        let (mut x, mut z) = (0.0, 0.0);
+        assert!(!chaperone.GetPlayAreaRect(&mut rect));
+        assert!(chaperone.GetPlayAreaSize(&mut x, &mut z));
+        assert_eq!((x, z), (1.0, 1.0));
+
+        fakexr::set_stage_bounds(
+            session,
+            Some(xr::Extent2Df {
+                width: 3.0,
+                height: 2.0,
+            }),
+        );
+        assert!(chaperone.GetPlayAreaRect(&mut rect));
+        assert_eq!(rect.vCorners[1].v, [1.5, 0.0, 1.0]);
+        assert!(chaperone.GetPlayAreaSize(&mut x, &mut z));
+        assert_eq!((x, z), (3.0, 2.0));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     applications::Applications,
-    chaperone::Chaperone,
+    chaperone::{Chaperone, ChaperoneSetup},
     compositor::Compositor,
     input::Input,
     misc_unknown::UnknownInterfaces,
@@ -221,10 +221,15 @@ impl IVRClientCore003_Interface for ClientCore {
             .or_else(|| self.try_interface(interface, |_| RenderModels::default()))
             .or_else(|| self.try_interface(interface, |_| OverlayMan::new(openxr.clone())))
             .or_else(|| self.try_interface(interface, |_| Chaperone::new(openxr.clone())))
+            .or_else(|| self.try_interface(interface, |_| ChaperoneSetup::new(openxr.clone())))
             .or_else(|| self.try_interface(interface, |_| Applications::default()))
             .or_else(|| self.try_interface(interface, |_| OverlayView::default()))
             .or_else(|| self.try_interface(interface, |_| Screenshots::default()))
-            .or_else(|| self.try_interface(interface, |_| Settings::default()))
+            .or_else(|| {
+                self.try_interface(interface, |injector| {
+                    Settings::new(openxr.clone(), injector)
+                })
+            })
             .or_else(|| self.try_interface(interface, |_| UnknownInterfaces::default()))
             .unwrap_or_else(|| {
                 warn!("app requested unknown interface {interface:?}");
@@ -241,6 +246,7 @@ impl IVRClientCore003_Interface for ClientCore {
                 RenderModels::supported_versions(),
                 OverlayMan::supported_versions(),
                 Chaperone::supported_versions(),
+                ChaperoneSetup::supported_versions(),
                 Applications::supported_versions(),
                 OverlayView::supported_versions(),
                 Screenshots::supported_versions(),
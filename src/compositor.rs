@@ -1,23 +1,44 @@
+mod color_fade;
+mod fade;
+mod grid_fade;
+mod vignette;
+
 use crate::{
     clientcore::{Injected, Injector},
-    graphics_backends::{supported_backends_enum, GraphicsBackend, SupportedBackend},
+    graphics_backends::{
+        supported_backends_enum, ColorAdjustment, GraphicsBackend, SupportedBackend,
+    },
     input::Input,
-    openxr_data::{self, FrameStream, OpenXrData, SessionCreateInfo, SessionData},
+    openxr_data::{
+        self, pose_from_glam, pose_to_glam, FrameStream, OpenXrData, SessionCreateInfo,
+        SessionData, SwapchainPurpose, SystemClock,
+    },
     overlay::OverlayMan,
+    screenshots::{EyeImage, Screenshots},
     system::System,
-    tracy_span, AtomicF64,
+    tracy_span, xr_call, AtomicF64,
 };
 
-use log::{debug, info, trace};
+use color_fade::{ColorFade, ColorQuad, SceneFade};
+use grid_fade::GridFade;
+use log::{debug, info, trace, warn};
 use openvr as vr;
 use openxr as xr;
+use std::cell::OnceCell;
+use std::collections::VecDeque;
 use std::mem::offset_of;
 use std::sync::{
     atomic::{AtomicU32, Ordering},
     Arc, Mutex, Once,
 };
-use std::time::Instant;
 use std::{ffi::c_char, ops::Deref};
+use vignette::{Vignette, VignetteConfig};
+
+/// How much the scene is darkened while the dashboard is visible.
+const DASHBOARD_SCENE_SCALE: f32 = 0.25;
+
+/// How many presented frames are remembered for GetFrameTiming(s).
+const FRAME_HISTORY_LEN: usize = 128;
 
 #[derive(Default)]
 pub struct CompositorSessionData(Mutex<Option<DynFrameController>>);
@@ -33,10 +54,32 @@ pub struct Compositor {
     /// Stores the backend data in between session restarts.
     tmp_backend: Mutex<Option<AnyTempBackendData>>,
     overlays: Injected<OverlayMan>,
+    screenshots: Injected<Screenshots>,
     metrics: FrameMetrics,
     timing_mode: Mutex<vr::EVRCompositorTimingMode>,
     frame_state: Mutex<FrameState>,
     focused: Once,
+    /// Number of frames in a row that the app didn't present.
+    skipped_frames: AtomicU32,
+    /// Number of skipped frames after which a blank frame is presented in WaitGetPoses, so the
+    /// runtime doesn't consider us unresponsive while the app is busy (e.g. loading). 0 disables.
+    blank_frame_threshold: u32,
+    vignette: VignetteConfig,
+    color_adjustment: ColorAdjustment,
+    grid_fade: GridFade,
+    color_fade: ColorFade,
+}
+
+const DEFAULT_BLANK_FRAME_THRESHOLD: u32 = 0;
+
+fn blank_frame_threshold_from_env() -> u32 {
+    let Ok(value) = std::env::var("XRIZER_BLANK_FRAME_THRESHOLD") else {
+        return DEFAULT_BLANK_FRAME_THRESHOLD;
+    };
+    value
+        .parse()
+        .inspect_err(|e| warn!("Invalid XRIZER_BLANK_FRAME_THRESHOLD value {value:?}: {e}"))
+        .unwrap_or(DEFAULT_BLANK_FRAME_THRESHOLD)
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -69,9 +112,106 @@ impl FrameState {
 }
 
 struct FrameMetrics {
-    system_start: Instant,
     index: AtomicU32,
     time: AtomicF64,
+    /// Most recently presented frames, oldest first.
+    history: Mutex<VecDeque<FrameRecord>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct FrameRecord {
+    index: u32,
+    system_time: f64,
+}
+
+impl FrameMetrics {
+    fn current(&self) -> FrameRecord {
+        FrameRecord {
+            index: self.index.load(Ordering::Relaxed),
+            system_time: self.time.load(),
+        }
+    }
+
+    fn record_present(&self, clock: &SystemClock) {
+        let index = self.index.fetch_add(1, Ordering::Relaxed) + 1;
+        let system_time = clock.record_vsync();
+        self.time.store(system_time);
+
+        let mut history = self.history.lock().unwrap();
+        if history.len() == FRAME_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(FrameRecord { index, system_time });
+    }
+
+    /// Returns the record for the frame presented `frames_ago` frames ago, clamped to the oldest
+    /// frame we still know about.
+    fn frame(&self, frames_ago: u32) -> FrameRecord {
+        let history = self.history.lock().unwrap();
+        let Some(last) = history.len().checked_sub(1) else {
+            return self.current();
+        };
+        history[last.saturating_sub(frames_ago as usize)]
+    }
+}
+
+/// Checks that a caller provided Compositor_FrameTiming is large enough for us to fill in.
+///
+/// # Safety
+/// `timing` must be non-null and point to at least the `m_nSize` member.
+unsafe fn frame_timing_size(timing: *const vr::Compositor_FrameTiming) -> Option<usize> {
+    let size = unsafe { (&raw const (*timing).m_nSize).read_unaligned() } as usize;
+    fn ptr_size<T>(_: *const T) -> usize {
+        std::mem::size_of::<T>()
+    }
+    (size
+        >= offset_of!(vr::Compositor_FrameTiming, m_HmdPose)
+            + ptr_size(unsafe { &raw const (*timing).m_HmdPose }))
+    .then_some(size)
+}
+
+/// # Safety
+/// `timing` must point to a writable Compositor_FrameTiming of at least the size checked by
+/// [`frame_timing_size`].
+unsafe fn write_frame_timing(timing: *mut vr::Compositor_FrameTiming, record: FrameRecord) {
+    // We're using raw pointers here because the Compositor_FrameTiming struct can be a
+    // varaible size, so we don't want to create a reference to a struct with an incorrect
+    // (to us) size, because that would be Undefined Behavior.
+    macro_rules! set {
+        ($member:ident, $value:expr) => {{
+            let ptr = &raw mut (*timing).$member;
+            ptr.write_unaligned($value)
+        }};
+    }
+
+    unsafe {
+        // TODO: These values are copy/pasted from OpenComposite, determine if real values are
+        // necessary/better
+        set!(m_nFrameIndex, record.index);
+        set!(m_nNumFramePresents, 1);
+        set!(m_nNumMisPresented, 0);
+        set!(m_nReprojectionFlags, 0);
+        set!(m_flSystemTimeInSeconds, record.system_time);
+        set!(m_flPreSubmitGpuMs, 8.0);
+        set!(m_flPostSubmitGpuMs, 1.0);
+        set!(m_flTotalRenderGpuMs, 9.0);
+
+        set!(m_flCompositorRenderGpuMs, 1.5);
+        set!(m_flCompositorRenderCpuMs, 3.0);
+        set!(m_flCompositorIdleCpuMs, 0.1);
+
+        set!(m_flClientFrameIntervalMs, 11.1);
+        set!(m_flPresentCallCpuMs, 0.0);
+        set!(m_flWaitForPresentCpuMs, 0.0);
+        set!(m_flSubmitFrameMs, 0.0);
+
+        set!(m_flWaitGetPosesCalledMs, 0.0);
+        set!(m_flNewPosesReadyMs, 0.0);
+        set!(m_flNewFrameReadyMs, 0.0); // second call to IVRCompositor::Submit
+        set!(m_flCompositorUpdateStartMs, 0.0);
+        set!(m_flCompositorUpdateEndMs, 0.0);
+        set!(m_flCompositorRenderStartMs, 0.0);
+    }
 }
 
 struct TempBackendData<G: GraphicsBackend> {
@@ -89,15 +229,144 @@ impl Compositor {
             system: injector.inject(),
             tmp_backend: Mutex::default(),
             overlays: injector.inject(),
+            screenshots: injector.inject(),
             metrics: FrameMetrics {
-                system_start: Instant::now(),
                 index: 0.into(),
                 time: 0.0.into(),
+                history: Mutex::new(VecDeque::with_capacity(FRAME_HISTORY_LEN)),
             },
             timing_mode: vr::EVRCompositorTimingMode::Implicit.into(),
             frame_state: FrameState::Submitted.into(),
             focused: Once::new(),
+            skipped_frames: 0.into(),
+            blank_frame_threshold: blank_frame_threshold_from_env(),
+            vignette: VignetteConfig::from_env(),
+            color_adjustment: ColorAdjustment::from_env(),
+            grid_fade: GridFade::default(),
+            color_fade: ColorFade::default(),
+        }
+    }
+
+    fn present_frame(&self, blank: bool) {
+        #[macros::any_graphics(DynFrameController)]
+        fn end_frame<G: GraphicsBackend + 'static>(
+            ctrl: &mut FrameController<G>,
+            session_data: &SessionData,
+            system: &System,
+            display_time: xr::Time,
+            overlays: Option<&OverlayMan>,
+            effects: FrameEffects,
+        ) where
+            for<'b> &'b crate::overlay::AnySwapchainMap:
+                TryInto<&'b crate::overlay::SwapchainMap<G::Api>, Error: std::fmt::Display>,
+            for<'b> &'b openxr_data::GraphicalSession:
+                TryInto<&'b openxr_data::Session<G::Api>, Error: std::fmt::Display>,
+        {
+            ctrl.end_frame(session_data, system, display_time, overlays, effects)
+        }
+
+        if *self.frame_state.lock().unwrap() != FrameState::Begun {
+            return;
+        }
+
+        let vignette = (!blank && self.vignette.enabled() && self.is_user_moving())
+            .then_some(self.vignette.intensity);
+        let can_tint = self
+            .openxr
+            .enabled_extensions
+            .khr_composition_layer_color_scale_bias;
+        let fade = self
+            .color_fade
+            .scene_fade(self.openxr.clock.now(), can_tint);
+        // Without XR_KHR_composition_layer_color_scale_bias, brightness is left to the eye shader.
+        let brightness = if can_tint {
+            1.0 + self.color_adjustment.brightness
+        } else {
+            1.0
+        };
+
+        let session_data = self.openxr.session_data.get();
+        let mut frame_lock = session_data.comp_data.0.lock().unwrap();
+        let Some(ctrl) = frame_lock.as_mut() else {
+            debug!("no frame controller - not presenting frame");
+            return;
+        };
+        trace!("presenting frame (blank: {blank})");
+        let system = self.system.force(|i| System::new(self.openxr.clone(), i));
+        let display_time = self.openxr.display_time.get();
+        let overlays = self.overlays.get();
+
+        ctrl.with_any_graphics_mut::<end_frame>((
+            &session_data,
+            &system,
+            display_time,
+            overlays.as_deref(),
+            FrameEffects {
+                blank,
+                vignette,
+                fade,
+                brightness,
+            },
+        ));
+
+        self.frame_state
+            .lock()
+            .unwrap()
+            .advance_to(FrameState::Submitted);
+        // Blank frames still reach the display, but aren't the app's, so they're left out of its
+        // frame timings.
+        if blank {
+            self.openxr.clock.record_vsync();
+        } else {
+            self.skipped_frames.store(0, Ordering::Relaxed);
+            self.metrics.record_present(&self.openxr.clock);
+        }
+        #[cfg(feature = "tracing")]
+        {
+            tracy_client::frame_mark();
+        }
+    }
+
+    /// Whether the comfort vignette should be shown, because the configured action is engaged or
+    /// the head is moving quickly.
+    fn is_user_moving(&self) -> bool {
+        if let (Some(action), Some(input)) = (&self.vignette.action, self.input.get()) {
+            if input.is_action_engaged(action) {
+                return true;
+            }
         }
+
+        // Velocity doesn't depend on the base space.
+        let session_data = self.openxr.session_data.get();
+        session_data
+            .view_space
+            .relate(
+                session_data.get_space_from_type(xr::ReferenceSpaceType::LOCAL),
+                self.openxr.display_time.get(),
+            )
+            .is_ok_and(|(_, velocity)| vignette::is_fast_head_motion(&velocity))
+    }
+
+    /// Called in WaitGetPoses when the current frame was never presented, which happens when the
+    /// app stops submitting (or handing off) frames. Presents a blank frame once this has gone on
+    /// for long enough, so the session stays focused.
+    fn maybe_present_blank_frame(&self, session_data: &SessionData) {
+        if session_data.comp_data.0.lock().unwrap().is_none() {
+            return;
+        }
+
+        let skipped = self.skipped_frames.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.blank_frame_threshold == 0 || skipped < self.blank_frame_threshold {
+            return;
+        }
+
+        if skipped == self.blank_frame_threshold {
+            debug!("App hasn't presented a frame in {skipped} frames - submitting blank frames");
+        }
+        if *self.frame_state.lock().unwrap() == FrameState::Waited {
+            self.maybe_begin_frame(session_data);
+        }
+        self.present_frame(true);
     }
 
     fn maybe_wait_frame(&self, session_data: &SessionData) {
@@ -113,13 +382,14 @@ impl Compositor {
         };
 
         #[macros::any_graphics(DynFrameController)]
-        fn wait_frame<G: GraphicsBackend + 'static>(ctrl: &mut FrameController<G>) -> xr::Time {
+        fn wait_frame<G: GraphicsBackend + 'static>(
+            ctrl: &mut FrameController<G>,
+        ) -> (xr::Time, xr::Duration) {
             ctrl.wait_frame()
         }
 
-        self.openxr
-            .display_time
-            .set(ctrl.with_any_graphics_mut::<wait_frame>(()));
+        let (display_time, display_period) = ctrl.with_any_graphics_mut::<wait_frame>(());
+        self.openxr.set_display_time(display_time, display_period);
     }
 
     fn maybe_begin_frame(&self, session_data: &SessionData) {
@@ -142,19 +412,26 @@ impl Compositor {
         ctrl.with_any_graphics_mut::<begin_frame>(());
     }
 
-    fn initialize_real_session(&self, texture: &vr::Texture_t, bounds: vr::VRTextureBounds_t) {
+    fn initialize_real_session(
+        &self,
+        texture: &vr::Texture_t,
+        bounds: vr::VRTextureBounds_t,
+    ) -> Result<(), vr::EVRCompositorError> {
         info!("Creating real backend for texture type {:?}", texture.eType);
-        let backend = SupportedBackend::new(texture, bounds);
+        let backend =
+            SupportedBackend::new(texture, bounds).ok_or(vr::EVRCompositorError::InvalidTexture)?;
 
         #[macros::any_graphics(SupportedBackend)]
         fn swapchain_info<G: GraphicsBackend>(
-            backend: G,
+            mut backend: G,
             texture: &vr::Texture_t,
             bounds: vr::VRTextureBounds_t,
+            color_adjustment: ColorAdjustment,
         ) -> AnyTempBackendData
         where
             AnyTempBackendData: From<TempBackendData<G>>,
         {
+            backend.set_color_adjustment(color_adjustment);
             let b_texture = G::get_texture(texture);
             let info = backend.swapchain_info_for_texture(b_texture, bounds, texture.eColorSpace);
             TempBackendData {
@@ -163,10 +440,21 @@ impl Compositor {
             }
             .into()
         }
-        *self.tmp_backend.lock().unwrap() =
-            Some(backend.with_any_graphics_owned::<swapchain_info>((texture, bounds)));
+        // Brightness is applied by scaling the scene layer's colors when the runtime can.
+        let mut color_adjustment = self.color_adjustment;
+        if self
+            .openxr
+            .enabled_extensions
+            .khr_composition_layer_color_scale_bias
+        {
+            color_adjustment.brightness = 0.0;
+        }
+        *self.tmp_backend.lock().unwrap() = Some(
+            backend.with_any_graphics_owned::<swapchain_info>((texture, bounds, color_adjustment)),
+        );
 
         self.openxr.restart_session();
+        Ok(())
     }
 }
 
@@ -349,6 +637,10 @@ impl vr::IVRCompositor028_Interface for Compositor {
         *self.timing_mode.lock().unwrap() = timing_mode;
     }
 
+    // These use the XR_KHR_vulkan_enable queries even if XR_KHR_vulkan_enable2 is available:
+    // enable2 has no way to query extension names, since the runtime adds what it needs itself
+    // in xrCreateVulkanInstanceKHR/xrCreateVulkanDeviceKHR, but OpenVR apps create their
+    // Vulkan instance and device on their own.
     fn GetVulkanDeviceExtensionsRequired(
         &self,
         _physical_device: *mut vr::VkPhysicalDevice_T,
@@ -359,7 +651,10 @@ impl vr::IVRCompositor028_Interface for Compositor {
             .openxr
             .instance
             .vulkan_legacy_device_extensions(self.openxr.system_id)
-            .unwrap();
+            .unwrap_or_else(|e| {
+                warn!("Failed to get required Vulkan device extensions: {e}");
+                String::new()
+            });
         log::debug!("required device extensions: {exts}");
         fill_vk_extensions_buffer(exts, buffer, buffer_size)
     }
@@ -373,23 +668,29 @@ impl vr::IVRCompositor028_Interface for Compositor {
             .openxr
             .instance
             .vulkan_legacy_instance_extensions(self.openxr.system_id)
-            .unwrap();
+            .unwrap_or_else(|e| {
+                warn!("Failed to get required Vulkan instance extensions: {e}");
+                String::new()
+            });
         log::debug!("required instance extensions: {exts}");
         fill_vk_extensions_buffer(exts, buffer, buffer_size)
     }
 
+    // There's no mirror texture to share, since the runtime composites the frame. GL games submit
+    // through the regular Submit path, so these only matter for games that show a mirror view.
     fn UnlockGLSharedTextureForAccess(&self, _glSharedTextureHandle: vr::glSharedTextureHandle_t) {
-        todo!()
+        crate::warn_unimplemented!("UnlockGLSharedTextureForAccess");
     }
     fn LockGLSharedTextureForAccess(&self, _glSharedTextureHandle: vr::glSharedTextureHandle_t) {
-        todo!()
+        crate::warn_unimplemented!("LockGLSharedTextureForAccess");
     }
     fn ReleaseSharedGLTexture(
         &self,
         _glTextureId: vr::glUInt_t,
         _glSharedTextureHandle: vr::glSharedTextureHandle_t,
     ) -> bool {
-        todo!()
+        crate::warn_unimplemented!("ReleaseSharedGLTexture");
+        false
     }
     fn GetMirrorTextureGL(
         &self,
@@ -397,7 +698,8 @@ impl vr::IVRCompositor028_Interface for Compositor {
         _pglTextureId: *mut vr::glUInt_t,
         _pglSharedTextureHandle: *mut vr::glSharedTextureHandle_t,
     ) -> vr::EVRCompositorError {
-        todo!()
+        crate::warn_unimplemented!("GetMirrorTextureGL");
+        vr::EVRCompositorError::SharedTexturesNotSupported
     }
     fn ReleaseMirrorTextureD3D11(&self, _pD3D11ShaderResourceView: *mut std::ffi::c_void) {
         todo!()
@@ -430,7 +732,23 @@ impl vr::IVRCompositor028_Interface for Compositor {
             .for_each(|ctrl| ctrl.with_any_graphics_mut::<set_suspend_render>(bSuspend));
     }
     fn ForceReconnectProcess(&self) {
-        todo!()
+        // Without a frame controller there's no real session (and no backend) to restart with yet.
+        let has_real_session = self
+            .openxr
+            .session_data
+            .get()
+            .comp_data
+            .0
+            .lock()
+            .unwrap()
+            .is_some();
+        if !has_real_session {
+            info!("Reconnect requested before any frames were submitted, ignoring");
+            return;
+        }
+
+        info!("Reconnect requested, restarting session");
+        self.openxr.restart_session();
     }
     fn ForceInterleavedReprojectionOn(&self, _: bool) {
         crate::warn_unimplemented!("ForceInterleavedReprojectionOn");
@@ -510,14 +828,19 @@ impl vr::IVRCompositor028_Interface for Compositor {
         }
 
         let textures = unsafe { std::slice::from_raw_parts(pTextures, unTextureCount as _) };
-        overlays.set_skybox(&self.openxr.session_data.get(), textures);
+        if !overlays.set_skybox(&self.openxr.session_data.get(), textures) {
+            return vr::EVRCompositorError::InvalidTexture;
+        }
 
         vr::EVRCompositorError::None
     }
     fn GetCurrentGridAlpha(&self) -> f32 {
-        0.0
+        self.grid_fade.alpha(self.openxr.clock.now())
     }
-    fn FadeGrid(&self, _fSeconds: f32, bFadeGridIn: bool) {
+    fn FadeGrid(&self, fSeconds: f32, bFadeGridIn: bool) {
+        self.grid_fade
+            .start(self.openxr.clock.now(), fSeconds, bFadeGridIn);
+
         #[macros::any_graphics(DynFrameController)]
         fn set_fade_grid<G: GraphicsBackend + 'static>(
             ctrl: &mut FrameController<G>,
@@ -536,19 +859,33 @@ impl vr::IVRCompositor028_Interface for Compositor {
             .iter_mut()
             .for_each(|ctrl| ctrl.with_any_graphics_mut::<set_fade_grid>(bFadeGridIn));
     }
-    fn GetCurrentFadeColor(&self, _bBackground: bool) -> vr::HmdColor_t {
-        todo!()
+    fn GetCurrentFadeColor(&self, bBackground: bool) -> vr::HmdColor_t {
+        if bBackground {
+            return Default::default();
+        }
+        self.color_fade.color(self.openxr.clock.now())
     }
     fn FadeToColor(
         &self,
-        _fSeconds: f32,
-        _fRed: f32,
-        _fGreen: f32,
-        _fBlue: f32,
-        _fAlpha: f32,
-        _bBackground: bool,
+        fSeconds: f32,
+        fRed: f32,
+        fGreen: f32,
+        fBlue: f32,
+        fAlpha: f32,
+        bBackground: bool,
     ) {
-        crate::warn_unimplemented!("FadeToColor");
+        if bBackground {
+            crate::warn_unimplemented!("FadeToColor (background)");
+            return;
+        }
+        let color = vr::HmdColor_t {
+            r: fRed,
+            g: fGreen,
+            b: fBlue,
+            a: fAlpha,
+        };
+        self.color_fade
+            .start(self.openxr.clock.now(), fSeconds, color);
     }
     fn GetCumulativeStats(
         &self,
@@ -561,115 +898,43 @@ impl vr::IVRCompositor028_Interface for Compositor {
         crate::warn_unimplemented!("GetFrameTimeRemaining");
         0.0
     }
-    fn GetFrameTimings(&self, _pTiming: *mut vr::Compositor_FrameTiming, _nFrames: u32) -> u32 {
-        todo!()
-    }
-    fn GetFrameTiming(&self, timing: *mut vr::Compositor_FrameTiming, _frames_ago: u32) -> bool {
-        if timing.is_null() || !timing.is_aligned() {
-            return false;
+    fn GetFrameTimings(&self, timing: *mut vr::Compositor_FrameTiming, frames: u32) -> u32 {
+        if timing.is_null() || frames == 0 {
+            return 0;
         }
 
-        let size = unsafe { (&raw const (*timing).m_nSize).read() } as usize;
-        fn ptr_size<T>(_: *const T) -> usize {
-            std::mem::size_of::<T>()
+        // Only the first entry's m_nSize is set by the caller, and it determines the stride.
+        let Some(size) = (unsafe { frame_timing_size(timing) }) else {
+            return 0;
+        };
+
+        let history = self.metrics.history.lock().unwrap();
+        let count = history.len().min(frames as usize);
+        // Entries are returned oldest to newest, so the last entry is the most recent frame.
+        for (i, record) in history.iter().skip(history.len() - count).enumerate() {
+            unsafe {
+                let entry = timing.byte_add(i * size);
+                (&raw mut (*entry).m_nSize).write_unaligned(size as u32);
+                write_frame_timing(entry, *record);
+            }
         }
-        if size
-            < offset_of!(vr::Compositor_FrameTiming, m_HmdPose)
-                + ptr_size(unsafe { &raw const (*timing).m_HmdPose })
-        {
+
+        count as u32
+    }
+    fn GetFrameTiming(&self, timing: *mut vr::Compositor_FrameTiming, frames_ago: u32) -> bool {
+        if timing.is_null() || !timing.is_aligned() {
             return false;
         }
-        // We're using raw pointers here because the Compositor_FrameTiming struct can be a
-        // varaible size, so we don't want to create a reference to a struct with an incorrect
-        // (to us) size, because that would be Undefined Behavior.
-        macro_rules! set {
-            ($member:ident, $value:expr) => {{
-                let ptr = &raw mut (*timing).$member;
-                ptr.write_unaligned($value)
-            }};
-        }
 
-        unsafe {
-            // TODO: These values are copy/pasted from OpenComposite, determine if real values are
-            // necessary/better
-            set!(m_nFrameIndex, self.metrics.index.load(Ordering::Relaxed));
-            set!(m_nNumFramePresents, 1);
-            set!(m_nNumMisPresented, 0);
-            set!(m_nReprojectionFlags, 0);
-            set!(m_flSystemTimeInSeconds, self.metrics.time.load());
-            set!(m_flPreSubmitGpuMs, 8.0);
-            set!(m_flPostSubmitGpuMs, 1.0);
-            set!(m_flTotalRenderGpuMs, 9.0);
-
-            set!(m_flCompositorRenderGpuMs, 1.5);
-            set!(m_flCompositorRenderCpuMs, 3.0);
-            set!(m_flCompositorIdleCpuMs, 0.1);
-
-            set!(m_flClientFrameIntervalMs, 11.1);
-            set!(m_flPresentCallCpuMs, 0.0);
-            set!(m_flWaitForPresentCpuMs, 0.0);
-            set!(m_flSubmitFrameMs, 0.0);
-
-            set!(m_flWaitGetPosesCalledMs, 0.0);
-            set!(m_flNewPosesReadyMs, 0.0);
-            set!(m_flNewFrameReadyMs, 0.0); // second call to IVRCompositor::Submit
-            set!(m_flCompositorUpdateStartMs, 0.0);
-            set!(m_flCompositorUpdateEndMs, 0.0);
-            set!(m_flCompositorRenderStartMs, 0.0);
+        if unsafe { frame_timing_size(timing) }.is_none() {
+            return false;
         }
 
+        unsafe { write_frame_timing(timing, self.metrics.frame(frames_ago)) };
         true
     }
     fn PostPresentHandoff(&self) {
-        #[macros::any_graphics(DynFrameController)]
-        fn end_frame<G: GraphicsBackend + 'static>(
-            ctrl: &mut FrameController<G>,
-            session_data: &SessionData,
-            system: &System,
-            display_time: xr::Time,
-            overlays: Option<&OverlayMan>,
-        ) where
-            for<'b> &'b crate::overlay::AnySwapchainMap:
-                TryInto<&'b crate::overlay::SwapchainMap<G::Api>, Error: std::fmt::Display>,
-        {
-            ctrl.end_frame(session_data, system, display_time, overlays)
-        }
-
-        if *self.frame_state.lock().unwrap() != FrameState::Begun {
-            return;
-        }
-
-        let session_data = self.openxr.session_data.get();
-        let mut frame_lock = session_data.comp_data.0.lock().unwrap();
-        let Some(ctrl) = frame_lock.as_mut() else {
-            debug!("no frame controller - not presenting frame");
-            return;
-        };
-        trace!("presenting frame");
-        let system = self.system.force(|i| System::new(self.openxr.clone(), i));
-        let display_time = self.openxr.display_time.get();
-        let overlays = self.overlays.get();
-
-        ctrl.with_any_graphics_mut::<end_frame>((
-            &session_data,
-            &system,
-            display_time,
-            overlays.as_deref(),
-        ));
-
-        self.frame_state
-            .lock()
-            .unwrap()
-            .advance_to(FrameState::Submitted);
-
-        self.metrics.index.fetch_add(1, Ordering::Relaxed);
-        self.metrics
-            .time
-            .store(self.metrics.system_start.elapsed().as_secs_f64());
-        #[cfg(feature = "tracing")]
-        {
-            tracy_client::frame_mark();
-        }
+        self.present_frame(false);
     }
     fn ClearLastSubmittedFrame(&self) {
         crate::warn_unimplemented!("ClearLastSubmittedFrame");
@@ -724,7 +989,9 @@ impl vr::IVRCompositor028_Interface for Compositor {
                 drop(session_lock);
 
                 info!("Received game texture, restarting session with new data");
-                self.initialize_real_session(texture, bounds);
+                if let Err(e) = self.initialize_real_session(texture, bounds) {
+                    return e;
+                }
 
                 session_lock = self.openxr.session_data.get();
                 frame_lock = session_lock.comp_data.0.lock().unwrap();
@@ -740,6 +1007,8 @@ impl vr::IVRCompositor028_Interface for Compositor {
             texture: &vr::Texture_t,
             bounds: vr::VRTextureBounds_t,
             flags: vr::EVRSubmitFlags,
+            screenshots: Option<&Screenshots>,
+            render_pose: Option<xr::Posef>,
         ) -> xr::Result<(), vr::EVRCompositorError>
         where
             for<'d> &'d openxr_data::GraphicalSession:
@@ -754,15 +1023,28 @@ impl vr::IVRCompositor028_Interface for Compositor {
                 texture.eColorSpace,
                 bounds,
                 flags,
+                screenshots,
+                render_pose,
             )
         }
 
+        // Games can pass the head pose they rendered with, which the scene is then shown from
+        // instead of the runtime's view poses.
+        let render_pose = ((submit_flags & vr::EVRSubmitFlags::TextureWithPose).0 > 0).then(|| {
+            let texture =
+                unsafe { &*(texture as *const vr::Texture_t).cast::<vr::VRTextureWithPose_t>() };
+            xr::Posef::from(texture.mDeviceToAbsoluteTracking)
+        });
+
+        let screenshots = self.screenshots.get();
         if let Err(e) = ctrl.with_any_graphics_mut::<submit>((
             &session_lock,
             eye,
             texture,
             bounds,
             submit_flags,
+            screenshots.as_deref(),
+            render_pose,
         )) {
             return e;
         }
@@ -831,6 +1113,10 @@ impl vr::IVRCompositor028_Interface for Compositor {
                 self.PostPresentHandoff();
             }
 
+            if *self.frame_state.lock().unwrap() != FrameState::Submitted {
+                self.maybe_present_blank_frame(&session_data);
+            }
+
             if *self.frame_state.lock().unwrap() == FrameState::Waited {
                 // discard frame
                 self.maybe_begin_frame(&session_data);
@@ -883,16 +1169,43 @@ impl vr::IVRCompositor021On022 for Compositor {
     }
 }
 
+/// Returns `pose` (relative to `parent`) in the space `parent` is in.
+fn transform_pose(parent: xr::Posef, pose: xr::Posef) -> xr::Posef {
+    let (rotation, translation) = pose_to_glam(parent);
+    let (orientation, position) = pose_to_glam(pose);
+    pose_from_glam(rotation * orientation, translation + rotation * position)
+}
+
+/// What's shown on top of, or instead of, the app's eyes in a presented frame.
+#[derive(Copy, Clone)]
+struct FrameEffects {
+    /// Nothing of the app's is shown.
+    blank: bool,
+    /// Intensity of the comfort vignette, if it's shown.
+    vignette: Option<f32>,
+    fade: SceneFade,
+    /// Scale applied to the scene's colors, with XR_KHR_composition_layer_color_scale_bias.
+    brightness: f32,
+}
+
 #[derive(Copy, Clone, Default)]
 struct SubmittedEye {
     extent: xr::Extent2Di,
     flip_vertically: bool,
+    /// The app submitted with Submit_LensDistortionAlreadyApplied. The texture is copied as is
+    /// either way, but OpenXR runtimes always apply their own distortion, so such frames end up
+    /// distorted twice.
+    lens_distortion_applied: bool,
+    /// The head pose the app rendered the eye with, from Submit_TextureWithPose.
+    render_pose: Option<xr::Posef>,
 }
 
 struct SwapchainData<G: xr::Graphics> {
     swapchain: xr::Swapchain<G>,
     info: xr::SwapchainCreateInfo<G>,
     initial_format: G::Format,
+    /// The sample count the app's textures asked for, which may be more than the swapchain has.
+    initial_sample_count: u32,
 }
 
 struct FrameController<G: GraphicsBackend> {
@@ -906,6 +1219,10 @@ struct FrameController<G: GraphicsBackend> {
     app_fade_grid: bool,
     eyes_submitted: [Option<SubmittedEye>; 2],
     submitting_null: bool,
+    /// Created the first time the vignette is shown, None if that failed.
+    vignette: OnceCell<Option<Vignette<G::Api>>>,
+    /// Created the first time a fade is shown with a quad, None if that failed.
+    color_quad: Option<Option<ColorQuad<G::Api>>>,
     backend: G,
 }
 supported_backends_enum!(enum DynFrameController: FrameController);
@@ -913,9 +1230,9 @@ supported_backends_enum!(enum DynFrameController: FrameController);
 impl<G: GraphicsBackend> FrameController<G> {
     fn init_swapchain(
         session_data: &SessionData,
-        create_info: &mut xr::SwapchainCreateInfo<G::Api>,
+        mut create_info: xr::SwapchainCreateInfo<G::Api>,
         backend: &mut G,
-    ) -> (xr::Swapchain<G::Api>, <G::Api as xr::Graphics>::Format)
+    ) -> SwapchainData<G::Api>
     where
         for<'a> &'a openxr_data::GraphicalSession:
             TryInto<&'a openxr_data::Session<G::Api>, Error: std::fmt::Display>,
@@ -929,12 +1246,23 @@ impl<G: GraphicsBackend> FrameController<G> {
         );
 
         let initial_format = create_info.format;
-        session_data.check_format::<G>(create_info);
-
-        let swapchain = session_data
-            .create_swapchain(create_info)
-            .unwrap_or_else(|err| {
-                panic!(
+        let initial_sample_count = create_info.sample_count;
+        session_data.check_format::<G>(&mut create_info, SwapchainPurpose::Scene);
+
+        let swapchain = loop {
+            match session_data.create_swapchain(&create_info) {
+                Ok(swapchain) => break swapchain,
+                // Runtimes may not support as many samples as the app renders with. Eyes are
+                // resolved when they're copied in, so a single sample still works. The array size
+                // can't be reduced, since each eye has its own layer.
+                Err(err) if create_info.sample_count > 1 => {
+                    warn!(
+                        "Failed to create swapchain with {} samples ({err}), retrying with 1",
+                        create_info.sample_count
+                    );
+                    create_info.sample_count = 1;
+                }
+                Err(err) => panic!(
                     "Failed to create swapchain: {err} (info: {:#?})",
                     [
                         ("create_flags", format!("{:?}", create_info.create_flags)),
@@ -942,8 +1270,9 @@ impl<G: GraphicsBackend> FrameController<G> {
                         ("height", create_info.height.to_string()),
                         ("sample_count", create_info.sample_count.to_string())
                     ]
-                )
-            });
+                ),
+            }
+        };
 
         let images = swapchain
             .enumerate_images()
@@ -955,7 +1284,12 @@ impl<G: GraphicsBackend> FrameController<G> {
             create_info.width, create_info.height, create_info.format
         );
 
-        (swapchain, initial_format)
+        SwapchainData {
+            swapchain,
+            info: create_info,
+            initial_format,
+            initial_sample_count,
+        }
     }
 
     fn new(
@@ -970,16 +1304,9 @@ impl<G: GraphicsBackend> FrameController<G> {
             TryInto<&'a openxr_data::Session<G::Api>, Error: std::fmt::Display>,
         <G::Api as xr::Graphics>::Format: PartialEq + std::fmt::Debug,
     {
-        let swapchain_data = if let Some(mut info) = create_info {
-            is_valid_swapchain_info(&info).then(|| {
-                let (swapchain, initial_format) =
-                    Self::init_swapchain(session_data, &mut info, &mut backend);
-                SwapchainData {
-                    swapchain,
-                    info,
-                    initial_format,
-                }
-            })
+        let swapchain_data = if let Some(info) = create_info {
+            is_valid_swapchain_info(&info)
+                .then(|| Self::init_swapchain(session_data, info, &mut backend))
         } else {
             None
         };
@@ -995,6 +1322,8 @@ impl<G: GraphicsBackend> FrameController<G> {
             app_fade_grid: false,
             eyes_submitted: Default::default(),
             submitting_null: false,
+            vignette: OnceCell::new(),
+            color_quad: None,
             backend,
         }
     }
@@ -1002,20 +1331,17 @@ impl<G: GraphicsBackend> FrameController<G> {
     fn recreate_swapchain(
         &mut self,
         session_data: &SessionData,
-        mut create_info: xr::SwapchainCreateInfo<G::Api>,
+        create_info: xr::SwapchainCreateInfo<G::Api>,
     ) where
         for<'a> &'a openxr_data::GraphicalSession:
             TryInto<&'a openxr_data::Session<G::Api>, Error: std::fmt::Display>,
         <G::Api as xr::Graphics>::Format: PartialEq + std::fmt::Debug,
     {
-        let (swapchain, initial_format) =
-            Self::init_swapchain(session_data, &mut create_info, &mut self.backend);
-
-        self.swapchain_data = Some(SwapchainData {
-            swapchain,
-            info: create_info,
-            initial_format,
-        });
+        self.swapchain_data = Some(Self::init_swapchain(
+            session_data,
+            create_info,
+            &mut self.backend,
+        ));
         self.acquire_swapchain_image();
         self.eyes_submitted = Default::default();
     }
@@ -1042,13 +1368,13 @@ impl<G: GraphicsBackend> FrameController<G> {
         self.image_acquired = true;
     }
 
-    fn wait_frame(&mut self) -> xr::Time {
-        let frame_state = {
-            tracy_span!("wait frame");
-            self.waiter.wait().unwrap()
-        };
+    fn wait_frame(&mut self) -> (xr::Time, xr::Duration) {
+        let frame_state = xr_call!("xrWaitFrame", self.waiter.wait()).unwrap();
         self.should_render = frame_state.should_render && !self.app_suspend_render;
-        frame_state.predicted_display_time
+        (
+            frame_state.predicted_display_time,
+            frame_state.predicted_display_period,
+        )
     }
 
     fn begin_frame(&mut self) {
@@ -1066,10 +1392,7 @@ impl<G: GraphicsBackend> FrameController<G> {
             self.acquire_swapchain_image();
         }
 
-        {
-            tracy_span!("begin frame");
-            self.stream.begin().expect("Couldn't begin frame");
-        }
+        xr_call!("xrBeginFrame", self.stream.begin()).expect("Couldn't begin frame");
         self.eyes_submitted = [None; 2];
         self.submitting_null = false;
         trace!("frame begin");
@@ -1083,6 +1406,8 @@ impl<G: GraphicsBackend> FrameController<G> {
         color_space: vr::EColorSpace,
         bounds: vr::VRTextureBounds_t,
         submit_flags: vr::EVRSubmitFlags,
+        screenshots: Option<&Screenshots>,
+        render_pose: Option<xr::Posef>,
     ) -> Result<(), vr::EVRCompositorError>
     where
         <G::Api as xr::Graphics>::Format: Eq,
@@ -1097,9 +1422,15 @@ impl<G: GraphicsBackend> FrameController<G> {
 
         self.eyes_submitted[eye as usize] = if self.should_render {
             // Make sure our image dimensions haven't changed.
-            let new_info = self
-                .backend
-                .swapchain_info_for_texture(texture, bounds, color_space);
+            let mut new_info =
+                self.backend
+                    .swapchain_info_for_texture(texture, bounds, color_space);
+            // Screenshots are read back from the swapchain, which it has to be created for. It's
+            // only recreated for that once the app asks for a screenshot.
+            let screenshots = screenshots.filter(|s| s.wants_eye(eye));
+            if screenshots.is_some() {
+                new_info.usage_flags |= xr::SwapchainUsageFlags::TRANSFER_SRC;
+            }
 
             is_valid_swapchain_info(&new_info)
                 .then(|| {
@@ -1109,22 +1440,58 @@ impl<G: GraphicsBackend> FrameController<G> {
                     );
 
                     if !self.swapchain_data.as_ref().is_some_and(|data| {
-                        is_usable_swapchain(&data.info, data.initial_format, &new_info)
+                        let info = xr::SwapchainCreateInfo {
+                            sample_count: data.initial_sample_count,
+                            ..data.info
+                        };
+                        is_usable_swapchain(&info, data.initial_format, &new_info)
                     }) {
                         info!("recreating swapchain (for {eye:?})");
                         self.recreate_swapchain(session_data, new_info);
                     }
 
+                    let extent = self.backend.copy_texture_to_swapchain(
+                        eye,
+                        texture,
+                        color_space,
+                        bounds,
+                        self.image_index,
+                        submit_flags,
+                    );
+
+                    // The image has to be read back before it's released to the runtime.
+                    if let Some(screenshots) = screenshots {
+                        if let Some(mut pixels) =
+                            self.backend.read_back_eye(eye, self.image_index, extent)
+                        {
+                            if bounds.vertically_flipped() {
+                                let stride = extent.width as usize * 4;
+                                pixels = pixels
+                                    .chunks_exact(stride)
+                                    .rev()
+                                    .flatten()
+                                    .copied()
+                                    .collect();
+                            }
+                            screenshots.capture_eye(
+                                eye,
+                                EyeImage {
+                                    width: extent.width as u32,
+                                    height: extent.height as u32,
+                                    pixels,
+                                },
+                            );
+                        }
+                    }
+
                     SubmittedEye {
-                        extent: self.backend.copy_texture_to_swapchain(
-                            eye,
-                            texture,
-                            color_space,
-                            bounds,
-                            self.image_index,
-                            submit_flags,
-                        ),
+                        extent,
                         flip_vertically: bounds.vertically_flipped(),
+                        lens_distortion_applied: (submit_flags
+                            & vr::EVRSubmitFlags::LensDistortionAlreadyApplied)
+                            .0
+                            > 0,
+                        render_pose,
                     }
                 })
                 .or_else(|| {
@@ -1155,13 +1522,23 @@ impl<G: GraphicsBackend> FrameController<G> {
         system: &System,
         display_time: xr::Time,
         overlays: Option<&OverlayMan>,
+        effects: FrameEffects,
     ) where
         for<'b> &'b crate::overlay::AnySwapchainMap:
             TryInto<&'b crate::overlay::SwapchainMap<G::Api>, Error: std::fmt::Display>,
+        for<'b> &'b openxr_data::GraphicalSession:
+            TryInto<&'b openxr_data::Session<G::Api>, Error: std::fmt::Display>,
     {
+        let FrameEffects {
+            blank,
+            vignette,
+            fade,
+            brightness,
+        } = effects;
         let mut proj_layer_views = Vec::new();
 
-        if self.should_render
+        if !blank
+            && self.should_render
             && !self.submitting_null
             && self.eyes_submitted.iter().all(|eye| eye.is_some())
         {
@@ -1170,30 +1547,53 @@ impl<G: GraphicsBackend> FrameController<G> {
                 .as_ref()
                 .expect("Swapchain data unexpectedly invalid on submit");
 
+            let valid_pose = |flags: xr::ViewStateFlags, pose: xr::Posef| xr::Posef {
+                orientation: if flags.contains(xr::ViewStateFlags::ORIENTATION_VALID) {
+                    pose.orientation
+                } else {
+                    xr::Quaternionf::IDENTITY
+                },
+                position: if flags.contains(xr::ViewStateFlags::POSITION_VALID) {
+                    pose.position
+                } else {
+                    xr::Vector3f::default()
+                },
+            };
+            // Eyes the app rendered from its own head pose are placed relative to that pose.
+            let eye_to_head = self
+                .eyes_submitted
+                .iter()
+                .any(|eye| eye.is_some_and(|eye| eye.render_pose.is_some()))
+                .then(|| {
+                    let crate::system::ViewData { flags, views } =
+                        system.get_views(xr::ReferenceSpaceType::VIEW);
+                    views.map(|view| valid_pose(flags, view.pose))
+                });
+
             let crate::system::ViewData { flags, views } =
                 system.get_views(session_data.current_origin_as_reference_space());
             proj_layer_views = views
                 .into_iter()
                 .enumerate()
                 .map(|(eye_index, view)| {
-                    let pose = xr::Posef {
-                        orientation: if flags.contains(xr::ViewStateFlags::ORIENTATION_VALID) {
-                            view.pose.orientation
-                        } else {
-                            xr::Quaternionf::IDENTITY
-                        },
-                        position: if flags.contains(xr::ViewStateFlags::POSITION_VALID) {
-                            view.pose.position
-                        } else {
-                            xr::Vector3f::default()
-                        },
-                    };
-
                     let SubmittedEye {
                         extent,
                         flip_vertically,
+                        lens_distortion_applied,
+                        render_pose,
                     } = self.eyes_submitted[eye_index]
                         .unwrap_or_else(|| panic!("Eye {eye_index} has not been submitted!"));
+                    let pose = match (render_pose, eye_to_head) {
+                        (Some(head), Some(eye_to_head)) => {
+                            transform_pose(head, eye_to_head[eye_index])
+                        }
+                        _ => valid_pose(flags, view.pose),
+                    };
+                    if lens_distortion_applied {
+                        crate::warn_once!(
+                            "App submitted frames with lens distortion already applied, but the runtime will distort them again"
+                        );
+                    }
                     let mut fov = view.fov;
                     if flip_vertically {
                         std::mem::swap(&mut fov.angle_up, &mut fov.angle_down);
@@ -1215,34 +1615,120 @@ impl<G: GraphicsBackend> FrameController<G> {
                 .collect()
         }
 
+        // While the dashboard is up, the scene is darkened behind it (like SteamVR), or hidden
+        // entirely if the runtime can't darken it. Fades to a color and brightness are applied to
+        // it the same way, and opaque fades without that hide it behind the color quad.
+        let dashboard_visible = overlays.is_some_and(OverlayMan::is_dashboard_visible);
+        let scene_scale = match (
+            dashboard_visible,
+            overlays.is_some_and(OverlayMan::can_dim_scene),
+        ) {
+            (false, _) => Some(brightness),
+            (true, true) => Some(DASHBOARD_SCENE_SCALE * brightness),
+            (true, false) => None,
+        };
+        let scene_tint = match (scene_scale, fade) {
+            (None, _) => None,
+            (_, SceneFade::Quad(color)) if color.a >= 1.0 => None,
+            (Some(scale), SceneFade::Tint(color)) => {
+                Some(Some(color_fade::scale_bias(scale, color)))
+            }
+            (Some(scale), SceneFade::None | SceneFade::Quad(_)) => {
+                Some((scale != 1.0).then(|| color_fade::scale_bias(scale, Default::default())))
+            }
+        };
+
+        // Overlays are submitted even when the scene isn't rendered, so they stay visible while
+        // the runtime or the app has rendering suspended.
+        let overlay_layers =
+            overlays.map(|overlay_man| overlay_man.get_layers(session_data, self.app_fade_grid));
+
         let mut proj_layer = None;
-        if !proj_layer_views.is_empty() {
+        if overlay_layers
+            .as_ref()
+            .is_some_and(|layers| !layers.skybox.is_empty())
+        {
+            // The skybox surrounds the user and stands in for the scene while the app has faded
+            // to the grid.
+            trace!("skybox visible - hiding projection layer");
+        } else if !proj_layer_views.is_empty() {
             trace!("projection layer present");
-            proj_layer = Some(
-                xr::CompositionLayerProjection::new()
-                    .space(session_data.tracking_space())
-                    .views(&proj_layer_views),
-            );
+            let layer = xr::CompositionLayerProjection::new()
+                .space(session_data.tracking_space())
+                .views(&proj_layer_views);
+
+            match &scene_tint {
+                Some(None) => proj_layer = Some(layer),
+                Some(Some(tint)) => {
+                    trace!("dimming or fading projection layer");
+                    let mut raw = layer.into_raw();
+                    raw.next = tint as *const _ as _;
+                    // SAFETY: scene_tint outlives the layer.
+                    proj_layer = Some(unsafe { xr::CompositionLayerProjection::from_raw(raw) });
+                }
+                None => trace!("dashboard visible or faded out - hiding projection layer"),
+            }
         }
 
-        let mut layers: Vec<&xr::CompositionLayerBase<_>> = Vec::new();
-        if let Some(l) = proj_layer.as_ref() {
-            layers.push(l);
-        }
-        let overlay_layers;
-        if let Some(overlay_man) = overlays {
-            overlay_layers = overlay_man.get_layers(session_data, self.app_fade_grid);
-            layers.extend(overlay_layers.iter().map(Deref::deref));
+        let mut vignette_layer = None;
+        if let (Some(intensity), Some(_)) = (vignette, &proj_layer) {
+            let format = self.swapchain_data.as_ref().unwrap().info.format;
+            let vignette = self
+                .vignette
+                .get_or_init(|| Vignette::new(session_data, &self.backend, format, intensity));
+            vignette_layer = vignette.as_ref().map(|v| v.layer(session_data));
         }
 
-        self.stream
-            .end(display_time, xr::EnvironmentBlendMode::OPAQUE, &layers)
-            .unwrap();
+        let mut color_quad_layer = None;
+        if let (SceneFade::Quad(color), Some(swapchain_data)) = (fade, &self.swapchain_data) {
+            let format = swapchain_data.info.format;
+            color_quad_layer = self
+                .color_quad
+                .get_or_insert_with(|| ColorQuad::new(session_data, format))
+                .as_mut()
+                .and_then(|quad| quad.layer(session_data, &self.backend, color));
+        }
+
+        // Layers are composited back to front:
+        // 1. the skybox, shown in place of the scene while the app has faded to the grid
+        // 2. the scene
+        // 3. overlays - behind the dashboard, the dashboard, then in front of it
+        // 4. the comfort vignette
+        // 5. the fade color, when the scene can't be tinted with it
+        let mut layers: Vec<&xr::CompositionLayerBase<_>> = Vec::new();
+        if let Some(overlay_layers) = &overlay_layers {
+            layers.extend(overlay_layers.skybox.iter().map(Deref::deref));
+        }
+        if let Some(l) = proj_layer.as_ref() {
+            layers.push(l);
+        }
+        if let Some(overlay_layers) = &overlay_layers {
+            layers.extend(overlay_layers.overlays.iter().map(Deref::deref));
+        }
+        if let Some(l) = vignette_layer.as_ref() {
+            trace!("vignette layer present");
+            layers.push(l);
+        }
+        if let Some(l) = color_quad_layer.as_ref() {
+            trace!("color quad layer present");
+            layers.push(l);
+        }
+
+        xr_call!(
+            "xrEndFrame",
+            self.stream
+                .end(display_time, xr::EnvironmentBlendMode::OPAQUE, &layers)
+        )
+        .unwrap();
 
         trace!("frame submitted");
     }
 }
 
+/// Whether a swapchain created from `current` can take textures described by `new`.
+/// `creation_format` is the format the app's textures had when the swapchain was created, before
+/// it was swapped for one the runtime supports. The swapchain's effective format is derived from
+/// it, so textures switching to another format (e.g. RGBA8 to RGBA16F) always recreate it.
 pub fn is_usable_swapchain<G: xr::Graphics>(
     current: &xr::SwapchainCreateInfo<G>,
     creation_format: G::Format,
@@ -1256,6 +1742,8 @@ where
         && current.height >= new.height
         && current.array_size == new.array_size
         && current.sample_count == new.sample_count
+        && current.create_flags == new.create_flags
+        && current.usage_flags.contains(new.usage_flags)
 }
 
 fn is_valid_swapchain_info<G: xr::Graphics>(info: &xr::SwapchainCreateInfo<G>) -> bool {
@@ -1269,9 +1757,10 @@ pub use tests::FakeGraphicsData;
 mod tests {
     use super::*;
     use crate::graphics_backends::{GraphicsBackend, VulkanData};
+    use glam::{Quat, Vec3};
     use openxr::sys::pfn::DestroySpatialGraphNodeBindingMSFT;
     use std::cell::Cell;
-    use std::ffi::CStr;
+    use std::ffi::{CStr, CString};
     use std::mem::MaybeUninit;
     use std::thread_local;
     use vr::EVRCompositorError::*;
@@ -1280,11 +1769,17 @@ mod tests {
     pub struct FakeGraphicsData {
         vk: Arc<VulkanData>,
         swapchain_format: Option<u32>,
+        color_adjustment: ColorAdjustment,
+        /// The adjustment last applied to an eye, as the eye shader would receive it.
+        applied_color_adjustment: Mutex<Option<ColorAdjustment>>,
+        /// The pixels last written to one of xrizer's own swapchains.
+        written_pixels: Mutex<Vec<u8>>,
     }
     thread_local! {
         static SWAPCHAIN_WIDTH: Cell<u32> = const { Cell::new(10) };
         static SWAPCHAIN_HEIGHT: Cell<u32> = const { Cell::new(10) };
         static SWAPCHAIN_FORMAT: Cell<u32> = const { Cell::new(0) };
+        static SWAPCHAIN_SAMPLE_COUNT: Cell<u32> = const { Cell::new(1) };
     }
 
     pub enum FakeApi {}
@@ -1350,7 +1845,7 @@ mod tests {
                 create_flags: xr::SwapchainCreateFlags::EMPTY,
                 usage_flags: xr::SwapchainUsageFlags::EMPTY,
                 format: SWAPCHAIN_FORMAT.get(),
-                sample_count: 1,
+                sample_count: SWAPCHAIN_SAMPLE_COUNT.get(),
                 width: SWAPCHAIN_WIDTH.get(),
                 height: SWAPCHAIN_HEIGHT.get(),
                 face_count: 1,
@@ -1367,6 +1862,10 @@ mod tests {
             self.swapchain_format.replace(format);
         }
 
+        fn set_color_adjustment(&mut self, adjustment: ColorAdjustment) {
+            self.color_adjustment = adjustment;
+        }
+
         fn copy_texture_to_swapchain(
             &self,
             _eye: openvr::EVREye,
@@ -1376,7 +1875,13 @@ mod tests {
             _image_index: usize,
             _submit_flags: openvr::EVRSubmitFlags,
         ) -> openxr::Extent2Di {
-            xr::Extent2Di::default()
+            if !self.color_adjustment.is_identity() {
+                *self.applied_color_adjustment.lock().unwrap() = Some(self.color_adjustment);
+            }
+            xr::Extent2Di {
+                width: SWAPCHAIN_WIDTH.get() as i32,
+                height: SWAPCHAIN_HEIGHT.get() as i32,
+            }
         }
 
         fn copy_overlay_to_swapchain(
@@ -1385,7 +1890,31 @@ mod tests {
             _bounds: openvr::VRTextureBounds_t,
             _image_index: usize,
         ) -> openxr::Extent2Di {
-            xr::Extent2Di::default()
+            xr::Extent2Di {
+                width: SWAPCHAIN_WIDTH.get() as i32,
+                height: SWAPCHAIN_HEIGHT.get() as i32,
+            }
+        }
+
+        fn read_back_eye(
+            &self,
+            eye: openvr::EVREye,
+            _image_index: usize,
+            extent: openxr::Extent2Di,
+        ) -> Option<Vec<u8>> {
+            let pixel = [eye as u8, 0, 0, 255];
+            Some(pixel.repeat((extent.width * extent.height) as usize))
+        }
+
+        fn write_swapchain_image(
+            &self,
+            _image: <Self::Api as openxr::Graphics>::SwapchainImage,
+            _format: <Self::Api as openxr::Graphics>::Format,
+            _extent: openxr::Extent2Di,
+            pixels: &[u8],
+        ) -> bool {
+            *self.written_pixels.lock().unwrap() = pixels.to_vec();
+            true
         }
     }
 
@@ -1408,6 +1937,9 @@ mod tests {
             Self {
                 vk,
                 swapchain_format: Option::None,
+                color_adjustment: Default::default(),
+                applied_color_adjustment: Default::default(),
+                written_pixels: Default::default(),
             }
         }
     }
@@ -1419,9 +1951,15 @@ mod tests {
 
     impl Fixture {
         fn new() -> Self {
-            let xr = Arc::new(OpenXrData::new(&Injector::default()).unwrap());
+            Self::new_with(|_| {})
+        }
+
+        fn new_with(setup: impl FnOnce(&mut Compositor)) -> Self {
+            let xr = OpenXrData::new_fake();
             let vk = Arc::new(VulkanData::new_temporary(&xr.instance, xr.system_id));
-            let comp = Arc::new(Compositor::new(xr.clone(), &Injector::default()));
+            let mut comp = Compositor::new(xr.clone(), &Injector::default());
+            setup(&mut comp);
+            let comp = Arc::new(comp);
             xr.compositor.set(Arc::downgrade(&comp));
             crate::init_logging();
 
@@ -1456,12 +1994,45 @@ mod tests {
                 assert_eq!(self.wait_get_poses(), None);
             }
 
+            self.fake_frame_controller(|ctrl| assert!(!ctrl.should_render));
+        }
+
+        /// Synchronizes the session and submits the first frame after that, which isn't rendered,
+        /// so that the following frames are.
+        fn start_rendering(&self) {
+            self.ensure_real_session(false);
+            self.render_frame();
+        }
+
+        #[track_caller]
+        fn fake_frame_controller<R>(
+            &self,
+            f: impl FnOnce(&FrameController<FakeGraphicsData>) -> R,
+        ) -> R {
             let data = self.comp.openxr.session_data.get();
             let lock = data.comp_data.0.lock().unwrap();
             let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
                 panic!("Frame controller was not set up or not faked!");
             };
-            assert!(!ctrl.should_render);
+            f(ctrl)
+        }
+
+        /// Submits both eyes and starts the next frame, returning the layers the submitted frame
+        /// was ended with.
+        fn render_frame(&self) -> Vec<fakexr::SubmittedLayer> {
+            assert_eq!(self.submit(vr::EVREye::Left), None);
+            assert_eq!(self.submit(vr::EVREye::Right), None);
+            assert_eq!(self.wait_get_poses(), None);
+            let session = self.comp.openxr.session_data.get().session.as_raw();
+            fakexr::last_frame_layers(session)
+        }
+
+        /// Hooks an overlay manager up to the compositor. Overlays are only drawn while the
+        /// returned manager is alive.
+        fn with_overlays(&self) -> Arc<OverlayMan> {
+            let overlays = Arc::new(OverlayMan::new(self.comp.openxr.clone()));
+            self.comp.overlays.set(Arc::downgrade(&overlays));
+            overlays
         }
 
         #[track_caller]
@@ -1520,6 +2091,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn gl_mirror_texture_not_supported() {
+        let f = Fixture::new();
+        let mut texture = 0;
+        let mut handle = std::ptr::null_mut();
+        assert_eq!(
+            f.comp
+                .GetMirrorTextureGL(vr::EVREye::Left, &mut texture, &mut handle),
+            SharedTexturesNotSupported
+        );
+        assert!(!f.comp.ReleaseSharedGLTexture(texture, handle));
+    }
+
+    #[test]
+    fn unsupported_texture_type() {
+        let f = Fixture::new();
+        assert_eq!(f.wait_get_poses(), None);
+
+        let texture = vr::Texture_t {
+            eType: vr::ETextureType::DirectX,
+            handle: std::ptr::null_mut(),
+            eColorSpace: vr::EColorSpace::Auto,
+        };
+        assert_eq!(
+            f.comp.Submit(
+                vr::EVREye::Left,
+                &texture,
+                std::ptr::null(),
+                vr::EVRSubmitFlags::Default,
+            ),
+            InvalidTexture
+        );
+
+        // Supported textures can still be submitted afterwards.
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+
+        let overlays = f.with_overlays();
+        let mut handle = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"d3d".as_ptr(), c"D3D".as_ptr(), &mut handle),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayTexture(handle, &texture),
+            vr::EVROverlayError::InvalidTexture
+        );
+        assert_eq!(
+            f.comp.SetSkyboxOverride([texture; 6].as_ptr(), 6),
+            InvalidTexture
+        );
+    }
+
     #[test]
     fn allow_flipped_bounds() {
         let Fixture { comp, .. } = Fixture::new();
@@ -1571,16 +2194,13 @@ mod tests {
         f.ensure_real_session(false);
 
         let get_swapchain_width = || {
-            let data = f.comp.openxr.session_data.get();
-            let lock = data.comp_data.0.lock().unwrap();
-            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
-                panic!("Frame controller was not set up or not faked!");
-            };
-            ctrl.swapchain_data
-                .as_ref()
-                .expect("swapchain info missing")
-                .info
-                .width
+            f.fake_frame_controller(|ctrl| {
+                ctrl.swapchain_data
+                    .as_ref()
+                    .expect("swapchain info missing")
+                    .info
+                    .width
+            })
         };
 
         let old_width = get_swapchain_width();
@@ -1623,6 +2243,81 @@ mod tests {
         assert!(!f.comp.GetFrameTiming(timing.as_mut_ptr(), 1));
     }
 
+    #[test]
+    fn get_frame_timings() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+        for _ in 0..4 {
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+            assert_eq!(f.wait_get_poses(), None);
+        }
+
+        let size = std::mem::size_of::<vr::Compositor_FrameTiming>();
+        let mut timings = [vr::Compositor_FrameTiming::default(); 3];
+        timings[0].m_nSize = size as u32;
+        assert_eq!(f.comp.GetFrameTimings(timings.as_mut_ptr(), 3), 3);
+
+        let current = f.comp.metrics.index.load(Ordering::Relaxed);
+        let indices: Vec<u32> = timings.iter().map(|t| t.m_nFrameIndex).collect();
+        assert_eq!(indices, [current - 2, current - 1, current]);
+        assert!(timings.iter().all(|t| t.m_nSize == size as u32));
+        assert!(timings
+            .windows(2)
+            .all(|w| w[0].m_flSystemTimeInSeconds <= w[1].m_flSystemTimeInSeconds));
+
+        let mut timing = vr::Compositor_FrameTiming {
+            m_nSize: size as u32,
+            ..Default::default()
+        };
+        assert!(f.comp.GetFrameTiming(&mut timing, 1));
+        assert_eq!(timing.m_nFrameIndex, current - 1);
+
+        // Asking for more frames than have been presented only fills in what we have.
+        let mut timings = vec![vr::Compositor_FrameTiming::default(); 200];
+        timings[0].m_nSize = size as u32;
+        assert_eq!(
+            f.comp.GetFrameTimings(timings.as_mut_ptr(), 200),
+            current.min(FRAME_HISTORY_LEN as u32)
+        );
+    }
+
+    #[test]
+    fn frame_timing_system_time() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+
+        let clock = &f.comp.openxr.clock;
+        let mut last_time = 0.0;
+        for _ in 0..4 {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+            assert_eq!(f.wait_get_poses(), None);
+
+            let mut timing = vr::Compositor_FrameTiming {
+                m_nSize: std::mem::size_of::<vr::Compositor_FrameTiming>() as u32,
+                ..Default::default()
+            };
+            assert!(f.comp.GetFrameTiming(&mut timing, 0));
+            let time = timing.m_flSystemTimeInSeconds;
+            assert!(
+                time > last_time,
+                "{time} should be greater than {last_time}"
+            );
+            let now = clock.now();
+            assert!(
+                time <= now && now - time < 0.1,
+                "frame time {time} doesn't match clock time {now}"
+            );
+            last_time = time;
+
+            let (since_vsync, vsyncs) = clock.since_last_vsync();
+            assert!(since_vsync >= 0.0 && since_vsync < 0.1, "{since_vsync}");
+            assert_eq!(vsyncs, f.comp.metrics.index.load(Ordering::Relaxed) as u64);
+        }
+    }
+
     #[test]
     fn zero_dims_texture() {
         let f = Fixture::new();
@@ -1634,42 +2329,96 @@ mod tests {
         assert_eq!(f.submit(vr::EVREye::Right), None);
 
         assert_eq!(f.wait_get_poses(), None);
-        {
-            let data = f.comp.openxr.session_data.get();
-            let lock = data.comp_data.0.lock().unwrap();
-            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
-                panic!("Frame controller was not set up or not faked!");
-            };
+        f.fake_frame_controller(|ctrl| {
             assert!(ctrl.swapchain_data.is_none());
             assert!(!ctrl.should_render);
-        }
+        });
         SWAPCHAIN_WIDTH.set(10);
         assert_eq!(f.submit(vr::EVREye::Left), None);
         assert_eq!(f.submit(vr::EVREye::Right), None);
 
         assert_eq!(f.wait_get_poses(), None);
-        {
-            let data = f.comp.openxr.session_data.get();
-            let lock = data.comp_data.0.lock().unwrap();
-            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
-                panic!("Frame controller was not set up or not faked!");
-            };
+        f.fake_frame_controller(|ctrl| {
             assert!(ctrl.swapchain_data.is_none());
             assert!(ctrl.should_render);
-        }
+        });
         SWAPCHAIN_HEIGHT.set(10);
         assert_eq!(f.submit(vr::EVREye::Left), None);
         assert_eq!(f.submit(vr::EVREye::Right), None);
 
         assert_eq!(f.wait_get_poses(), None);
-        {
-            let data = f.comp.openxr.session_data.get();
-            let lock = data.comp_data.0.lock().unwrap();
-            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
-                panic!("Frame controller was not set up or not faked!");
-            };
+        f.fake_frame_controller(|ctrl| {
             assert!(ctrl.swapchain_data.is_some());
             assert!(ctrl.should_render);
+        });
+    }
+
+    #[test]
+    fn lens_distortion_already_applied_flag() {
+        let f = Fixture::new();
+        f.start_rendering();
+
+        assert_eq!(
+            f.comp.Submit(
+                vr::EVREye::Left,
+                &FakeGraphicsData::texture(&f.vk),
+                std::ptr::null(),
+                vr::EVRSubmitFlags::LensDistortionAlreadyApplied,
+            ),
+            None
+        );
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+
+        f.fake_frame_controller(|ctrl| {
+            assert!(ctrl.should_render);
+            let [left, right] = ctrl.eyes_submitted.map(Option::unwrap);
+            assert!(left.lens_distortion_applied);
+            assert!(!right.lens_distortion_applied);
+        });
+    }
+
+    #[test]
+    fn submit_with_render_pose() {
+        let f = Fixture::new();
+        f.start_rendering();
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        let view_poses = || {
+            fakexr::last_frame_layers(session)
+                .into_iter()
+                .find_map(|layer| layer.view_poses)
+                .expect("No projection layer was submitted")
+        };
+
+        f.render_frame();
+        for pose in view_poses() {
+            let p = pose.position;
+            assert_eq!([p.x, p.y, p.z], [0.0; 3]);
+        }
+
+        let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        let head = pose_from_glam(rotation, position);
+        let texture = vr::VRTextureWithPose_t {
+            _base: FakeGraphicsData::texture(&f.vk),
+            mDeviceToAbsoluteTracking: head.into(),
+        };
+        for eye in [vr::EVREye::Left, vr::EVREye::Right] {
+            assert_eq!(
+                f.comp.Submit(
+                    eye,
+                    (&raw const texture).cast(),
+                    std::ptr::null(),
+                    vr::EVRSubmitFlags::TextureWithPose,
+                ),
+                None
+            );
+        }
+        assert_eq!(f.wait_get_poses(), None);
+
+        for pose in view_poses() {
+            let (orientation, translation) = pose_to_glam(pose);
+            assert!(translation.abs_diff_eq(position, 1e-5));
+            assert!(orientation.angle_between(rotation) < 1e-3);
         }
     }
 
@@ -1718,17 +2467,120 @@ mod tests {
         assert_eq!(f.wait_get_poses(), None);
         assert_eq!(f.submit(vr::EVREye::Left), None);
         assert_eq!(f.submit(vr::EVREye::Right), None);
+        f.fake_frame_controller(|ctrl| {
+            let data = ctrl
+                .swapchain_data
+                .as_ref()
+                .expect("Swapchain data is missing");
+            assert_eq!(data.initial_format, 1);
+            assert_eq!(data.info.format, 0);
+        });
+    }
+
+    #[test]
+    fn reduced_sample_count() {
+        let f = Fixture::new();
+        SWAPCHAIN_SAMPLE_COUNT.set(8);
+        f.ensure_real_session(false);
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        fakexr::set_max_swapchain_sample_count(session, 2);
+
+        for _ in 0..2 {
+            assert_eq!(f.wait_get_poses(), None);
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+        }
+
+        f.fake_frame_controller(|ctrl| {
+            let data = ctrl
+                .swapchain_data
+                .as_ref()
+                .expect("Swapchain data is missing");
+            assert_eq!(data.info.sample_count, 1);
+            assert_eq!(data.info.array_size, 2);
+            assert_eq!(data.initial_sample_count, 8);
+        });
+        // The reduced swapchain is kept for later frames.
+        assert_eq!(fakexr::swapchain_create_flags(session).len(), 1);
+    }
+
+    #[test]
+    fn format_change_recreates_swapchain() {
+        let f = Fixture::new();
+        SWAPCHAIN_FORMAT.set(0);
+        f.ensure_real_session(false);
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+
+        let frame = || {
+            assert_eq!(f.wait_get_poses(), None);
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+        };
+        let formats = || {
+            f.fake_frame_controller(|ctrl| {
+                let data = ctrl
+                    .swapchain_data
+                    .as_ref()
+                    .expect("Swapchain data is missing");
+                (data.initial_format, data.info.format)
+            })
+        };
+
+        frame();
+        frame();
+        assert_eq!(formats(), (0, 0));
+        assert_eq!(fakexr::swapchain_create_flags(session).len(), 1);
+
+        // The game switches to another format mid-session.
+        SWAPCHAIN_FORMAT.set(2);
+        frame();
+        assert_eq!(formats(), (2, 2));
+        assert_eq!(fakexr::swapchain_create_flags(session).len(), 2);
+
+        // And to one the runtime doesn't support, which still needs a new swapchain, in the
+        // closest supported format.
+        SWAPCHAIN_FORMAT.set(1);
+        frame();
+        assert_eq!(formats(), (1, 2));
+        assert_eq!(fakexr::swapchain_create_flags(session).len(), 3);
+
+        // Staying on that format keeps the swapchain.
+        frame();
+        assert_eq!(fakexr::swapchain_create_flags(session).len(), 3);
+    }
+
+    #[test]
+    fn per_purpose_swapchain_formats() {
+        let f = Fixture::new();
+        SWAPCHAIN_FORMAT.set(2);
+        f.ensure_real_session(false);
+
         let data = f.comp.openxr.session_data.get();
-        let lock = data.comp_data.0.lock().unwrap();
-        let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
-            panic!("Frame controller was not set up or not faked!");
+        assert_eq!(data.negotiated_format(SwapchainPurpose::Scene), Some(2));
+
+        let check = |format, purpose| {
+            let mut info = xr::SwapchainCreateInfo::<FakeApi> {
+                create_flags: xr::SwapchainCreateFlags::EMPTY,
+                usage_flags: xr::SwapchainUsageFlags::EMPTY,
+                format,
+                sample_count: 1,
+                width: 10,
+                height: 10,
+                face_count: 1,
+                array_size: 1,
+                mip_count: 1,
+            };
+            data.check_format::<FakeGraphicsData>(&mut info, purpose);
+            info.format
         };
-        let data = ctrl
-            .swapchain_data
-            .as_ref()
-            .expect("Swapchain data is missing");
-        assert_eq!(data.initial_format, 1);
-        assert_eq!(data.info.format, 0);
+
+        assert_eq!(check(0, SwapchainPurpose::Overlay), 0);
+        assert_eq!(data.negotiated_format(SwapchainPurpose::Overlay), Some(0));
+        assert_eq!(data.negotiated_format(SwapchainPurpose::Scene), Some(2));
+
+        // Unsupported formats fall back to what was previously used for the same purpose
+        assert_eq!(check(1, SwapchainPurpose::Scene), 2);
+        assert_eq!(check(1, SwapchainPurpose::Overlay), 0);
     }
 
     #[test]
@@ -1782,6 +2634,97 @@ mod tests {
         f.check_frame_state(fakexr::FrameState::Waited);
     }
 
+    #[test]
+    fn stereo_screenshot() {
+        use vr::IVRScreenshots001_Interface;
+
+        let f = Fixture::new();
+        let screenshots = Arc::new(Screenshots::default());
+        f.comp.screenshots.set(Arc::downgrade(&screenshots));
+        f.start_rendering();
+
+        let dir = std::env::temp_dir().join(format!("xrizer-screenshot-{}", std::process::id()));
+        let preview = CString::new(dir.join("preview").to_str().unwrap()).unwrap();
+        let vr_image = CString::new(dir.join("vr").to_str().unwrap()).unwrap();
+        let mut handle = 0;
+        assert_eq!(
+            screenshots.RequestScreenshot(
+                &mut handle,
+                vr::EVRScreenshotType::Stereo,
+                preview.as_ptr(),
+                vr_image.as_ptr()
+            ),
+            vr::EVRScreenshotError::None
+        );
+        assert_ne!(handle, vr::k_unScreenshotHandleInvalid);
+
+        let mut err = vr::EVRScreenshotError::NotFound;
+        assert_eq!(
+            screenshots.GetScreenshotPropertyType(handle, &mut err),
+            vr::EVRScreenshotType::Stereo
+        );
+        assert_eq!(err, vr::EVRScreenshotError::None);
+
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+        assert!(!dir.join("vr.tga").exists());
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+
+        // The header ends with the width and height, then the pixel format
+        let dims = |name: &str| {
+            let tga = std::fs::read(dir.join(name)).unwrap();
+            assert_eq!(tga[2], 2);
+            assert_eq!(&tga[16..18], &[32, 0x28]);
+            let width = u16::from_le_bytes(tga[12..14].try_into().unwrap()) as u32;
+            let height = u16::from_le_bytes(tga[14..16].try_into().unwrap()) as u32;
+            assert_eq!(tga.len(), 18 + (width * height * 4) as usize);
+            (width, height)
+        };
+        let (width, height) = (SWAPCHAIN_WIDTH.get(), SWAPCHAIN_HEIGHT.get());
+        assert_eq!(dims("preview.tga"), (width, height));
+        assert_eq!(dims("vr.tga"), (width * 2, height));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn blank_frames_when_app_stops_presenting() {
+        const THRESHOLD: u32 = 3;
+        let f = Fixture::new_with(|comp| comp.blank_frame_threshold = THRESHOLD);
+        f.ensure_real_session(false);
+        f.comp.SetExplicitTimingMode(
+            vr::EVRCompositorTimingMode::Explicit_ApplicationPerformsPostPresentHandoff,
+        );
+
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        let ended = fakexr::ended_frame_count(session);
+        assert_eq!(f.wait_get_poses(), None);
+        for _ in 1..THRESHOLD {
+            assert_eq!(f.wait_get_poses(), None);
+            f.check_frame_state(fakexr::FrameState::Waited);
+        }
+        assert_eq!(fakexr::ended_frame_count(session), ended);
+
+        let presents = f.comp.metrics.index.load(Ordering::Relaxed);
+        for i in 1..=3 {
+            assert_eq!(f.wait_get_poses(), None);
+            f.check_frame_state(fakexr::FrameState::Waited);
+            assert_eq!(fakexr::ended_frame_count(session), ended + i);
+            assert!(fakexr::last_frame_layers(session).is_empty());
+        }
+        // Blank frames don't count towards the app's frame timings.
+        assert_eq!(f.comp.metrics.index.load(Ordering::Relaxed), presents);
+
+        // Presenting a real frame resets the count
+        assert_eq!(f.comp.SubmitExplicitTimingData(), None);
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+        f.comp.PostPresentHandoff();
+        let ended = fakexr::ended_frame_count(session);
+        assert_eq!(f.wait_get_poses(), None);
+        assert_eq!(f.wait_get_poses(), None);
+        assert_eq!(fakexr::ended_frame_count(session), ended);
+    }
+
     #[test]
     fn explicit_timing_session_restart_after_waitgetposes() {
         let f = Fixture::new();
@@ -1812,4 +2755,609 @@ mod tests {
         assert_eq!(f.submit(vr::EVREye::Left), None);
         assert_eq!(f.submit(vr::EVREye::Right), None);
     }
+
+    #[test]
+    fn force_reconnect() {
+        let f = Fixture::new();
+        // Nothing to reconnect yet.
+        f.comp.ForceReconnectProcess();
+
+        f.ensure_real_session(false);
+        let old_session = f.comp.openxr.session_data.get().session.as_raw();
+        f.comp.ForceReconnectProcess();
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        assert_ne!(session, old_session);
+
+        f.start_rendering();
+        assert!(f
+            .render_frame()
+            .iter()
+            .any(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_PROJECTION));
+    }
+
+    #[test]
+    fn comfort_vignette() {
+        let f = Fixture::new_with(|comp| comp.vignette.intensity = 0.5);
+        f.start_rendering();
+
+        let has_vignette = || {
+            let layers = f.render_frame();
+            assert!(layers
+                .iter()
+                .any(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_PROJECTION));
+            layers
+                .iter()
+                .any(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_QUAD)
+        };
+        let still = xr::Vector3f::default();
+
+        assert!(!has_vignette());
+
+        fakexr::set_view_velocity(
+            xr::Vector3f {
+                x: 2.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            still,
+        );
+        assert!(has_vignette());
+        assert!(has_vignette());
+
+        fakexr::set_view_velocity(
+            still,
+            xr::Vector3f {
+                x: 0.0,
+                y: 3.0,
+                z: 0.0,
+            },
+        );
+        assert!(has_vignette());
+
+        fakexr::set_view_velocity(still, still);
+        assert!(!has_vignette());
+    }
+
+    #[test]
+    fn layer_order() {
+        use vr::IVROverlay027_Interface;
+
+        let f = Fixture::new_with(|comp| comp.vignette.intensity = 0.5);
+        let overlays = f.with_overlays();
+        f.start_rendering();
+        let texture = FakeGraphicsData::texture(&f.vk);
+
+        // Overlays are told apart by their width.
+        let show = |key: &CStr, width, in_dashboard| {
+            let mut handle = 0;
+            assert_eq!(
+                overlays.CreateOverlay(key.as_ptr(), key.as_ptr(), &mut handle),
+                vr::EVROverlayError::None
+            );
+            assert_eq!(
+                overlays.SetOverlayFlag(
+                    handle,
+                    vr::VROverlayFlags::VisibleInDashboard,
+                    in_dashboard
+                ),
+                vr::EVROverlayError::None
+            );
+            assert_eq!(
+                overlays.SetOverlayWidthInMeters(handle, width),
+                vr::EVROverlayError::None
+            );
+            assert_eq!(
+                overlays.SetOverlayTexture(handle, &texture),
+                vr::EVROverlayError::None
+            );
+            assert_eq!(overlays.ShowOverlay(handle), vr::EVROverlayError::None);
+        };
+        show(c"front", 2.0, true);
+        show(c"world", 1.0, false);
+
+        fakexr::set_view_velocity(
+            xr::Vector3f {
+                x: 2.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            Default::default(),
+        );
+        let render_frame = || {
+            f.render_frame()
+                .into_iter()
+                .map(|l| (l.ty, l.size.map(|size| size.width)))
+                .collect::<Vec<_>>()
+        };
+        let quad = |width| (xr::StructureType::COMPOSITION_LAYER_QUAD, Some(width));
+
+        assert_eq!(
+            render_frame(),
+            [
+                (xr::StructureType::COMPOSITION_LAYER_PROJECTION, None),
+                quad(1.0),
+                quad(2.0),
+                quad(4.0), // vignette
+            ]
+        );
+
+        assert_eq!(f.comp.SetSkyboxOverride([texture; 6].as_ptr(), 6), None);
+        f.comp.FadeGrid(0.0, true);
+        // The skybox replaces the scene, and the vignette goes with it.
+        let mut expected = vec![quad(1000.0); 6];
+        expected.extend([quad(1.0), quad(2.0)]);
+        assert_eq!(render_frame(), expected);
+    }
+
+    #[test]
+    fn grid_alpha() {
+        let f = Fixture::new();
+        assert_eq!(f.comp.GetCurrentGridAlpha(), 0.0);
+
+        f.comp.FadeGrid(0.0, true);
+        assert_eq!(f.comp.GetCurrentGridAlpha(), 1.0);
+
+        // A slow fade out has barely started.
+        f.comp.FadeGrid(600.0, false);
+        let alpha = f.comp.GetCurrentGridAlpha();
+        assert!(alpha > 0.99 && alpha <= 1.0, "{alpha}");
+    }
+
+    #[test]
+    fn comfort_vignette_disabled() {
+        let f = Fixture::new();
+        f.start_rendering();
+        fakexr::set_view_velocity(
+            xr::Vector3f {
+                x: 2.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            Default::default(),
+        );
+
+        assert!(!f
+            .render_frame()
+            .iter()
+            .any(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_QUAD));
+    }
+
+    #[test]
+    fn dashboard_hides_scene() {
+        use vr::IVROverlay027_Interface;
+
+        let f = Fixture::new();
+        let overlays = f.with_overlays();
+        f.start_rendering();
+
+        let has_projection = |layers: &[fakexr::SubmittedLayer]| {
+            layers
+                .iter()
+                .any(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_PROJECTION)
+        };
+
+        assert!(has_projection(&f.render_frame()));
+
+        let mut main = 0;
+        let mut thumbnail = 0;
+        assert_eq!(
+            overlays.CreateDashboardOverlay(
+                c"dash".as_ptr(),
+                c"Dash".as_ptr(),
+                &mut main,
+                &mut thumbnail
+            ),
+            vr::EVROverlayError::None
+        );
+        assert!(!overlays.IsDashboardVisible());
+        assert!(has_projection(&f.render_frame()));
+
+        assert_eq!(overlays.ShowOverlay(main), vr::EVROverlayError::None);
+        assert!(overlays.IsDashboardVisible());
+        assert!(overlays.IsActiveDashboardOverlay(main));
+        assert!(!overlays.IsActiveDashboardOverlay(thumbnail));
+        // fakexr doesn't advertise XR_KHR_composition_layer_color_scale_bias by default, so the
+        // scene should be hidden instead of dimmed.
+        assert!(!has_projection(&f.render_frame()));
+
+        assert_eq!(overlays.HideOverlay(main), vr::EVROverlayError::None);
+        assert!(has_projection(&f.render_frame()));
+    }
+
+    #[test]
+    fn fade_to_color() {
+        fakexr::add_instance_extension(
+            xr::sys::KHR_COMPOSITION_LAYER_COLOR_SCALE_BIAS_EXTENSION_NAME,
+        );
+        let f = Fixture::new();
+        assert!(
+            f.comp
+                .openxr
+                .enabled_extensions
+                .khr_composition_layer_color_scale_bias
+        );
+        f.start_rendering();
+
+        let render_frame = || {
+            f.render_frame()
+                .into_iter()
+                .find(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_PROJECTION)
+                .map(|l| (l.color_scale, l.color_bias))
+        };
+
+        assert_eq!(render_frame(), Some((None, None)));
+
+        // Fading halfway to red blends half of it over the scene.
+        f.comp.FadeToColor(0.0, 1.0, 0.0, 0.0, 0.5, false);
+        let color = f.comp.GetCurrentFadeColor(false);
+        assert_eq!([color.r, color.g, color.b, color.a], [1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(
+            render_frame(),
+            Some((
+                Some(xr::Color4f {
+                    r: 0.5,
+                    g: 0.5,
+                    b: 0.5,
+                    a: 1.0
+                }),
+                Some(xr::Color4f {
+                    r: 0.5,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 0.0
+                })
+            ))
+        );
+
+        f.comp.FadeToColor(0.0, 0.0, 0.0, 0.0, 0.0, false);
+        assert_eq!(render_frame(), Some((None, None)));
+    }
+
+    #[test]
+    fn fade_to_color_without_color_scale_bias() {
+        let f = Fixture::new();
+        f.start_rendering();
+
+        // Whether the scene is shown, and the color of the quad over it, if any.
+        let render_frame = || {
+            let layers = f.render_frame();
+            let has_scene = layers
+                .iter()
+                .any(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_PROJECTION);
+            let quad = layers
+                .last()
+                .filter(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_QUAD)
+                .map(|l| {
+                    assert_eq!(l.size.unwrap().width, 4.0);
+                    f.fake_frame_controller(|ctrl| {
+                        ctrl.backend.written_pixels.lock().unwrap()[..4].to_vec()
+                    })
+                });
+            (has_scene, quad)
+        };
+
+        assert_eq!(render_frame(), (true, None));
+
+        // The color is shown with a quad over the scene, which it hides once it's opaque.
+        f.comp.FadeToColor(0.0, 1.0, 0.0, 0.0, 0.5, false);
+        assert_eq!(render_frame(), (true, Some(vec![255, 0, 0, 128])));
+        f.comp.FadeToColor(0.0, 0.0, 0.0, 0.0, 1.0, false);
+        assert_eq!(render_frame(), (false, Some(vec![0, 0, 0, 255])));
+
+        f.comp.FadeToColor(0.0, 0.0, 0.0, 0.0, 0.0, false);
+        assert_eq!(render_frame(), (true, None));
+    }
+
+    #[test]
+    fn brightness_with_color_scale_bias() {
+        fakexr::add_instance_extension(
+            xr::sys::KHR_COMPOSITION_LAYER_COLOR_SCALE_BIAS_EXTENSION_NAME,
+        );
+        let f = Fixture::new_with(|comp| {
+            comp.color_adjustment = ColorAdjustment {
+                gamma: 1.0,
+                brightness: 0.5,
+            }
+        });
+        f.start_rendering();
+
+        // The scene layer is brightened instead of the eyes.
+        let scene = f
+            .render_frame()
+            .into_iter()
+            .find(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_PROJECTION)
+            .unwrap();
+        assert_eq!(
+            scene.color_scale,
+            Some(xr::Color4f {
+                r: 1.5,
+                g: 1.5,
+                b: 1.5,
+                a: 1.0
+            })
+        );
+        assert_eq!(scene.color_bias, Some(xr::Color4f::default()));
+        f.fake_frame_controller(|ctrl| {
+            assert_eq!(*ctrl.backend.applied_color_adjustment.lock().unwrap(), None)
+        });
+    }
+
+    #[test]
+    fn overlays_submitted_without_scene() {
+        use vr::IVROverlay027_Interface;
+
+        let f = Fixture::new();
+        let overlays = f.with_overlays();
+        f.ensure_real_session(false);
+
+        let mut handle = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"hud".as_ptr(), c"HUD".as_ptr(), &mut handle),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayTexture(handle, &FakeGraphicsData::texture(&f.vk)),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(overlays.ShowOverlay(handle), vr::EVROverlayError::None);
+
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        fakexr::set_should_render(session, false);
+        f.render_frame();
+
+        let layers: Vec<_> = f.render_frame().into_iter().map(|l| l.ty).collect();
+        assert_eq!(layers, [xr::StructureType::COMPOSITION_LAYER_QUAD]);
+    }
+
+    #[test]
+    fn side_by_side_overlays() {
+        use vr::IVROverlay027_Interface;
+
+        let f = Fixture::new();
+        let overlays = f.with_overlays();
+        f.start_rendering();
+        SWAPCHAIN_WIDTH.set(20);
+        let texture = FakeGraphicsData::texture(&f.vk);
+
+        let mut overlay = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"sbs".as_ptr(), c"SBS".as_ptr(), &mut overlay),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayTexture(overlay, &texture),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(overlays.ShowOverlay(overlay), vr::EVROverlayError::None);
+
+        let half = |x| xr::Rect2Di {
+            offset: xr::Offset2Di { x, y: 0 },
+            extent: xr::Extent2Di {
+                width: 10,
+                height: 10,
+            },
+        };
+        let eye_rects = |flag| {
+            assert_eq!(
+                overlays.SetOverlayFlag(overlay, flag, true),
+                vr::EVROverlayError::None
+            );
+            let layers: Vec<_> = f
+                .render_frame()
+                .into_iter()
+                .filter(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_QUAD)
+                .collect();
+            assert_eq!(
+                overlays.SetOverlayFlag(overlay, flag, false),
+                vr::EVROverlayError::None
+            );
+            for layer in &layers {
+                // Each eye's half keeps the overlay's width.
+                assert_eq!(
+                    layer.size,
+                    Some(xr::Extent2Df {
+                        width: 1.0,
+                        height: 1.0
+                    })
+                );
+            }
+            layers
+                .into_iter()
+                .map(|l| (l.eye_visibility.unwrap(), l.image_rect.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            eye_rects(vr::VROverlayFlags::SideBySide_Parallel),
+            [
+                (xr::EyeVisibility::LEFT, half(0)),
+                (xr::EyeVisibility::RIGHT, half(10))
+            ]
+        );
+        assert_eq!(
+            eye_rects(vr::VROverlayFlags::SideBySide_Crossed),
+            [
+                (xr::EyeVisibility::LEFT, half(10)),
+                (xr::EyeVisibility::RIGHT, half(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn overlay_size_from_width_and_aspect() {
+        use vr::IVROverlay027_Interface;
+
+        let f = Fixture::new();
+        let overlays = f.with_overlays();
+        f.start_rendering();
+        SWAPCHAIN_WIDTH.set(160);
+        SWAPCHAIN_HEIGHT.set(90);
+        let texture = FakeGraphicsData::texture(&f.vk);
+
+        let mut overlay = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"video".as_ptr(), c"Video".as_ptr(), &mut overlay),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayWidthInMeters(overlay, 2.0),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayTexture(overlay, &texture),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(overlays.ShowOverlay(overlay), vr::EVROverlayError::None);
+
+        let quad_size = || {
+            let sizes: Vec<_> = f
+                .render_frame()
+                .into_iter()
+                .filter(|l| l.ty == xr::StructureType::COMPOSITION_LAYER_QUAD)
+                .map(|l| l.size.unwrap())
+                .collect();
+            assert_eq!(sizes.len(), 1);
+            (sizes[0].width, sizes[0].height)
+        };
+
+        // 2m wide at 16:9.
+        assert_eq!(quad_size(), (2.0, 1.125));
+
+        // The height follows changes to the width...
+        assert_eq!(
+            overlays.SetOverlayWidthInMeters(overlay, 1.0),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(quad_size(), (1.0, 0.5625));
+
+        // ...and to the texture's aspect ratio.
+        SWAPCHAIN_WIDTH.set(90);
+        SWAPCHAIN_HEIGHT.set(160);
+        assert_eq!(
+            overlays.SetOverlayTexture(overlay, &texture),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(quad_size(), (1.0, 160.0 / 90.0));
+    }
+
+    #[test]
+    fn overlays_ordered_around_dashboard() {
+        use vr::IVROverlay027_Interface;
+
+        let f = Fixture::new();
+        let overlays = f.with_overlays();
+        f.start_rendering();
+        let texture = FakeGraphicsData::texture(&f.vk);
+
+        // Overlays are told apart by their width.
+        let show = |handle, width| {
+            assert_eq!(
+                overlays.SetOverlayWidthInMeters(handle, width),
+                vr::EVROverlayError::None
+            );
+            assert_eq!(
+                overlays.SetOverlayTexture(handle, &texture),
+                vr::EVROverlayError::None
+            );
+            assert_eq!(overlays.ShowOverlay(handle), vr::EVROverlayError::None);
+        };
+
+        let mut front = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"front".as_ptr(), c"Front".as_ptr(), &mut front),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayFlag(front, vr::VROverlayFlags::VisibleInDashboard, true),
+            vr::EVROverlayError::None
+        );
+        show(front, 3.0);
+
+        let mut dashboard = 0;
+        let mut thumbnail = 0;
+        assert_eq!(
+            overlays.CreateDashboardOverlay(
+                c"dash".as_ptr(),
+                c"Dash".as_ptr(),
+                &mut dashboard,
+                &mut thumbnail
+            ),
+            vr::EVROverlayError::None
+        );
+        show(dashboard, 2.0);
+
+        let mut back = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"back".as_ptr(), c"Back".as_ptr(), &mut back),
+            vr::EVROverlayError::None
+        );
+        show(back, 1.0);
+
+        let widths: Vec<f32> = f
+            .render_frame()
+            .iter()
+            .filter_map(|l| l.size.map(|size| size.width))
+            .collect();
+        assert_eq!(widths, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn protected_overlay_swapchain() {
+        use vr::IVROverlay027_Interface;
+
+        let f = Fixture::new();
+        let overlays = f.with_overlays();
+        f.ensure_real_session(false);
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        let texture = FakeGraphicsData::texture(&f.vk);
+        let last_create_flags = || *fakexr::swapchain_create_flags(session).last().unwrap();
+
+        let mut handle = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"video".as_ptr(), c"Video".as_ptr(), &mut handle),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayTexture(handle, &texture),
+            vr::EVROverlayError::None
+        );
+        assert!(!last_create_flags().contains(xr::SwapchainCreateFlags::PROTECTED_CONTENT));
+
+        assert_eq!(
+            overlays.SetOverlayFlag(handle, vr::VROverlayFlags::ProtectedContent, true),
+            vr::EVROverlayError::None
+        );
+        let mut enabled = false;
+        assert_eq!(
+            overlays.GetOverlayFlag(handle, vr::VROverlayFlags::ProtectedContent, &mut enabled),
+            vr::EVROverlayError::None
+        );
+        assert!(enabled);
+
+        // The swapchain should be recreated as protected.
+        assert_eq!(
+            overlays.SetOverlayTexture(handle, &texture),
+            vr::EVROverlayError::None
+        );
+        assert!(last_create_flags().contains(xr::SwapchainCreateFlags::PROTECTED_CONTENT));
+    }
+
+    #[test]
+    fn color_adjustment() {
+        let adjustment = ColorAdjustment {
+            gamma: 2.2,
+            brightness: 0.0,
+        };
+        let f = Fixture::new_with(|comp| comp.color_adjustment = adjustment);
+        f.start_rendering();
+
+        f.render_frame();
+
+        f.fake_frame_controller(|ctrl| {
+            assert_eq!(
+                *ctrl.backend.applied_color_adjustment.lock().unwrap(),
+                Some(adjustment)
+            )
+        });
+    }
 }
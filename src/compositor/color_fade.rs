@@ -0,0 +1,187 @@
+use super::{fade::Fade, vignette::ViewQuad};
+use crate::{
+    graphics_backends::GraphicsBackend,
+    openxr_data::{GraphicalSession, Session, SessionData},
+};
+use openvr as vr;
+use openxr as xr;
+
+/// Width and height of the color quad's images, which are a single color.
+const QUAD_IMAGE_SIZE: i32 = 8;
+
+/// The color the scene is faded to with FadeToColor. Its alpha goes from 0 (the scene is shown) to
+/// 1 (only the color is). The color is faded premultiplied by its alpha, so it's blended in at the
+/// same rate the scene is blended out. Times are seconds on the instance's
+/// [`SystemClock`](crate::openxr_data::SystemClock).
+#[derive(Default)]
+pub struct ColorFade(Fade<[f32; 4]>);
+
+/// How a fade affects the scene layer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SceneFade {
+    None,
+    /// Blended over the scene with XR_KHR_composition_layer_color_scale_bias. The color is
+    /// premultiplied by its alpha.
+    Tint(xr::Color4f),
+    /// Without XR_KHR_composition_layer_color_scale_bias, a [`ColorQuad`] is shown over everything
+    /// instead. The color is premultiplied by its alpha.
+    Quad(xr::Color4f),
+}
+
+impl ColorFade {
+    /// Starts fading to `color` over `seconds`, from wherever the current fade is at `now`.
+    pub fn start(&self, now: f64, seconds: f32, color: vr::HmdColor_t) {
+        let a = color.a.clamp(0.0, 1.0);
+        self.0
+            .start(now, seconds, [color.r * a, color.g * a, color.b * a, a]);
+    }
+
+    pub fn color(&self, now: f64) -> vr::HmdColor_t {
+        match self.0.value(now) {
+            [_, _, _, a] if a <= 0.0 => vr::HmdColor_t::default(),
+            [r, g, b, a] => vr::HmdColor_t {
+                r: r / a,
+                g: g / a,
+                b: b / a,
+                a,
+            },
+        }
+    }
+
+    pub fn scene_fade(&self, now: f64, can_tint: bool) -> SceneFade {
+        let [r, g, b, a] = self.0.value(now);
+        match a {
+            a if a <= 0.0 => SceneFade::None,
+            _ if can_tint => SceneFade::Tint(xr::Color4f { r, g, b, a }),
+            _ => SceneFade::Quad(xr::Color4f { r, g, b, a }),
+        }
+    }
+}
+
+/// A quad of the fade color covering the view, for runtimes without
+/// XR_KHR_composition_layer_color_scale_bias.
+pub struct ColorQuad<G: xr::Graphics> {
+    quad: ViewQuad<G>,
+    /// The RGBA8 color last written to the quad.
+    written: Option<[u8; 4]>,
+}
+
+impl<G: xr::Graphics> ColorQuad<G> {
+    pub fn new(session_data: &SessionData, format: G::Format) -> Option<Self>
+    where
+        for<'a> &'a GraphicalSession: TryInto<&'a Session<G>, Error: std::fmt::Display>,
+    {
+        let quad = ViewQuad::new(session_data, format, QUAD_IMAGE_SIZE, false)?;
+        Some(Self {
+            quad,
+            written: None,
+        })
+    }
+
+    /// Returns the layer showing `color`, premultiplied by its alpha, or None if it couldn't be
+    /// written. The quad's image is only rewritten when the color changes.
+    pub fn layer<'a, B: GraphicsBackend<Api = G>>(
+        &'a mut self,
+        session_data: &'a SessionData,
+        backend: &B,
+        color: xr::Color4f,
+    ) -> Option<xr::CompositionLayerQuad<'a, G>> {
+        let unpremultiply = |c: f32| c / color.a;
+        let pixel = [
+            unpremultiply(color.r),
+            unpremultiply(color.g),
+            unpremultiply(color.b),
+            color.a,
+        ]
+        .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        if self.written != Some(pixel) {
+            let pixels = pixel.repeat((QUAD_IMAGE_SIZE * QUAD_IMAGE_SIZE) as usize);
+            self.written = self.quad.write(backend, &pixels).then_some(pixel);
+        }
+        self.written
+            .is_some()
+            .then(|| self.quad.layer(session_data))
+    }
+}
+
+/// The color scale and bias that darken the scene by `scale` and blend `color`, premultiplied by
+/// its alpha, over it.
+pub fn scale_bias(scale: f32, color: xr::Color4f) -> xr::sys::CompositionLayerColorScaleBiasKHR {
+    let scale = scale * (1.0 - color.a);
+    xr::sys::CompositionLayerColorScaleBiasKHR {
+        ty: xr::StructureType::COMPOSITION_LAYER_COLOR_SCALE_BIAS_KHR,
+        next: std::ptr::null(),
+        color_scale: xr::Color4f {
+            r: scale,
+            g: scale,
+            b: scale,
+            a: 1.0,
+        },
+        color_bias: xr::Color4f {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: 0.0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scale_bias, ColorFade, SceneFade};
+    use openvr as vr;
+    use openxr as xr;
+
+    const RED: vr::HmdColor_t = vr::HmdColor_t {
+        r: 1.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+
+    #[test]
+    fn fade_over_time() {
+        let fade = ColorFade::default();
+        assert_eq!(fade.scene_fade(0.0, true), SceneFade::None);
+
+        fade.start(10.0, 2.0, RED);
+        let tint = |r, a| {
+            SceneFade::Tint(xr::Color4f {
+                r,
+                g: 0.0,
+                b: 0.0,
+                a,
+            })
+        };
+        assert_eq!(fade.scene_fade(11.0, true), tint(0.5, 0.5));
+        assert_eq!(fade.scene_fade(12.0, true), tint(1.0, 1.0));
+        let color = |now| {
+            let vr::HmdColor_t { r, g, b, a } = fade.color(now);
+            [r, g, b, a]
+        };
+        assert_eq!(color(11.0), [1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(color(13.0), [1.0, 0.0, 0.0, 1.0]);
+
+        // Halfway there, the scene and the color are blended half and half.
+        let SceneFade::Tint(halfway) = fade.scene_fade(11.0, true) else {
+            unreachable!();
+        };
+        let tint = scale_bias(1.0, halfway);
+        assert_eq!(tint.color_scale.r, 0.5);
+        assert_eq!(tint.color_bias.r, 0.5);
+
+        // Without tinting, the color is shown with a quad.
+        assert_eq!(
+            fade.scene_fade(11.0, false),
+            SceneFade::Quad(xr::Color4f {
+                r: 0.5,
+                g: 0.0,
+                b: 0.0,
+                a: 0.5
+            })
+        );
+
+        fade.start(20.0, 0.0, vr::HmdColor_t::default());
+        assert_eq!(fade.scene_fade(20.0, true), SceneFade::None);
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+
+/// A value that can be faded between.
+pub trait Lerp: Copy + Default {
+    /// Interpolates from `self` to `to`, where `t` goes from 0 to 1.
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl<const N: usize> Lerp for [f32; N] {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        std::array::from_fn(|i| self[i].lerp(to[i], t))
+    }
+}
+
+/// A value fading linearly to a target over time. Times are seconds on the instance's
+/// [`SystemClock`](crate::openxr_data::SystemClock).
+#[derive(Default)]
+pub struct Fade<T>(Mutex<State<T>>);
+
+#[derive(Clone, Copy, Default)]
+struct State<T> {
+    from: T,
+    to: T,
+    start: f64,
+    seconds: f32,
+}
+
+impl<T: Lerp> State<T> {
+    fn value(&self, now: f64) -> T {
+        if self.seconds <= 0.0 {
+            return self.to;
+        }
+        let progress = ((now - self.start) / self.seconds as f64).clamp(0.0, 1.0) as f32;
+        self.from.lerp(self.to, progress)
+    }
+}
+
+impl<T: Lerp> Fade<T> {
+    /// Starts fading to `to` over `seconds`, from wherever the current fade is at `now`.
+    pub fn start(&self, now: f64, seconds: f32, to: T) {
+        let mut state = self.0.lock().unwrap();
+        *state = State {
+            from: state.value(now),
+            to,
+            start: now,
+            seconds: seconds.max(0.0),
+        };
+    }
+
+    pub fn value(&self, now: f64) -> T {
+        self.0.lock().unwrap().value(now)
+    }
+}
@@ -0,0 +1,41 @@
+use super::fade::Fade;
+
+/// Opacity of the grid the app fades to with FadeGrid, from 0 (the app is shown) to 1 (the grid
+/// is). Times are seconds on the instance's [`SystemClock`](crate::openxr_data::SystemClock).
+#[derive(Default)]
+pub struct GridFade(Fade<f32>);
+
+impl GridFade {
+    /// Starts fading in or out over `seconds`, from wherever the current fade is at `now`.
+    pub fn start(&self, now: f64, seconds: f32, fade_in: bool) {
+        self.0.start(now, seconds, if fade_in { 1.0 } else { 0.0 });
+    }
+
+    pub fn alpha(&self, now: f64) -> f32 {
+        self.0.value(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GridFade;
+
+    #[test]
+    fn fade_over_time() {
+        let fade = GridFade::default();
+        assert_eq!(fade.alpha(0.0), 0.0);
+
+        fade.start(10.0, 2.0, true);
+        let alphas = [10.0, 10.5, 11.0, 11.5, 12.0, 20.0].map(|now| fade.alpha(now));
+        assert_eq!(alphas, [0.0, 0.25, 0.5, 0.75, 1.0, 1.0]);
+
+        // Fading out halfway through fading in starts from where the fade in got to.
+        fade.start(30.0, 1.0, false);
+        fade.start(30.5, 2.0, true);
+        assert_eq!(fade.alpha(30.5), 0.5);
+        assert_eq!(fade.alpha(31.5), 0.75);
+
+        fade.start(40.0, 0.0, false);
+        assert_eq!(fade.alpha(40.0), 0.0);
+    }
+}
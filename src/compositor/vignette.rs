@@ -0,0 +1,213 @@
+use crate::{
+    graphics_backends::GraphicsBackend,
+    openxr_data::{GraphicalSession, Session, SessionData},
+};
+use log::{debug, warn};
+use openxr as xr;
+use std::ffi::CString;
+
+/// Head speed (m/s) above which the user is considered to be moving.
+const LINEAR_SPEED_THRESHOLD: f32 = 1.0;
+/// Head turning speed (rad/s) above which the user is considered to be moving.
+const ANGULAR_SPEED_THRESHOLD: f32 = 2.0;
+
+/// Distance of view quads from the eyes, in meters.
+const QUAD_DISTANCE: f32 = 1.0;
+/// Width and height of view quads, in meters - wide enough to cover common HMD fields of view at
+/// QUAD_DISTANCE.
+const QUAD_SIZE: f32 = 4.0;
+const IMAGE_SIZE: i32 = 256;
+/// Where the vignette starts fading in and where it reaches full intensity, as a fraction of the
+/// distance from the center of the quad to its edge.
+const INNER_RADIUS: f32 = 0.35;
+const OUTER_RADIUS: f32 = 0.7;
+
+/// Comfort vignette settings.
+#[derive(Default)]
+pub struct VignetteConfig {
+    /// Opacity of the vignette at the edges of the view, 0 disables it.
+    pub intensity: f32,
+    /// Manifest action that marks the user as moving while engaged, in addition to head motion.
+    pub action: Option<CString>,
+}
+
+impl VignetteConfig {
+    /// Reads the intensity from `XRIZER_COMFORT_VIGNETTE` and the action from
+    /// `XRIZER_COMFORT_VIGNETTE_ACTION`, defaulting to no vignette.
+    pub fn from_env() -> Self {
+        let intensity = std::env::var("XRIZER_COMFORT_VIGNETTE")
+            .ok()
+            .and_then(|value| {
+                value
+                    .parse::<f32>()
+                    .inspect_err(|e| warn!("Invalid XRIZER_COMFORT_VIGNETTE value {value:?}: {e}"))
+                    .ok()
+            })
+            .map(|intensity| intensity.clamp(0.0, 1.0))
+            .unwrap_or(0.0);
+        let action = std::env::var("XRIZER_COMFORT_VIGNETTE_ACTION")
+            .ok()
+            .and_then(|action| CString::new(action).ok());
+
+        if intensity > 0.0 {
+            debug!("Comfort vignette enabled (intensity: {intensity}, action: {action:?})");
+        }
+        Self { intensity, action }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.intensity > 0.0
+    }
+}
+
+/// Returns true if the head is moving or turning fast enough to show the vignette.
+pub fn is_fast_head_motion(velocity: &xr::SpaceVelocity) -> bool {
+    let length = |v: xr::Vector3f| (v.x * v.x + v.y * v.y + v.z * v.z).sqrt();
+    (velocity
+        .velocity_flags
+        .contains(xr::SpaceVelocityFlags::LINEAR_VALID)
+        && length(velocity.linear_velocity) > LINEAR_SPEED_THRESHOLD)
+        || (velocity
+            .velocity_flags
+            .contains(xr::SpaceVelocityFlags::ANGULAR_VALID)
+            && length(velocity.angular_velocity) > ANGULAR_SPEED_THRESHOLD)
+}
+
+/// A swapchain of square RGBA8 images, shown as a head locked quad layer covering the view.
+pub struct ViewQuad<G: xr::Graphics> {
+    swapchain: xr::Swapchain<G>,
+    format: G::Format,
+    image_size: i32,
+}
+
+impl<G: xr::Graphics> ViewQuad<G> {
+    /// Creates the swapchain. Static swapchains can only be written once.
+    pub fn new(
+        session_data: &SessionData,
+        format: G::Format,
+        image_size: i32,
+        static_image: bool,
+    ) -> Option<Self>
+    where
+        for<'a> &'a GraphicalSession: TryInto<&'a Session<G>, Error: std::fmt::Display>,
+    {
+        let info = xr::SwapchainCreateInfo {
+            create_flags: if static_image {
+                xr::SwapchainCreateFlags::STATIC_IMAGE
+            } else {
+                xr::SwapchainCreateFlags::EMPTY
+            },
+            usage_flags: xr::SwapchainUsageFlags::COLOR_ATTACHMENT
+                | xr::SwapchainUsageFlags::TRANSFER_DST,
+            format,
+            sample_count: 1,
+            width: image_size as u32,
+            height: image_size as u32,
+            face_count: 1,
+            array_size: 1,
+            mip_count: 1,
+        };
+        let swapchain = session_data
+            .create_swapchain(&info)
+            .inspect_err(|e| warn!("Failed to create view quad swapchain: {e}"))
+            .ok()?;
+        Some(Self {
+            swapchain,
+            format,
+            image_size,
+        })
+    }
+
+    /// Writes tightly packed RGBA8 pixels to the next image, which is shown from then on. Returns
+    /// false if the backend couldn't write them.
+    pub fn write<B: GraphicsBackend<Api = G>>(&mut self, backend: &B, pixels: &[u8]) -> bool {
+        let images = self
+            .swapchain
+            .enumerate_images()
+            .expect("Couldn't enumerate swapchain images");
+
+        let idx = self.swapchain.acquire_image().unwrap();
+        self.swapchain.wait_image(xr::Duration::INFINITE).unwrap();
+        let written = images.into_iter().nth(idx as usize).is_none_or(|image| {
+            backend.write_swapchain_image(image, self.format, self.extent(), pixels)
+        });
+        self.swapchain.release_image().unwrap();
+        written
+    }
+
+    pub fn layer<'a>(&'a self, session_data: &'a SessionData) -> xr::CompositionLayerQuad<'a, G> {
+        xr::CompositionLayerQuad::new()
+            .space(&session_data.view_space)
+            .layer_flags(
+                xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
+                    | xr::CompositionLayerFlags::UNPREMULTIPLIED_ALPHA,
+            )
+            .eye_visibility(xr::EyeVisibility::BOTH)
+            .sub_image(
+                xr::SwapchainSubImage::new()
+                    .swapchain(&self.swapchain)
+                    .image_rect(xr::Rect2Di {
+                        offset: xr::Offset2Di::default(),
+                        extent: self.extent(),
+                    }),
+            )
+            .pose(xr::Posef {
+                orientation: xr::Quaternionf::IDENTITY,
+                position: xr::Vector3f {
+                    x: 0.0,
+                    y: 0.0,
+                    z: -QUAD_DISTANCE,
+                },
+            })
+            .size(xr::Extent2Df {
+                width: QUAD_SIZE,
+                height: QUAD_SIZE,
+            })
+    }
+
+    fn extent(&self) -> xr::Extent2Di {
+        xr::Extent2Di {
+            width: self.image_size,
+            height: self.image_size,
+        }
+    }
+}
+
+/// The vignette, written once to a static [`ViewQuad`].
+pub struct Vignette<G: xr::Graphics>(ViewQuad<G>);
+
+impl<G: xr::Graphics> Vignette<G> {
+    pub fn new<B: GraphicsBackend<Api = G>>(
+        session_data: &SessionData,
+        backend: &B,
+        format: G::Format,
+        intensity: f32,
+    ) -> Option<Self>
+    where
+        for<'a> &'a GraphicalSession: TryInto<&'a Session<G>, Error: std::fmt::Display>,
+    {
+        let mut quad = ViewQuad::new(session_data, format, IMAGE_SIZE, true)?;
+        quad.write(backend, &vignette_pixels(intensity))
+            .then_some(Self(quad))
+    }
+
+    pub fn layer<'a>(&'a self, session_data: &'a SessionData) -> xr::CompositionLayerQuad<'a, G> {
+        self.0.layer(session_data)
+    }
+}
+
+/// Black RGBA8 image that's transparent in the middle and fades to `intensity` towards the edges.
+fn vignette_pixels(intensity: f32) -> Vec<u8> {
+    let half = IMAGE_SIZE as f32 / 2.0;
+    (0..IMAGE_SIZE)
+        .flat_map(|y| (0..IMAGE_SIZE).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            let dx = (x as f32 + 0.5 - half) / half;
+            let dy = (y as f32 + 0.5 - half) / half;
+            let t = ((dx.hypot(dy) - INNER_RADIUS) / (OUTER_RADIUS - INNER_RADIUS)).clamp(0.0, 1.0);
+            // smoothstep
+            let alpha = t * t * (3.0 - 2.0 * t) * intensity;
+            [0, 0, 0, (alpha * 255.0).round() as u8]
+        })
+        .collect()
+}
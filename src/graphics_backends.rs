@@ -3,10 +3,61 @@ mod vulkan;
 
 use derive_more::{From, TryInto};
 pub use gl::GlData;
+use log::{debug, warn};
 use openvr as vr;
 use openxr as xr;
 pub use vulkan::VulkanData;
 
+/// Gamma and brightness applied to the eye textures as they're copied into the swapchain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorAdjustment {
+    /// Output is raised to the power of 1/gamma, values above 1 brighten the midtones.
+    pub gamma: f32,
+    /// Output is scaled by 1 + brightness.
+    pub brightness: f32,
+}
+
+impl Default for ColorAdjustment {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            brightness: 0.0,
+        }
+    }
+}
+
+impl ColorAdjustment {
+    /// Reads the adjustment from `XRIZER_GAMMA` and `XRIZER_BRIGHTNESS`, defaulting to no
+    /// adjustment.
+    pub fn from_env() -> Self {
+        let read_var = |name: &str, valid: fn(f32) -> bool| {
+            let value = std::env::var(name).ok()?;
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite() && valid(*v))
+                .or_else(|| {
+                    warn!("Invalid {name} value {value:?}");
+                    None
+                })
+        };
+
+        let default = Self::default();
+        let ret = Self {
+            gamma: read_var("XRIZER_GAMMA", |v| v > 0.0).unwrap_or(default.gamma),
+            brightness: read_var("XRIZER_BRIGHTNESS", |v| v > -1.0).unwrap_or(default.brightness),
+        };
+        if !ret.is_identity() {
+            debug!("Adjusting eye colors: {ret:?}");
+        }
+        ret
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 pub trait GraphicsBackend: Into<SupportedBackend> {
     type Api: xr::Graphics + 'static;
     type OpenVrTexture: Copy;
@@ -31,6 +82,9 @@ pub trait GraphicsBackend: Into<SupportedBackend> {
         format: <Self::Api as xr::Graphics>::Format,
     );
 
+    /// Sets the adjustment applied by later calls to copy_texture_to_swapchain.
+    fn set_color_adjustment(&mut self, adjustment: ColorAdjustment);
+
     fn copy_texture_to_swapchain(
         &self,
         eye: vr::EVREye,
@@ -47,6 +101,25 @@ pub trait GraphicsBackend: Into<SupportedBackend> {
         bounds: vr::VRTextureBounds_t,
         image_index: usize,
     ) -> xr::Extent2Di;
+
+    /// Reads back an eye previously copied into the scene swapchain as tightly packed RGBA8
+    /// pixels, top row first. Returns None if the swapchain format can't be read back.
+    fn read_back_eye(
+        &self,
+        eye: vr::EVREye,
+        image_index: usize,
+        extent: xr::Extent2Di,
+    ) -> Option<Vec<u8>>;
+
+    /// Fills an image of a swapchain that xrizer created for its own content (rather than for an
+    /// app texture) with tightly packed RGBA8 pixels. Returns false if the format can't be written.
+    fn write_swapchain_image(
+        &self,
+        image: <Self::Api as xr::Graphics>::SwapchainImage,
+        format: <Self::Api as xr::Graphics>::Format,
+        extent: xr::Extent2Di,
+        pixels: &[u8],
+    ) -> bool;
 }
 
 #[derive(macros::Backends, TryInto, From)]
@@ -107,18 +180,25 @@ pub trait WithAnyGraphicsOwned<G>: WithAnyGraphicsParams {
 }
 
 impl SupportedBackend {
-    pub fn new(texture: &vr::Texture_t, _bounds: vr::VRTextureBounds_t) -> Self {
+    /// Creates the backend for a game texture, or returns `None` if its type isn't supported.
+    /// There's no Direct3D backend: Proton's vrclient translates D3D11 textures into Vulkan ones
+    /// before they get here, and native Linux OpenXR runtimes don't support the Direct3D graphics
+    /// bindings anyway.
+    pub fn new(texture: &vr::Texture_t, _bounds: vr::VRTextureBounds_t) -> Option<Self> {
         match texture.eType {
             vr::ETextureType::Vulkan => {
                 let vk_texture = unsafe { &*(texture.handle as *const vr::VRVulkanTextureData_t) };
-                Self::Vulkan(VulkanData::new(vk_texture))
+                Some(Self::Vulkan(VulkanData::new(vk_texture)))
             }
-            vr::ETextureType::OpenGL => Self::OpenGL(GlData::new()),
+            vr::ETextureType::OpenGL => Some(Self::OpenGL(GlData::new())),
             #[cfg(test)]
-            vr::ETextureType::Reserved => {
-                Self::Fake(crate::compositor::FakeGraphicsData::new(texture))
+            vr::ETextureType::Reserved => Some(Self::Fake(
+                crate::compositor::FakeGraphicsData::new(texture),
+            )),
+            other => {
+                crate::warn_once!("Unsupported texture type: {other:?}");
+                None
             }
-            other => panic!("Unsupported texture type: {other:?}"),
         }
     }
 }
@@ -1,4 +1,4 @@
-use super::GraphicsBackend;
+use super::{ColorAdjustment, GraphicsBackend};
 use derive_more::Deref;
 use glutin_glx_sys::{
     glx::{self, Glx},
@@ -185,6 +185,12 @@ impl GraphicsBackend for GlData {
         }
     }
 
+    fn set_color_adjustment(&mut self, adjustment: ColorAdjustment) {
+        if !adjustment.is_identity() {
+            warn!("Eye color adjustment is not supported with OpenGL, ignoring it");
+        }
+    }
+
     fn copy_texture_to_swapchain(
         &self,
         eye: vr::EVREye,
@@ -280,6 +286,81 @@ impl GraphicsBackend for GlData {
             vr::EVRSubmitFlags::Default,
         )
     }
+
+    fn read_back_eye(
+        &self,
+        eye: vr::EVREye,
+        image_index: usize,
+        extent: xr::Extent2Di,
+    ) -> Option<Vec<u8>> {
+        let stride = extent.width as usize * 4;
+        let mut pixels = vec![0u8; stride * extent.height as usize];
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.read_fbo);
+            gl::FramebufferTextureLayer(
+                gl::READ_FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                self.images[image_index],
+                0,
+                eye as i32,
+            );
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                extent.width,
+                extent.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr().cast(),
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+
+        // GL rows start at the bottom
+        Some(
+            pixels
+                .chunks_exact(stride)
+                .rev()
+                .flatten()
+                .copied()
+                .collect(),
+        )
+    }
+
+    fn write_swapchain_image(
+        &self,
+        image: u32,
+        _format: u32,
+        extent: xr::Extent2Di,
+        pixels: &[u8],
+    ) -> bool {
+        // GL rows start at the bottom
+        let stride = extent.width as usize * 4;
+        let flipped: Vec<u8> = pixels
+            .chunks_exact(stride)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, image);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                0,
+                0,
+                extent.width,
+                extent.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                flipped.as_ptr().cast(),
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        true
+    }
 }
 
 fn texture_rect_from_bounds(
@@ -1,11 +1,11 @@
-use super::GraphicsBackend;
+use super::{ColorAdjustment, GraphicsBackend};
 use ash::vk::{self, Handle};
 use log::warn;
 use openvr as vr;
 use openxr as xr;
 use std::collections::HashSet;
 use std::ffi::{c_char, CString};
-use std::sync::{LazyLock, Mutex};
+use std::sync::{LazyLock, Mutex, Once, OnceLock};
 
 struct RealSessionData {
     images: Vec<vk::Image>,
@@ -13,6 +13,8 @@ struct RealSessionData {
     pool: vk::CommandPool,
     bufs: Vec<vk::CommandBuffer>,
     overlay_pipeline: Option<PipelineData>,
+    /// Created the first time an eye is copied with a color adjustment.
+    eye_pipelines: OnceLock<[PipelineData; 2]>,
 }
 
 pub struct VulkanData {
@@ -23,6 +25,7 @@ pub struct VulkanData {
     pub queue: vk::Queue,
     pub queue_family_index: u32,
     real_data: Option<RealSessionData>,
+    color_adjustment: ColorAdjustment,
 }
 
 impl Drop for VulkanData {
@@ -39,12 +42,9 @@ impl Drop for VulkanData {
             // Real session - the handles come from the app, only destroy the command pool we created
             Some(data) => unsafe {
                 self.device.destroy_command_pool(data.pool, None);
-                if let Some(data) = &data.overlay_pipeline {
-                    self.device.destroy_pipeline(data.pipeline, None);
-                    self.device.destroy_pipeline_layout(data.layout, None);
-                    self.device.destroy_render_pass(data.renderpass, None);
-                    self.device.destroy_descriptor_pool(data.pool, None);
-                    self.device.destroy_sampler(data.sampler, None);
+                let eye_pipelines = data.eye_pipelines.get().into_iter().flatten();
+                for pipeline in data.overlay_pipeline.iter().chain(eye_pipelines) {
+                    pipeline.destroy(&self.device);
                 }
             },
         }
@@ -121,13 +121,21 @@ impl GraphicsBackend for VulkanData {
             pool,
             bufs,
             overlay_pipeline: Default::default(),
+            eye_pipelines: Default::default(),
         }) {
             unsafe {
                 self.device.destroy_command_pool(data.pool, None);
+                for pipeline in data.eye_pipelines.get().into_iter().flatten() {
+                    pipeline.destroy(&self.device);
+                }
             }
         }
     }
 
+    fn set_color_adjustment(&mut self, adjustment: ColorAdjustment) {
+        self.color_adjustment = adjustment;
+    }
+
     fn swapchain_info_for_texture(
         &self,
         texture: *const vr::VRVulkanTextureData_t,
@@ -178,6 +186,27 @@ impl GraphicsBackend for VulkanData {
         let (extent, offset) = texture_extent_from_bounds(texture, bounds);
         log::trace!("{:?} extent: {:?} | bounds: {:?}", eye, extent, bounds);
 
+        let game_layer = array_data.map(|d| d.m_unArrayIndex).unwrap_or(0);
+        let game_format = get_colorspace_corrected_format(
+            vk::Format::from_raw(texture.m_nFormat as _),
+            color_space,
+        );
+
+        if !self.color_adjustment.is_identity() {
+            if texture.m_nSampleCount <= 1 {
+                self.draw_adjusted_eye(eye, texture, game_layer, game_format, bounds, image_index);
+                return xr::Extent2Di {
+                    width: extent.width as _,
+                    height: extent.height as _,
+                };
+            }
+
+            static WARN_MULTISAMPLED: Once = Once::new();
+            WARN_MULTISAMPLED.call_once(|| {
+                warn!("Color adjustment isn't supported for multisampled eye textures");
+            });
+        }
+
         self.record_commands(buf, || unsafe {
             // transition swapchain image to TRANSFER_DST
             let swapchain_res = vk::ImageSubresourceRange {
@@ -215,7 +244,6 @@ impl GraphicsBackend for VulkanData {
             };
 
             let game_image = vk::Image::from_raw(texture.m_nImage);
-            let game_layer = array_data.map(|d| d.m_unArrayIndex).unwrap_or(0);
 
             let copy = vk::ImageResolve {
                 src_subresource: vk::ImageSubresourceLayers {
@@ -231,10 +259,6 @@ impl GraphicsBackend for VulkanData {
                 extent,
             };
 
-            let game_format = get_colorspace_corrected_format(
-                vk::Format::from_raw(texture.m_nFormat as _),
-                color_space,
-            );
             if texture.m_nSampleCount > 1 {
                 self.device.cmd_resolve_image(
                     buf,
@@ -456,8 +480,455 @@ impl GraphicsBackend for VulkanData {
             height: extent.height as _,
         }
     }
+
+    fn read_back_eye(
+        &self,
+        eye: vr::EVREye,
+        image_index: usize,
+        extent: xr::Extent2Di,
+    ) -> Option<Vec<u8>> {
+        let data = self.real_data.as_ref().unwrap();
+        let swap_rb = match data.format {
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => false,
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => true,
+            other => {
+                warn!("Can't read back swapchain image with format {other:?}");
+                return None;
+            }
+        };
+
+        let size = extent.width as u64 * extent.height as u64 * 4;
+        let swapchain_image = data.images[image_index];
+        let swapchain_res = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: eye as u32,
+            layer_count: 1,
+        };
+        let (buffer, memory) = self.create_host_buffer(size, vk::BufferUsageFlags::TRANSFER_DST)?;
+
+        let copied = self.one_time_commands(|buf| unsafe {
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    old_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image: swapchain_image,
+                    subresource_range: swapchain_res,
+                    ..Default::default()
+                }],
+            );
+
+            self.device.cmd_copy_image_to_buffer(
+                buf,
+                swapchain_image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer,
+                &[buffer_image_copy(eye as u32, extent)],
+            );
+
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    dst_access_mask: vk::AccessFlags::empty(),
+                    old_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    new_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    image: swapchain_image,
+                    subresource_range: swapchain_res,
+                    ..Default::default()
+                }],
+            );
+        });
+
+        let pixels = copied.and_then(|()| unsafe {
+            let mapped = self
+                .device
+                .map_memory(memory, 0, size, vk::MemoryMapFlags::empty())?;
+            let pixels = std::slice::from_raw_parts(mapped.cast::<u8>(), size as usize).to_vec();
+            self.device.unmap_memory(memory);
+            Ok(pixels)
+        });
+        unsafe {
+            self.device.destroy_buffer(buffer, None);
+            self.device.free_memory(memory, None);
+        }
+
+        let mut pixels = pixels
+            .inspect_err(|e| warn!("Failed to read back swapchain image: {e}"))
+            .ok()?;
+        if swap_rb {
+            pixels.chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+        }
+        Some(pixels)
+    }
+
+    fn write_swapchain_image(
+        &self,
+        image: u64,
+        format: u32,
+        extent: xr::Extent2Di,
+        pixels: &[u8],
+    ) -> bool {
+        let swap_rb = match vk::Format::from_raw(format as _) {
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => false,
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => true,
+            other => {
+                warn!("Can't write swapchain image with format {other:?}");
+                return false;
+            }
+        };
+        if self.real_data.is_none() {
+            return false;
+        }
+
+        let size = pixels.len() as u64;
+        let Some((buffer, memory)) =
+            self.create_host_buffer(size, vk::BufferUsageFlags::TRANSFER_SRC)
+        else {
+            return false;
+        };
+        let mapped = unsafe {
+            self.device
+                .map_memory(memory, 0, size, vk::MemoryMapFlags::empty())
+        };
+        let written = mapped.map(|mapped| unsafe {
+            let mapped = std::slice::from_raw_parts_mut(mapped.cast::<u8>(), pixels.len());
+            mapped.copy_from_slice(pixels);
+            if swap_rb {
+                mapped.chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+            }
+            self.device.unmap_memory(memory);
+        });
+
+        let image = vk::Image::from_raw(image);
+        let res = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let written = written.and_then(|()| {
+            self.one_time_commands(|buf| unsafe {
+                self.device.cmd_pipeline_barrier(
+                    buf,
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[vk::ImageMemoryBarrier {
+                        src_access_mask: vk::AccessFlags::empty(),
+                        dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                        old_layout: vk::ImageLayout::UNDEFINED,
+                        new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        image,
+                        subresource_range: res,
+                        ..Default::default()
+                    }],
+                );
+
+                self.device.cmd_copy_buffer_to_image(
+                    buf,
+                    buffer,
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[buffer_image_copy(0, extent)],
+                );
+
+                self.device.cmd_pipeline_barrier(
+                    buf,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[vk::ImageMemoryBarrier {
+                        src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                        dst_access_mask: vk::AccessFlags::empty(),
+                        old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        new_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                        image,
+                        subresource_range: res,
+                        ..Default::default()
+                    }],
+                );
+            })
+        });
+
+        unsafe {
+            self.device.destroy_buffer(buffer, None);
+            self.device.free_memory(memory, None);
+        }
+        written
+            .inspect_err(|e| warn!("Failed to write swapchain image: {e}"))
+            .is_ok()
+    }
 }
 impl VulkanData {
+    /// Creates a host visible buffer, for moving image data between the CPU and GPU.
+    fn create_host_buffer(
+        &self,
+        size: u64,
+        usage: vk::BufferUsageFlags,
+    ) -> Option<(vk::Buffer, vk::DeviceMemory)> {
+        unsafe {
+            let buffer = self
+                .device
+                .create_buffer(
+                    &vk::BufferCreateInfo::default()
+                        .size(size)
+                        .usage(usage)
+                        .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                    None,
+                )
+                .inspect_err(|e| warn!("Failed to create host buffer: {e}"))
+                .ok()?;
+            let requirements = self.device.get_buffer_memory_requirements(buffer);
+            let memory_props = self
+                .instance
+                .get_physical_device_memory_properties(self.physical_device);
+            let wanted_flags =
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+            let Some(memory_type) = (0..memory_props.memory_type_count).find(|&i| {
+                requirements.memory_type_bits & (1 << i) != 0
+                    && memory_props.memory_types[i as usize]
+                        .property_flags
+                        .contains(wanted_flags)
+            }) else {
+                warn!("No host visible memory available");
+                self.device.destroy_buffer(buffer, None);
+                return None;
+            };
+            let memory = self
+                .device
+                .allocate_memory(
+                    &vk::MemoryAllocateInfo::default()
+                        .allocation_size(requirements.size)
+                        .memory_type_index(memory_type),
+                    None,
+                )
+                .and_then(|memory| {
+                    self.device
+                        .bind_buffer_memory(buffer, memory, 0)
+                        .inspect_err(|_| self.device.free_memory(memory, None))
+                        .map(|()| memory)
+                });
+            match memory {
+                Ok(memory) => Some((buffer, memory)),
+                Err(e) => {
+                    warn!("Failed to allocate host buffer memory: {e}");
+                    self.device.destroy_buffer(buffer, None);
+                    None
+                }
+            }
+        }
+    }
+
+    /// Draws an eye texture into the swapchain with the color adjustment applied, in place of
+    /// copying it.
+    fn draw_adjusted_eye(
+        &self,
+        eye: vr::EVREye,
+        texture: &vr::VRVulkanTextureData_t,
+        game_layer: u32,
+        game_format: vk::Format,
+        bounds: vr::VRTextureBounds_t,
+        image_index: usize,
+    ) {
+        let data = self.real_data.as_ref().unwrap();
+        let buf = data.bufs[2 * image_index + eye as usize];
+        let (extent, _) = texture_extent_from_bounds(texture, bounds);
+        let rect = vk::Rect2D {
+            offset: vk::Offset2D::default(),
+            extent: vk::Extent2D {
+                width: extent.width,
+                height: extent.height,
+            },
+        };
+        let pipeline_data = &data.eye_pipelines.get_or_init(|| {
+            [vr::EVREye::Left, vr::EVREye::Right]
+                .map(|eye| PipelineData::new_eye(&self.device, data.format, &data.images, eye))
+        })[eye as usize];
+
+        // Textures holding sRGB data in a UNORM format are sampled without decoding, so the
+        // shader has to decode them before the swapchain encodes them again.
+        let texture_format = vk::Format::from_raw(texture.m_nFormat as _);
+        let decode_srgb = texture_format != game_format
+            && is_srgb_format(game_format)
+            && is_srgb_format(data.format);
+
+        let swapchain_view = pipeline_data.image_views[image_index];
+        let game_view = unsafe {
+            self.device
+                .create_image_view(
+                    &vk::ImageViewCreateInfo::default()
+                        .image(vk::Image::from_raw(texture.m_nImage))
+                        .format(texture_format)
+                        .view_type(vk::ImageViewType::TYPE_2D)
+                        .components(vk::ComponentMapping::default())
+                        .subresource_range(vk::ImageSubresourceRange {
+                            aspect_mask: vk::ImageAspectFlags::COLOR,
+                            base_mip_level: 0,
+                            level_count: 1,
+                            base_array_layer: game_layer,
+                            layer_count: 1,
+                        }),
+                    None,
+                )
+                .unwrap()
+        };
+        let fb = unsafe {
+            self.device
+                .create_framebuffer(
+                    &vk::FramebufferCreateInfo::default()
+                        .render_pass(pipeline_data.renderpass)
+                        .attachments(&[swapchain_view])
+                        .width(extent.width)
+                        .height(extent.height)
+                        .layers(1),
+                    None,
+                )
+                .unwrap()
+        };
+
+        unsafe {
+            self.device.update_descriptor_sets(
+                &[vk::WriteDescriptorSet::default()
+                    .dst_set(pipeline_data.set)
+                    .dst_binding(0)
+                    .dst_array_element(0)
+                    .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .image_info(&[vk::DescriptorImageInfo {
+                        sampler: pipeline_data.sampler,
+                        image_view: game_view,
+                        image_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    }])],
+                &[],
+            )
+        }
+
+        self.record_commands(buf, || unsafe {
+            self.device.cmd_bind_pipeline(
+                buf,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_data.pipeline,
+            );
+            self.device.cmd_set_viewport(
+                buf,
+                0,
+                &[vk::Viewport {
+                    width: extent.width as f32,
+                    height: extent.height as f32,
+                    x: 0.0,
+                    y: 0.0,
+                    min_depth: 0.0,
+                    max_depth: 0.0,
+                }],
+            );
+            self.device.cmd_set_scissor(buf, 0, &[rect]);
+            self.device.cmd_bind_descriptor_sets(
+                buf,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_data.layout,
+                0,
+                &[pipeline_data.set],
+                &[],
+            );
+            let pc = [bounds.uMin, bounds.uMax, bounds.vMin, bounds.vMax];
+            self.device.cmd_push_constants(
+                buf,
+                pipeline_data.layout,
+                TEXTURE_COORDINATES_PC.stage_flags,
+                TEXTURE_COORDINATES_PC.offset,
+                pc.align_to().1,
+            );
+            let pc = [
+                self.color_adjustment.gamma.to_bits(),
+                self.color_adjustment.brightness.to_bits(),
+                decode_srgb as u32,
+            ];
+            self.device.cmd_push_constants(
+                buf,
+                pipeline_data.layout,
+                COLOR_ADJUSTMENT_PC.stage_flags,
+                COLOR_ADJUSTMENT_PC.offset,
+                pc.align_to().1,
+            );
+            self.device.cmd_begin_render_pass(
+                buf,
+                &vk::RenderPassBeginInfo::default()
+                    .render_pass(pipeline_data.renderpass)
+                    .framebuffer(fb)
+                    .render_area(rect),
+                vk::SubpassContents::INLINE,
+            );
+            self.device.cmd_draw(buf, 4, 1, 0, 0);
+
+            self.device.cmd_end_render_pass(buf);
+        });
+
+        unsafe {
+            self.device.destroy_framebuffer(fb, None);
+            self.device.destroy_image_view(game_view, None);
+        }
+    }
+
+    /// Records and submits commands, and waits for just them to finish.
+    fn one_time_commands(&self, cmds: impl FnOnce(vk::CommandBuffer)) -> Result<(), vk::Result> {
+        let pool = self.real_data.as_ref().unwrap().pool;
+        unsafe {
+            let buf = self.device.allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::default()
+                    .command_pool(pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(1),
+            )?[0];
+            let fence = self
+                .device
+                .create_fence(&vk::FenceCreateInfo::default(), None)
+                .inspect_err(|_| self.device.free_command_buffers(pool, &[buf]))?;
+
+            let result = self
+                .device
+                .begin_command_buffer(
+                    buf,
+                    &vk::CommandBufferBeginInfo::default()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )
+                .and_then(|()| {
+                    cmds(buf);
+                    self.device.end_command_buffer(buf)
+                })
+                .and_then(|()| {
+                    self.device.queue_submit(
+                        self.queue,
+                        &[vk::SubmitInfo::default().command_buffers(&[buf])],
+                        fence,
+                    )
+                })
+                .and_then(|()| self.device.wait_for_fences(&[fence], true, u64::MAX));
+
+            self.device.destroy_fence(fence, None);
+            self.device.free_command_buffers(pool, &[buf]);
+            result
+        }
+    }
+
     pub fn record_commands(&self, buf: vk::CommandBuffer, cmds: impl FnOnce()) {
         unsafe {
             self.device
@@ -507,6 +978,7 @@ impl VulkanData {
             queue: vk::Queue::from_raw(data.m_pQueue as _),
             queue_family_index: data.m_nQueueFamilyIndex,
             real_data: Default::default(),
+            color_adjustment: Default::default(),
         }
     }
 
@@ -587,10 +1059,25 @@ impl VulkanData {
             queue,
             queue_family_index,
             real_data: Default::default(),
+            color_adjustment: Default::default(),
         }
     }
 }
 
+/// Texture bounds (uMin, uMax, vMin, vMax) for the vertex shader.
+const TEXTURE_COORDINATES_PC: vk::PushConstantRange = vk::PushConstantRange {
+    stage_flags: vk::ShaderStageFlags::VERTEX,
+    offset: 0,
+    size: std::mem::size_of::<[f32; 4]>() as u32,
+};
+
+/// Gamma, brightness and whether to decode sRGB, for the color adjustment fragment shader.
+const COLOR_ADJUSTMENT_PC: vk::PushConstantRange = vk::PushConstantRange {
+    stage_flags: vk::ShaderStageFlags::FRAGMENT,
+    offset: TEXTURE_COORDINATES_PC.size,
+    size: std::mem::size_of::<[u32; 3]>() as u32,
+};
+
 struct PipelineData {
     pipeline: vk::Pipeline,
     layout: vk::PipelineLayout,
@@ -603,6 +1090,7 @@ struct PipelineData {
 }
 
 impl PipelineData {
+    /// Creates a pipeline that draws overlay textures into an overlay swapchain.
     fn new(
         device: &ash::Device,
         source_format: vk::Format,
@@ -662,6 +1150,66 @@ impl PipelineData {
 
         let renderpass = unsafe { device.create_render_pass(&info, None).unwrap() };
 
+        Self::with_render_pass(
+            device,
+            renderpass,
+            target_format,
+            images,
+            0,
+            &include_bytes!(concat!(env!("OUT_DIR"), "/frag_overlay.spv"))[..],
+            &[TEXTURE_COORDINATES_PC],
+        )
+    }
+
+    /// Creates a pipeline that draws an eye texture into its layer of the scene swapchain, applying
+    /// the color adjustment.
+    fn new_eye(
+        device: &ash::Device,
+        target_format: vk::Format,
+        images: &[vk::Image],
+        eye: vr::EVREye,
+    ) -> Self {
+        let attachment = vk::AttachmentDescription {
+            format: target_format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::DONT_CARE,
+            store_op: vk::AttachmentStoreOp::STORE,
+            initial_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            ..Default::default()
+        };
+        let subpass = vk::SubpassDescription::default()
+            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+            .color_attachments(&[vk::AttachmentReference {
+                attachment: 0,
+                layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            }]);
+        let info = vk::RenderPassCreateInfo::default()
+            .attachments(std::slice::from_ref(&attachment))
+            .subpasses(std::slice::from_ref(&subpass));
+
+        let renderpass = unsafe { device.create_render_pass(&info, None).unwrap() };
+
+        Self::with_render_pass(
+            device,
+            renderpass,
+            target_format,
+            images,
+            eye as u32,
+            &include_bytes!(concat!(env!("OUT_DIR"), "/frag_color_adjust.spv"))[..],
+            &[TEXTURE_COORDINATES_PC, COLOR_ADJUSTMENT_PC],
+        )
+    }
+
+    fn with_render_pass(
+        device: &ash::Device,
+        renderpass: vk::RenderPass,
+        target_format: vk::Format,
+        images: &[vk::Image],
+        layer: u32,
+        frag_shader: &[u8],
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> Self {
         let load_module = |stage, bytes| {
             struct ShaderModule<'a>(&'a ash::Device, vk::ShaderModule);
             impl Drop for ShaderModule<'_> {
@@ -695,10 +1243,7 @@ impl PipelineData {
             vk::ShaderStageFlags::VERTEX,
             &include_bytes!(concat!(env!("OUT_DIR"), "/vert_overlay.spv"))[..],
         );
-        let (_frag_module, frag_stage) = load_module(
-            vk::ShaderStageFlags::FRAGMENT,
-            &include_bytes!(concat!(env!("OUT_DIR"), "/frag_overlay.spv"))[..],
-        );
+        let (_frag_module, frag_stage) = load_module(vk::ShaderStageFlags::FRAGMENT, frag_shader);
 
         let binding = vk::DescriptorSetLayoutBinding::default()
             .binding(0)
@@ -738,17 +1283,12 @@ impl PipelineData {
                 .unwrap()[0]
         };
 
-        let texture_coordinates_pc = vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::VERTEX,
-            offset: 0,
-            size: std::mem::size_of::<[f32; 4]>() as u32,
-        };
         let pipeline_layout = unsafe {
             device
                 .create_pipeline_layout(
                     &vk::PipelineLayoutCreateInfo::default()
                         .set_layouts(std::slice::from_ref(&set_layout))
-                        .push_constant_ranges(&[texture_coordinates_pc]),
+                        .push_constant_ranges(push_constant_ranges),
                     None,
                 )
                 .unwrap()
@@ -822,7 +1362,7 @@ impl PipelineData {
                                 aspect_mask: vk::ImageAspectFlags::COLOR,
                                 base_mip_level: 0,
                                 level_count: 1,
-                                base_array_layer: 0,
+                                base_array_layer: layer,
                                 layer_count: 1,
                             }),
                         None,
@@ -848,6 +1388,14 @@ impl PipelineData {
             sampler,
         }
     }
+
+    unsafe fn destroy(&self, device: &ash::Device) {
+        device.destroy_pipeline(self.pipeline, None);
+        device.destroy_pipeline_layout(self.layout, None);
+        device.destroy_render_pass(self.renderpass, None);
+        device.destroy_descriptor_pool(self.pool, None);
+        device.destroy_sampler(self.sampler, None);
+    }
 }
 
 #[inline]
@@ -859,6 +1407,7 @@ fn get_colorspace_corrected_format(format: vk::Format, color_space: vr::EColorSp
             vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => vk::Format::R8G8B8A8_SRGB,
             vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => vk::Format::B8G8R8A8_SRGB,
             vk::Format::BC3_SRGB_BLOCK => format,
+            f if is_hdr_format(f) => format,
             _ => {
                 if UNSUPPORTED.lock().unwrap().insert(format) {
                     warn!("Unhandled texture format: {format:?}");
@@ -866,7 +1415,58 @@ fn get_colorspace_corrected_format(format: vk::Format, color_space: vr::EColorSp
                 format
             }
         },
-        vr::EColorSpace::Linear => todo!("Linear colorspace not implemented yet"),
+        // Linear content must not be run through an sRGB decode, and extended range formats
+        // are kept as-is so HDR content isn't clipped.
+        vr::EColorSpace::Linear => match format {
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => vk::Format::R8G8B8A8_UNORM,
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => vk::Format::B8G8R8A8_UNORM,
+            f if is_hdr_format(f) => format,
+            _ => {
+                if UNSUPPORTED.lock().unwrap().insert(format) {
+                    warn!("Unhandled linear texture format: {format:?}");
+                }
+                format
+            }
+        },
+    }
+}
+
+fn is_srgb_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::R8G8B8A8_SRGB | vk::Format::B8G8R8A8_SRGB | vk::Format::BC3_SRGB_BLOCK
+    )
+}
+
+#[inline]
+fn is_hdr_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::R16G16B16A16_SFLOAT
+            | vk::Format::R32G32B32A32_SFLOAT
+            | vk::Format::R16G16B16A16_UNORM
+            | vk::Format::A2B10G10R10_UNORM_PACK32
+            | vk::Format::B10G11R11_UFLOAT_PACK32
+    )
+}
+
+fn buffer_image_copy(layer: u32, extent: xr::Extent2Di) -> vk::BufferImageCopy {
+    vk::BufferImageCopy {
+        buffer_offset: 0,
+        buffer_row_length: 0,
+        buffer_image_height: 0,
+        image_subresource: vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            base_array_layer: layer,
+            layer_count: 1,
+        },
+        image_offset: vk::Offset3D::default(),
+        image_extent: vk::Extent3D {
+            width: extent.width as _,
+            height: extent.height as _,
+            depth: 1,
+        },
     }
 }
 
@@ -906,3 +1506,42 @@ fn new_entry() -> ash::Entry {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_overlay_keeps_hdr_format() {
+        for format in [
+            vk::Format::R16G16B16A16_SFLOAT,
+            vk::Format::R32G32B32A32_SFLOAT,
+            vk::Format::R16G16B16A16_UNORM,
+        ] {
+            assert_eq!(
+                get_colorspace_corrected_format(format, vr::EColorSpace::Linear),
+                format
+            );
+            assert_eq!(
+                get_colorspace_corrected_format(format, vr::EColorSpace::Auto),
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn linear_overlay_avoids_srgb() {
+        assert_eq!(
+            get_colorspace_corrected_format(vk::Format::R8G8B8A8_SRGB, vr::EColorSpace::Linear),
+            vk::Format::R8G8B8A8_UNORM
+        );
+        assert_eq!(
+            get_colorspace_corrected_format(vk::Format::B8G8R8A8_UNORM, vr::EColorSpace::Linear),
+            vk::Format::B8G8R8A8_UNORM
+        );
+        assert_eq!(
+            get_colorspace_corrected_format(vk::Format::R8G8B8A8_UNORM, vr::EColorSpace::Gamma),
+            vk::Format::R8G8B8A8_SRGB
+        );
+    }
+}
@@ -1,12 +1,19 @@
 mod action_manifest;
+mod action_state;
+mod analog_transform;
 mod custom_bindings;
+mod grip_curve;
 mod legacy;
+mod lost_tracking;
+mod pose_filter;
 mod profiles;
 mod skeletal;
+mod source_type;
 
 #[cfg(test)]
 mod tests;
 
+pub use action_state::ActionStateReader;
 use profiles::MainAxisType;
 pub use profiles::{InteractionProfile, Profiles};
 use skeletal::FingerState;
@@ -14,19 +21,24 @@ use skeletal::SkeletalInputActionData;
 
 use crate::{
     openxr_data::{self, Hand, OpenXrData, SessionData},
-    tracy_span, AtomicF32,
+    tracy_span, xr_call, AtomicF32,
 };
+use analog_transform::AnalogTransform;
 use custom_bindings::{BindingData, GrabActions};
+use grip_curve::GripCurve;
 use legacy::{setup_legacy_bindings, LegacyActionData};
-use log::{debug, info, trace, warn};
+use log::{debug, error, info, trace, warn};
+use lost_tracking::LostTracking;
 use openvr::{self as vr, space_relation_to_openvr_pose};
 use openxr as xr;
+use pose_filter::PoseFilter;
 use slotmap::{new_key_type, Key, KeyData, SecondaryMap, SlotMap};
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::ffi::{c_char, CStr, CString};
 use std::mem::ManuallyDrop;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
 new_key_type! {
@@ -44,15 +56,31 @@ pub struct Input<C: openxr_data::Compositor> {
     input_source_map: RwLock<SlotMap<InputSourceKey, CString>>,
     left_hand_key: InputSourceKey,
     right_hand_key: InputSourceKey,
+    head_key: InputSourceKey,
+    gamepad_key: InputSourceKey,
+    /// Subaction paths for the head and gamepad input sources. Actions are only declared with the
+    /// hand subaction paths, so these are resolved but have no action state of their own.
+    head_path: xr::Path,
+    gamepad_path: xr::Path,
     action_map: RwLock<SlotMap<ActionKey, Action>>,
     set_map: RwLock<SlotMap<ActionSetKey, String>>,
     loaded_actions_path: OnceLock<PathBuf>,
+    /// Hash of the contents of the currently loaded action manifest
+    loaded_actions_hash: Mutex<Option<u64>>,
     cached_poses: Mutex<CachedSpaces>,
+    pose_filters: [PoseFilter; 2],
+    lost_tracking: [LostTracking; 2],
+    /// Response curves for analog grips, by interaction profile. Profiles without an entry are
+    /// linear.
+    grip_curves: HashMap<xr::Path, GripCurve>,
     legacy_state: legacy::LegacyState,
     skeletal_tracking_level: RwLock<vr::EVRSkeletalTrackingLevel>,
-    profile_map: HashMap<xr::Path, &'static profiles::ProfileProperties>,
+    profile_map: HashMap<xr::Path, &'static dyn InteractionProfile>,
     estimated_finger_state: [Mutex<FingerState>; 2],
     events: Mutex<VecDeque<InputEvent>>,
+    /// When cleared, the game's action sets aren't synced, so all of its actions read inactive.
+    /// Only the legacy and skeletal sets are, so devices are still tracked.
+    input_enabled: AtomicBool,
 }
 
 struct InputEvent {
@@ -66,6 +94,40 @@ struct Action {
     path: String,
 }
 
+/// Render model component that hand skeletons are rooted on (k_pch_Controller_Component_HandGrip).
+const HAND_ROOT_COMPONENT: &CStr = c"handgrip";
+
+/// Sections of an origin's localized name (EVRInputStringBits).
+const INPUT_STRING_HAND: i32 = 0x01;
+const INPUT_STRING_CONTROLLER_TYPE: i32 = 0x02;
+
+/// Normalizes an action or action set path the way SteamVR does, so that paths like
+/// `actions/main/in/Jump` and `/actions//main/in/jump` map to the same handle as
+/// `/actions/main/in/jump`.
+fn normalize_action_path(path: &str) -> String {
+    let mut ret = String::with_capacity(path.len() + 1);
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        ret.push('/');
+        ret.push_str(&segment.to_lowercase());
+    }
+    ret
+}
+
+/// Pose action data for actions that aren't bound or can't be located.
+fn inactive_pose_data() -> vr::InputPoseActionData_t {
+    vr::InputPoseActionData_t {
+        bActive: false,
+        activeOrigin: vr::k_ulInvalidInputValueHandle,
+        pose: vr::TrackedDevicePose_t {
+            mDeviceToAbsoluteTracking: xr::Posef::IDENTITY.into(),
+            eTrackingResult: vr::ETrackingResult::Uninitialized,
+            bPoseIsValid: false,
+            bDeviceIsConnected: false,
+            ..Default::default()
+        },
+    }
+}
+
 struct WriteOnDrop<T> {
     value: ManuallyDrop<T>,
     ptr: *mut T,
@@ -94,6 +156,10 @@ impl<C: openxr_data::Compositor> Input<C> {
         let mut map = SlotMap::with_key();
         let left_hand_key = map.insert(c"/user/hand/left".into());
         let right_hand_key = map.insert(c"/user/hand/right".into());
+        let head_key = map.insert(c"/user/head".into());
+        let gamepad_key = map.insert(c"/user/gamepad".into());
+        let head_path = openxr.instance.string_to_path("/user/head").unwrap();
+        let gamepad_path = openxr.instance.string_to_path("/user/gamepad").unwrap();
         let profile_map = Profiles::get()
             .profiles_iter()
             .map(|profile| {
@@ -102,10 +168,11 @@ impl<C: openxr_data::Compositor> Input<C> {
                         .instance
                         .string_to_path(profile.profile_path())
                         .unwrap(),
-                    profile.properties(),
+                    profile,
                 )
             })
             .collect();
+        let grip_curves = grip_curve::grip_curves_from_env(&openxr.instance);
 
         Self {
             openxr,
@@ -114,9 +181,17 @@ impl<C: openxr_data::Compositor> Input<C> {
             action_map: Default::default(),
             set_map: Default::default(),
             loaded_actions_path: OnceLock::new(),
+            loaded_actions_hash: Mutex::default(),
             left_hand_key,
             right_hand_key,
+            head_key,
+            gamepad_key,
+            head_path,
+            gamepad_path,
             cached_poses: Mutex::default(),
+            pose_filters: [PoseFilter::from_env(), PoseFilter::from_env()],
+            lost_tracking: [LostTracking::from_env(), LostTracking::from_env()],
+            grip_curves,
             legacy_state: Default::default(),
             skeletal_tracking_level: RwLock::new(vr::EVRSkeletalTrackingLevel::Estimated),
             profile_map,
@@ -125,6 +200,20 @@ impl<C: openxr_data::Compositor> Input<C> {
                 Mutex::new(FingerState::new()),
             ],
             events: Mutex::default(),
+            input_enabled: (!crate::env_flag("XRIZER_DISABLE_INPUT")).into(),
+        }
+    }
+
+    pub fn input_enabled(&self) -> bool {
+        self.input_enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_input_enabled(&self, enabled: bool) {
+        if self.input_enabled.swap(enabled, Ordering::Relaxed) != enabled {
+            info!(
+                "Game input {}",
+                if enabled { "enabled" } else { "disabled" }
+            );
         }
     }
 
@@ -135,85 +224,82 @@ impl<C: openxr_data::Compositor> Input<C> {
             match InputSourceKey::from(KeyData::from_ffi(handle)) {
                 x if x == self.left_hand_key => Some(self.openxr.left_hand.subaction_path),
                 x if x == self.right_hand_key => Some(self.openxr.right_hand.subaction_path),
+                x if x == self.head_key => Some(self.head_path),
+                x if x == self.gamepad_key => Some(self.gamepad_path),
                 _ => None,
             }
         }
     }
 
-    fn state_from_bindings_left_right(
-        &self,
-        action: vr::VRActionHandle_t,
-    ) -> Option<(xr::ActionState<bool>, vr::VRInputValueHandle_t)> {
-        debug_assert!(self.left_hand_key.0.as_ffi() != 0);
-        debug_assert!(self.right_hand_key.0.as_ffi() != 0);
-        let left_state = self.state_from_bindings(action, self.left_hand_key.0.as_ffi());
-
-        match left_state {
-            None => self.state_from_bindings(action, self.right_hand_key.0.as_ffi()),
-            Some((left, _)) => {
-                if left.is_active && left.current_state {
-                    return left_state;
-                }
-                let right_state = self.state_from_bindings(action, self.right_hand_key.0.as_ffi());
-                match right_state {
-                    None => left_state,
-                    Some((right, _)) => {
-                        if right.is_active && right.current_state {
-                            return right_state;
-                        }
-                        if left.is_active {
-                            return left_state;
-                        }
-                        right_state
-                    }
-                }
-            }
-        }
+    /// Whether actions were declared with this subaction path, and can therefore be queried with it.
+    fn is_action_subaction_path(&self, path: xr::Path) -> bool {
+        path == xr::Path::NULL
+            || path == self.openxr.left_hand.subaction_path
+            || path == self.openxr.right_hand.subaction_path
     }
 
-    fn state_from_bindings(
+    /// Returns the origins of the hands an action is currently bound to. If `restrict_to_device`
+    /// is a hand, only that hand's origin is returned, if it's bound.
+    fn action_origins(
         &self,
         action: vr::VRActionHandle_t,
         restrict_to_device: vr::VRInputValueHandle_t,
-    ) -> Option<(xr::ActionState<bool>, vr::VRInputValueHandle_t)> {
-        let subaction = self.subaction_path_from_handle(restrict_to_device)?;
-        if subaction == xr::Path::NULL {
-            return self.state_from_bindings_left_right(action);
-        }
-
-        let session = self.openxr.session_data.get();
-        let Ok(loaded_actions) = session.input_data.loaded_actions.get()?.read() else {
-            return None;
+    ) -> Result<Vec<vr::VRInputValueHandle_t>, vr::EVRInputError> {
+        let subaction_path = self
+            .subaction_path_from_handle(restrict_to_device)
+            .ok_or(vr::EVRInputError::InvalidDevice)?;
+
+        // Poses and skeletons aren't bound to sources, so their hands come from the bindings.
+        let unsourced_hands = {
+            let session_data = self.openxr.session_data.get();
+            let loaded = session_data
+                .input_data
+                .get_loaded_actions()
+                .ok_or(vr::EVRInputError::InvalidHandle)?;
+            match loaded.try_get_action(action)? {
+                ActionData::Pose => Some(
+                    [Hand::Left, Hand::Right]
+                        .into_iter()
+                        .filter(|hand| {
+                            let hand_info = match hand {
+                                Hand::Left => &self.openxr.left_hand,
+                                Hand::Right => &self.openxr.right_hand,
+                            };
+                            loaded
+                                .try_get_pose(action, hand_info.profile_path.load())
+                                .is_ok_and(|bound| match hand {
+                                    Hand::Left => bound.left.is_some(),
+                                    Hand::Right => bound.right.is_some(),
+                                })
+                        })
+                        .collect(),
+                ),
+                ActionData::Skeleton { hand, .. } => Some(vec![*hand]),
+                _ => None,
+            }
         };
-
-        let interaction_profile = session
-            .session
-            .current_interaction_profile(subaction)
-            .ok()?;
-        let bindings = loaded_actions
-            .try_get_bindings(action, interaction_profile)
-            .ok()?;
-        let extra_data = loaded_actions.try_get_extra(action).ok()?;
-
-        let mut best_state: Option<xr::ActionState<bool>> = None;
-
-        for x in bindings.iter() {
-            let Ok(Some(state)) = x.state(&session, extra_data, subaction) else {
-                continue;
-            };
-
-            if state.is_active
-                && (!best_state.is_some_and(|x| x.is_active)
-                    || state.current_state && !best_state.is_some_and(|x| x.current_state))
-            {
-                best_state = Some(state);
-                if state.current_state {
-                    break;
-                }
+        let bound_hands: Vec<Hand> = match unsourced_hands {
+            Some(hands) => hands,
+            None => {
+                let sources = self.bound_sources(action, vr::k_ulInvalidInputValueHandle)?;
+                [Hand::Left, Hand::Right]
+                    .into_iter()
+                    .filter(|hand| sources.iter().any(|source| source.hand == *hand))
+                    .collect()
             }
-        }
+        };
 
-        best_state.map(|x| (x, restrict_to_device))
+        Ok(bound_hands
+            .into_iter()
+            .filter_map(|hand| {
+                let (hand_info, key) = match hand {
+                    Hand::Left => (&self.openxr.left_hand, self.left_hand_key),
+                    Hand::Right => (&self.openxr.right_hand, self.right_hand_key),
+                };
+                (subaction_path == xr::Path::NULL || subaction_path == hand_info.subaction_path)
+                    .then(|| key.data().as_ffi())
+            })
+            .collect())
     }
 }
 
@@ -301,8 +387,8 @@ macro_rules! get_action_from_handle {
 macro_rules! get_subaction_path {
     ($self:expr, $restrict:expr, $data:expr) => {
         match $self.subaction_path_from_handle($restrict) {
-            Some(p) => p,
-            None => {
+            Some(p) if $self.is_action_subaction_path(p) => p,
+            _ => {
                 unsafe {
                     $data.write(Default::default());
                 }
@@ -315,11 +401,27 @@ macro_rules! get_subaction_path {
 impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
     fn GetBindingVariant(
         &self,
-        _: vr::VRInputValueHandle_t,
-        _: *mut c_char,
-        _: u32,
+        device_path: vr::VRInputValueHandle_t,
+        variant: *mut c_char,
+        variant_size: u32,
     ) -> vr::EVRInputError {
-        crate::warn_unimplemented!("GetBindingVariant");
+        if variant.is_null() || variant_size == 0 {
+            return vr::EVRInputError::None;
+        }
+
+        // Games pick controller specific models with this, so it's the controller type.
+        let hand = match InputSourceKey::from(KeyData::from_ffi(device_path)) {
+            x if x == self.left_hand_key => Some(Hand::Left),
+            x if x == self.right_hand_key => Some(Hand::Right),
+            _ => None,
+        };
+        let name = hand
+            .and_then(|hand| self.get_profile_data(hand))
+            .map(|data| data.openvr_controller_type.to_bytes())
+            .unwrap_or_default();
+
+        let variant = unsafe { std::slice::from_raw_parts_mut(variant, variant_size as usize) };
+        crate::copy_truncated(variant, name);
         vr::EVRInputError::None
     }
     fn OpenBindingUI(
@@ -332,7 +434,13 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         todo!()
     }
     fn IsUsingLegacyInput(&self) -> bool {
-        todo!()
+        self.openxr
+            .session_data
+            .get()
+            .input_data
+            .loaded_actions
+            .get()
+            .is_none()
     }
     fn GetComponentStateForBinding(
         &self,
@@ -363,15 +471,34 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
     }
     fn GetActionBindingInfo(
         &self,
-        _: vr::VRActionHandle_t,
-        _: *mut vr::InputBindingInfo_t,
-        _: u32,
-        _: u32,
+        action: vr::VRActionHandle_t,
+        binding_info: *mut vr::InputBindingInfo_t,
+        binding_info_size: u32,
+        binding_info_count: u32,
         returned_binding_info_count: *mut u32,
     ) -> vr::EVRInputError {
-        crate::warn_unimplemented!("GetActionBindingInfo");
+        if binding_info_size as usize != std::mem::size_of::<vr::InputBindingInfo_t>() {
+            return vr::EVRInputError::InvalidParam;
+        }
+        let sources = match self.bound_sources(action, vr::k_ulInvalidInputValueHandle) {
+            Ok(sources) => sources,
+            Err(e) => return e,
+        };
+
         if !returned_binding_info_count.is_null() {
-            unsafe { *returned_binding_info_count = 0 };
+            unsafe { *returned_binding_info_count = sources.len() as u32 };
+        }
+        if binding_info_count == 0 || binding_info.is_null() {
+            return vr::EVRInputError::None;
+        }
+        if (binding_info_count as usize) < sources.len() {
+            return vr::EVRInputError::BufferTooSmall;
+        }
+
+        let infos =
+            unsafe { std::slice::from_raw_parts_mut(binding_info, binding_info_count as usize) };
+        for (info, source) in infos.iter_mut().zip(&sources) {
+            *info = source.binding_info();
         }
         vr::EVRInputError::None
     }
@@ -393,9 +520,12 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         }
 
         // Superhot needs this device index to render controllers.
-        let index = match key {
-            x if x == self.left_hand_key => Hand::Left as u32,
-            x if x == self.right_hand_key => Hand::Right as u32,
+        // The hands are also the origins of skeletal actions, so they report the component the
+        // hand skeleton is attached to.
+        let (index, component) = match key {
+            x if x == self.left_hand_key => (Hand::Left as u32, HAND_ROOT_COMPONENT),
+            x if x == self.right_hand_key => (Hand::Right as u32, HAND_ROOT_COMPONENT),
+            x if x == self.head_key => (vr::k_unTrackedDeviceIndex_Hmd, c""),
             _ => {
                 unsafe {
                     info.write(Default::default());
@@ -404,33 +534,93 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
             }
         };
 
+        let mut component_name = [0; 128];
+        for (dst, src) in component_name.iter_mut().zip(component.to_bytes()) {
+            *dst = *src as c_char;
+        }
         unsafe {
             *info.as_mut().unwrap() = vr::InputOriginInfo_t {
                 devicePath: handle,
                 trackedDeviceIndex: index,
-                rchRenderModelComponentName: [0; 128],
+                rchRenderModelComponentName: component_name,
             };
         }
         vr::EVRInputError::None
     }
     fn GetOriginLocalizedName(
         &self,
-        _: vr::VRInputValueHandle_t,
-        _: *mut c_char,
-        _: u32,
-        _: i32,
+        origin: vr::VRInputValueHandle_t,
+        name_out: *mut c_char,
+        name_size: u32,
+        sections: i32,
     ) -> vr::EVRInputError {
-        crate::warn_unimplemented!("GetOriginLocalizedName");
+        let key = InputSourceKey::from(KeyData::from_ffi(origin));
+        let hand = match key {
+            x if x == self.left_hand_key => Some(Hand::Left),
+            x if x == self.right_hand_key => Some(Hand::Right),
+            _ => None,
+        };
+
+        let name = match hand {
+            Some(hand) => {
+                let mut parts = Vec::new();
+                if sections & INPUT_STRING_HAND != 0 {
+                    parts.push(match hand {
+                        Hand::Left => "Left Hand",
+                        Hand::Right => "Right Hand",
+                    });
+                }
+                if sections & INPUT_STRING_CONTROLLER_TYPE != 0 {
+                    if let Some(data) = self.get_profile_data(hand) {
+                        parts.extend(data.model.get(hand).to_str().ok());
+                    }
+                }
+                // Origins are whole hands rather than individual inputs, so there's no input
+                // source section.
+                parts.join(" ")
+            }
+            None => {
+                let map = self.input_source_map.read().unwrap();
+                let Some(path) = map.get(key) else {
+                    return vr::EVRInputError::InvalidHandle;
+                };
+                path.to_string_lossy().into_owned()
+            }
+        };
+
+        if name_out.is_null() || name_size == 0 {
+            return vr::EVRInputError::BufferTooSmall;
+        }
+        let name_out = unsafe { std::slice::from_raw_parts_mut(name_out, name_size as usize) };
+        crate::copy_truncated(name_out, name.as_bytes());
         vr::EVRInputError::None
     }
     fn GetActionOrigins(
         &self,
         _: vr::VRActionSetHandle_t,
-        _: vr::VRActionHandle_t,
-        _: *mut vr::VRInputValueHandle_t,
-        _: u32,
+        action: vr::VRActionHandle_t,
+        origins_out: *mut vr::VRInputValueHandle_t,
+        origin_out_count: u32,
     ) -> vr::EVRInputError {
-        crate::warn_unimplemented!("GetActionOrigins");
+        let origins = match self.action_origins(action, vr::k_ulInvalidInputValueHandle) {
+            Ok(origins) => origins,
+            Err(e) => return e,
+        };
+        if origin_out_count == 0 {
+            return vr::EVRInputError::None;
+        }
+        if origins_out.is_null() {
+            return vr::EVRInputError::InvalidParam;
+        }
+
+        // Entries past the action's origins are set to the invalid handle.
+        let out = unsafe { std::slice::from_raw_parts_mut(origins_out, origin_out_count as usize) };
+        for (idx, out) in out.iter_mut().enumerate() {
+            *out = origins
+                .get(idx)
+                .copied()
+                .unwrap_or(vr::k_ulInvalidInputValueHandle);
+        }
         vr::EVRInputError::None
     }
     fn TriggerHapticVibrationAction(
@@ -443,7 +633,10 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         restrict_to_device: vr::VRInputValueHandle_t,
     ) -> vr::EVRInputError {
         get_action_from_handle!(self, action, session_data, action);
-        let Some(subaction_path) = self.subaction_path_from_handle(restrict_to_device) else {
+        let Some(subaction_path) = self
+            .subaction_path_from_handle(restrict_to_device)
+            .filter(|path| self.is_action_subaction_path(*path))
+        else {
             return vr::EVRInputError::None;
         };
 
@@ -491,24 +684,26 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
     fn GetSkeletalSummaryData(
         &self,
         action: vr::VRActionHandle_t,
-        _: vr::EVRSummaryType,
+        summary_type: vr::EVRSummaryType,
         data: *mut vr::VRSkeletalSummaryData_t,
     ) -> vr::EVRInputError {
-        crate::warn_unimplemented!("GetSkeletalSummaryData");
-        get_action_from_handle!(self, action, session_data, _action);
-        unsafe {
-            data.write(vr::VRSkeletalSummaryData_t {
-                flFingerSplay: [0.2; 4],
-                flFingerCurl: [0.0; 5],
-            })
+        if data.is_null() {
+            return vr::EVRInputError::InvalidParam;
         }
+        get_action_from_handle!(self, action, session_data, action);
+        let ActionData::Skeleton { hand, .. } = action else {
+            return vr::EVRInputError::WrongType;
+        };
+
+        let summary = self.get_summary_data(&session_data, *hand, summary_type);
+        unsafe { data.write(summary) };
         vr::EVRInputError::None
     }
     fn GetSkeletalBoneData(
         &self,
         handle: vr::VRActionHandle_t,
         transform_space: vr::EVRSkeletalTransformSpace,
-        _motion_range: vr::EVRSkeletalMotionRange,
+        motion_range: vr::EVRSkeletalMotionRange,
         transform_array: *mut vr::VRBoneTransform_t,
         transform_array_count: u32,
     ) -> vr::EVRInputError {
@@ -530,12 +725,19 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
                 &self.openxr,
                 &session_data,
                 transform_space,
+                motion_range,
                 hand_tracker,
                 *hand,
                 transforms,
             )
         } else {
-            self.get_estimated_bones(&session_data, transform_space, *hand, transforms);
+            self.get_estimated_bones(
+                &session_data,
+                transform_space,
+                motion_range,
+                *hand,
+                transforms,
+            );
         }
 
         vr::EVRInputError::None
@@ -595,20 +797,49 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
     }
     fn GetBoneName(
         &self,
-        _: vr::VRActionHandle_t,
-        _: vr::BoneIndex_t,
-        _: *mut c_char,
-        _: u32,
+        handle: vr::VRActionHandle_t,
+        bone: vr::BoneIndex_t,
+        name: *mut c_char,
+        name_size: u32,
     ) -> vr::EVRInputError {
-        todo!()
+        get_action_from_handle!(self, handle, session_data, action);
+        let ActionData::Skeleton { hand, .. } = action else {
+            return vr::EVRInputError::WrongType;
+        };
+        let Some(bone_name) = usize::try_from(bone)
+            .ok()
+            .and_then(|bone| skeletal::bone_name(bone, *hand))
+        else {
+            return vr::EVRInputError::InvalidParam;
+        };
+        if name.is_null() || name_size == 0 {
+            return vr::EVRInputError::BufferTooSmall;
+        }
+
+        // Names that don't fit are truncated.
+        let out = unsafe { std::slice::from_raw_parts_mut(name, name_size as usize) };
+        crate::copy_truncated(out, bone_name.as_bytes());
+        vr::EVRInputError::None
     }
     fn GetBoneHierarchy(
         &self,
-        _: vr::VRActionHandle_t,
-        _: *mut vr::BoneIndex_t,
-        _: u32,
+        handle: vr::VRActionHandle_t,
+        parent_indices: *mut vr::BoneIndex_t,
+        index_array_count: u32,
     ) -> vr::EVRInputError {
-        todo!()
+        get_action_from_handle!(self, handle, session_data, action);
+        if !matches!(action, ActionData::Skeleton { .. }) {
+            return vr::EVRInputError::WrongType;
+        }
+        if parent_indices.is_null() || index_array_count < skeletal::HandSkeletonBone::Count as u32
+        {
+            return vr::EVRInputError::InvalidParam;
+        }
+
+        let parents =
+            unsafe { std::slice::from_raw_parts_mut(parent_indices, skeletal::BONE_PARENTS.len()) };
+        parents.copy_from_slice(&skeletal::BONE_PARENTS);
+        vr::EVRInputError::None
     }
     fn GetBoneCount(&self, handle: vr::VRActionHandle_t, count: *mut u32) -> vr::EVRInputError {
         get_action_from_handle!(self, handle, session_data, action);
@@ -634,36 +865,48 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         &self,
         action: vr::VRActionHandle_t,
         action_data: *mut vr::InputSkeletalActionData_t,
-        _action_data_size: u32,
+        action_data_size: u32,
     ) -> vr::EVRInputError {
-        //assert_eq!(
-        //    action_data_size as usize,
-        //    std::mem::size_of::<vr::InputSkeletalActionData_t>()
-        //);
+        if action_data_size as usize != std::mem::size_of::<vr::InputSkeletalActionData_t>() {
+            warn!(
+                "Got an unexpected size for InputSkeletalActionData_t (expected {}, got {action_data_size})",
+                std::mem::size_of::<vr::InputSkeletalActionData_t>()
+            );
+            return vr::EVRInputError::InvalidParam;
+        }
+        let Some(action_data) = (unsafe { action_data.as_mut() }) else {
+            return vr::EVRInputError::InvalidParam;
+        };
 
-        let data = self.openxr.session_data.get();
-        let Some(loaded) = data.input_data.get_loaded_actions() else {
-            return vr::EVRInputError::InvalidHandle;
+        get_action_from_handle!(self, action, session_data, action);
+        let ActionData::Skeleton { hand, .. } = action else {
+            return vr::EVRInputError::WrongType;
         };
-        let origin = match loaded.try_get_action(action) {
-            Ok(ActionData::Skeleton { hand, .. }) => match hand {
-                Hand::Left => self.left_hand_key.data().as_ffi(),
-                Hand::Right => self.right_hand_key.data().as_ffi(),
-            },
-            Ok(_) => return vr::EVRInputError::WrongType,
-            Err(e) => return e,
+        let (origin, hand_info) = match hand {
+            Hand::Left => (self.left_hand_key, &self.openxr.left_hand),
+            Hand::Right => (self.right_hand_key, &self.openxr.right_hand),
         };
-        let legacy = data.input_data.legacy_actions.get().unwrap();
-        unsafe {
-            std::ptr::addr_of_mut!((*action_data).bActive).write(
+
+        let active = session_data
+            .input_data
+            .legacy_actions
+            .get()
+            .is_some_and(|legacy| {
                 legacy
                     .actions
                     .grip_pose
-                    .is_active(&data.session, xr::Path::NULL)
-                    .unwrap(),
-            );
-            std::ptr::addr_of_mut!((*action_data).activeOrigin).write(origin);
-        }
+                    .is_active(&session_data.session, hand_info.subaction_path)
+                    .unwrap()
+            });
+
+        *action_data = vr::InputSkeletalActionData_t {
+            bActive: active,
+            activeOrigin: if active {
+                origin.data().as_ffi()
+            } else {
+                vr::k_ulInvalidInputValueHandle
+            },
+        };
         vr::EVRInputError::None
     }
     fn GetPoseActionDataForNextFrame(
@@ -699,7 +942,7 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         macro_rules! no_data {
             () => {{
                 unsafe {
-                    action_data.write(Default::default());
+                    action_data.write(inactive_pose_data());
                 }
                 return vr::EVRInputError::None;
             }};
@@ -785,11 +1028,15 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
 
         drop(loaded);
         drop(data);
+        let Some(pose) = self.get_controller_pose(hand, Some(origin)) else {
+            trace!("couldn't locate {hand:?} hand for pose action");
+            no_data!()
+        };
         unsafe {
             action_data.write(vr::InputPoseActionData_t {
                 bActive: true,
                 activeOrigin: active_origin,
-                pose: self.get_controller_pose(hand, Some(origin)).expect("wtf"),
+                pose,
             })
         }
 
@@ -827,76 +1074,13 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         );
 
         let mut out = WriteOnDrop::new(action_data);
-        get_action_from_handle!(self, handle, session_data, action, loaded);
-        let subaction_path = get_subaction_path!(self, restrict_to_device, action_data);
-
-        let mut active_hand = restrict_to_device;
-        let (state, delta) = match action {
-            ActionData::Vector1 { action, last_value } => {
-                let mut state = action.state(&session_data.session, subaction_path).unwrap();
-
-                // It's generally not clear how SteamVR handles float actions with multiple bindings;
-                //   so emulate OpenXR, which takes maximum among active actions
-                if let Some((binding_state, binding_source)) =
-                    self.state_from_bindings(handle, restrict_to_device)
-                {
-                    if binding_state.is_active
-                        && (binding_state.current_state && state.current_state != 1.0
-                            || !state.is_active)
-                    {
-                        state = xr::ActionState {
-                            current_state: if binding_state.current_state {
-                                1.0
-                            } else {
-                                0.0
-                            },
-                            is_active: binding_state.is_active,
-                            changed_since_last_sync: binding_state.changed_since_last_sync,
-                            last_change_time: binding_state.last_change_time,
-                        };
-                        active_hand = binding_source;
-                    }
-                }
-
-                let delta = xr::Vector2f {
-                    x: state.current_state - last_value.swap(state.current_state),
-                    y: 0.0,
-                };
-                (
-                    xr::ActionState::<xr::Vector2f> {
-                        current_state: xr::Vector2f {
-                            x: state.current_state,
-                            y: 0.0,
-                        },
-                        changed_since_last_sync: state.changed_since_last_sync,
-                        last_change_time: state.last_change_time,
-                        is_active: state.is_active,
-                    },
-                    delta,
-                )
-            }
-            ActionData::Vector2 { action, last_value } => {
-                let state = action.state(&session_data.session, subaction_path).unwrap();
-                let delta = xr::Vector2f {
-                    x: state.current_state.x - last_value.0.swap(state.current_state.x),
-                    y: state.current_state.y - last_value.1.swap(state.current_state.y),
-                };
-                (state, delta)
+        match self.read_action_states(|states| states.analog(handle, restrict_to_device)) {
+            Ok(data) => {
+                *out.value = data;
+                vr::EVRInputError::None
             }
-            _ => return vr::EVRInputError::WrongType,
-        };
-
-        *out.value = vr::InputAnalogActionData_t {
-            bActive: state.is_active,
-            activeOrigin: active_hand,
-            x: state.current_state.x,
-            deltaX: delta.x,
-            y: state.current_state.y,
-            deltaY: delta.y,
-            ..Default::default()
-        };
-
-        vr::EVRInputError::None
+            Err(e) => e,
+        }
     }
 
     fn GetDigitalActionData(
@@ -912,36 +1096,13 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         );
 
         let mut out = WriteOnDrop::new(action_data);
-
-        get_action_from_handle!(self, handle, session_data, action);
-        let subaction_path = get_subaction_path!(self, restrict_to_device, action_data);
-        let ActionData::Bool(action) = &action else {
-            return vr::EVRInputError::WrongType;
-        };
-
-        let mut state = action.state(&session_data.session, subaction_path).unwrap();
-
-        let mut active_hand = restrict_to_device;
-        if let Some((binding_state, binding_source)) =
-            self.state_from_bindings(handle, restrict_to_device)
-        {
-            if binding_state.is_active
-                && (binding_state.current_state && !state.current_state || !state.is_active)
-            {
-                state = binding_state;
-                active_hand = binding_source;
+        match self.read_action_states(|states| states.digital(handle, restrict_to_device)) {
+            Ok(data) => {
+                *out.value = data;
+                vr::EVRInputError::None
             }
+            Err(e) => e,
         }
-
-        *out.value = vr::InputDigitalActionData_t {
-            bActive: state.is_active,
-            bState: state.current_state,
-            activeOrigin: active_hand,
-            bChanged: state.changed_since_last_sync,
-            fUpdateTime: 0.0, // TODO
-        };
-
-        vr::EVRInputError::None
     }
 
     fn UpdateActionState(
@@ -978,6 +1139,8 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         let mut sync_sets = Vec::with_capacity(active_sets.len() + 1);
         {
             tracy_span!("UpdateActionState generate active sets");
+            let mut activated = Vec::with_capacity(active_sets.len());
+            let input_enabled = self.input_enabled();
             for set in active_sets {
                 let key = ActionSetKey::from(KeyData::from_ffi(set.ulActionSet));
                 let name = set_map.get(key);
@@ -985,6 +1148,16 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
                     debug!("Application passed invalid action set key: {key:?} ({name:?})");
                     return vr::EVRInputError::InvalidHandle;
                 };
+                // Sets are always synced for all devices, so passing one again (e.g. as both a
+                // primary and secondary set) changes nothing - but some runtimes reject
+                // duplicates.
+                if activated.contains(&key) {
+                    continue;
+                }
+                activated.push(key);
+                if !input_enabled {
+                    continue;
+                }
                 debug!("Activating set {}", name.unwrap());
                 sync_sets.push(set.into());
             }
@@ -996,10 +1169,7 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
             self.legacy_state.on_action_sync();
         }
 
-        {
-            tracy_span!("xrSyncActions");
-            data.session.sync_actions(&sync_sets).unwrap();
-        }
+        xr_call!("xrSyncActions", data.session.sync_actions(&sync_sets)).unwrap();
 
         vr::EVRInputError::None
     }
@@ -1037,9 +1207,7 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         action_name: *const c_char,
         handle: *mut vr::VRActionHandle_t,
     ) -> vr::EVRInputError {
-        let name = unsafe { CStr::from_ptr(action_name) }
-            .to_string_lossy()
-            .to_lowercase();
+        let name = normalize_action_path(&unsafe { CStr::from_ptr(action_name) }.to_string_lossy());
         let guard = self.action_map.read().unwrap();
         let val = match guard.iter().find(|(_, action)| action.path == name) {
             Some((key, _)) => key.data().as_ffi(),
@@ -1064,9 +1232,8 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         action_set_name: *const c_char,
         handle: *mut vr::VRActionSetHandle_t,
     ) -> vr::EVRInputError {
-        let name = unsafe { CStr::from_ptr(action_set_name) }
-            .to_string_lossy()
-            .to_lowercase();
+        let name =
+            normalize_action_path(&unsafe { CStr::from_ptr(action_set_name) }.to_string_lossy());
         let guard = self.set_map.read().unwrap();
         let val = match guard.iter().find(|(_, set)| **set == name) {
             Some((key, _)) => key.data().as_ffi(),
@@ -1092,17 +1259,39 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
         }
         let path = unsafe { CStr::from_ptr(path) }.to_string_lossy();
         let path = std::path::Path::new(&*path);
+
+        // Some games set the same manifest multiple times - avoid reloading it (and restarting
+        // the session) if it hasn't changed.
+        let mut data = self.openxr.session_data.get();
+        let reloading = data.input_data.loaded_actions.get().is_some();
+        if reloading && self.loaded_actions_path.get().is_some_and(|p| p == path) {
+            let hash = action_manifest::hash_manifest(path);
+            if hash.is_some() && hash == *self.loaded_actions_hash.lock().unwrap() {
+                debug!("action manifest {path:?} is unchanged, not reloading");
+                return vr::EVRInputError::None;
+            }
+        }
         info!("loading action manifest from {path:?}");
 
         // We need to restart the session if the legacy actions have already been attached.
-        let mut data = self.openxr.session_data.get();
         if data.input_data.legacy_actions.get().is_some() {
             drop(data);
             self.openxr.restart_session();
             data = self.openxr.session_data.get();
         }
         match self.load_action_manifest(&data, path) {
-            Ok(_) => vr::EVRInputError::None,
+            Ok(_) => {
+                // Let the game know it should refresh anything derived from the old bindings,
+                // such as button prompts.
+                if reloading {
+                    self.events.lock().unwrap().push_back(InputEvent {
+                        ty: vr::EVREventType::ActionBindingReloaded,
+                        index: vr::k_unTrackedDeviceIndexInvalid,
+                        data: Default::default(),
+                    });
+                }
+                vr::EVRInputError::None
+            }
             Err(e) => e,
         }
     }
@@ -1152,7 +1341,13 @@ impl<C: openxr_data::Compositor> Input<C> {
         origin: Option<vr::ETrackingUniverseOrigin>,
     ) {
         tracy_span!();
-        poses[0] = self.get_hmd_pose(origin);
+        // Devices we don't know about are reported as disconnected, rather than left as whatever
+        // the app's array happened to hold.
+        poses.fill(Default::default());
+        let Some(hmd) = poses.first_mut() else {
+            return;
+        };
+        *hmd = self.get_hmd_pose(origin);
 
         if poses.len() > Hand::Left as usize {
             poses[Hand::Left as usize] = self
@@ -1174,9 +1369,10 @@ impl<C: openxr_data::Compositor> Input<C> {
             .get_pose_impl(
                 &self.openxr,
                 &data,
-                self.openxr.display_time.get(),
+                self.openxr.pose_time.get(),
                 None,
                 origin.unwrap_or(data.current_origin),
+                None,
             )
             .unwrap()
     }
@@ -1190,13 +1386,42 @@ impl<C: openxr_data::Compositor> Input<C> {
         tracy_span!();
         let mut spaces = self.cached_poses.lock().unwrap();
         let data = self.openxr.session_data.get();
-        spaces.get_pose_impl(
+        let origin = origin.unwrap_or(data.current_origin);
+        let mut pose = spaces.get_pose_impl(
             &self.openxr,
             &data,
-            self.openxr.display_time.get(),
+            self.openxr.pose_time.get(),
             Some(hand),
-            origin.unwrap_or(data.current_origin),
-        )
+            origin,
+            Some(&self.pose_filters[hand as usize - 1]),
+        )?;
+        self.lost_tracking[hand as usize - 1].apply(origin, &mut pose);
+        Some(pose)
+    }
+
+    /// Returns true if the named manifest action is currently held (boolean actions) or
+    /// deflected (analog actions).
+    pub fn is_action_engaged(&self, name: &CStr) -> bool {
+        const ANALOG_THRESHOLD: f32 = 0.1;
+
+        let mut handle = 0;
+        if <Self as vr::IVRInput010_Interface>::GetActionHandle(self, name.as_ptr(), &mut handle)
+            != vr::EVRInputError::None
+        {
+            return false;
+        }
+
+        self.read_action_states(|states| {
+            match states.digital(handle, vr::k_ulInvalidInputValueHandle) {
+                Ok(digital) => digital.bActive && digital.bState,
+                Err(vr::EVRInputError::WrongType) => states
+                    .analog(handle, vr::k_ulInvalidInputValueHandle)
+                    .is_ok_and(|analog| {
+                        analog.bActive && analog.x.hypot(analog.y) > ANALOG_THRESHOLD
+                    }),
+                Err(_) => false,
+            }
+        })
     }
 
     pub fn frame_start_update(&self) {
@@ -1212,18 +1437,24 @@ impl<C: openxr_data::Compositor> Input<C> {
             let loaded = loaded.read().unwrap();
             if !self.openxr.left_hand.connected() || !self.openxr.right_hand.connected() {
                 debug!("no controllers connected - syncing info set");
-                data.session
-                    .sync_actions(&[xr::ActiveActionSet::new(&loaded.info_set)])
-                    .unwrap();
+                xr_call!(
+                    "xrSyncActions",
+                    data.session
+                        .sync_actions(&[xr::ActiveActionSet::new(&loaded.info_set)])
+                )
+                .unwrap();
             }
             return;
         }
 
         match data.input_data.legacy_actions.get() {
             Some(actions) => {
-                data.session
-                    .sync_actions(&[xr::ActiveActionSet::new(&actions.set)])
-                    .unwrap();
+                xr_call!(
+                    "xrSyncActions",
+                    data.session
+                        .sync_actions(&[xr::ActiveActionSet::new(&actions.set)])
+                )
+                .unwrap();
 
                 self.legacy_state.on_action_sync();
             }
@@ -1252,7 +1483,12 @@ impl<C: openxr_data::Compositor> Input<C> {
                     self.openxr.left_hand.subaction_path,
                     self.openxr.right_hand.subaction_path,
                 );
-                setup_legacy_bindings(&self.openxr.instance, &data.session, &legacy);
+                setup_legacy_bindings(
+                    &self.openxr.instance,
+                    &self.openxr.enabled_extensions,
+                    &data.session,
+                    &legacy,
+                );
                 data.input_data
                     .legacy_actions
                     .set(legacy)
@@ -1261,13 +1497,85 @@ impl<C: openxr_data::Compositor> Input<C> {
         }
     }
 
-    fn get_profile_data(&self, hand: Hand) -> Option<&profiles::ProfileProperties> {
+    /// The grip curve for the hand's current interaction profile.
+    fn grip_curve(&self, hand: Hand) -> GripCurve {
+        let hand = match hand {
+            Hand::Left => &self.openxr.left_hand,
+            Hand::Right => &self.openxr.right_hand,
+        };
+        let profile = hand.profile_path.load();
+        self.grip_curves.get(&profile).copied().unwrap_or_default()
+    }
+
+    /// Remaps the state of a float action with the grip curve of the hand it comes from, if the
+    /// action is only bound to analog grips in that hand's interaction profile.
+    fn apply_grip_curve(
+        &self,
+        loaded: &LoadedActions,
+        session: &xr::Session<xr::AnyGraphics>,
+        handle: vr::VRActionHandle_t,
+        action: &xr::Action<f32>,
+        subaction_path: xr::Path,
+        state: &mut xr::ActionState<f32>,
+    ) {
+        if self.grip_curves.is_empty() {
+            return;
+        }
+
+        let hand_info = |hand| match hand {
+            Hand::Left => &self.openxr.left_hand,
+            Hand::Right => &self.openxr.right_hand,
+        };
+        let curve_for = |hand| {
+            let profile = hand_info(hand).profile_path.load();
+            if loaded.is_grip_action(handle, profile) {
+                self.grip_curve(hand)
+            } else {
+                GripCurve::Linear
+            }
+        };
+
+        if subaction_path != xr::Path::NULL {
+            if let Some(hand) = [Hand::Left, Hand::Right]
+                .into_iter()
+                .find(|hand| hand_info(*hand).subaction_path == subaction_path)
+            {
+                state.current_state = curve_for(hand).apply(state.current_state);
+            }
+            return;
+        }
+
+        // OpenXR combines both hands by taking the larger value, but the hands may be using
+        // different curves, so combine them again after applying each hand's curve.
+        if [Hand::Left, Hand::Right]
+            .into_iter()
+            .all(|hand| curve_for(hand) == GripCurve::Linear)
+        {
+            return;
+        }
+        state.current_state = [Hand::Left, Hand::Right]
+            .into_iter()
+            .filter_map(|hand| {
+                let hand_state = action
+                    .state(session, hand_info(hand).subaction_path)
+                    .ok()
+                    .filter(|s| s.is_active)?;
+                Some(curve_for(hand).apply(hand_state.current_state))
+            })
+            .fold(0.0, f32::max);
+    }
+
+    fn get_profile(&self, hand: Hand) -> Option<&'static dyn InteractionProfile> {
         let hand = match hand {
             Hand::Left => &self.openxr.left_hand,
             Hand::Right => &self.openxr.right_hand,
         };
         let profile = hand.profile_path.load();
-        self.profile_map.get(&profile).map(|v| &**v)
+        self.profile_map.get(&profile).copied()
+    }
+
+    fn get_profile_data(&self, hand: Hand) -> Option<&'static profiles::ProfileProperties> {
+        self.get_profile(hand).map(|profile| profile.properties())
     }
 
     pub fn get_controller_string_tracked_property(
@@ -1300,6 +1608,10 @@ impl<C: openxr_data::Compositor> Input<C> {
                     Some(*data.serial_number.get(hand))
                 }
                 vr::ETrackedDeviceProperty::ManufacturerName_String => Some(data.manufacturer_name),
+                // Model swapping mods identify controllers with this and the model number
+                vr::ETrackedDeviceProperty::HardwareRevision_String => {
+                    Some(*data.hardware_revision.get(hand))
+                }
                 _ => None,
             }
         })
@@ -1336,8 +1648,10 @@ impl<C: openxr_data::Compositor> Input<C> {
         hand: Hand,
         property: vr::ETrackedDeviceProperty,
     ) -> Option<u64> {
-        self.get_profile_data(hand).and_then(|data| match property {
-            vr::ETrackedDeviceProperty::SupportedButtons_Uint64 => Some(data.legacy_buttons_mask),
+        self.get_profile(hand).and_then(|profile| match property {
+            vr::ETrackedDeviceProperty::SupportedButtons_Uint64 => {
+                Some(profile.legacy_buttons_mask())
+            }
             _ => None,
         })
     }
@@ -1346,7 +1660,9 @@ impl<C: openxr_data::Compositor> Input<C> {
         // This function is called while a write lock is called on the session, and as such should
         // not use self.openxr.session_data.get().
         if let Some(path) = self.loaded_actions_path.get() {
-            self.load_action_manifest(data, path).unwrap();
+            if let Err(e) = self.load_action_manifest(data, path) {
+                error!("Failed to reload action manifest {path:?} after session restart: {e:?}");
+            }
         }
     }
 
@@ -1401,6 +1717,7 @@ impl CachedSpaces {
         display_time: xr::Time,
         hand: Option<Hand>,
         origin: vr::ETrackingUniverseOrigin,
+        filter: Option<&PoseFilter>,
     ) -> Option<vr::TrackedDevicePose_t> {
         tracy_span!();
         let space = match origin {
@@ -1427,20 +1744,29 @@ impl CachedSpaces {
             };
 
             if let Some(raw) = spaces.try_get_or_init_raw(xr_data, session_data, &legacy.actions) {
-                raw.relate(session_data.get_space_for_origin(origin), display_time)
-                    .unwrap()
+                xr_call!(
+                    "xrLocateSpace",
+                    raw.relate(session_data.get_space_for_origin(origin), display_time)
+                )
+                .unwrap()
             } else {
                 trace!("failed to get raw space, making empty pose");
                 (xr::SpaceLocation::default(), xr::SpaceVelocity::default())
             }
         } else {
-            session_data
-                .view_space
-                .relate(session_data.get_space_for_origin(origin), display_time)
-                .unwrap()
+            xr_call!(
+                "xrLocateSpace",
+                session_data
+                    .view_space
+                    .relate(session_data.get_space_for_origin(origin), display_time)
+            )
+            .unwrap()
         };
 
-        let ret = space_relation_to_openvr_pose(loc, velo);
+        let mut ret = space_relation_to_openvr_pose(loc, velo);
+        if let Some(filter) = filter {
+            filter.apply(origin, &mut ret);
+        }
         Some(*pose.insert(ret))
     }
 }
@@ -1451,6 +1777,12 @@ struct LoadedActions {
     extra_actions: SecondaryMap<ActionKey, ExtraActionData>,
     per_profile_pose_bindings: HashMap<xr::Path, SecondaryMap<ActionKey, BoundPose>>,
     per_profile_bindings: HashMap<xr::Path, SecondaryMap<ActionKey, Vec<BindingData>>>,
+    /// Float actions that are only bound to analog grips in each profile.
+    per_profile_grip_actions: HashMap<xr::Path, SecondaryMap<ActionKey, ()>>,
+    /// Deadzones and inversion from the binding parameters of vector2 actions in each profile, by
+    /// the hand they're bound on.
+    per_profile_analog_transforms:
+        HashMap<xr::Path, SecondaryMap<ActionKey, HashMap<xr::Path, AnalogTransform>>>,
     info_set: xr::ActionSet,
     _info_action: xr::Action<bool>,
 }
@@ -1489,6 +1821,26 @@ impl LoadedActions {
             .ok_or(vr::EVRInputError::InvalidHandle)
     }
 
+    fn is_grip_action(&self, handle: vr::VRActionHandle_t, interaction_profile: xr::Path) -> bool {
+        let key = ActionKey::from(KeyData::from_ffi(handle));
+        self.per_profile_grip_actions
+            .get(&interaction_profile)
+            .is_some_and(|actions| actions.contains_key(key))
+    }
+
+    fn analog_transform(
+        &self,
+        handle: vr::VRActionHandle_t,
+        interaction_profile: xr::Path,
+        hand: xr::Path,
+    ) -> Option<&AnalogTransform> {
+        let key = ActionKey::from(KeyData::from_ffi(handle));
+        self.per_profile_analog_transforms
+            .get(&interaction_profile)?
+            .get(key)?
+            .get(&hand)
+    }
+
     fn try_get_pose(
         &self,
         handle: vr::VRActionHandle_t,
@@ -6,6 +6,7 @@ use super::{
     ActionData, ActionKey, BoundPoseType, Input,
 };
 use crate::openxr_data::{self, Hand, SessionData};
+use crate::xr_call;
 use helpers::{BindingsLoadContext, BindingsProfileLoadContext, DpadActivatorData, DpadHapticData};
 use log::{debug, error, info, trace, warn};
 use openvr as vr;
@@ -15,7 +16,8 @@ use serde::{
     Deserialize,
 };
 use slotmap::{SecondaryMap, SlotMap};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::RwLock;
@@ -39,6 +41,14 @@ fn action_map_to_secondary<T>(
         .collect()
 }
 
+/// Hashes the manifest at `path` along with every manifest it includes, or returns `None` if any
+/// of them can't be read.
+pub(super) fn hash_manifest(path: &Path) -> Option<u64> {
+    ActionManifest::read_with_includes(path)
+        .ok()
+        .map(|(hash, _)| hash)
+}
+
 impl<C: openxr_data::Compositor> Input<C> {
     pub(super) fn load_action_manifest(
         &self,
@@ -55,15 +65,9 @@ impl<C: openxr_data::Compositor> Input<C> {
                 .unwrap(),
         }
 
-        let data = std::fs::read(manifest_path).map_err(|e| {
-            error!("Failed to read manifest {}: {e}", manifest_path.display());
-            vr::EVRInputError::InvalidParam
-        })?;
-
-        let manifest: ActionManifest = serde_json::from_slice(&data).map_err(|e| {
-            error!("Failed to parse action manifest: {e}");
-            vr::EVRInputError::InvalidParam
-        })?;
+        // Only remembered once the manifest has loaded, so a failed load is retried.
+        self.loaded_actions_hash.lock().unwrap().take();
+        let (hash, manifest) = ActionManifest::read_with_includes(manifest_path)?;
 
         // TODO: support non english localization?
         let english = manifest
@@ -138,6 +142,8 @@ impl<C: openxr_data::Compositor> Input<C> {
             extra_actions,
             per_profile_bindings,
             per_profile_pose_bindings,
+            per_profile_grip_actions,
+            per_profile_analog_transforms,
             ..
         } = binding_context;
 
@@ -148,10 +154,13 @@ impl<C: openxr_data::Compositor> Input<C> {
         session_data.session.attach_action_sets(&xr_sets).unwrap();
 
         // Try forcing an interaction profile now
-        session_data
-            .session
-            .sync_actions(&[xr::ActiveActionSet::new(&info_set)])
-            .unwrap();
+        xr_call!(
+            "xrSyncActions",
+            session_data
+                .session
+                .sync_actions(&[xr::ActiveActionSet::new(&info_set)])
+        )
+        .unwrap();
 
         // Transform actions and sets into maps
         // If the application has already requested the handle for an action/set, we need to
@@ -184,12 +193,31 @@ impl<C: openxr_data::Compositor> Input<C> {
             .map(|(k, v)| (k, action_map_to_secondary(&mut act_guard, v)))
             .collect();
 
+        let per_profile_grip_actions = per_profile_grip_actions
+            .into_iter()
+            .map(|(k, v)| {
+                let grip_only = v
+                    .into_iter()
+                    .filter(|(_, grip_only)| *grip_only)
+                    .map(|(name, _)| (name, ()))
+                    .collect();
+                (k, action_map_to_secondary(&mut act_guard, grip_only))
+            })
+            .collect();
+
+        let per_profile_analog_transforms = per_profile_analog_transforms
+            .into_iter()
+            .map(|(k, v)| (k, action_map_to_secondary(&mut act_guard, v)))
+            .collect();
+
         let loaded = super::LoadedActions {
             sets,
             actions,
             extra_actions,
             per_profile_bindings,
             per_profile_pose_bindings,
+            per_profile_grip_actions,
+            per_profile_analog_transforms,
             _info_action: info_action,
             info_set,
         };
@@ -206,6 +234,7 @@ impl<C: openxr_data::Compositor> Input<C> {
                     .unwrap_or_else(|_| unreachable!());
             }
         }
+        *self.loaded_actions_hash.lock().unwrap() = Some(hash);
         Ok(())
     }
 }
@@ -217,12 +246,133 @@ impl<C: openxr_data::Compositor> Input<C> {
 
 #[derive(Deserialize)]
 struct ActionManifest {
+    #[serde(default)] // may be left to the parent in included manifests
     default_bindings: Vec<DefaultBindings>,
     #[serde(default)] // optional apparently
     action_sets: Vec<ActionSetJson>,
     actions: Vec<ActionType>,
     localization: Option<Vec<Localization>>,
     // localization_files
+    /// Other manifests whose action sets, actions and bindings are merged into this one.
+    /// Paths are relative to the including manifest.
+    #[serde(default)]
+    includes: Vec<PathBuf>,
+}
+
+impl ActionManifest {
+    /// Reads and parses the manifest at `path`, also returning its contents. Missing files are
+    /// reported as NameNotFound, actions of unknown types as WrongType, and any other problem as
+    /// InvalidParam.
+    fn read(path: &Path) -> Result<(Vec<u8>, Self), vr::EVRInputError> {
+        let data = std::fs::read(path).map_err(|e| {
+            error!("Failed to read action manifest {}: {e}", path.display());
+            match e.kind() {
+                std::io::ErrorKind::NotFound => vr::EVRInputError::NameNotFound,
+                _ => vr::EVRInputError::InvalidParam,
+            }
+        })?;
+
+        let manifest = serde_json::from_slice(&data).map_err(|e| {
+            if e.is_data() {
+                if let Some((name, ty)) = find_unknown_action_type(&data) {
+                    error!(
+                        "Action {name:?} in action manifest {} has unsupported type {ty:?}",
+                        path.display()
+                    );
+                    return vr::EVRInputError::WrongType;
+                }
+            }
+            // Includes the line and column of the problem.
+            error!("Failed to parse action manifest {}: {e}", path.display());
+            vr::EVRInputError::InvalidParam
+        })?;
+        Ok((data, manifest))
+    }
+
+    /// Reads the manifest at `path` and merges its includes into it, also returning a hash of the
+    /// contents of every manifest read, so unchanged manifests don't need to be reloaded.
+    fn read_with_includes(path: &Path) -> Result<(u64, Self), vr::EVRInputError> {
+        let mut hasher = DefaultHasher::new();
+        let (data, mut manifest) = Self::read(path)?;
+        data.hash(&mut hasher);
+        manifest.merge_includes(path, &mut Vec::new(), &mut HashSet::new(), &mut hasher)?;
+        Ok((hasher.finish(), manifest))
+    }
+
+    /// Recursively merges the manifests included by this one, which was read from `path`.
+    /// `stack` holds the manifests currently being merged, to detect include cycles, and
+    /// `merged` all manifests merged so far, so ones included more than once are only merged once.
+    /// The contents of each included manifest are fed to `hasher`.
+    fn merge_includes(
+        &mut self,
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        merged: &mut HashSet<PathBuf>,
+        hasher: &mut DefaultHasher,
+    ) -> Result<(), vr::EVRInputError> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        merged.insert(canonical.clone());
+        stack.push(canonical);
+
+        let dir = path.parent().unwrap();
+        for include in std::mem::take(&mut self.includes) {
+            let include_path = dir.join(&include).canonicalize().map_err(|e| {
+                error!(
+                    "Failed to resolve included action manifest {} (from {}): {e}",
+                    include.display(),
+                    path.display()
+                );
+                vr::EVRInputError::InvalidParam
+            })?;
+            if stack.contains(&include_path) {
+                error!(
+                    "Action manifest {} includes itself through {}",
+                    include_path.display(),
+                    path.display()
+                );
+                return Err(vr::EVRInputError::InvalidParam);
+            }
+            if !merged.insert(include_path.clone()) {
+                debug!(
+                    "Action manifest {} already included, skipping",
+                    include_path.display()
+                );
+                continue;
+            }
+
+            debug!("Including action manifest {}", include_path.display());
+            let (data, mut child) = ActionManifest::read(&include_path)?;
+            data.hash(hasher);
+            child.merge_includes(&include_path, stack, merged, hasher)?;
+
+            // The included manifest's bindings are relative to it, not to us.
+            let child_dir = include_path.parent().unwrap();
+            self.default_bindings
+                .extend(child.default_bindings.into_iter().map(|mut bindings| {
+                    bindings.binding_url = child_dir.join(bindings.binding_url);
+                    bindings
+                }));
+            self.action_sets.extend(child.action_sets);
+            self.actions.extend(child.actions);
+            for child_loc in child.localization.into_iter().flatten() {
+                let localization = self.localization.get_or_insert_with(Vec::new);
+                match localization
+                    .iter_mut()
+                    .find(|l| l.language_tag == child_loc.language_tag)
+                {
+                    Some(loc) => {
+                        for (name, localized) in child_loc.localized_names {
+                            loc.localized_names.entry(name).or_insert(localized);
+                        }
+                    }
+                    None => localization.push(child_loc),
+                }
+            }
+        }
+
+        stack.pop();
+        Ok(())
+    }
 }
 
 #[derive(Deserialize)]
@@ -258,6 +408,59 @@ enum ActionType {
     Skeleton(SkeletonData),
 }
 
+/// Returns the name and type of the first action in a manifest with a type that isn't an
+/// ActionType, for a more helpful error than serde's.
+fn find_unknown_action_type(data: &[u8]) -> Option<(String, String)> {
+    /// Only records whether deserializing failed because of an unknown variant.
+    #[derive(Debug)]
+    struct UnknownVariant(bool);
+    impl std::fmt::Display for UnknownVariant {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(if self.0 {
+                "unknown variant"
+            } else {
+                "other error"
+            })
+        }
+    }
+    impl std::error::Error for UnknownVariant {}
+    impl Error for UnknownVariant {
+        fn custom<T: std::fmt::Display>(_: T) -> Self {
+            Self(false)
+        }
+        fn unknown_variant(_: &str, _: &'static [&'static str]) -> Self {
+            Self(true)
+        }
+    }
+
+    // Deserializes just the tag, so known types fail on their missing fields instead.
+    let is_unknown = |ty: &str| {
+        let tag = serde::de::value::MapDeserializer::<_, UnknownVariant>::new(std::iter::once((
+            "type", ty,
+        )));
+        ActionType::deserialize(tag).is_err_and(|e| e.0)
+    };
+
+    #[derive(Deserialize)]
+    struct Manifest {
+        actions: Vec<UntypedAction>,
+    }
+    #[derive(Deserialize)]
+    struct UntypedAction {
+        #[serde(default)]
+        name: String,
+        #[serde(rename = "type")]
+        ty: String,
+    }
+
+    let manifest: Manifest = serde_json::from_slice(data).ok()?;
+    manifest
+        .actions
+        .into_iter()
+        .find(|action| is_unknown(&action.ty))
+        .map(|action| (action.name, action.ty))
+}
+
 #[derive(Deserialize)]
 struct ActionDataCommon {
     name: String,
@@ -592,7 +795,6 @@ enum ActionBinding {
     Trigger {
         path: String,
         inputs: TriggerInput,
-        #[allow(unused)]
         parameters: Option<ClickThresholdParams>,
     },
     ScalarConstant {
@@ -769,6 +971,14 @@ struct ScrollParameters {
 struct Vector2Mode {
     path: String,
     inputs: Vector2Input,
+    parameters: Option<Vector2Parameters>,
+}
+
+#[derive(Deserialize)]
+struct Vector2Parameters {
+    deadzone_pct: Option<FromString<f32>>,
+    invert_x: Option<FromString<bool>>,
+    invert_y: Option<FromString<bool>>,
 }
 
 #[derive(Deserialize)]
@@ -829,6 +1039,7 @@ impl<C: openxr_data::Compositor> Input<C> {
                     let profiles = Profiles::get()
                         .list
                         .iter()
+                        .filter(|(_, p)| p.is_supported(&self.openxr.enabled_extensions))
                         .filter_map(|(ty, p)| (*ty == *other).then_some(*p));
                     let bindings = LazyCell::new(load_bindings);
                     for profile in profiles {
@@ -1180,7 +1391,11 @@ fn handle_sources(
                             context.instance.string_to_path(&translated).unwrap(),
                         );
 
-                        context.add_custom_button_binding(output, &translated, parameters)
+                        context.add_custom_button_binding(
+                            output,
+                            &translated,
+                            parameters.map(|x| &x.click_threshold),
+                        )
                     }
                 }
 
@@ -1216,7 +1431,7 @@ fn handle_sources(
             ActionBinding::Trigger {
                 path,
                 inputs: TriggerInput { pull, touch, click },
-                ..
+                parameters,
             } => {
                 let suffixes_and_outputs = [("pull", pull), ("touch", touch), ("click", click)]
                     .into_iter()
@@ -1228,6 +1443,22 @@ fn handle_sources(
                         continue;
                     };
 
+                    // Controllers without a trigger click have it translated to the analog
+                    // value, so the click is emulated with a threshold like a button binding.
+                    if suffix == "click" && !translated.ends_with("/click") {
+                        let float_name_with_as = context.get_or_create_analog_extra_action(
+                            output,
+                            action_set_name,
+                            action_set,
+                        );
+                        context.push_binding(
+                            float_name_with_as,
+                            context.instance.string_to_path(&translated).unwrap(),
+                        );
+                        context.add_custom_button_binding(output, &translated, parameters.as_ref());
+                        continue;
+                    }
+
                     context.try_get_bool_binding(output.to_string(), translated);
                 }
             }
@@ -1313,7 +1544,11 @@ fn handle_sources(
                 warn!("Got scroll binding for input {}, but these are currently unimplemented, skipping", inputs.scroll.output);
             }
             ActionBinding::Trackpad(data) | ActionBinding::Joystick(data) => {
-                let Vector2Mode { path, inputs } = data;
+                let Vector2Mode {
+                    path,
+                    inputs,
+                    parameters,
+                } = data;
                 let Ok(translated) =
                     path_translator(path).inspect_err(translate_warn("<vector2 input>"))
                 else {
@@ -1347,6 +1582,13 @@ fn handle_sources(
                 }
 
                 if let Some(position) = position.as_ref() {
+                    if let Some(parameters) = parameters.as_ref() {
+                        context.add_analog_transform(
+                            position.output.to_string(),
+                            &translated,
+                            parameters,
+                        );
+                    }
                     context.try_get_v2_binding(position.output.to_string(), translated);
                 }
             }
@@ -1,6 +1,8 @@
 use crate::input::action_manifest::{
-    ButtonParameters, ControllerType, GrabParameters, LoadedActionDataMap, LowercaseActionPath,
+    ClickThresholdParams, ControllerType, GrabParameters, LoadedActionDataMap, LowercaseActionPath,
+    Vector2Parameters,
 };
+use crate::input::analog_transform::AnalogTransform;
 use crate::input::custom_bindings::{
     BindingData, DpadActions, DpadData, DpadDirection, GrabActions, GrabBindingData,
     ThresholdBindingData,
@@ -21,6 +23,11 @@ pub(super) struct BindingsLoadContext<'a> {
     pub extra_actions: HashMap<String, ExtraActionData>,
     pub per_profile_bindings: HashMap<xr::Path, HashMap<String, Vec<BindingData>>>,
     pub per_profile_pose_bindings: HashMap<xr::Path, HashMap<String, BoundPose>>,
+    /// Float actions with bindings in each profile, and whether they're only bound to analog grips.
+    pub per_profile_grip_actions: HashMap<xr::Path, HashMap<String, bool>>,
+    /// Analog transforms of vector2 actions in each profile, by the hand they're bound on.
+    pub per_profile_analog_transforms:
+        HashMap<xr::Path, HashMap<String, HashMap<xr::Path, AnalogTransform>>>,
     pub legacy_actions: &'a LegacyActions,
     pub info_action: &'a xr::Action<bool>,
     pub skeletal_input: &'a SkeletalInputActionData,
@@ -40,6 +47,8 @@ impl<'a> BindingsLoadContext<'a> {
             extra_actions: Default::default(),
             per_profile_bindings: Default::default(),
             per_profile_pose_bindings: Default::default(),
+            per_profile_grip_actions: Default::default(),
+            per_profile_analog_transforms: Default::default(),
             legacy_actions,
             info_action,
             skeletal_input,
@@ -73,6 +82,14 @@ impl BindingsLoadContext<'_> {
             .per_profile_pose_bindings
             .entry(interaction_profile)
             .or_default();
+        let grip_actions = self
+            .per_profile_grip_actions
+            .entry(interaction_profile)
+            .or_default();
+        let analog_transforms = self
+            .per_profile_analog_transforms
+            .entry(interaction_profile)
+            .or_default();
         Some(BindingsProfileLoadContext {
             profile,
             controller_type,
@@ -81,6 +98,8 @@ impl BindingsLoadContext<'_> {
             extra_actions: &mut self.extra_actions,
             bindings_parsed,
             pose_bindings,
+            grip_actions,
+            analog_transforms,
             legacy_actions: self.legacy_actions,
             info_action: self.info_action,
             skeletal_input: self.skeletal_input,
@@ -99,6 +118,8 @@ pub(super) struct BindingsProfileLoadContext<'a> {
     extra_actions: &'a mut HashMap<String, ExtraActionData>,
     bindings_parsed: &'a mut HashMap<String, Vec<BindingData>>,
     pub pose_bindings: &'a mut HashMap<String, BoundPose>,
+    grip_actions: &'a mut HashMap<String, bool>,
+    analog_transforms: &'a mut HashMap<String, HashMap<xr::Path, AnalogTransform>>,
     pub legacy_actions: &'a LegacyActions,
     pub info_action: &'a xr::Action<bool>,
     pub skeletal_input: &'a SkeletalInputActionData,
@@ -186,6 +207,10 @@ impl BindingsProfileLoadContext<'_> {
         if self.find_action(&action_path) {
             action_pattern.check_match(&self.actions[&action_path], &action_path);
             trace!("suggesting {input_path} for {action_path}");
+            if let Vector1 { .. } = &self.actions[&action_path] {
+                let is_grip = input_path.ends_with("/squeeze/value");
+                *self.grip_actions.entry(action_path.clone()).or_insert(true) &= is_grip;
+            }
             let binding_path = self.instance.string_to_path(&input_path).unwrap();
             self.bindings.push((action_path, binding_path));
         }
@@ -207,6 +232,38 @@ impl BindingsProfileLoadContext<'_> {
         self.try_get_binding(action_path, input_path, action_match!(Vector2 { .. }));
     }
 
+    /// Records the deadzone and inversion parameters of a joystick or trackpad binding, for the
+    /// hand of its input path only.
+    pub fn add_analog_transform(
+        &mut self,
+        action_path: String,
+        input_path: &str,
+        parameters: &Vector2Parameters,
+    ) {
+        let transform = AnalogTransform {
+            deadzone: parameters
+                .deadzone_pct
+                .as_ref()
+                .map_or(0.0, |pct| pct.0.clamp(0.0, 99.0) / 100.0),
+            invert_x: parameters.invert_x.as_ref().is_some_and(|invert| invert.0),
+            invert_y: parameters.invert_y.as_ref().is_some_and(|invert| invert.0),
+        };
+        if transform.is_identity() {
+            return;
+        }
+        let Some(hand) = parse_hand_from_path(self.instance, input_path) else {
+            warn!(
+                "Binding parameters on {input_path} have unknown hand path, they will be ignored"
+            );
+            return;
+        };
+        trace!("applying {transform:?} to {action_path} on {input_path}");
+        self.analog_transforms
+            .entry(action_path)
+            .or_default()
+            .insert(hand, transform);
+    }
+
     pub fn add_custom_toggle_binding(&mut self, output: &LowercaseActionPath, translated: &str) {
         if let Some(binding_hand) = parse_hand_from_path(self.instance, translated) {
             self.bindings_parsed
@@ -222,10 +279,9 @@ impl BindingsProfileLoadContext<'_> {
         &mut self,
         output: &LowercaseActionPath,
         translated: &str,
-        parameters: Option<&ButtonParameters>,
+        thresholds: Option<&ClickThresholdParams>,
     ) {
         if let Some(binding_hand) = parse_hand_from_path(self.instance, translated) {
-            let thresholds = parameters.map(|x| &x.click_threshold);
             self.bindings_parsed
                 .entry(output.to_lowercase())
                 .or_default()
@@ -0,0 +1,369 @@
+use super::{analog_transform::AnalogTransform, ActionData, Input, LoadedActions};
+use crate::openxr_data::{self, SessionData};
+use openvr as vr;
+use openxr as xr;
+use slotmap::Key;
+
+/// Reads the state of digital and analog actions. The session and the loaded actions are locked
+/// for as long as the reader lives, so reading many actions through one reader only takes the
+/// locks once.
+pub struct ActionStateReader<'a, C: openxr_data::Compositor> {
+    input: &'a Input<C>,
+    session_data: &'a SessionData,
+    loaded: Option<&'a LoadedActions>,
+}
+
+impl<C: openxr_data::Compositor> Input<C> {
+    pub fn read_action_states<R>(&self, f: impl FnOnce(&ActionStateReader<'_, C>) -> R) -> R {
+        let session_data = self.openxr.session_data.get();
+        let loaded = session_data.input_data.get_loaded_actions();
+        f(&ActionStateReader {
+            input: self,
+            session_data: &session_data,
+            loaded: loaded.as_deref(),
+        })
+    }
+}
+
+impl<C: openxr_data::Compositor> ActionStateReader<'_, C> {
+    fn action(&self, handle: vr::VRActionHandle_t) -> Result<&ActionData, vr::EVRInputError> {
+        self.loaded
+            .ok_or(vr::EVRInputError::InvalidHandle)?
+            .try_get_action(handle)
+    }
+
+    /// Returns None if the actions can't be queried for this device, in which case they're
+    /// reported as inactive.
+    fn subaction_path(&self, restrict_to_device: vr::VRInputValueHandle_t) -> Option<xr::Path> {
+        self.input
+            .subaction_path_from_handle(restrict_to_device)
+            .filter(|path| self.input.is_action_subaction_path(*path))
+    }
+
+    /// The deadzone and inversion from the bindings of a vector2 action on the hand its state was
+    /// read from, in that hand's interaction profile.
+    fn analog_transform(
+        &self,
+        handle: vr::VRActionHandle_t,
+        hand: vr::VRInputValueHandle_t,
+    ) -> Option<&AnalogTransform> {
+        let loaded = self.loaded?;
+        let subaction_path = self.input.subaction_path_from_handle(hand)?;
+        [&self.input.openxr.left_hand, &self.input.openxr.right_hand]
+            .into_iter()
+            .find(|hand| hand.subaction_path == subaction_path)
+            .and_then(|hand| {
+                loaded.analog_transform(handle, hand.profile_path.load(), subaction_path)
+            })
+    }
+
+    /// The hand an unrestricted action's state most likely came from, since OpenXR combines the
+    /// hands' states without saying which one it took: the active hand whose own state is the most
+    /// engaged, as measured by `engagement`.
+    fn active_hand<T: xr::ActionInput>(
+        &self,
+        action: &xr::Action<T>,
+        engagement: impl Fn(&T) -> f32,
+    ) -> vr::VRInputValueHandle_t {
+        let hands = [
+            (&self.input.openxr.left_hand, self.input.left_hand_key),
+            (&self.input.openxr.right_hand, self.input.right_hand_key),
+        ];
+        let mut active: Option<(f32, vr::VRInputValueHandle_t)> = None;
+        for (hand, key) in hands {
+            let Ok(state) = action.state(&self.session_data.session, hand.subaction_path) else {
+                continue;
+            };
+            let engagement = engagement(&state.current_state);
+            if state.is_active && active.is_none_or(|(best, _)| engagement > best) {
+                active = Some((engagement, key.data().as_ffi()));
+            }
+        }
+        active.map_or(vr::k_ulInvalidInputValueHandle, |(_, hand)| hand)
+    }
+
+    /// Adds the custom bindings of both hands to an unrestricted digital action's `state`, and
+    /// returns the hand the state came from: the one where the action or any of its bindings is
+    /// pressed, preferring a hand that was pressed since the last sync.
+    fn add_hand_bindings(
+        &self,
+        handle: vr::VRActionHandle_t,
+        action: &xr::Action<bool>,
+        state: &mut xr::ActionState<bool>,
+    ) -> vr::VRInputValueHandle_t {
+        let hands = [
+            (&self.input.openxr.left_hand, self.input.left_hand_key),
+            (&self.input.openxr.right_hand, self.input.right_hand_key),
+        ];
+        let mut origin: Option<(Engagement, vr::VRInputValueHandle_t)> = None;
+        for (hand, key) in hands {
+            let key = key.data().as_ffi();
+            // Reading bindings updates their last state, so each hand's are only read once.
+            let bound = self
+                .state_from_bindings(handle, key)
+                .map(|(bound, _)| bound)
+                .filter(|bound| bound.is_active);
+            if let Some(bound) = bound {
+                *state = or_states(*state, bound);
+            }
+            let main = action
+                .state(&self.session_data.session, hand.subaction_path)
+                .ok();
+            let Some(hand_state) = main.into_iter().chain(bound).reduce(or_states) else {
+                continue;
+            };
+            let engagement = Engagement::of(&hand_state);
+            if hand_state.is_active && origin.is_none_or(|(best, _)| engagement > best) {
+                origin = Some((engagement, key));
+            }
+        }
+        match origin {
+            Some((_, hand)) if state.is_active => hand,
+            _ => vr::k_ulInvalidInputValueHandle,
+        }
+    }
+
+    /// Seconds from now back to when an action's state last changed, which is negative for
+    /// changes in the past, or 0 if the runtime didn't say.
+    fn update_time(&self, last_change_time: xr::Time) -> f32 {
+        if last_change_time.as_nanos() == 0 {
+            return 0.0;
+        }
+        let now = self.input.openxr.now();
+        ((last_change_time.as_nanos() - now.as_nanos()) as f64 / 1e9) as f32
+    }
+
+    /// The data returned by GetDigitalActionData.
+    pub fn digital(
+        &self,
+        handle: vr::VRActionHandle_t,
+        restrict_to_device: vr::VRInputValueHandle_t,
+    ) -> Result<vr::InputDigitalActionData_t, vr::EVRInputError> {
+        let action = self.action(handle)?;
+        let Some(subaction_path) = self.subaction_path(restrict_to_device) else {
+            return Ok(Default::default());
+        };
+        let ActionData::Bool(action) = action else {
+            return Err(vr::EVRInputError::WrongType);
+        };
+
+        let mut state = action
+            .state(&self.session_data.session, subaction_path)
+            .unwrap();
+
+        let mut active_hand = restrict_to_device;
+        if restrict_to_device == vr::k_ulInvalidInputValueHandle {
+            active_hand = self.add_hand_bindings(handle, action, &mut state);
+        } else if let Some((binding_state, _)) =
+            self.state_from_bindings(handle, restrict_to_device)
+        {
+            if binding_state.is_active {
+                state = or_states(state, binding_state);
+            }
+        }
+
+        Ok(vr::InputDigitalActionData_t {
+            bActive: state.is_active,
+            bState: state.current_state,
+            activeOrigin: active_hand,
+            bChanged: state.changed_since_last_sync,
+            fUpdateTime: self.update_time(state.last_change_time),
+        })
+    }
+
+    /// The data returned by GetAnalogActionData.
+    pub fn analog(
+        &self,
+        handle: vr::VRActionHandle_t,
+        restrict_to_device: vr::VRInputValueHandle_t,
+    ) -> Result<vr::InputAnalogActionData_t, vr::EVRInputError> {
+        let action = self.action(handle)?;
+        let Some(subaction_path) = self.subaction_path(restrict_to_device) else {
+            return Ok(Default::default());
+        };
+        let session = &self.session_data.session;
+
+        let mut active_hand = restrict_to_device;
+        let (state, delta) = match action {
+            ActionData::Vector1 { action, last_value } => {
+                let mut state = action.state(session, subaction_path).unwrap();
+                if restrict_to_device == vr::k_ulInvalidInputValueHandle && state.is_active {
+                    active_hand = self.active_hand(action, |value| *value);
+                }
+                self.input.apply_grip_curve(
+                    self.loaded.unwrap(),
+                    session,
+                    handle,
+                    action,
+                    subaction_path,
+                    &mut state,
+                );
+
+                // It's generally not clear how SteamVR handles float actions with multiple bindings;
+                //   so emulate OpenXR, which takes maximum among active actions
+                if let Some((binding_state, binding_source)) =
+                    self.state_from_bindings(handle, restrict_to_device)
+                {
+                    if binding_state.is_active
+                        && (binding_state.current_state && state.current_state != 1.0
+                            || !state.is_active)
+                    {
+                        state = xr::ActionState {
+                            current_state: if binding_state.current_state {
+                                1.0
+                            } else {
+                                0.0
+                            },
+                            is_active: binding_state.is_active,
+                            changed_since_last_sync: binding_state.changed_since_last_sync,
+                            last_change_time: binding_state.last_change_time,
+                        };
+                        active_hand = binding_source;
+                    }
+                }
+
+                let delta = xr::Vector2f {
+                    x: state.current_state - last_value.swap(state.current_state),
+                    y: 0.0,
+                };
+                (
+                    xr::ActionState::<xr::Vector2f> {
+                        current_state: xr::Vector2f {
+                            x: state.current_state,
+                            y: 0.0,
+                        },
+                        changed_since_last_sync: state.changed_since_last_sync,
+                        last_change_time: state.last_change_time,
+                        is_active: state.is_active,
+                    },
+                    delta,
+                )
+            }
+            ActionData::Vector2 { action, last_value } => {
+                let mut state = action.state(session, subaction_path).unwrap();
+                if restrict_to_device == vr::k_ulInvalidInputValueHandle && state.is_active {
+                    active_hand = self.active_hand(action, |value| value.x.hypot(value.y));
+                }
+                // Unrestricted reads get the state of the most engaged hand, so they're
+                // transformed by that hand's bindings.
+                if let Some(transform) = self.analog_transform(handle, active_hand) {
+                    state.current_state = transform.apply(state.current_state);
+                }
+                let delta = xr::Vector2f {
+                    x: state.current_state.x - last_value.0.swap(state.current_state.x),
+                    y: state.current_state.y - last_value.1.swap(state.current_state.y),
+                };
+                (state, delta)
+            }
+            _ => return Err(vr::EVRInputError::WrongType),
+        };
+
+        Ok(vr::InputAnalogActionData_t {
+            bActive: state.is_active,
+            activeOrigin: active_hand,
+            x: state.current_state.x,
+            deltaX: delta.x,
+            y: state.current_state.y,
+            deltaY: delta.y,
+            fUpdateTime: self.update_time(state.last_change_time),
+            ..Default::default()
+        })
+    }
+
+    fn state_from_bindings_left_right(
+        &self,
+        action: vr::VRActionHandle_t,
+    ) -> Option<(xr::ActionState<bool>, vr::VRInputValueHandle_t)> {
+        let left_hand = self.input.left_hand_key.0.as_ffi();
+        let right_hand = self.input.right_hand_key.0.as_ffi();
+        debug_assert!(left_hand != 0);
+        debug_assert!(right_hand != 0);
+        let left_state = self.state_from_bindings(action, left_hand);
+
+        let right_state = self.state_from_bindings(action, right_hand);
+
+        match (left_state, right_state) {
+            (Some((left, _)), Some((right, _))) => {
+                let source = if Engagement::of(&right) > Engagement::of(&left) {
+                    right_hand
+                } else {
+                    left_hand
+                };
+                Some((or_states(left, right), source))
+            }
+            (state, None) | (None, state) => state,
+        }
+    }
+
+    fn state_from_bindings(
+        &self,
+        action: vr::VRActionHandle_t,
+        restrict_to_device: vr::VRInputValueHandle_t,
+    ) -> Option<(xr::ActionState<bool>, vr::VRInputValueHandle_t)> {
+        let subaction = self.subaction_path(restrict_to_device)?;
+        if subaction == xr::Path::NULL {
+            return self.state_from_bindings_left_right(action);
+        }
+
+        let loaded_actions = self.loaded?;
+        let interaction_profile = self
+            .session_data
+            .session
+            .current_interaction_profile(subaction)
+            .ok()?;
+        let bindings = loaded_actions
+            .try_get_bindings(action, interaction_profile)
+            .ok()?;
+        let extra_data = loaded_actions.try_get_extra(action).ok()?;
+
+        bindings
+            .iter()
+            .filter_map(|x| {
+                x.state(self.session_data, extra_data, subaction)
+                    .ok()
+                    .flatten()
+            })
+            .reduce(or_states)
+            .map(|x| (x, restrict_to_device))
+    }
+}
+
+/// How engaged a hand's digital state is, in increasing order: inactive, active, pressed, and
+/// pressed since the last sync.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Engagement {
+    active: bool,
+    pressed: bool,
+    changed: bool,
+}
+
+impl Engagement {
+    fn of(state: &xr::ActionState<bool>) -> Self {
+        let pressed = state.is_active && state.current_state;
+        Self {
+            active: state.is_active,
+            pressed,
+            changed: pressed && state.changed_since_last_sync,
+        }
+    }
+}
+
+/// Combines the states of two sources of a digital action the way OpenXR combines the sources
+/// bound to a single action: it's pressed while either source is, and it only changed if the
+/// combined state did, so pressing one source while the other is held isn't a new press.
+fn or_states(a: xr::ActionState<bool>, b: xr::ActionState<bool>) -> xr::ActionState<bool> {
+    let pressed = |s: &xr::ActionState<bool>| s.is_active && s.current_state;
+    let was_pressed =
+        |s: &xr::ActionState<bool>| s.is_active && s.current_state != s.changed_since_last_sync;
+    let current_state = pressed(&a) || pressed(&b);
+    xr::ActionState {
+        current_state,
+        changed_since_last_sync: current_state != (was_pressed(&a) || was_pressed(&b)),
+        last_change_time: if a.last_change_time.as_nanos() >= b.last_change_time.as_nanos() {
+            a.last_change_time
+        } else {
+            b.last_change_time
+        },
+        is_active: a.is_active || b.is_active,
+    }
+}
@@ -0,0 +1,74 @@
+use openxr as xr;
+
+/// Deadzone and axis inversion from the parameters of a joystick or trackpad binding, applied to
+/// the state of the vector2 action it's bound to.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct AnalogTransform {
+    /// Fraction of the stick's range around the center that reads as 0, in the range [0, 1).
+    pub deadzone: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+impl AnalogTransform {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, value: xr::Vector2f) -> xr::Vector2f {
+        let mut value = value;
+        if self.deadzone > 0.0 {
+            // Rescale what's outside the deadzone to the full range, so there's no jump at its
+            // edge.
+            let length = value.x.hypot(value.y);
+            let scale = if length <= self.deadzone {
+                0.0
+            } else {
+                ((length - self.deadzone) / (1.0 - self.deadzone)).min(1.0) / length
+            };
+            value.x *= scale;
+            value.y *= scale;
+        }
+        if self.invert_x {
+            value.x = -value.x;
+        }
+        if self.invert_y {
+            value.y = -value.y;
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnalogTransform;
+    use openxr as xr;
+
+    fn apply(transform: AnalogTransform, x: f32, y: f32) -> (f32, f32) {
+        let value = transform.apply(xr::Vector2f { x, y });
+        (value.x, value.y)
+    }
+
+    #[test]
+    fn deadzone() {
+        let transform = AnalogTransform {
+            deadzone: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(apply(transform, 0.1, -0.1), (0.0, 0.0));
+        assert_eq!(apply(transform, 0.0, 1.0), (0.0, 1.0));
+        let (x, y) = apply(transform, 0.6, 0.0);
+        assert!((x - 0.5).abs() < 1e-6, "{x}");
+        assert_eq!(y, 0.0);
+    }
+
+    #[test]
+    fn inversion() {
+        let transform = AnalogTransform {
+            invert_y: true,
+            ..Default::default()
+        };
+        assert_eq!(apply(transform, 0.5, 0.25), (0.5, -0.25));
+        assert_eq!(apply(AnalogTransform::default(), 0.5, 0.25), (0.5, 0.25));
+    }
+}
@@ -349,6 +349,7 @@ impl BindingData {
 mod tests {
     use super::*;
     use crate::input::profiles::knuckles::Knuckles;
+    use crate::input::profiles::oculus_touch::Touch;
     use crate::input::profiles::vive_controller::ViveWands;
     use crate::input::tests::Fixture;
     use crate::input::InteractionProfile;
@@ -884,4 +885,46 @@ mod tests {
         assert!(s_right.bState);
         assert!(s_right.bChanged);
     }
+
+    #[test]
+    fn emulated_trigger_click() {
+        let f = Fixture::new();
+        let set1 = f.get_action_set_handle(c"/actions/set1");
+        let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+        f.load_actions(c"actions_trigger_click.json");
+
+        // Touch controllers have no trigger click, so it's emulated from the trigger's value.
+        f.verify_bindings::<f32>(
+            Touch.profile_path(),
+            c"/actions/set1/boolact_asfloat",
+            ["/user/hand/left/input/trigger/value".into()],
+        );
+        let analog = {
+            let data = f.input.openxr.session_data.get();
+            let actions = data.input_data.get_loaded_actions().unwrap();
+            let ExtraActionData { analog_action, .. } = actions.try_get_extra(boolact).unwrap();
+            analog_action.as_ref().unwrap().as_raw()
+        };
+
+        f.set_interaction_profile(&Touch, LeftHand);
+        let value_state_check = |value, state, changed, line| {
+            fakexr::set_action_state(analog, fakexr::ActionState::Float(value), LeftHand);
+            f.sync(vr::VRActiveActionSet_t {
+                ulActionSet: set1,
+                ..Default::default()
+            });
+
+            let s = f.get_bool_state(boolact).unwrap();
+            assert_eq!(s.bState, state, "state failed (line {line})");
+            assert!(s.bActive, "active failed (line {line})");
+            assert_eq!(s.bChanged, changed, "changed failed (line {line})");
+        };
+
+        // The bindings set the click to 0.8 and the release to 0.6.
+        value_state_check(0.5, false, false, line!());
+        value_state_check(0.79, false, false, line!());
+        value_state_check(0.8, true, true, line!());
+        value_state_check(0.65, true, false, line!());
+        value_state_check(0.5, false, true, line!());
+    }
 }
@@ -0,0 +1,105 @@
+use super::profiles::Profiles;
+use log::{info, warn};
+use openxr as xr;
+use std::collections::HashMap;
+
+/// Response curve applied to analog grip values, to even out the grab feel of different
+/// controllers.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) enum GripCurve {
+    #[default]
+    Linear,
+    /// Raises the grip value to the power of gamma - values above 1 require squeezing harder
+    /// to reach the same value, values below 1 make the grip more sensitive.
+    Exponential { gamma: f32 },
+}
+
+impl GripCurve {
+    /// Parses either `linear` or a gamma value.
+    fn parse(value: &str) -> Option<Self> {
+        if value.eq_ignore_ascii_case("linear") {
+            return Some(Self::Linear);
+        }
+
+        match value.parse::<f32>() {
+            Ok(gamma) if gamma == 1.0 => Some(Self::Linear),
+            Ok(gamma) if gamma.is_finite() && gamma > 0.0 => Some(Self::Exponential { gamma }),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Exponential { gamma } => value.clamp(0.0, 1.0).powf(gamma),
+        }
+    }
+}
+
+/// Reads the grip curve for each interaction profile. `XRIZER_GRIP_CURVE` applies to all
+/// controllers, and `XRIZER_GRIP_CURVE_<CONTROLLER TYPE>` (e.g. `XRIZER_GRIP_CURVE_KNUCKLES`)
+/// overrides it for a single controller type. Profiles with a linear curve are left out.
+pub(super) fn grip_curves_from_env(instance: &xr::Instance) -> HashMap<xr::Path, GripCurve> {
+    let read_var = |name: &str| {
+        let value = std::env::var(name).ok()?;
+        GripCurve::parse(&value).or_else(|| {
+            warn!("Invalid {name} value {value:?}, expected \"linear\" or a positive gamma");
+            None
+        })
+    };
+
+    let default = read_var("XRIZER_GRIP_CURVE").unwrap_or_default();
+    Profiles::get()
+        .profiles_iter()
+        .filter_map(|profile| {
+            let controller_type = profile
+                .properties()
+                .openvr_controller_type
+                .to_string_lossy();
+            let curve = read_var(&format!(
+                "XRIZER_GRIP_CURVE_{}",
+                controller_type.to_uppercase()
+            ))
+            .unwrap_or(default);
+            if curve == GripCurve::Linear {
+                return None;
+            }
+
+            info!("Using grip curve {curve:?} for {controller_type}");
+            let path = instance.string_to_path(profile.profile_path()).unwrap();
+            Some((path, curve))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GripCurve;
+
+    #[test]
+    fn parse() {
+        assert_eq!(GripCurve::parse("linear"), Some(GripCurve::Linear));
+        assert_eq!(GripCurve::parse("Linear"), Some(GripCurve::Linear));
+        assert_eq!(GripCurve::parse("1"), Some(GripCurve::Linear));
+        assert_eq!(
+            GripCurve::parse("2.2"),
+            Some(GripCurve::Exponential { gamma: 2.2 })
+        );
+        assert_eq!(GripCurve::parse("0"), None);
+        assert_eq!(GripCurve::parse("-1"), None);
+        assert_eq!(GripCurve::parse("steep"), None);
+    }
+
+    #[test]
+    fn apply() {
+        assert_eq!(GripCurve::Linear.apply(0.5), 0.5);
+
+        let curve = GripCurve::Exponential { gamma: 2.0 };
+        assert_eq!(curve.apply(0.0), 0.0);
+        assert_eq!(curve.apply(0.5), 0.25);
+        assert_eq!(curve.apply(1.0), 1.0);
+
+        let curve = GripCurve::Exponential { gamma: 0.5 };
+        assert_eq!(curve.apply(0.25), 0.5);
+    }
+}
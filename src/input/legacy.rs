@@ -1,5 +1,6 @@
 use super::{Input, Profiles};
 use crate::openxr_data::{self, Hand, OpenXrData, SessionData};
+use crate::xr_call;
 use glam::Quat;
 use log::{debug, trace, warn};
 use openvr as vr;
@@ -12,8 +13,11 @@ use std::{
     },
 };
 
+/// Legacy input state that outlives individual sessions.
 #[derive(Default)]
 pub(super) struct LegacyState {
+    /// Kept here rather than in the per session [`LegacyActions`] so that it keeps increasing
+    /// across session restarts - games compare packet numbers to detect new input.
     packet_num: AtomicU32,
     got_state_this_frame: [AtomicBool; 2],
 }
@@ -31,12 +35,6 @@ impl LegacyState {
 pub const fn button_mask_from_id(id: vr::EVRButtonId) -> u64 {
     1_u64 << (id as u32)
 }
-#[macro_export]
-macro_rules! button_mask_from_ids {
-    ($($x:expr), * $(,)?) => {
-        0u64 $(| button_mask_from_id($x))*
-    };
-}
 
 impl<C: openxr_data::Compositor> Input<C> {
     pub fn get_legacy_controller_state(
@@ -134,6 +132,11 @@ impl<C: openxr_data::Compositor> Input<C> {
         read_button(vr::EVRButtonId::A, &actions.a, None);
         read_button(vr::EVRButtonId::Grip, &actions.squeeze_click, None);
         read_button(vr::EVRButtonId::Axis2, &actions.squeeze_click, None);
+        read_button(
+            vr::EVRButtonId::Axis3,
+            &actions.secondary_xy_click,
+            Some(&actions.secondary_xy_touch),
+        );
 
         let j = actions.main_xy.state(&data.session, hand_path).unwrap();
         state.rAxis[0] = vr::VRControllerAxis_t {
@@ -153,8 +156,50 @@ impl<C: openxr_data::Compositor> Input<C> {
             y: 0.0,
         };
 
+        let p = actions
+            .secondary_xy
+            .state(&data.session, hand_path)
+            .unwrap();
+        state.rAxis[3] = vr::VRControllerAxis_t {
+            x: p.current_state.x,
+            y: p.current_state.y,
+        };
+
         true
     }
+
+    pub fn trigger_legacy_haptic_pulse(
+        &self,
+        device_index: vr::TrackedDeviceIndex_t,
+        duration_micros: u16,
+    ) {
+        let data = self.openxr.session_data.get();
+        let Some(legacy) = data.input_data.legacy_actions.get() else {
+            debug!("tried triggering haptic pulse, but legacy actions aren't ready");
+            return;
+        };
+
+        let Ok(hand) = Hand::try_from(device_index) else {
+            debug!("requested haptic pulse for invalid device index: {device_index}");
+            return;
+        };
+
+        let hand_path = match hand {
+            Hand::Left => self.openxr.left_hand.subaction_path,
+            Hand::Right => self.openxr.right_hand.subaction_path,
+        };
+
+        if let Err(e) = legacy.actions.haptic.apply_feedback(
+            &data.session,
+            hand_path,
+            &xr::HapticVibration::new()
+                .amplitude(1.0)
+                .frequency(xr::FREQUENCY_UNSPECIFIED)
+                .duration(xr::Duration::from_nanos(duration_micros as i64 * 1000)),
+        ) {
+            warn!("Failed to trigger legacy haptic pulse: {e}");
+        }
+    }
 }
 
 macro_rules! legacy_actions_and_bindings {
@@ -191,6 +236,11 @@ legacy_actions_and_bindings! {
     main_xy: xr::Action<xr::Vector2f>,
     main_xy_touch: xr::Action<bool>,
     main_xy_click: xr::Action<bool>,
+    // Only used by controllers that have both a stick and a trackpad (i.e., Index)
+    secondary_xy: xr::Action<xr::Vector2f>,
+    secondary_xy_touch: xr::Action<bool>,
+    secondary_xy_click: xr::Action<bool>,
+    haptic: xr::Action<xr::Haptic>,
 }
 
 pub(super) struct LegacyActionData {
@@ -251,6 +301,16 @@ impl LegacyActionData {
             main_xy_touch: set
                 .create_action("main-joystick-touch", "Main Joystick Touch", &leftright)
                 .unwrap(),
+            secondary_xy: set
+                .create_action("secondary-xy", "Secondary Joystick/Trackpad", &leftright)
+                .unwrap(),
+            secondary_xy_click: set
+                .create_action("secondary-xy-click", "Secondary Trackpad Click", &leftright)
+                .unwrap(),
+            secondary_xy_touch: set
+                .create_action("secondary-xy-touch", "Secondary Trackpad Touch", &leftright)
+                .unwrap(),
+            haptic: set.create_action("haptic", "Haptic", &leftright).unwrap(),
         };
 
         Self {
@@ -264,13 +324,17 @@ impl LegacyActionData {
 
 pub fn setup_legacy_bindings(
     instance: &xr::Instance,
+    extensions: &xr::ExtensionSet,
     session: &xr::Session<xr::AnyGraphics>,
     legacy: &LegacyActionData,
 ) {
     debug!("setting up legacy bindings");
 
     let actions = &legacy.actions;
-    for profile in Profiles::get().profiles_iter() {
+    for profile in Profiles::get()
+        .profiles_iter()
+        .filter(|profile| profile.is_supported(extensions))
+    {
         const fn constrain<F>(f: F) -> F
         where
             F: for<'a> Fn(&'a str) -> xr::Path,
@@ -289,9 +353,11 @@ pub fn setup_legacy_bindings(
     }
 
     session.attach_action_sets(&[&legacy.set]).unwrap();
-    session
-        .sync_actions(&[xr::ActiveActionSet::new(&legacy.set)])
-        .unwrap();
+    xr_call!(
+        "xrSyncActions",
+        session.sync_actions(&[xr::ActiveActionSet::new(&legacy.set)])
+    )
+    .unwrap();
 }
 
 pub(super) struct HandSpaces {
@@ -434,6 +500,42 @@ mod tests {
         assert!(!got_input);
     }
 
+    #[test]
+    fn packet_num_persists_across_session_restart() {
+        use fakexr::UserPath::*;
+        let f = Fixture::new();
+        let setup = || {
+            f.input.openxr.restart_session();
+            f.set_interaction_profile(&Knuckles, LeftHand);
+            f.input.frame_start_update();
+            f.input.openxr.poll_events();
+        };
+        let packet_num = || {
+            let mut state = vr::VRControllerState_t::default();
+            assert!(f.input.get_legacy_controller_state(
+                1,
+                &mut state,
+                std::mem::size_of_val(&state) as u32
+            ));
+            state.unPacketNum
+        };
+
+        setup();
+        f.input.frame_start_update();
+        let first = packet_num();
+        f.input.frame_start_update();
+        let second = packet_num();
+        assert!(second > first, "{second} should be greater than {first}");
+
+        setup();
+        f.input.frame_start_update();
+        let after_restart = packet_num();
+        assert!(
+            after_restart > second,
+            "packet number went from {second} to {after_restart} after restart"
+        );
+    }
+
     fn legacy_input(
         get_action: impl FnOnce(&super::LegacyActions) -> openxr::sys::Action,
         ids: &[vr::EVRButtonId],
@@ -656,4 +758,85 @@ mod tests {
         vr::EVRButtonId::Grip | vr::EVRButtonId::Axis2
     );
     test_button!(a, vr::EVRButtonId::A);
+    test_button!(
+        secondary_xy_click,
+        vr::EVRButtonId::Axis3,
+        secondary_xy_touch
+    );
+
+    #[test]
+    fn haptic_bindings() {
+        let f = Fixture::new();
+        f.input.openxr.restart_session();
+        f.input.frame_start_update();
+
+        let data = f.input.openxr.session_data.get();
+        let action = data
+            .input_data
+            .legacy_actions
+            .get()
+            .expect("Legacy actions should be set up")
+            .actions
+            .haptic
+            .as_raw();
+
+        // Profiles without haptics, like bare hands, have nothing to bind.
+        let has_haptics = |profile: &&dyn crate::input::InteractionProfile| {
+            profile
+                .legal_paths()
+                .iter()
+                .any(|path| path.ends_with("/output/haptic"))
+        };
+        for profile in super::Profiles::get().profiles_iter().filter(has_haptics) {
+            let path = f
+                .input
+                .openxr
+                .instance
+                .string_to_path(profile.profile_path())
+                .unwrap();
+            let mut bindings = fakexr::get_suggested_bindings(action, path);
+            bindings.sort();
+            assert_eq!(
+                bindings,
+                [
+                    "/user/hand/left/output/haptic",
+                    "/user/hand/right/output/haptic"
+                ],
+                "{}",
+                profile.profile_path()
+            );
+        }
+    }
+
+    #[test]
+    fn haptic_pulse() {
+        use fakexr::UserPath::*;
+        let f = Fixture::new();
+        f.input.openxr.restart_session();
+
+        f.set_interaction_profile(&Knuckles, LeftHand);
+        f.set_interaction_profile(&Knuckles, RightHand);
+        f.input.frame_start_update();
+        f.input.openxr.poll_events();
+
+        let action = f
+            .input
+            .openxr
+            .session_data
+            .get()
+            .input_data
+            .legacy_actions
+            .get()
+            .unwrap()
+            .actions
+            .haptic
+            .as_raw();
+
+        f.input.trigger_legacy_haptic_pulse(2, 3000);
+
+        assert!(fakexr::take_haptic_pulse(action, LeftHand).is_none());
+        let pulse = fakexr::take_haptic_pulse(action, RightHand).expect("No haptic pulse");
+        assert_eq!(pulse.duration, openxr::Duration::from_nanos(3_000_000));
+        assert_eq!(pulse.amplitude, 1.0);
+    }
 }
@@ -0,0 +1,59 @@
+use log::{info, warn};
+use openvr as vr;
+use std::sync::Mutex;
+
+/// Decides what's reported for a controller that isn't tracked. By default its pose is invalid,
+/// but some games handle that badly, so the last valid pose can be frozen in place instead.
+pub(super) struct LostTracking {
+    /// Whether the last valid pose is reported in place of invalid ones.
+    freeze: bool,
+    last_valid: Mutex<Option<(vr::ETrackingUniverseOrigin, vr::TrackedDevicePose_t)>>,
+}
+
+impl LostTracking {
+    pub fn new(freeze: bool) -> Self {
+        Self {
+            freeze,
+            last_valid: Mutex::default(),
+        }
+    }
+
+    /// Reads the behavior from `XRIZER_LOST_TRACKING` (`invalid` or `freeze`), defaulting to
+    /// invalid poses.
+    pub fn from_env() -> Self {
+        let freeze = match std::env::var("XRIZER_LOST_TRACKING").as_deref() {
+            Err(_) | Ok("invalid") => false,
+            Ok("freeze") => true,
+            Ok(other) => {
+                warn!("Invalid XRIZER_LOST_TRACKING value {other:?}");
+                false
+            }
+        };
+
+        if freeze {
+            info!("Freezing controller poses while they're not tracked");
+        }
+        Self::new(freeze)
+    }
+
+    pub fn apply(&self, origin: vr::ETrackingUniverseOrigin, pose: &mut vr::TrackedDevicePose_t) {
+        let mut last_valid = self.last_valid.lock().unwrap();
+        if pose.bPoseIsValid {
+            *last_valid = Some((origin, *pose));
+            return;
+        }
+        if !self.freeze {
+            return;
+        }
+
+        if let Some((_, last)) = last_valid.as_ref().filter(|(o, _)| *o == origin) {
+            *pose = vr::TrackedDevicePose_t {
+                mDeviceToAbsoluteTracking: last.mDeviceToAbsoluteTracking,
+                bPoseIsValid: true,
+                bDeviceIsConnected: true,
+                eTrackingResult: vr::ETrackingResult::Running_OutOfRange,
+                ..Default::default()
+            };
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::openxr_data::{pose_from_glam, pose_to_glam};
+use glam::{Quat, Vec3};
+use log::{info, warn};
+use openvr as vr;
+use std::sync::Mutex;
+
+/// Controller speed (in m/s) above which poses are passed through unfiltered, so that fast
+/// motion doesn't lag behind.
+const BYPASS_VELOCITY: f32 = 1.0;
+
+/// Exponential low pass filter for controller poses, for runtimes with noisy tracking.
+pub(super) struct PoseFilter {
+    /// How much of the previous filtered pose is kept on each update, in the range [0, 1).
+    /// 0 disables filtering.
+    smoothing: f32,
+    /// The last filtered pose in each origin (seated, then standing). Games can read poses in
+    /// both origins every frame, so each is filtered separately.
+    states: Mutex<[Option<FilterState>; 2]>,
+}
+
+struct FilterState {
+    position: Vec3,
+    orientation: Quat,
+}
+
+impl PoseFilter {
+    pub fn new(smoothing: f32) -> Self {
+        Self {
+            smoothing: smoothing.clamp(0.0, 0.99),
+            states: Mutex::default(),
+        }
+    }
+
+    /// Reads the smoothing factor from `XRIZER_POSE_SMOOTHING`, defaulting to no filtering.
+    pub fn from_env() -> Self {
+        let smoothing = std::env::var("XRIZER_POSE_SMOOTHING")
+            .ok()
+            .and_then(|value| {
+                value
+                    .parse::<f32>()
+                    .inspect_err(|e| warn!("Invalid XRIZER_POSE_SMOOTHING value {value:?}: {e}"))
+                    .ok()
+            })
+            .unwrap_or(0.0);
+
+        if smoothing > 0.0 {
+            info!("Controller pose smoothing enabled (factor {smoothing})");
+        }
+        Self::new(smoothing)
+    }
+
+    pub fn apply(&self, origin: vr::ETrackingUniverseOrigin, pose: &mut vr::TrackedDevicePose_t) {
+        if self.smoothing == 0.0 {
+            return;
+        }
+
+        let mut states = self.states.lock().unwrap();
+        let state = match origin {
+            vr::ETrackingUniverseOrigin::Seated => &mut states[0],
+            vr::ETrackingUniverseOrigin::Standing => &mut states[1],
+            vr::ETrackingUniverseOrigin::RawAndUncalibrated => unreachable!(),
+        };
+        if !pose.bPoseIsValid {
+            *state = None;
+            return;
+        }
+
+        let (orientation, position) = pose_to_glam(pose.mDeviceToAbsoluteTracking.into());
+        let speed = Vec3::from_array(pose.vVelocity.v).length();
+
+        let (position, orientation) = match state.as_ref() {
+            Some(prev) if speed < BYPASS_VELOCITY => {
+                let t = 1.0 - self.smoothing;
+                (
+                    prev.position.lerp(position, t),
+                    prev.orientation.slerp(orientation, t),
+                )
+            }
+            _ => (position, orientation),
+        };
+
+        *state = Some(FilterState {
+            position,
+            orientation,
+        });
+        pose.mDeviceToAbsoluteTracking = pose_from_glam(orientation, position).into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PoseFilter;
+    use openvr as vr;
+    use openxr as xr;
+
+    const ORIGIN: vr::ETrackingUniverseOrigin = vr::ETrackingUniverseOrigin::Standing;
+
+    fn pose(x: f32, velocity: f32) -> vr::TrackedDevicePose_t {
+        vr::TrackedDevicePose_t {
+            mDeviceToAbsoluteTracking: xr::Posef {
+                position: xr::Vector3f { x, y: 1.0, z: 0.0 },
+                orientation: xr::Quaternionf::IDENTITY,
+            }
+            .into(),
+            vVelocity: vr::HmdVector3_t {
+                v: [velocity, 0.0, 0.0],
+            },
+            bPoseIsValid: true,
+            ..Default::default()
+        }
+    }
+
+    fn filtered_x(filter: &PoseFilter, x: f32, velocity: f32) -> f32 {
+        filtered_x_in(filter, ORIGIN, x, velocity)
+    }
+
+    fn filtered_x_in(
+        filter: &PoseFilter,
+        origin: vr::ETrackingUniverseOrigin,
+        x: f32,
+        velocity: f32,
+    ) -> f32 {
+        let mut pose = pose(x, velocity);
+        filter.apply(origin, &mut pose);
+        xr::Posef::from(pose.mDeviceToAbsoluteTracking).position.x
+    }
+
+    fn variance(values: &[f32]) -> f32 {
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32
+    }
+
+    #[test]
+    fn reduces_jitter() {
+        let filter = PoseFilter::new(0.8);
+        let raw: Vec<f32> = (0..200)
+            .map(|i: usize| ((i * 7919) % 13) as f32 / 13.0 * 0.02 - 0.01)
+            .collect();
+        let filtered: Vec<f32> = raw.iter().map(|x| filtered_x(&filter, *x, 0.0)).collect();
+
+        // Skip the first few samples while the filter settles
+        let raw_variance = variance(&raw[10..]);
+        let filtered_variance = variance(&filtered[10..]);
+        assert!(
+            filtered_variance < raw_variance / 2.0,
+            "filtered variance {filtered_variance} should be well below raw variance {raw_variance}"
+        );
+    }
+
+    #[test]
+    fn filters_each_origin() {
+        let filter = PoseFilter::new(0.8);
+        let raw: Vec<f32> = (0..200)
+            .map(|i: usize| ((i * 7919) % 13) as f32 / 13.0 * 0.02 - 0.01)
+            .collect();
+        // The seated origin is offset from the standing one, so mixing them up would add jitter.
+        let (seated, standing): (Vec<f32>, Vec<f32>) = raw
+            .iter()
+            .map(|x| {
+                (
+                    filtered_x_in(&filter, vr::ETrackingUniverseOrigin::Seated, x + 1.0, 0.0),
+                    filtered_x_in(&filter, vr::ETrackingUniverseOrigin::Standing, *x, 0.0),
+                )
+            })
+            .unzip();
+
+        let raw_variance = variance(&raw[10..]);
+        for filtered in [&seated, &standing] {
+            let filtered_variance = variance(&filtered[10..]);
+            assert!(
+                filtered_variance < raw_variance / 2.0,
+                "filtered variance {filtered_variance} should be well below raw variance {raw_variance}"
+            );
+        }
+    }
+
+    #[test]
+    fn tracks_large_moves() {
+        let filter = PoseFilter::new(0.8);
+        assert_eq!(filtered_x(&filter, 0.0, 0.0), 0.0);
+
+        // Fast motion bypasses the filter entirely
+        assert_eq!(filtered_x(&filter, 0.5, 2.0), 0.5);
+
+        // Slow motion still converges on the real position
+        let mut x = 0.0;
+        for _ in 0..60 {
+            x = filtered_x(&filter, 1.0, 0.0);
+        }
+        assert!((x - 1.0).abs() < 1e-3, "{x}");
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let filter = PoseFilter::new(0.0);
+        assert_eq!(filtered_x(&filter, 0.0, 0.0), 0.0);
+        assert_eq!(filtered_x(&filter, 0.3, 0.0), 0.3);
+    }
+}
@@ -1,27 +1,51 @@
+pub mod hand_interaction;
 pub mod knuckles;
 pub mod oculus_touch;
 pub mod simple_controller;
 pub mod vive_controller;
 
 use super::{
-    action_manifest::ControllerType, legacy::LegacyBindings, skeletal::SkeletalInputBindings,
+    action_manifest::ControllerType,
+    legacy::{button_mask_from_id, LegacyBindings},
+    skeletal::SkeletalInputBindings,
 };
 use crate::openxr_data::Hand;
 use glam::Mat4;
+use hand_interaction::HandInteraction;
 use knuckles::Knuckles;
 use oculus_touch::Touch;
+use openvr as vr;
 use openxr as xr;
 use simple_controller::SimpleController;
+use std::collections::HashMap;
 use std::ffi::CStr;
+use std::sync::LazyLock;
 use vive_controller::ViveWands;
 
 #[allow(private_interfaces)]
 pub trait InteractionProfile: Sync + Send {
     fn profile_path(&self) -> &'static str;
+    /// Whether bindings can be suggested for this profile, for profiles that need an extension.
+    fn is_supported(&self, _: &xr::ExtensionSet) -> bool {
+        true
+    }
     fn properties(&self) -> &'static ProfileProperties;
     fn translate_map(&self) -> &'static [PathTranslation];
 
     fn legal_paths(&self) -> Box<[String]>;
+    /// Corresponds to Prop_SupportedButtons_Uint64, derived from the inputs in legacy_bindings
+    fn legacy_buttons_mask(&self) -> u64 {
+        static MASKS: LazyLock<HashMap<&'static str, u64>> = LazyLock::new(|| {
+            Profiles::get()
+                .profiles_iter()
+                .map(|p| (p.profile_path(), buttons_mask(p)))
+                .collect()
+        });
+        MASKS
+            .get(self.profile_path())
+            .copied()
+            .unwrap_or_else(|| buttons_mask(self))
+    }
     fn legacy_bindings(&self, string_to_path: &dyn StringToPath) -> LegacyBindings;
     /// Can be extracted from SteamVR rendermodel files, it is the inverse of the "grip" or "openxr_grip" value
     fn offset_grip_pose(&self, _: Hand) -> Mat4;
@@ -50,6 +74,32 @@ pub enum MainAxisType {
     Trackpad,
 }
 
+/// The input that curls a finger in the estimated hand skeleton.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CurlSource {
+    /// The `index_curl` skeletal input (usually the trigger)
+    Trigger,
+    /// The `rest_curl` skeletal input (usually the grip)
+    Grip,
+}
+
+pub struct FingerCurlSources {
+    pub index: CurlSource,
+    pub middle: CurlSource,
+    pub ring: CurlSource,
+    pub pinky: CurlSource,
+}
+
+impl FingerCurlSources {
+    /// Index finger follows the trigger, the rest of the hand follows the grip.
+    pub const TRIGGER_AND_GRIP: Self = Self {
+        index: CurlSource::Trigger,
+        middle: CurlSource::Grip,
+        ring: CurlSource::Grip,
+        pinky: CurlSource::Grip,
+    };
+}
+
 pub struct ProfileProperties {
     /// Corresponds to Prop_ModelNumber_String
     /// Can be pulled from a SteamVR System Report
@@ -65,13 +115,45 @@ pub struct ProfileProperties {
     pub registered_device_type: Property<&'static CStr>,
     /// Corresponds to Prop_SerialNumber_String
     pub serial_number: Property<&'static CStr>,
+    /// Corresponds to Prop_HardwareRevision_String
+    /// Can be pulled from a SteamVR System Report
+    pub hardware_revision: Property<&'static CStr>,
     /// Corresponds to Prop_TrackingSystemName_String
     pub tracking_system_name: &'static CStr,
     /// Corresponds to Prop_ManufacturerName_String
     pub manufacturer_name: &'static CStr,
-    /// Corresponds to Prop_SupportedButtons_Uint64
-    /// Can be pulled from a SteamVR System Report
-    pub legacy_buttons_mask: u64,
+    /// Which inputs curl each finger of the estimated skeleton
+    pub finger_curl: FingerCurlSources,
+}
+
+/// Maps the legacy bindings of a profile to the legacy buttons they correspond to.
+fn buttons_mask(profile: &(impl InteractionProfile + ?Sized)) -> u64 {
+    use vr::EVRButtonId::*;
+
+    // Only which bindings exist matters, not their paths.
+    let bindings = profile.legacy_bindings(&|_: &str| xr::Path::NULL);
+    let legal_paths = profile.legal_paths();
+
+    [
+        // Every controller has a system button, even if it's reserved by the runtime.
+        (System, true),
+        (ApplicationMenu, !bindings.app_menu.is_empty()),
+        (Grip, !bindings.squeeze_click.is_empty()),
+        (A, !bindings.a.is_empty()),
+        (Axis0, !bindings.main_xy.is_empty()),
+        (Axis1, !bindings.trigger.is_empty()),
+        // Analog grip
+        (
+            Axis2,
+            legal_paths.iter().any(|path| {
+                path.ends_with("/input/squeeze/value") || path.ends_with("/input/grasp_ext/value")
+            }),
+        ),
+        (Axis3, !bindings.secondary_xy.is_empty()),
+    ]
+    .into_iter()
+    .filter(|(_, supported)| *supported)
+    .fold(0, |mask, (id, _)| mask | button_mask_from_id(id))
 }
 
 pub(super) struct PathTranslation {
@@ -113,6 +195,7 @@ impl Profiles {
                 (ControllerType::Knuckles, &Knuckles),
                 (ControllerType::OculusTouch, &Touch),
                 (ControllerType::ViveController, &SimpleController),
+                (ControllerType::OculusTouch, &HandInteraction),
             ],
         };
         &P
@@ -129,3 +212,45 @@ impl Profiles {
             .find_map(|(_, p)| (p.profile_path() == name).then_some(*p))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        HandInteraction, InteractionProfile, Knuckles, SimpleController, Touch, ViveWands,
+    };
+    use crate::input::legacy::button_mask_from_id;
+    use openvr::EVRButtonId::{self, *};
+
+    fn supports(profile: &dyn InteractionProfile, button: EVRButtonId) -> bool {
+        profile.legacy_buttons_mask() & button_mask_from_id(button) != 0
+    }
+
+    #[test]
+    fn legacy_buttons_mask() {
+        // Trigger
+        assert!(supports(&ViveWands, Axis1));
+        assert!(supports(&ViveWands, ApplicationMenu));
+        assert!(!supports(&ViveWands, A));
+
+        // B is reported as the application menu
+        assert!(supports(&Knuckles, A));
+        assert!(supports(&Knuckles, ApplicationMenu));
+        // Thumbstick and trackpad
+        assert!(supports(&Knuckles, Axis0));
+        assert!(supports(&Knuckles, Axis3));
+
+        assert!(supports(&Touch, A));
+        assert!(!supports(&Touch, Axis3));
+
+        // The menu button doubles as the grip in the legacy bindings.
+        assert!(supports(&SimpleController, Grip));
+        assert!(!supports(&SimpleController, Axis2));
+        assert!(!supports(&SimpleController, Axis0));
+
+        // Pinch and grasp
+        assert!(supports(&HandInteraction, Axis1));
+        assert!(supports(&HandInteraction, Grip));
+        assert!(supports(&HandInteraction, Axis2));
+        assert!(!supports(&HandInteraction, A));
+    }
+}
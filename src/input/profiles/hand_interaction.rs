@@ -0,0 +1,241 @@
+use super::{
+    FingerCurlSources, InteractionProfile, MainAxisType, PathTranslation, ProfileProperties,
+    Property, SkeletalInputBindings, StringToPath,
+};
+use crate::input::legacy::LegacyBindings;
+use crate::openxr_data::Hand;
+use glam::Mat4;
+use openxr as xr;
+
+/// Bare hands on runtimes with hand tracking (XR_EXT_hand_interaction). Pinching stands in for
+/// the trigger and grasping for the grip, so games made for controllers can be played without
+/// them.
+pub struct HandInteraction;
+
+impl InteractionProfile for HandInteraction {
+    fn properties(&self) -> &'static ProfileProperties {
+        static DEVICE_PROPERTIES: ProfileProperties = ProfileProperties {
+            model: Property::BothHands(c"Hand Interaction"),
+            // Bindings for Touch controllers are used, so games should show those prompts.
+            openvr_controller_type: c"oculus_touch",
+            render_model_name: Property::BothHands(c"generic_controller"),
+            main_axis: MainAxisType::Thumbstick,
+            registered_device_type: Property::PerHand {
+                left: c"xrizer/hand_interaction_left",
+                right: c"xrizer/hand_interaction_right",
+            },
+            serial_number: Property::PerHand {
+                left: c"HAND-INTERACTION-LEFT",
+                right: c"HAND-INTERACTION-RIGHT",
+            },
+            hardware_revision: Property::BothHands(c"1"),
+            tracking_system_name: c"xrizer",
+            manufacturer_name: c"xrizer",
+            finger_curl: FingerCurlSources::TRIGGER_AND_GRIP,
+        };
+        &DEVICE_PROPERTIES
+    }
+    fn profile_path(&self) -> &'static str {
+        "/interaction_profiles/ext/hand_interaction_ext"
+    }
+    fn is_supported(&self, extensions: &xr::ExtensionSet) -> bool {
+        extensions.ext_hand_interaction
+    }
+    fn translate_map(&self) -> &'static [PathTranslation] {
+        &[
+            PathTranslation {
+                from: "trigger/click",
+                to: "pinch_ext/value",
+                stop: true,
+            },
+            PathTranslation {
+                from: "trigger/pull",
+                to: "pinch_ext/value",
+                stop: true,
+            },
+            PathTranslation {
+                from: "trigger/value",
+                to: "pinch_ext/value",
+                stop: true,
+            },
+            PathTranslation {
+                from: "trigger/touch",
+                to: "pinch_ext/ready_ext",
+                stop: true,
+            },
+            PathTranslation {
+                from: "grip/click",
+                to: "grasp_ext/value",
+                stop: true,
+            },
+            PathTranslation {
+                from: "grip/pull",
+                to: "grasp_ext/value",
+                stop: true,
+            },
+            PathTranslation {
+                from: "grip/value",
+                to: "grasp_ext/value",
+                stop: true,
+            },
+            PathTranslation {
+                from: "grip/touch",
+                to: "grasp_ext/ready_ext",
+                stop: true,
+            },
+        ]
+    }
+
+    fn legacy_bindings(&self, stp: &dyn StringToPath) -> LegacyBindings {
+        LegacyBindings {
+            grip_pose: stp.leftright("input/grip/pose"),
+            aim_pose: stp.leftright("input/aim/pose"),
+            trigger: stp.leftright("input/pinch_ext/value"),
+            trigger_click: stp.leftright("input/pinch_ext/value"),
+            app_menu: vec![],
+            a: vec![],
+            squeeze: stp.leftright("input/grasp_ext/value"),
+            squeeze_click: stp.leftright("input/grasp_ext/value"),
+            main_xy: vec![],
+            main_xy_click: vec![],
+            main_xy_touch: vec![],
+            secondary_xy: vec![],
+            secondary_xy_click: vec![],
+            secondary_xy_touch: vec![],
+            haptic: vec![],
+        }
+    }
+
+    fn skeletal_input_bindings(&self, stp: &dyn StringToPath) -> SkeletalInputBindings {
+        SkeletalInputBindings {
+            thumb_touch: Vec::new(),
+            thumb_click: Vec::new(),
+            index_touch: stp.leftright("input/pinch_ext/ready_ext"),
+            index_curl: stp.leftright("input/pinch_ext/value"),
+            rest_curl: stp.leftright("input/grasp_ext/value"),
+        }
+    }
+
+    fn legal_paths(&self) -> Box<[String]> {
+        [
+            "input/pinch_ext/value",
+            "input/pinch_ext/ready_ext",
+            "input/aim_activate_ext/value",
+            "input/aim_activate_ext/ready_ext",
+            "input/grasp_ext/value",
+            "input/grasp_ext/ready_ext",
+            "input/grip/pose",
+            "input/aim/pose",
+            "input/pinch_ext/pose",
+            "input/poke_ext/pose",
+        ]
+        .iter()
+        .flat_map(|s| {
+            [
+                format!("/user/hand/left/{s}"),
+                format!("/user/hand/right/{s}"),
+            ]
+        })
+        .collect()
+    }
+
+    fn offset_grip_pose(&self, _: Hand) -> Mat4 {
+        Mat4::IDENTITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HandInteraction, InteractionProfile};
+    use crate::input::tests::Fixture;
+    use fakexr::UserPath::*;
+    use openvr as vr;
+
+    #[test]
+    fn verify_bindings() {
+        let f = Fixture::new();
+        assert!(f.input.openxr.enabled_extensions.ext_hand_interaction);
+        f.load_actions(c"actions.json");
+
+        let path = HandInteraction.profile_path();
+        f.verify_bindings::<f32>(
+            path,
+            c"/actions/set1/boolact_asfloat",
+            [
+                "/user/hand/left/input/grasp_ext/value".into(),
+                "/user/hand/right/input/grasp_ext/value".into(),
+                "/user/hand/left/input/pinch_ext/value".into(),
+                "/user/hand/right/input/pinch_ext/value".into(),
+            ],
+        );
+
+        f.verify_bindings::<f32>(
+            path,
+            c"/actions/set1/in/vec1act",
+            [
+                "/user/hand/left/input/pinch_ext/value".into(),
+                "/user/hand/right/input/pinch_ext/value".into(),
+            ],
+        );
+    }
+
+    #[test]
+    fn legacy_trigger_and_grip() {
+        let f = Fixture::new();
+        f.input.openxr.restart_session();
+        f.set_interaction_profile(&HandInteraction, LeftHand);
+        f.input.frame_start_update();
+        f.input.openxr.poll_events();
+
+        let data = f.input.openxr.session_data.get();
+        let actions = &data.input_data.legacy_actions.get().unwrap().actions;
+        let profile = f
+            .input
+            .openxr
+            .instance
+            .string_to_path(HandInteraction.profile_path())
+            .unwrap();
+        let mut bindings = fakexr::get_suggested_bindings(actions.trigger.as_raw(), profile);
+        bindings.sort();
+        assert_eq!(
+            bindings,
+            [
+                "/user/hand/left/input/pinch_ext/value",
+                "/user/hand/right/input/pinch_ext/value"
+            ]
+        );
+        let mut bindings = fakexr::get_suggested_bindings(actions.squeeze.as_raw(), profile);
+        bindings.sort();
+        assert_eq!(
+            bindings,
+            [
+                "/user/hand/left/input/grasp_ext/value",
+                "/user/hand/right/input/grasp_ext/value"
+            ]
+        );
+
+        // Pinching and grasping move the trigger and grip axes.
+        fakexr::set_action_state(
+            actions.trigger.as_raw(),
+            fakexr::ActionState::Float(0.25),
+            LeftHand,
+        );
+        fakexr::set_action_state(
+            actions.squeeze.as_raw(),
+            fakexr::ActionState::Float(0.75),
+            LeftHand,
+        );
+        drop(data);
+        f.input.frame_start_update();
+
+        let mut state = vr::VRControllerState_t::default();
+        assert!(f.input.get_legacy_controller_state(
+            1,
+            &mut state,
+            std::mem::size_of_val(&state) as u32
+        ));
+        let axes = state.rAxis;
+        assert_eq!(axes[1].x, 0.25);
+        assert_eq!(axes[2].x, 0.75);
+    }
+}
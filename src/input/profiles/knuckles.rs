@@ -1,12 +1,10 @@
 use super::{
-    InteractionProfile, MainAxisType, PathTranslation, ProfileProperties, Property,
-    SkeletalInputBindings, StringToPath,
+    FingerCurlSources, InteractionProfile, MainAxisType, PathTranslation, ProfileProperties,
+    Property, SkeletalInputBindings, StringToPath,
 };
-use crate::button_mask_from_ids;
-use crate::input::legacy::{button_mask_from_id, LegacyBindings};
+use crate::input::legacy::LegacyBindings;
 use crate::openxr_data::Hand;
 use glam::{EulerRot, Mat4, Quat, Vec3};
-use openvr::EVRButtonId;
 use std::iter::Iterator;
 
 pub struct Knuckles;
@@ -35,17 +33,12 @@ impl InteractionProfile for Knuckles {
                 left: c"LHR-FFFFFFF1",
                 right: c"LHR-FFFFFFF2",
             },
+            hardware_revision: Property::BothHands(c"product 17 rev 14.1.9 lot 2019/4/20 0"),
             tracking_system_name: c"lighthouse",
             manufacturer_name: c"Valve",
-            legacy_buttons_mask: button_mask_from_ids!(
-                EVRButtonId::System,
-                EVRButtonId::ApplicationMenu,
-                EVRButtonId::Grip,
-                EVRButtonId::A,
-                EVRButtonId::Axis0,
-                EVRButtonId::Axis1,
-                EVRButtonId::Axis2
-            ),
+            // The grip value is derived from the per finger capacitive sensors, which is as
+            // close as OpenXR gets us to the actual finger tracking.
+            finger_curl: FingerCurlSources::TRIGGER_AND_GRIP,
         };
         &DEVICE_PROPERTIES
     }
@@ -130,6 +123,10 @@ impl InteractionProfile for Knuckles {
             main_xy: stp.leftright("input/thumbstick"),
             main_xy_click: stp.leftright("input/thumbstick/click"),
             main_xy_touch: stp.leftright("input/thumbstick/touch"),
+            secondary_xy: stp.leftright("input/trackpad"),
+            secondary_xy_click: stp.leftright("input/trackpad/force"),
+            secondary_xy_touch: stp.leftright("input/trackpad/touch"),
+            haptic: stp.leftright("output/haptic"),
         }
     }
 
@@ -140,6 +137,7 @@ impl InteractionProfile for Knuckles {
                 .into_iter()
                 .chain(stp.leftright("input/trackpad/touch"))
                 .collect(),
+            thumb_click: stp.leftright("input/thumbstick/click"),
             index_touch: stp.leftright("input/trigger/touch"),
             index_curl: stp.leftright("input/trigger/value"),
             rest_curl: stp.leftright("input/squeeze/value"),
@@ -260,4 +258,20 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn legacy_bindings_include_stick_and_trackpad() {
+        let f = Fixture::new();
+        let stp = |s: &str| f.input.openxr.instance.string_to_path(s).unwrap();
+        let bindings = Knuckles.legacy_bindings(&stp);
+
+        for hand in ["left", "right"] {
+            assert!(bindings
+                .main_xy
+                .contains(&stp(&format!("/user/hand/{hand}/input/thumbstick"))));
+            assert!(bindings
+                .secondary_xy
+                .contains(&stp(&format!("/user/hand/{hand}/input/trackpad"))));
+        }
+    }
 }
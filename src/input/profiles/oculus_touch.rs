@@ -1,13 +1,10 @@
 use super::{
-    InteractionProfile, MainAxisType, PathTranslation, ProfileProperties, Property,
-    SkeletalInputBindings, StringToPath,
+    FingerCurlSources, InteractionProfile, MainAxisType, PathTranslation, ProfileProperties,
+    Property, SkeletalInputBindings, StringToPath,
 };
-use crate::button_mask_from_ids;
-use crate::input::legacy::button_mask_from_id;
 use crate::input::legacy::LegacyBindings;
 use crate::openxr_data::Hand;
 use glam::{EulerRot, Mat4, Quat, Vec3};
-use openvr::EVRButtonId::{ApplicationMenu, Axis0, Axis1, Axis2, Grip, System, A};
 
 pub struct Touch;
 
@@ -31,18 +28,11 @@ impl InteractionProfile for Touch {
                 left: c"WMHD315M3010GV_Controller_Left",
                 right: c"WMHD315M3010GV_Controller_Right",
             },
+            hardware_revision: Property::BothHands(c"14"),
             tracking_system_name: c"oculus",
             manufacturer_name: c"Oculus",
             main_axis: MainAxisType::Thumbstick,
-            legacy_buttons_mask: button_mask_from_ids!(
-                System,
-                ApplicationMenu,
-                Grip,
-                A,
-                Axis0,
-                Axis1,
-                Axis2
-            ),
+            finger_curl: FingerCurlSources::TRIGGER_AND_GRIP,
         };
         &DEVICE_PROPERTIES
     }
@@ -108,6 +98,10 @@ impl InteractionProfile for Touch {
             main_xy: stp.leftright("input/thumbstick"),
             main_xy_click: stp.leftright("input/thumbstick/click"),
             main_xy_touch: stp.leftright("input/thumbstick/touch"),
+            secondary_xy: vec![],
+            secondary_xy_click: vec![],
+            secondary_xy_touch: vec![],
+            haptic: stp.leftright("output/haptic"),
         }
     }
 
@@ -122,6 +116,7 @@ impl InteractionProfile for Touch {
                 .chain(stp.right("input/b/touch"))
                 .chain(stp.leftright("input/thumbrest/touch"))
                 .collect(),
+            thumb_click: stp.leftright("input/thumbstick/click"),
             index_touch: stp.leftright("input/trigger/touch"),
             index_curl: stp.leftright("input/trigger/value"),
             rest_curl: stp.leftright("input/squeeze/value"),
@@ -1,13 +1,10 @@
 use super::{
-    InteractionProfile, MainAxisType, PathTranslation, ProfileProperties, Property,
-    SkeletalInputBindings, StringToPath,
+    FingerCurlSources, InteractionProfile, MainAxisType, PathTranslation, ProfileProperties,
+    Property, SkeletalInputBindings, StringToPath,
 };
-use crate::button_mask_from_ids;
-use crate::input::legacy::button_mask_from_id;
 use crate::input::legacy::LegacyBindings;
 use crate::openxr_data::Hand;
 use glam::Mat4;
-use openvr::EVRButtonId::{ApplicationMenu, Axis0, Axis1, Grip, System};
 
 pub struct SimpleController;
 
@@ -27,9 +24,10 @@ impl InteractionProfile for SimpleController {
                 left: c"LHR-00000001",
                 right: c"LHR-00000002",
             },
+            hardware_revision: Property::BothHands(c"1"),
             tracking_system_name: c"lighthouse",
             manufacturer_name: c"HTC",
-            legacy_buttons_mask: button_mask_from_ids!(System, ApplicationMenu, Grip, Axis0, Axis1),
+            finger_curl: FingerCurlSources::TRIGGER_AND_GRIP,
         };
         &DEVICE_PROPERTIES
     }
@@ -64,12 +62,17 @@ impl InteractionProfile for SimpleController {
             main_xy: vec![],
             main_xy_click: vec![],
             main_xy_touch: vec![],
+            secondary_xy: vec![],
+            secondary_xy_click: vec![],
+            secondary_xy_touch: vec![],
+            haptic: stp.leftright("output/haptic"),
         }
     }
 
     fn skeletal_input_bindings(&self, stp: &dyn StringToPath) -> SkeletalInputBindings {
         SkeletalInputBindings {
             thumb_touch: Vec::new(),
+            thumb_click: Vec::new(),
             index_touch: stp.leftright("input/select/click"),
             index_curl: stp.leftright("input/select/click"),
             rest_curl: stp.leftright("input/menu/click"),
@@ -1,13 +1,10 @@
 use super::{
-    InteractionProfile, MainAxisType, PathTranslation, ProfileProperties, Property,
-    SkeletalInputBindings, StringToPath,
+    CurlSource, FingerCurlSources, InteractionProfile, MainAxisType, PathTranslation,
+    ProfileProperties, Property, SkeletalInputBindings, StringToPath,
 };
-use crate::button_mask_from_ids;
-use crate::input::legacy::button_mask_from_id;
 use crate::input::legacy::LegacyBindings;
 use crate::openxr_data::Hand;
 use glam::Mat4;
-use openvr::EVRButtonId::{ApplicationMenu, Axis0, Axis1, Grip, System};
 
 pub struct ViveWands;
 
@@ -26,9 +23,15 @@ impl InteractionProfile for ViveWands {
                 left: c"LHR-00000001",
                 right: c"LHR-00000002",
             },
+            hardware_revision: Property::BothHands(c"product 129 rev 1.5.0 lot 2000/0/0 0"),
             tracking_system_name: c"lighthouse",
             manufacturer_name: c"HTC",
-            legacy_buttons_mask: button_mask_from_ids!(System, ApplicationMenu, Grip, Axis0, Axis1),
+            finger_curl: FingerCurlSources {
+                index: CurlSource::Trigger,
+                middle: CurlSource::Trigger,
+                ring: CurlSource::Trigger,
+                pinky: CurlSource::Trigger,
+            },
         };
         &DEVICE_PROPERTIES
     }
@@ -98,16 +101,17 @@ impl InteractionProfile for ViveWands {
             main_xy: stp.leftright("input/trackpad"),
             main_xy_click: stp.leftright("input/trackpad/click"),
             main_xy_touch: stp.leftright("input/trackpad/touch"),
+            secondary_xy: vec![],
+            secondary_xy_click: vec![],
+            secondary_xy_touch: vec![],
+            haptic: stp.leftright("output/haptic"),
         }
     }
 
     fn skeletal_input_bindings(&self, stp: &dyn StringToPath) -> SkeletalInputBindings {
         SkeletalInputBindings {
-            thumb_touch: stp
-                .leftright("input/trackpad/click")
-                .into_iter()
-                .chain(stp.leftright("input/trackpad/touch"))
-                .collect(),
+            thumb_touch: stp.leftright("input/trackpad/touch"),
+            thumb_click: stp.leftright("input/trackpad/click"),
             index_touch: stp.leftright("input/trigger/click"),
             index_curl: stp.leftright("input/trigger/value"),
             rest_curl: stp.leftright("input/squeeze/click"),
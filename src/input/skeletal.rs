@@ -1,8 +1,9 @@
 #[path = "skeletal_generated.rs"]
 mod gen;
 
+use super::profiles::{CurlSource, FingerCurlSources};
 use super::Input;
-use crate::openxr_data::{self, Hand, OpenXrData, SessionData};
+use crate::openxr_data::{self, pose_to_glam, Hand, OpenXrData, SessionData};
 use glam::{Affine3A, Quat, Vec3};
 use log::debug;
 use openvr as vr;
@@ -20,6 +21,7 @@ impl<C: openxr_data::Compositor> Input<C> {
         xr_data: &OpenXrData<C>,
         session_data: &SessionData,
         space: vr::EVRSkeletalTransformSpace,
+        range: vr::EVRSkeletalMotionRange,
         hand_tracker: &xr::HandTracker,
         hand: Hand,
         transforms: &mut [vr::VRBoneTransform_t],
@@ -27,30 +29,26 @@ impl<C: openxr_data::Compositor> Input<C> {
         use HandSkeletonBone::*;
 
         let legacy = session_data.input_data.legacy_actions.get().unwrap();
-        let display_time = self.openxr.display_time.get();
+        let display_time = self.openxr.pose_time.get();
         let Some(raw) = match hand {
             Hand::Left => &legacy.left_spaces,
             Hand::Right => &legacy.right_spaces,
         }
         .try_get_or_init_raw(xr_data, session_data, &legacy.actions) else {
-            self.get_estimated_bones(session_data, space, hand, transforms);
+            self.get_estimated_bones(session_data, space, range, hand, transforms);
             return;
         };
 
         let Some(joints) = raw.locate_hand_joints(hand_tracker, display_time).unwrap() else {
-            self.get_estimated_bones(session_data, space, hand, transforms);
+            self.get_estimated_bones(session_data, space, range, hand, transforms);
             return;
         };
 
         let mut joints: Box<[_]> = joints
             .into_iter()
             .map(|joint_location| {
-                let position = joint_location.pose.position;
-                let orientation = joint_location.pose.orientation;
-                Affine3A::from_rotation_translation(
-                    Quat::from_xyzw(orientation.x, orientation.y, orientation.z, orientation.w),
-                    Vec3::from_array([position.x, position.y, position.z]),
-                )
+                let (orientation, position) = pose_to_glam(joint_location.pose);
+                Affine3A::from_rotation_translation(orientation, position)
             })
             .collect();
 
@@ -159,17 +157,30 @@ impl<C: openxr_data::Compositor> Input<C> {
         *self.skeletal_tracking_level.write().unwrap() = vr::EVRSkeletalTrackingLevel::Full;
     }
 
+    /// Curls the fingers from the open hand pose toward a fist, or only as far as they'd close
+    /// around the controller if `range` is WithController.
     pub(super) fn get_estimated_bones(
         &self,
         session_data: &SessionData,
         space: vr::EVRSkeletalTransformSpace,
+        range: vr::EVRSkeletalMotionRange,
         hand: Hand,
         transforms: &mut [vr::VRBoneTransform_t],
     ) {
         let finger_state = self.get_finger_state(session_data, hand);
-        let (open, fist) = match hand {
-            Hand::Left => (&gen::left_hand::OPENHAND, &gen::left_hand::FIST),
-            Hand::Right => (&gen::right_hand::OPENHAND, &gen::right_hand::FIST),
+        let (open, fist) = match (hand, range) {
+            (Hand::Left, vr::EVRSkeletalMotionRange::WithController) => {
+                (&gen::left_hand::OPENHAND, &gen::left_hand::GRIPLIMIT)
+            }
+            (Hand::Left, vr::EVRSkeletalMotionRange::WithoutController) => {
+                (&gen::left_hand::OPENHAND, &gen::left_hand::FIST)
+            }
+            (Hand::Right, vr::EVRSkeletalMotionRange::WithController) => {
+                (&gen::right_hand::OPENHAND, &gen::right_hand::GRIPLIMIT)
+            }
+            (Hand::Right, vr::EVRSkeletalMotionRange::WithoutController) => {
+                (&gen::right_hand::OPENHAND, &gen::right_hand::FIST)
+            }
         };
 
         const fn constrain<'a, F, G>(f: F) -> F
@@ -203,11 +214,66 @@ impl<C: openxr_data::Compositor> Input<C> {
         *self.skeletal_tracking_level.write().unwrap() = vr::EVRSkeletalTrackingLevel::Estimated;
     }
 
+    /// Finger curls for VRSkeletalSummaryData_t. Animation summaries follow the smoothed curls the
+    /// estimated skeleton is posed with, device summaries report each finger as fully curled or not
+    /// from the current inputs.
+    pub(super) fn get_summary_data(
+        &self,
+        session_data: &SessionData,
+        hand: Hand,
+        ty: vr::EVRSummaryType,
+    ) -> vr::VRSkeletalSummaryData_t {
+        let state = match ty {
+            vr::EVRSummaryType::FromAnimation => self.get_finger_state(session_data, hand),
+            vr::EVRSummaryType::FromDevice => {
+                let target = self.get_finger_target(session_data, hand);
+                let binary = |curl: f32| if curl >= 0.5 { 1.0 } else { 0.0 };
+                FingerState {
+                    index: binary(target.index),
+                    middle: binary(target.middle),
+                    ring: binary(target.ring),
+                    pinky: binary(target.pinky),
+                    thumb: binary(target.thumb),
+                    ..target
+                }
+            }
+        };
+
+        vr::VRSkeletalSummaryData_t {
+            flFingerSplay: [0.2; 4],
+            flFingerCurl: [
+                state.thumb,
+                state.index,
+                state.middle,
+                state.ring,
+                state.pinky,
+            ]
+            .map(|curl| curl.clamp(0.0, 1.0)),
+        }
+    }
+
     fn get_finger_state(&self, session_data: &SessionData, hand: Hand) -> FingerState {
         // Determines the speed at which fingers follow the input states
         // This value seems to feel right for both analog inputs and binary ones (like vive wands)
         const FINGER_SMOOTHING_SPEED: f32 = 24.0;
 
+        let target = self.get_finger_target(session_data, hand);
+        let mut state = self.estimated_finger_state[hand as usize - 1]
+            .lock()
+            .unwrap();
+
+        let elapsed_time = target.time.duration_since(state.time).as_secs_f32();
+        let t = (elapsed_time * FINGER_SMOOTHING_SPEED).min(1.0);
+
+        *state = state.lerp(&target, t);
+
+        *state
+    }
+
+    /// The curls the fingers are moving toward, from the current inputs.
+    fn get_finger_target(&self, session_data: &SessionData, hand: Hand) -> FingerState {
+        const THUMB_TOUCH_CURL: f32 = 0.5;
+
         let actions = &session_data
             .input_data
             .estimated_skeleton_actions
@@ -224,6 +290,11 @@ impl<C: openxr_data::Compositor> Input<C> {
             .state(&session_data.session, subaction)
             .unwrap()
             .current_state;
+        let thumb_click = actions
+            .thumb_click
+            .state(&session_data.session, subaction)
+            .unwrap()
+            .current_state;
         let index_touch = actions
             .index_touch
             .state(&session_data.session, subaction)
@@ -234,43 +305,50 @@ impl<C: openxr_data::Compositor> Input<C> {
             .state(&session_data.session, subaction)
             .unwrap()
             .current_state;
-        let rest_curl = actions
-            .rest_curl
-            .state(&session_data.session, subaction)
-            .unwrap()
-            .current_state;
+        let rest_curl = self.grip_curve(hand).apply(
+            actions
+                .rest_curl
+                .state(&session_data.session, subaction)
+                .unwrap()
+                .current_state,
+        );
 
-        let index = index_curl.max(
+        let sources = self
+            .get_profile_data(hand)
+            .map_or(&FingerCurlSources::TRIGGER_AND_GRIP, |data| {
+                &data.finger_curl
+            });
+        let curl = |source| match source {
+            CurlSource::Trigger => index_curl,
+            CurlSource::Grip => rest_curl,
+        };
+
+        let index_input = curl(sources.index);
+        let index = index_input.max(
             // Curl the index finger slightly on touch input
-            if index_touch || index_curl > 0.0 {
+            if index_touch || index_input > 0.0 {
                 0.3
             } else {
                 0.0
             },
         );
 
-        let mut state = self.estimated_finger_state[hand as usize - 1]
-            .lock()
-            .unwrap();
-
-        let current_time = std::time::Instant::now();
-
-        let target = FingerState {
+        FingerState {
             index,
             // Make other fingers curl with the index slightly to mimic how real human hands work
-            middle: rest_curl.max(index / 2.0),
-            ring: rest_curl.max(index / 4.0),
-            pinky: rest_curl.max(index / 6.0),
-            thumb: if thumb_touch { 1.0 } else { 0.0 },
-            time: current_time,
-        };
-
-        let elapsed_time = current_time.duration_since(state.time).as_secs_f32();
-        let t = (elapsed_time * FINGER_SMOOTHING_SPEED).min(1.0);
-
-        *state = state.lerp(&target, t);
-
-        *state
+            middle: curl(sources.middle).max(index / 2.0),
+            ring: curl(sources.ring).max(index / 4.0),
+            pinky: curl(sources.pinky).max(index / 6.0),
+            // Resting the thumb on a button only curls it partway, pressing it curls it fully
+            thumb: if thumb_click {
+                1.0
+            } else if thumb_touch {
+                THUMB_TOUCH_CURL
+            } else {
+                0.0
+            },
+            time: Instant::now(),
+        }
     }
 
     pub(super) fn get_reference_transforms(
@@ -532,6 +610,88 @@ pub(super) enum HandSkeletonBone {
     Count,
 }
 
+/// SteamVR's names for each [`HandSkeletonBone`], with `{}` standing in for the hand (`l` or `r`).
+const BONE_NAMES: [&str; HandSkeletonBone::Count as usize] = [
+    "Root",
+    "wrist_{}",
+    "finger_thumb_0_{}",
+    "finger_thumb_1_{}",
+    "finger_thumb_2_{}",
+    "finger_thumb_{}_end",
+    "finger_index_meta_{}",
+    "finger_index_0_{}",
+    "finger_index_1_{}",
+    "finger_index_2_{}",
+    "finger_index_{}_end",
+    "finger_middle_meta_{}",
+    "finger_middle_0_{}",
+    "finger_middle_1_{}",
+    "finger_middle_2_{}",
+    "finger_middle_{}_end",
+    "finger_ring_meta_{}",
+    "finger_ring_0_{}",
+    "finger_ring_1_{}",
+    "finger_ring_2_{}",
+    "finger_ring_{}_end",
+    "finger_pinky_meta_{}",
+    "finger_pinky_0_{}",
+    "finger_pinky_1_{}",
+    "finger_pinky_2_{}",
+    "finger_pinky_{}_end",
+    "finger_thumb_{}_aux",
+    "finger_index_{}_aux",
+    "finger_middle_{}_aux",
+    "finger_ring_{}_aux",
+    "finger_pinky_{}_aux",
+];
+
+/// The parent of each [`HandSkeletonBone`], or -1 for the root. The first bone of each finger hangs
+/// off the wrist and every other bone off the one before it. Aux bones are reported in model space
+/// (see [`parent_to_model_space_bone_data`]), so they're children of the root.
+pub(super) const BONE_PARENTS: [vr::BoneIndex_t; HandSkeletonBone::Count as usize] = [
+    -1,
+    Root as vr::BoneIndex_t,
+    Wrist as vr::BoneIndex_t,
+    Thumb0 as vr::BoneIndex_t,
+    Thumb1 as vr::BoneIndex_t,
+    Thumb2 as vr::BoneIndex_t,
+    Wrist as vr::BoneIndex_t,
+    IndexFinger0 as vr::BoneIndex_t,
+    IndexFinger1 as vr::BoneIndex_t,
+    IndexFinger2 as vr::BoneIndex_t,
+    IndexFinger3 as vr::BoneIndex_t,
+    Wrist as vr::BoneIndex_t,
+    MiddleFinger0 as vr::BoneIndex_t,
+    MiddleFinger1 as vr::BoneIndex_t,
+    MiddleFinger2 as vr::BoneIndex_t,
+    MiddleFinger3 as vr::BoneIndex_t,
+    Wrist as vr::BoneIndex_t,
+    RingFinger0 as vr::BoneIndex_t,
+    RingFinger1 as vr::BoneIndex_t,
+    RingFinger2 as vr::BoneIndex_t,
+    RingFinger3 as vr::BoneIndex_t,
+    Wrist as vr::BoneIndex_t,
+    PinkyFinger0 as vr::BoneIndex_t,
+    PinkyFinger1 as vr::BoneIndex_t,
+    PinkyFinger2 as vr::BoneIndex_t,
+    PinkyFinger3 as vr::BoneIndex_t,
+    Root as vr::BoneIndex_t,
+    Root as vr::BoneIndex_t,
+    Root as vr::BoneIndex_t,
+    Root as vr::BoneIndex_t,
+    Root as vr::BoneIndex_t,
+];
+
+/// The name SteamVR reports for a bone of the given hand's skeleton, or None if there's no such
+/// bone.
+pub(super) fn bone_name(bone: usize, hand: Hand) -> Option<String> {
+    let side = match hand {
+        Hand::Left => "l",
+        Hand::Right => "r",
+    };
+    BONE_NAMES.get(bone).map(|name| name.replace("{}", side))
+}
+
 macro_rules! skeletal_input_actions {
     ($($field:ident: $ty:ty),+$(,)?) => {
         pub struct SkeletalInputActions {
@@ -555,6 +715,7 @@ macro_rules! skeletal_input_actions {
 
 skeletal_input_actions! {
     thumb_touch: bool,
+    thumb_click: bool,
     index_touch: bool,
     index_curl: f32,
     rest_curl: f32,
@@ -575,6 +736,9 @@ impl SkeletalInputActionData {
         let thumb_touch = set
             .create_action("thumb-touch", "Thumb Touch", &leftright)
             .unwrap();
+        let thumb_click = set
+            .create_action("thumb-click", "Thumb Click", &leftright)
+            .unwrap();
         let index_touch = set
             .create_action("index-touch", "Index Touch", &leftright)
             .unwrap();
@@ -589,6 +753,7 @@ impl SkeletalInputActionData {
             set,
             actions: SkeletalInputActions {
                 thumb_touch,
+                thumb_click,
                 index_touch,
                 index_curl,
                 rest_curl,
@@ -596,3 +761,285 @@ impl SkeletalInputActionData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{bone_transform_to_glam, gen, FingerState, HandSkeletonBone};
+    use crate::input::grip_curve::GripCurve;
+    use crate::input::profiles::{knuckles::Knuckles, vive_controller::ViveWands};
+    use crate::input::tests::Fixture;
+    use crate::input::InteractionProfile;
+    use crate::openxr_data::Hand;
+    use fakexr::UserPath::*;
+    use openvr as vr;
+    use std::time::{Duration, Instant};
+
+    impl Fixture {
+        /// Returns the finger state for the hand with smoothing already settled on the target.
+        fn settled_finger_state(&self, hand: Hand) -> FingerState {
+            *self.input.estimated_finger_state[hand as usize - 1]
+                .lock()
+                .unwrap() = FingerState {
+                time: Instant::now() - Duration::from_secs(1),
+                ..FingerState::new()
+            };
+            let data = self.input.openxr.session_data.get();
+            self.input.get_finger_state(&data, hand)
+        }
+    }
+
+    #[test]
+    fn thumb_curls_more_on_click_than_touch() {
+        let f = Fixture::new();
+        let set1 = f.get_action_set_handle(c"/actions/set1");
+        f.load_actions(c"actions.json");
+        f.set_interaction_profile(&Knuckles, LeftHand);
+
+        let (touch, click) = {
+            let data = f.input.openxr.session_data.get();
+            let actions = &data
+                .input_data
+                .estimated_skeleton_actions
+                .get()
+                .unwrap()
+                .actions;
+            (actions.thumb_touch.as_raw(), actions.thumb_click.as_raw())
+        };
+
+        let sync = || {
+            f.sync(vr::VRActiveActionSet_t {
+                ulActionSet: set1,
+                ..Default::default()
+            })
+        };
+
+        sync();
+        let resting = f.settled_finger_state(Hand::Left).thumb;
+        assert_eq!(resting, 0.0);
+
+        fakexr::set_action_state(touch, true.into(), LeftHand);
+        sync();
+        let touched = f.settled_finger_state(Hand::Left).thumb;
+
+        fakexr::set_action_state(click, true.into(), LeftHand);
+        sync();
+        let clicked = f.settled_finger_state(Hand::Left).thumb;
+
+        assert!(
+            touched > resting,
+            "{touched} should be greater than {resting}"
+        );
+        assert!(
+            clicked > touched,
+            "{clicked} should be greater than {touched}"
+        );
+        assert_eq!(clicked, 1.0);
+    }
+
+    #[test]
+    fn finger_curl_follows_profile_sources() {
+        let f = Fixture::new();
+        let set1 = f.get_action_set_handle(c"/actions/set1");
+        f.load_actions(c"actions.json");
+        f.set_interaction_profile(&Knuckles, LeftHand);
+        f.set_interaction_profile(&ViveWands, RightHand);
+
+        let (trigger, grip) = {
+            let data = f.input.openxr.session_data.get();
+            let actions = &data
+                .input_data
+                .estimated_skeleton_actions
+                .get()
+                .unwrap()
+                .actions;
+            (actions.index_curl.as_raw(), actions.rest_curl.as_raw())
+        };
+
+        let set_inputs = |trigger_value, grip_value| {
+            for hand in [LeftHand, RightHand] {
+                fakexr::set_action_state(trigger, fakexr::ActionState::Float(trigger_value), hand);
+                fakexr::set_action_state(grip, fakexr::ActionState::Float(grip_value), hand);
+            }
+            f.sync(vr::VRActiveActionSet_t {
+                ulActionSet: set1,
+                ..Default::default()
+            });
+            f.input.openxr.poll_events();
+        };
+
+        // Vive wands curl the whole hand with the trigger, Index only curls the index finger
+        set_inputs(1.0, 0.0);
+        let knuckles = f.settled_finger_state(Hand::Left);
+        let vive = f.settled_finger_state(Hand::Right);
+        assert_eq!(knuckles.index, 1.0);
+        assert!(knuckles.pinky < 0.5, "{}", knuckles.pinky);
+        assert_eq!(vive.index, 1.0);
+        assert_eq!(vive.pinky, 1.0);
+
+        // Index curls the rest of the hand with the grip, Vive wands ignore it
+        set_inputs(0.0, 1.0);
+        let knuckles = f.settled_finger_state(Hand::Left);
+        let vive = f.settled_finger_state(Hand::Right);
+        assert_eq!(knuckles.index, 0.0);
+        assert_eq!(knuckles.middle, 1.0);
+        assert_eq!(vive.middle, 0.0);
+        assert_eq!(vive.pinky, 0.0);
+    }
+
+    #[test]
+    fn finger_curl_follows_grip_curve() {
+        let f = Fixture::new_with(|input| {
+            let knuckles = input
+                .openxr
+                .instance
+                .string_to_path(Knuckles.profile_path())
+                .unwrap();
+            input
+                .grip_curves
+                .insert(knuckles, GripCurve::Exponential { gamma: 2.0 });
+        });
+        let set1 = f.get_action_set_handle(c"/actions/set1");
+        f.load_actions(c"actions.json");
+        f.set_interaction_profile(&Knuckles, LeftHand);
+
+        let grip = f
+            .input
+            .openxr
+            .session_data
+            .get()
+            .input_data
+            .estimated_skeleton_actions
+            .get()
+            .unwrap()
+            .actions
+            .rest_curl
+            .as_raw();
+        fakexr::set_action_state(grip, fakexr::ActionState::Float(0.5), LeftHand);
+        f.sync(vr::VRActiveActionSet_t {
+            ulActionSet: set1,
+            ..Default::default()
+        });
+        f.input.openxr.poll_events();
+
+        let state = f.settled_finger_state(Hand::Left);
+        assert_eq!(state.middle, 0.25);
+        assert_eq!(state.pinky, 0.25);
+    }
+
+    #[test]
+    fn closed_hand_reaches_fist() {
+        use vr::IVRInput010_Interface;
+
+        let f = Fixture::new();
+        let set1 = f.get_action_set_handle(c"/actions/set1");
+        let skel = f.get_action_handle(c"/actions/set1/in/SkellyL");
+        f.load_actions(c"actions.json");
+        f.set_interaction_profile(&Knuckles, LeftHand);
+
+        let (trigger, grip, thumb) = {
+            let data = f.input.openxr.session_data.get();
+            let actions = &data
+                .input_data
+                .estimated_skeleton_actions
+                .get()
+                .unwrap()
+                .actions;
+            (
+                actions.index_curl.as_raw(),
+                actions.rest_curl.as_raw(),
+                actions.thumb_click.as_raw(),
+            )
+        };
+        fakexr::set_action_state(trigger, fakexr::ActionState::Float(1.0), LeftHand);
+        fakexr::set_action_state(grip, fakexr::ActionState::Float(1.0), LeftHand);
+        fakexr::set_action_state(thumb, true.into(), LeftHand);
+        f.sync(vr::VRActiveActionSet_t {
+            ulActionSet: set1,
+            ..Default::default()
+        });
+        f.input.openxr.poll_events();
+        f.settled_finger_state(Hand::Left);
+
+        let check_bones = |range, expected: &[vr::VRBoneTransform_t]| {
+            let mut bones = [vr::VRBoneTransform_t::default(); HandSkeletonBone::Count as usize];
+            assert_eq!(
+                f.input.GetSkeletalBoneData(
+                    skel,
+                    vr::EVRSkeletalTransformSpace::Parent,
+                    range,
+                    bones.as_mut_ptr(),
+                    bones.len() as u32
+                ),
+                vr::EVRInputError::None
+            );
+            for (idx, (bone, expected)) in bones.into_iter().zip(expected).enumerate() {
+                let (pos, rot) = bone_transform_to_glam(bone);
+                let (expected_pos, expected_rot) = bone_transform_to_glam(*expected);
+                assert!(pos.abs_diff_eq(expected_pos, 1e-4), "bone {idx}: {pos}");
+                assert!(rot.angle_between(expected_rot) < 1e-3, "bone {idx}: {rot}");
+            }
+        };
+
+        // Without a controller in the way the fingers close into a fist, with one they stop
+        // where they'd grip it.
+        check_bones(
+            vr::EVRSkeletalMotionRange::WithoutController,
+            &gen::left_hand::FIST,
+        );
+        check_bones(
+            vr::EVRSkeletalMotionRange::WithController,
+            &gen::left_hand::GRIPLIMIT,
+        );
+    }
+
+    #[test]
+    fn summary_data_follows_inputs() {
+        use vr::IVRInput010_Interface;
+
+        let f = Fixture::new();
+        let set1 = f.get_action_set_handle(c"/actions/set1");
+        let skel = f.get_action_handle(c"/actions/set1/in/SkellyL");
+        let vec1 = f.get_action_handle(c"/actions/set1/in/Vec1Act");
+        f.load_actions(c"actions.json");
+        f.set_interaction_profile(&Knuckles, LeftHand);
+
+        let (trigger, thumb) = {
+            let data = f.input.openxr.session_data.get();
+            let actions = &data
+                .input_data
+                .estimated_skeleton_actions
+                .get()
+                .unwrap()
+                .actions;
+            (actions.index_curl.as_raw(), actions.thumb_touch.as_raw())
+        };
+        fakexr::set_action_state(trigger, fakexr::ActionState::Float(0.75), LeftHand);
+        fakexr::set_action_state(thumb, true.into(), LeftHand);
+        f.sync(vr::VRActiveActionSet_t {
+            ulActionSet: set1,
+            ..Default::default()
+        });
+        f.input.openxr.poll_events();
+        f.settled_finger_state(Hand::Left);
+
+        let get_summary = |action, ty| {
+            let mut data = vr::VRSkeletalSummaryData_t::default();
+            let ret = f.input.GetSkeletalSummaryData(action, ty, &mut data);
+            (ret, data.flFingerCurl)
+        };
+
+        // Curls are ordered thumb to pinky.
+        assert_eq!(
+            get_summary(skel, vr::EVRSummaryType::FromAnimation),
+            (vr::EVRInputError::None, [0.5, 0.75, 0.375, 0.1875, 0.125])
+        );
+        assert_eq!(
+            get_summary(skel, vr::EVRSummaryType::FromDevice),
+            (vr::EVRInputError::None, [1.0, 1.0, 0.0, 0.0, 0.0])
+        );
+        assert_eq!(
+            get_summary(vec1, vr::EVRSummaryType::FromDevice).0,
+            vr::EVRInputError::WrongType
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use super::{ActionData, Input};
+use crate::copy_truncated;
+use crate::openxr_data::{self, Hand};
+use log::warn;
+use openvr as vr;
+use openxr as xr;
+use std::ffi::CStr;
+
+/// The kind of input a bound source is, which prompts use to pick a glyph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SourceType {
+    Button,
+    Trigger,
+    Joystick,
+    Trackpad,
+}
+
+impl SourceType {
+    /// Classifies an OpenXR input source path, like `/user/hand/left/input/trigger/value`. Poses
+    /// aren't something a prompt would show, so they have no type.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, input) = path.split_once("/input/")?;
+        let mut parts = input.split('/');
+        let component = parts.next()?;
+        if parts.next_back() == Some("pose") {
+            return None;
+        }
+
+        Some(match component {
+            "trigger" | "select" | "pinch_ext" => Self::Trigger,
+            "thumbstick" | "joystick" => Self::Joystick,
+            "trackpad" => Self::Trackpad,
+            _ => Self::Button,
+        })
+    }
+
+    /// The name SteamVR reports in `InputBindingInfo_t::rchInputSourceType`.
+    pub fn name(self) -> &'static CStr {
+        match self {
+            Self::Button => c"button",
+            Self::Trigger => c"trigger",
+            Self::Joystick => c"joystick",
+            Self::Trackpad => c"trackpad",
+        }
+    }
+}
+
+/// A source the runtime bound an action to.
+#[derive(Debug)]
+pub struct BoundSource {
+    pub hand: Hand,
+    /// The full OpenXR path, e.g. `/user/hand/right/input/trigger/value`.
+    pub path: String,
+    pub ty: Option<SourceType>,
+}
+
+impl BoundSource {
+    pub fn binding_info(&self) -> vr::InputBindingInfo_t {
+        let device = match self.hand {
+            Hand::Left => "/user/hand/left",
+            Hand::Right => "/user/hand/right",
+        };
+        let input = self.path.strip_prefix(device).unwrap_or(&self.path);
+        // The last part of the path is the slot, e.g. "value" or "click", if there is one.
+        let (input, slot) = match input.rsplit_once('/') {
+            Some((component, slot)) if component.starts_with("/input/") => (component, slot),
+            _ => (input, ""),
+        };
+        // Games compare these against SteamVR's names, which differ from OpenXR's for some
+        // components and slots.
+        let input = match input.strip_prefix("/input/") {
+            Some(component) => format!("/input/{}", openvr_component_name(component)),
+            None => input.to_string(),
+        };
+        let slot = match slot {
+            "value" => "pull",
+            "" | "x" | "y"
+                if matches!(self.ty, Some(SourceType::Joystick | SourceType::Trackpad)) =>
+            {
+                "position"
+            }
+            slot => slot,
+        };
+
+        let mut info = vr::InputBindingInfo_t::default();
+        copy_truncated(&mut info.rchDevicePathName, device.as_bytes());
+        copy_truncated(&mut info.rchInputPathName, input.as_bytes());
+        copy_truncated(&mut info.rchSlotName, slot.as_bytes());
+        if let Some(ty) = self.ty {
+            copy_truncated(&mut info.rchInputSourceType, ty.name().to_bytes());
+        }
+        info
+    }
+}
+
+/// The name SteamVR's input profiles use for an OpenXR input component.
+fn openvr_component_name(component: &str) -> &str {
+    match component {
+        "squeeze" | "grasp_ext" => "grip",
+        "thumbstick" => "joystick",
+        "select" | "pinch_ext" => "trigger",
+        "menu" => "application_menu",
+        other => other,
+    }
+}
+
+impl<C: openxr_data::Compositor> Input<C> {
+    /// The sources the runtime bound an action to in the hands' current interaction profiles. If
+    /// `restrict_to_device` is a hand, only that hand's sources are returned.
+    pub fn bound_sources(
+        &self,
+        action: vr::VRActionHandle_t,
+        restrict_to_device: vr::VRInputValueHandle_t,
+    ) -> Result<Vec<BoundSource>, vr::EVRInputError> {
+        let subaction_path = self
+            .subaction_path_from_handle(restrict_to_device)
+            .ok_or(vr::EVRInputError::InvalidDevice)?;
+        let session_data = self.openxr.session_data.get();
+        let loaded = session_data
+            .input_data
+            .get_loaded_actions()
+            .ok_or(vr::EVRInputError::InvalidHandle)?;
+
+        let session = &session_data.session;
+        let sources = match loaded.try_get_action(action)? {
+            ActionData::Bool(action) => action.bound_sources(session),
+            ActionData::Vector1 { action, .. } => action.bound_sources(session),
+            ActionData::Vector2 { action, .. } => action.bound_sources(session),
+            ActionData::Haptic(action) => action.bound_sources(session),
+            // Poses and skeletons aren't bound to sources with a type.
+            ActionData::Pose | ActionData::Skeleton { .. } => Ok(Vec::new()),
+        }
+        .inspect_err(|e| warn!("Failed to enumerate bound sources: {e}"))
+        .unwrap_or_default();
+
+        Ok(sources
+            .into_iter()
+            .filter_map(|source| self.openxr.instance.path_to_string(source).ok())
+            .filter_map(|path| {
+                let hand = if path.starts_with("/user/hand/left/") {
+                    Hand::Left
+                } else if path.starts_with("/user/hand/right/") {
+                    Hand::Right
+                } else {
+                    return None;
+                };
+                let hand_path = match hand {
+                    Hand::Left => self.openxr.left_hand.subaction_path,
+                    Hand::Right => self.openxr.right_hand.subaction_path,
+                };
+                (subaction_path == xr::Path::NULL || subaction_path == hand_path).then(|| {
+                    BoundSource {
+                        hand,
+                        ty: SourceType::from_path(&path),
+                        path,
+                    }
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundSource, SourceType};
+    use crate::openxr_data::Hand;
+    use std::ffi::CStr;
+
+    #[test]
+    fn source_types() {
+        let ty = |path| SourceType::from_path(path);
+        assert_eq!(
+            ty("/user/hand/left/input/trigger/value"),
+            Some(SourceType::Trigger)
+        );
+        assert_eq!(
+            ty("/user/hand/left/input/select/click"),
+            Some(SourceType::Trigger)
+        );
+        assert_eq!(
+            ty("/user/hand/right/input/thumbstick"),
+            Some(SourceType::Joystick)
+        );
+        assert_eq!(
+            ty("/user/hand/right/input/trackpad/x"),
+            Some(SourceType::Trackpad)
+        );
+        assert_eq!(
+            ty("/user/hand/right/input/a/click"),
+            Some(SourceType::Button)
+        );
+        assert_eq!(
+            ty("/user/hand/right/input/squeeze/value"),
+            Some(SourceType::Button)
+        );
+        assert_eq!(ty("/user/hand/right/input/grip/pose"), None);
+        assert_eq!(ty("/user/hand/right/output/haptic"), None);
+    }
+
+    #[test]
+    fn openvr_names() {
+        let info = |path: &str| {
+            let info = BoundSource {
+                hand: Hand::Left,
+                path: path.to_string(),
+                ty: SourceType::from_path(path),
+            }
+            .binding_info();
+            let field = |field: &[std::ffi::c_char]| {
+                unsafe { CStr::from_ptr(field.as_ptr()) }
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            };
+            (field(&info.rchInputPathName), field(&info.rchSlotName))
+        };
+
+        assert_eq!(
+            info("/user/hand/left/input/squeeze/value"),
+            ("/input/grip".into(), "pull".into())
+        );
+        assert_eq!(
+            info("/user/hand/left/input/thumbstick"),
+            ("/input/joystick".into(), "position".into())
+        );
+        assert_eq!(
+            info("/user/hand/left/input/trackpad/x"),
+            ("/input/trackpad".into(), "position".into())
+        );
+        assert_eq!(
+            info("/user/hand/left/input/trigger/click"),
+            ("/input/trigger".into(), "click".into())
+        );
+        assert_eq!(
+            info("/user/hand/left/input/menu/click"),
+            ("/input/application_menu".into(), "click".into())
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use super::{
+    grip_curve::GripCurve,
+    lost_tracking::LostTracking,
     profiles::{
-        knuckles::Knuckles, oculus_touch::Touch, simple_controller::SimpleController,
-        vive_controller::ViveWands,
+        hand_interaction::HandInteraction, knuckles::Knuckles, oculus_touch::Touch,
+        simple_controller::SimpleController, vive_controller::ViveWands,
     },
     ActionData, Input, InteractionProfile,
 };
@@ -92,14 +94,21 @@ impl_action_type!(xr::Haptic, "haptic", ActionData::Haptic(a) => a.as_raw());
 
 impl Fixture {
     pub fn new() -> Self {
+        Self::new_with(|_| {})
+    }
+
+    /// Creates the fixture, letting the test adjust the input state before it's shared.
+    pub fn new_with(setup: impl FnOnce(&mut Input<FakeCompositor>)) -> Self {
         crate::init_logging();
         let xr = Arc::new(OpenXrData::new(&crate::clientcore::Injector::default()).unwrap());
         let comp = Arc::new(FakeCompositor(
             crate::graphics_backends::VulkanData::new_temporary(&xr.instance, xr.system_id),
         ));
         xr.compositor.set(Arc::downgrade(&comp));
+        let mut input = Input::new(xr.clone());
+        setup(&mut input);
         let ret = Self {
-            input: Input::new(xr.clone()).into(),
+            input: input.into(),
             _comp: comp,
         };
         xr.input.set(Arc::downgrade(&ret.input));
@@ -108,15 +117,18 @@ impl Fixture {
     }
 
     pub fn load_actions(&self, file: &CStr) {
-        let path = &[ACTIONS_JSONS_DIR.to_bytes(), file.to_bytes_with_nul()].concat();
         assert_eq!(
-            self.input.SetActionManifestPath(path.as_ptr() as _),
+            self.try_load_actions(file),
             vr::EVRInputError::None,
-            "check manifest path: {}",
-            std::str::from_utf8(path).expect("Non utf8 path!")
+            "check manifest path: {file:?}"
         );
     }
 
+    pub fn try_load_actions(&self, file: &CStr) -> vr::EVRInputError {
+        let path = &[ACTIONS_JSONS_DIR.to_bytes(), file.to_bytes_with_nul()].concat();
+        self.input.SetActionManifestPath(path.as_ptr() as _)
+    }
+
     #[track_caller]
     pub fn verify_bindings<T: ActionType>(
         &self,
@@ -323,6 +335,23 @@ fn handles_dont_change_after_load() {
     assert_eq!(act_load, boolact);
 }
 
+#[test]
+fn equivalent_paths_share_handles() {
+    let f = Fixture::new();
+
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    assert_eq!(f.get_action_set_handle(c"actions/set1"), set1);
+    assert_eq!(f.get_action_set_handle(c"/Actions/Set1/"), set1);
+
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    assert_eq!(f.get_action_handle(c"actions/set1/in/boolact"), boolact);
+    assert_eq!(f.get_action_handle(c"//actions/set1//in/BoolAct"), boolact);
+    assert_ne!(f.get_action_handle(c"/actions/set1/in/boolact2"), boolact);
+
+    f.load_actions(c"actions.json");
+    assert_eq!(f.get_action_handle(c"actions/set1/in/boolact"), boolact);
+}
+
 #[test]
 fn input_state_flow() {
     let f = Fixture::new();
@@ -867,6 +896,47 @@ fn implicit_action_sets() {
     assert!(res.is_ok(), "{res:?}");
 }
 
+#[test]
+fn included_manifests() {
+    let f = Fixture::new();
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    let child_boolact = f.get_action_handle(c"/actions/child/in/boolact");
+    f.load_actions(c"actions_include.json");
+
+    f.get_action::<bool>(boolact);
+    f.get_action::<bool>(child_boolact);
+    // The included manifest's bindings are found relative to it
+    f.verify_bindings::<bool>(
+        Knuckles.profile_path(),
+        c"/actions/child/in/boolact",
+        ["/user/hand/left/input/a/click".into()],
+    );
+}
+
+#[test]
+fn cyclic_manifest_includes() {
+    let f = Fixture::new();
+    assert_eq!(
+        f.try_load_actions(c"actions_include_cycle.json"),
+        vr::EVRInputError::InvalidParam
+    );
+}
+
+#[test]
+fn manifest_errors() {
+    let check = |file, expected| {
+        let f = Fixture::new();
+        assert_eq!(f.try_load_actions(file), expected, "{file:?}");
+    };
+
+    check(c"actions_nonexistent.json", vr::EVRInputError::NameNotFound);
+    check(
+        c"actions_invalid_json.json",
+        vr::EVRInputError::InvalidParam,
+    );
+    check(c"actions_unknown_type.json", vr::EVRInputError::WrongType);
+}
+
 #[test]
 fn detect_controller_after_manifest_load() {
     let f = Fixture::new();
@@ -888,3 +958,1504 @@ fn detect_controller_after_manifest_load() {
     frame();
     assert!(f.input.openxr.left_hand.connected());
 }
+
+#[test]
+fn openxr_call_logging() {
+    let f = Fixture::new();
+    f.input.openxr.restart_session();
+    let was_enabled = crate::OPENXR_CALL_LOGGING.swap(true, std::sync::atomic::Ordering::Relaxed);
+    crate::take_logged_openxr_calls();
+
+    // Sets up and syncs legacy actions.
+    f.input.frame_start_update();
+
+    let calls = crate::take_logged_openxr_calls();
+    // The flag is global, so put it back before asserting to keep other tests unaffected.
+    crate::OPENXR_CALL_LOGGING.store(was_enabled, std::sync::atomic::Ordering::Relaxed);
+    assert!(
+        calls
+            .iter()
+            .any(|c| c.starts_with("xrSyncActions -> Ok(())")),
+        "no sync logged: {calls:?}"
+    );
+}
+
+#[test]
+fn session_restart_releases_spaces() {
+    let f = Fixture::new();
+    f.load_actions(c"actions.json");
+    let instance = f.input.openxr.instance.as_raw();
+    let live_spaces = || {
+        f.set_interaction_profile(&Knuckles, LeftHand);
+        f.set_interaction_profile(&Knuckles, RightHand);
+        f.input.frame_start_update();
+        for hand in [Hand::Left, Hand::Right] {
+            f.input
+                .get_controller_pose(hand, Some(vr::ETrackingUniverseOrigin::Seated))
+                .unwrap();
+        }
+        fakexr::live_action_space_count(instance)
+    };
+
+    let spaces = live_spaces();
+    assert!(spaces > 0);
+    for manifest in [c"actions_analog_params.json", c"actions.json"].repeat(5) {
+        // Loading a different manifest restarts the session too.
+        f.load_actions(manifest);
+        assert_eq!(live_spaces(), spaces);
+        f.input.openxr.restart_session();
+        assert_eq!(live_spaces(), spaces);
+    }
+}
+
+#[test]
+fn same_manifest_does_not_restart_session() {
+    let f = Fixture::new();
+    f.load_actions(c"actions.json");
+    let session = f.input.openxr.session_data.get().session.as_raw();
+
+    f.load_actions(c"actions.json");
+    assert_eq!(f.input.openxr.session_data.get().session.as_raw(), session);
+}
+
+#[test]
+fn skeletal_action_data() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let vec1 = f.get_action_handle(c"/actions/set1/in/vec1act");
+    let skel = f.get_action_handle(c"/actions/set1/in/skellyl");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+
+    let get_data = |handle, size| {
+        let mut data = vr::InputSkeletalActionData_t::default();
+        let err = f.input.GetSkeletalActionData(handle, &mut data, size);
+        (err, data)
+    };
+    let size = std::mem::size_of::<vr::InputSkeletalActionData_t>() as u32;
+
+    assert_eq!(get_data(vec1, size).0, vr::EVRInputError::WrongType);
+    assert_eq!(get_data(skel, size - 1).0, vr::EVRInputError::InvalidParam);
+
+    let (err, data) = get_data(skel, size);
+    assert_eq!(err, vr::EVRInputError::None);
+    assert!(!data.bActive);
+
+    let grip_pose = f
+        .input
+        .openxr
+        .session_data
+        .get()
+        .input_data
+        .legacy_actions
+        .get()
+        .unwrap()
+        .actions
+        .grip_pose
+        .as_raw();
+    fakexr::set_action_state(grip_pose, fakexr::ActionState::Pose, LeftHand);
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    let (err, data) = get_data(skel, size);
+    assert_eq!(err, vr::EVRInputError::None);
+    assert!(data.bActive);
+    assert_eq!(data.activeOrigin, left_hand);
+}
+
+#[test]
+fn head_and_gamepad_input_sources() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    let vec1 = f.get_action_handle(c"/actions/set1/in/vec1act");
+    let pose = f.get_action_handle(c"/actions/set1/in/pose");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    let head = f.get_input_source_handle(c"/user/head");
+    let gamepad = f.get_input_source_handle(c"/user/gamepad");
+
+    let sources = [left_hand, right_hand, head, gamepad];
+    assert!(sources
+        .iter()
+        .all(|s| *s != vr::k_ulInvalidInputValueHandle));
+    assert_eq!(
+        sources.iter().collect::<HashSet<_>>().len(),
+        sources.len(),
+        "input source handles should be unique"
+    );
+    assert_eq!(f.get_input_source_handle(c"/user/head"), head);
+
+    let mut info = vr::InputOriginInfo_t::default();
+    assert_eq!(
+        f.input
+            .GetOriginTrackedDeviceInfo(head, &mut info, std::mem::size_of_val(&info) as u32),
+        vr::EVRInputError::None
+    );
+    assert_eq!(info.devicePath, head);
+    assert_eq!(info.trackedDeviceIndex, vr::k_unTrackedDeviceIndex_Hmd);
+
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    fakexr::set_action_state(
+        f.get_action::<bool>(boolact),
+        fakexr::ActionState::Bool(true),
+        LeftHand,
+    );
+    fakexr::set_action_state(
+        f.get_action::<f32>(vec1),
+        fakexr::ActionState::Float(0.5),
+        LeftHand,
+    );
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    // Actions are only declared for the hands, so the head and gamepad have no state.
+    for source in [head, gamepad] {
+        let state = f.get_bool_state_hand(boolact, source).unwrap();
+        assert!(!state.bActive);
+        assert!(!state.bState);
+
+        let mut state = vr::InputAnalogActionData_t::default();
+        assert_eq!(
+            f.input.GetAnalogActionData(
+                vec1,
+                &mut state,
+                std::mem::size_of_val(&state) as u32,
+                source
+            ),
+            vr::EVRInputError::None
+        );
+        assert!(!state.bActive);
+        assert_eq!(state.x, 0.0);
+
+        let state = f.get_pose(pose, source).unwrap();
+        assert!(!state.bActive);
+    }
+
+    let state = f.get_bool_state_hand(boolact, left_hand).unwrap();
+    assert!(state.bActive);
+    assert!(state.bState);
+}
+
+#[test]
+fn binding_reload_event() {
+    let f = Fixture::new();
+    let dir = std::env::temp_dir().join(format!("xrizer-binding-reload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input_data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/input_data");
+    for file in ["actions.json", "wands.json", "knuckles.json", "oculus.json"] {
+        std::fs::copy(input_data.join(file), dir.join(file)).unwrap();
+    }
+    let manifest = dir.join("actions.json");
+    let manifest_c = std::ffi::CString::new(manifest.to_str().unwrap()).unwrap();
+    let load = || {
+        assert_eq!(
+            f.input.SetActionManifestPath(manifest_c.as_ptr()),
+            vr::EVRInputError::None
+        );
+    };
+    let reload_events = || {
+        let mut count = 0;
+        let mut event = vr::VREvent_t::default();
+        while f
+            .input
+            .get_next_event(std::mem::size_of_val(&event) as u32, &mut event)
+        {
+            if event.eventType == vr::EVREventType::ActionBindingReloaded as u32 {
+                count += 1;
+            }
+        }
+        count
+    };
+
+    // The initial load isn't a reload.
+    load();
+    assert_eq!(reload_events(), 0);
+
+    // Neither is setting an unchanged manifest.
+    load();
+    assert_eq!(reload_events(), 0);
+
+    // Drop the Oculus Touch bindings.
+    let mut contents: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&manifest).unwrap()).unwrap();
+    contents["default_bindings"]
+        .as_array_mut()
+        .unwrap()
+        .retain(|b| b["controller_type"] != "oculus_touch");
+    std::fs::write(&manifest, serde_json::to_vec(&contents).unwrap()).unwrap();
+
+    load();
+    assert_eq!(reload_events(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn included_manifest_change_reloads() {
+    let f = Fixture::new();
+    let dir = std::env::temp_dir().join(format!("xrizer-include-reload-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("include")).unwrap();
+    let input_data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/input_data");
+    for file in [
+        "actions_include.json",
+        "include/actions_child.json",
+        "include/knuckles_child.json",
+    ] {
+        std::fs::copy(input_data.join(file), dir.join(file)).unwrap();
+    }
+    let manifest = dir.join("actions_include.json");
+    let manifest_c = std::ffi::CString::new(manifest.to_str().unwrap()).unwrap();
+    let load = || {
+        assert_eq!(
+            f.input.SetActionManifestPath(manifest_c.as_ptr()),
+            vr::EVRInputError::None
+        );
+    };
+    let reloaded = || {
+        let mut reloaded = false;
+        let mut event = vr::VREvent_t::default();
+        while f
+            .input
+            .get_next_event(std::mem::size_of_val(&event) as u32, &mut event)
+        {
+            reloaded |= event.eventType == vr::EVREventType::ActionBindingReloaded as u32;
+        }
+        reloaded
+    };
+
+    load();
+    load();
+    assert!(!reloaded());
+
+    // Only the included manifest changes.
+    let child = dir.join("include/actions_child.json");
+    let mut contents: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&child).unwrap()).unwrap();
+    contents["default_bindings"] = serde_json::json!([]);
+    std::fs::write(&child, serde_json::to_vec(&contents).unwrap()).unwrap();
+
+    load();
+    assert!(reloaded());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn grip_curve() {
+    let f = Fixture::new_with(|input| {
+        let knuckles = input
+            .openxr
+            .instance
+            .string_to_path(Knuckles.profile_path())
+            .unwrap();
+        input
+            .grip_curves
+            .insert(knuckles, GripCurve::Exponential { gamma: 2.0 });
+    });
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let grip = f.get_action_handle(c"/actions/set1/in/gripact");
+    let trigger = f.get_action_handle(c"/actions/set1/in/triggeract");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    f.load_actions(c"actions_grip.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+
+    for action in [grip, trigger] {
+        fakexr::set_action_state(
+            f.get_action::<f32>(action),
+            fakexr::ActionState::Float(0.5),
+            LeftHand,
+        );
+    }
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+    f.input.openxr.poll_events();
+
+    let get_value = |action, restrict| {
+        let mut state = vr::InputAnalogActionData_t::default();
+        assert_eq!(
+            f.input.GetAnalogActionData(
+                action,
+                &mut state,
+                std::mem::size_of_val(&state) as u32,
+                restrict
+            ),
+            vr::EVRInputError::None
+        );
+        assert!(state.bActive);
+        state.x
+    };
+
+    assert_eq!(get_value(grip, left_hand), 0.25);
+    assert_eq!(get_value(grip, 0), 0.25);
+    // Only grips are remapped.
+    assert_eq!(get_value(trigger, left_hand), 0.5);
+}
+
+#[test]
+fn hand_interaction_pinch_and_grasp() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let grip = f.get_action_handle(c"/actions/set1/in/gripact");
+    let trigger = f.get_action_handle(c"/actions/set1/in/triggeract");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    f.load_actions(c"actions_grip.json");
+
+    // Hands use the game's Touch bindings, with pinching as the trigger and grasping as the grip.
+    let path = HandInteraction.profile_path();
+    f.verify_bindings::<f32>(
+        path,
+        c"/actions/set1/in/gripact",
+        [
+            "/user/hand/left/input/grasp_ext/value".into(),
+            "/user/hand/right/input/grasp_ext/value".into(),
+        ],
+    );
+    f.verify_bindings::<f32>(
+        path,
+        c"/actions/set1/in/triggeract",
+        [
+            "/user/hand/left/input/pinch_ext/value".into(),
+            "/user/hand/right/input/pinch_ext/value".into(),
+        ],
+    );
+
+    f.set_interaction_profile(&HandInteraction, LeftHand);
+    fakexr::set_action_state(
+        f.get_action::<f32>(trigger),
+        fakexr::ActionState::Float(0.25),
+        LeftHand,
+    );
+    fakexr::set_action_state(
+        f.get_action::<f32>(grip),
+        fakexr::ActionState::Float(0.75),
+        LeftHand,
+    );
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+    f.input.openxr.poll_events();
+
+    let get_value = |action| {
+        let mut state = vr::InputAnalogActionData_t::default();
+        assert_eq!(
+            f.input.GetAnalogActionData(
+                action,
+                &mut state,
+                std::mem::size_of_val(&state) as u32,
+                left_hand
+            ),
+            vr::EVRInputError::None
+        );
+        assert!(state.bActive);
+        state.x
+    };
+
+    assert_eq!(get_value(trigger), 0.25);
+    assert_eq!(get_value(grip), 0.75);
+}
+
+#[test]
+fn active_origin_and_update_time() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    let vec2act = f.get_action_handle(c"/actions/set1/in/vec2act");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.set_interaction_profile(&Knuckles, RightHand);
+
+    // The frame was waited on half a second before its display time.
+    f.input.openxr.set_display_time(
+        xr::Time::from_nanos(2_000_000_000),
+        xr::Duration::from_nanos(500_000_000),
+    );
+    fakexr::set_action_change_time(xr::Time::from_nanos(1_000_000_000));
+    fakexr::set_action_state(
+        f.get_action::<bool>(boolact),
+        fakexr::ActionState::Bool(true),
+        LeftHand,
+    );
+    fakexr::set_action_state(
+        f.get_action::<xr::Vector2f>(vec2act),
+        fakexr::ActionState::Vector2(0.25, -0.75),
+        RightHand,
+    );
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    let digital = f.get_bool_state(boolact).unwrap();
+    assert!(digital.bState);
+    assert_eq!(digital.activeOrigin, left_hand);
+    // Half a second before the frame was waited on.
+    assert!((digital.fUpdateTime + 0.5).abs() < 0.01);
+
+    let get_analog = |restrict| {
+        let mut state = vr::InputAnalogActionData_t::default();
+        assert_eq!(
+            f.input.GetAnalogActionData(
+                vec2act,
+                &mut state,
+                std::mem::size_of_val(&state) as u32,
+                restrict
+            ),
+            vr::EVRInputError::None
+        );
+        assert!(state.bActive);
+        state
+    };
+    // The hand that's actually pushing the stick is the origin.
+    assert_eq!(get_analog(0).activeOrigin, right_hand);
+    let right = get_analog(right_hand);
+    assert_eq!(right.activeOrigin, right_hand);
+    assert!((right.fUpdateTime + 0.5).abs() < 0.01);
+    // Restricted reads come from the device they're restricted to.
+    assert_eq!(get_analog(left_hand).activeOrigin, left_hand);
+}
+
+#[test]
+fn update_time_from_runtime_clock() {
+    fakexr::add_instance_extension(xr::sys::KHR_CONVERT_TIMESPEC_TIME_EXTENSION_NAME);
+    let f = Fixture::new();
+    assert!(f.input.openxr.enabled_extensions.khr_convert_timespec_time);
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+
+    // The display time is far off, but it isn't what the change is relative to.
+    f.input
+        .openxr
+        .set_display_time(xr::Time::from_nanos(1), xr::Duration::from_nanos(1));
+    fakexr::set_action_change_time(f.input.openxr.now());
+    fakexr::set_action_state(
+        f.get_action::<bool>(boolact),
+        fakexr::ActionState::Bool(true),
+        LeftHand,
+    );
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    let digital = f.get_bool_state(boolact).unwrap();
+    assert!(digital.bState);
+    assert!(digital.fUpdateTime.abs() < 0.01, "{}", digital.fUpdateTime);
+}
+
+#[test]
+fn batched_action_states() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    let boolact2 = f.get_action_handle(c"/actions/set1/in/boolact2");
+    let vec1act = f.get_action_handle(c"/actions/set1/in/vec1act");
+    let vec2act = f.get_action_handle(c"/actions/set1/in/vec2act");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.set_interaction_profile(&Knuckles, RightHand);
+
+    fakexr::set_action_state(
+        f.get_action::<bool>(boolact),
+        fakexr::ActionState::Bool(true),
+        LeftHand,
+    );
+    fakexr::set_action_state(
+        f.get_action::<f32>(vec1act),
+        fakexr::ActionState::Float(0.5),
+        RightHand,
+    );
+    fakexr::set_action_state(
+        f.get_action::<xr::Vector2f>(vec2act),
+        fakexr::ActionState::Vector2(0.25, -0.75),
+        LeftHand,
+    );
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    // Many reads of the same actions, like a game polling all of its actions every frame.
+    let requests: Vec<_> = std::iter::repeat([boolact, boolact2, vec1act, vec2act])
+        .take(50)
+        .flatten()
+        .flat_map(|action| [0, left_hand, right_hand].map(|device| (action, device)))
+        .collect();
+
+    let digital = |data: vr::InputDigitalActionData_t| {
+        (data.bActive, data.bState, data.bChanged, data.activeOrigin)
+    };
+    let analog = |data: vr::InputAnalogActionData_t| {
+        (
+            data.bActive,
+            data.x,
+            data.y,
+            data.deltaX,
+            data.deltaY,
+            data.activeOrigin,
+        )
+    };
+    let individual = |(action, device)| {
+        let mut digital_data = vr::InputDigitalActionData_t::default();
+        let digital_err = f.input.GetDigitalActionData(
+            action,
+            &mut digital_data,
+            std::mem::size_of_val(&digital_data) as u32,
+            device,
+        );
+        let mut analog_data = vr::InputAnalogActionData_t::default();
+        let analog_err = f.input.GetAnalogActionData(
+            action,
+            &mut analog_data,
+            std::mem::size_of_val(&analog_data) as u32,
+            device,
+        );
+        (
+            (digital_err == vr::EVRInputError::None)
+                .then(|| digital(digital_data))
+                .ok_or(digital_err),
+            (analog_err == vr::EVRInputError::None)
+                .then(|| analog(analog_data))
+                .ok_or(analog_err),
+        )
+    };
+
+    // Reading analog actions updates their deltas, so settle them first.
+    for &request in &requests {
+        individual(request);
+    }
+
+    let expected: Vec<_> = requests.iter().copied().map(individual).collect();
+    let batched: Vec<_> = f.input.read_action_states(|states| {
+        requests
+            .iter()
+            .map(|&(action, device)| {
+                (
+                    states.digital(action, device).map(digital),
+                    states.analog(action, device).map(analog),
+                )
+            })
+            .collect()
+    });
+    assert_eq!(batched, expected);
+
+    // Sanity check that the states aren't all default.
+    assert_eq!(
+        expected[1].0.map(|d| (d.0, d.1)),
+        Ok((true, true)),
+        "boolact, left hand"
+    );
+    assert_eq!(expected[8].1.map(|a| a.1), Ok(0.5), "vec1act, right hand");
+}
+
+#[test]
+fn unbound_pose_action_is_inactive() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let posel = f.get_action_handle(c"/actions/set1/in/posel");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.set_interaction_profile(&Knuckles, RightHand);
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    // posel has no bindings for Knuckles
+    let state = f.get_pose(posel, 0).unwrap();
+    assert!(!state.bActive);
+    assert!(!state.pose.bPoseIsValid);
+    compare_pose(
+        xr::Posef::IDENTITY,
+        state.pose.mDeviceToAbsoluteTracking.into(),
+    );
+}
+
+#[test]
+fn unbound_pose_action_space_is_untracked() {
+    let f = Fixture::new();
+    let openxr = &f.input.openxr;
+    let left_hand = openxr.left_hand.subaction_path;
+    let right_hand = openxr.right_hand.subaction_path;
+    let set = openxr
+        .instance
+        .create_action_set("test", "test", 0)
+        .unwrap();
+    let action = set
+        .create_action::<xr::Posef>("pose", "pose", &[left_hand, right_hand])
+        .unwrap();
+
+    // Only bind the left hand.
+    let profile = openxr
+        .instance
+        .string_to_path(Knuckles.profile_path())
+        .unwrap();
+    let grip = openxr
+        .instance
+        .string_to_path("/user/hand/left/input/grip/pose")
+        .unwrap();
+    openxr
+        .instance
+        .suggest_interaction_profile_bindings(profile, &[xr::Binding::new(&action, grip)])
+        .unwrap();
+
+    let data = openxr.session_data.get();
+    data.session.attach_action_sets(&[&set]).unwrap();
+    f.set_interaction_profile(&Knuckles, RightHand);
+    data.session
+        .sync_actions(&[xr::ActiveActionSet::new(&set)])
+        .unwrap();
+
+    let space = action
+        .create_space(&data.session, right_hand, xr::Posef::IDENTITY)
+        .unwrap();
+    let location = space
+        .locate(
+            data.get_space_from_type(xr::ReferenceSpaceType::LOCAL),
+            openxr.display_time.get(),
+        )
+        .unwrap();
+    assert!(location.location_flags.is_empty());
+}
+
+#[test]
+fn skeletal_origin_component() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let skel = f.get_action_handle(c"/actions/set1/in/skellyl");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+
+    let grip_pose = f
+        .input
+        .openxr
+        .session_data
+        .get()
+        .input_data
+        .legacy_actions
+        .get()
+        .unwrap()
+        .actions
+        .grip_pose
+        .as_raw();
+    fakexr::set_action_state(grip_pose, fakexr::ActionState::Pose, LeftHand);
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    let mut data = vr::InputSkeletalActionData_t::default();
+    assert_eq!(
+        f.input
+            .GetSkeletalActionData(skel, &mut data, std::mem::size_of_val(&data) as u32),
+        vr::EVRInputError::None
+    );
+    assert!(data.bActive);
+
+    let mut info = vr::InputOriginInfo_t::default();
+    assert_eq!(
+        f.input.GetOriginTrackedDeviceInfo(
+            data.activeOrigin,
+            &mut info,
+            std::mem::size_of_val(&info) as u32
+        ),
+        vr::EVRInputError::None
+    );
+    assert_eq!(info.trackedDeviceIndex, Hand::Left as u32);
+    let component = unsafe { CStr::from_ptr(info.rchRenderModelComponentName.as_ptr()) };
+    assert_eq!(component, c"handgrip");
+}
+
+#[test]
+fn bone_names() {
+    let f = Fixture::new();
+    let vec1 = f.get_action_handle(c"/actions/set1/in/vec1act");
+    let skel = f.get_action_handle(c"/actions/set1/in/skellyr");
+    f.load_actions(c"actions.json");
+
+    let get_name = |handle, bone, size: usize| {
+        let mut name = vec![0; size];
+        let err = f
+            .input
+            .GetBoneName(handle, bone, name.as_mut_ptr(), size as u32);
+        let name = CStr::from_bytes_until_nul(unsafe {
+            std::slice::from_raw_parts(name.as_ptr().cast(), size)
+        })
+        .map(|name| name.to_str().unwrap().to_string())
+        .unwrap_or_default();
+        (err, name)
+    };
+    let name = |bone| {
+        let (err, name) = get_name(skel, bone, 64);
+        assert_eq!(err, vr::EVRInputError::None, "bone {bone}");
+        name
+    };
+
+    assert_eq!(name(0), "Root");
+    assert_eq!(name(1), "wrist_r");
+    assert_eq!(name(2), "finger_thumb_0_r");
+    assert_eq!(name(5), "finger_thumb_r_end");
+    assert_eq!(name(6), "finger_index_meta_r");
+    assert_eq!(name(13), "finger_middle_1_r");
+    assert_eq!(name(20), "finger_ring_r_end");
+    assert_eq!(name(24), "finger_pinky_2_r");
+    assert_eq!(name(26), "finger_thumb_r_aux");
+    assert_eq!(name(30), "finger_pinky_r_aux");
+
+    assert_eq!(get_name(skel, 31, 64).0, vr::EVRInputError::InvalidParam);
+    assert_eq!(get_name(skel, -1, 64).0, vr::EVRInputError::InvalidParam);
+    assert_eq!(
+        get_name(skel, 1, 7),
+        (vr::EVRInputError::None, "wrist_".to_string())
+    );
+    assert_eq!(get_name(skel, 1, 8).1, "wrist_r");
+    assert_eq!(get_name(skel, 1, 0).0, vr::EVRInputError::BufferTooSmall);
+
+    let mut count = 0;
+    assert_eq!(
+        f.input.GetBoneCount(skel, &mut count),
+        vr::EVRInputError::None
+    );
+    assert_eq!(count, 31);
+    assert_eq!(get_name(vec1, 1, 64).0, vr::EVRInputError::WrongType);
+}
+
+#[test]
+fn bone_hierarchy() {
+    let f = Fixture::new();
+    let vec1 = f.get_action_handle(c"/actions/set1/in/vec1act");
+    let skel = f.get_action_handle(c"/actions/set1/in/skellyl");
+    f.load_actions(c"actions.json");
+
+    let mut parents = [0; 31];
+    assert_eq!(
+        f.input
+            .GetBoneHierarchy(skel, parents.as_mut_ptr(), parents.len() as u32),
+        vr::EVRInputError::None
+    );
+    #[rustfmt::skip]
+    assert_eq!(
+        parents,
+        [
+            -1, 0,
+            1, 2, 3, 4,
+            1, 6, 7, 8, 9,
+            1, 11, 12, 13, 14,
+            1, 16, 17, 18, 19,
+            1, 21, 22, 23, 24,
+            0, 0, 0, 0, 0,
+        ]
+    );
+
+    assert_eq!(
+        f.input.GetBoneHierarchy(skel, parents.as_mut_ptr(), 30),
+        vr::EVRInputError::InvalidParam
+    );
+    assert_eq!(
+        f.input
+            .GetBoneHierarchy(vec1, parents.as_mut_ptr(), parents.len() as u32),
+        vr::EVRInputError::WrongType
+    );
+}
+
+#[test]
+fn default_interaction_profile() {
+    let f = Fixture::new();
+    let xr = &f.input.openxr;
+    xr.left_hand.assume_profile(&xr.instance, &Knuckles);
+    xr.right_hand.assume_profile(&xr.instance, &Knuckles);
+    f.load_actions(c"actions.json");
+
+    let controller_type = |hand| {
+        f.input.get_controller_string_tracked_property(
+            hand,
+            vr::ETrackedDeviceProperty::ControllerType_String,
+        )
+    };
+
+    // The assumed profile is reported before the runtime has reported any profile.
+    assert!(xr.left_hand.connected());
+    assert_eq!(controller_type(Hand::Left), Some(c"knuckles"));
+    assert_eq!(controller_type(Hand::Right), Some(c"knuckles"));
+
+    let frame = || {
+        xr.poll_events();
+        f.input.frame_start_update();
+    };
+
+    // A real profile replaces the assumption, but a hand the runtime has no profile for yet
+    // keeps it.
+    f.set_interaction_profile(&Touch, LeftHand);
+    frame();
+    frame();
+    assert_eq!(controller_type(Hand::Left), Some(c"oculus_touch"));
+    assert!(xr.right_hand.connected());
+    assert_eq!(controller_type(Hand::Right), Some(c"knuckles"));
+}
+
+#[test]
+fn model_number_and_hardware_revision() {
+    let f = Fixture::new();
+    f.load_actions(c"actions.json");
+    let frame = || {
+        f.input.openxr.poll_events();
+        f.input.frame_start_update();
+    };
+    let props = |hand| {
+        let prop = |prop| f.input.get_controller_string_tracked_property(hand, prop);
+        (
+            prop(vr::ETrackedDeviceProperty::ModelNumber_String).unwrap(),
+            prop(vr::ETrackedDeviceProperty::HardwareRevision_String).unwrap(),
+        )
+    };
+
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.set_interaction_profile(&Knuckles, RightHand);
+    frame();
+    frame();
+    let (index_left, index_revision) = props(Hand::Left);
+    let (index_right, _) = props(Hand::Right);
+    assert_eq!(index_left, c"Knuckles Left");
+    assert_eq!(index_right, c"Knuckles Right");
+
+    f.set_interaction_profile(&ViveWands, LeftHand);
+    frame();
+    frame();
+    let (vive_model, vive_revision) = props(Hand::Left);
+    assert_ne!(vive_model, index_left);
+    assert_ne!(vive_revision, index_revision);
+}
+
+#[test]
+fn action_origins_by_hand() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.set_interaction_profile(&Knuckles, RightHand);
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    assert_eq!(
+        f.input.action_origins(boolact, left_hand),
+        Ok(vec![left_hand])
+    );
+    assert_eq!(
+        f.input.action_origins(boolact, right_hand),
+        Ok(vec![right_hand])
+    );
+
+    let mut origins = [0; 3];
+    assert_eq!(
+        f.input
+            .GetActionOrigins(set1, boolact, origins.as_mut_ptr(), origins.len() as u32),
+        vr::EVRInputError::None
+    );
+    assert_eq!(
+        origins,
+        [left_hand, right_hand, vr::k_ulInvalidInputValueHandle]
+    );
+}
+
+#[test]
+fn duplicate_active_sets_synced_once() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    f.load_actions(c"actions.json");
+
+    let set = vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    };
+    f.sync(set);
+    let synced = fakexr::last_synced_action_sets(f.raw_session());
+
+    let mut sets = [set, set];
+    assert_eq!(
+        f.input.UpdateActionState(
+            sets.as_mut_ptr(),
+            std::mem::size_of::<vr::VRActiveActionSet_t>() as u32,
+            sets.len() as u32
+        ),
+        vr::EVRInputError::None
+    );
+    let synced_twice = fakexr::last_synced_action_sets(f.raw_session());
+    assert_eq!(synced_twice, synced);
+    assert_eq!(
+        synced_twice.iter().collect::<HashSet<_>>().len(),
+        synced_twice.len()
+    );
+}
+
+#[test]
+fn rotation_only_pose() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let posel = f.get_action_handle(c"/actions/set1/in/posel");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&SimpleController, LeftHand);
+    fakexr::set_location_flags(
+        f.raw_session(),
+        LeftHand,
+        xr::SpaceLocationFlags::ORIENTATION_VALID | xr::SpaceLocationFlags::ORIENTATION_TRACKED,
+    );
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    let data = f.get_pose(posel, 0).unwrap();
+    assert!(data.bActive);
+    assert!(data.pose.bPoseIsValid);
+    assert!(data.pose.bDeviceIsConnected);
+    assert_eq!(
+        data.pose.eTrackingResult,
+        vr::ETrackingResult::Fallback_RotationOnly
+    );
+}
+
+#[test]
+fn lost_tracking_pose() {
+    let origin = Some(vr::ETrackingUniverseOrigin::Seated);
+    let setup = |f: &Fixture| {
+        f.load_actions(c"actions.json");
+        f.set_interaction_profile(&SimpleController, LeftHand);
+        let pose = xr::Posef {
+            position: xr::Vector3f {
+                x: 0.5,
+                y: 1.0,
+                z: -0.5,
+            },
+            orientation: xr::Quaternionf::IDENTITY,
+        };
+        fakexr::set_grip(f.raw_session(), LeftHand, pose);
+        fakexr::set_aim(f.raw_session(), LeftHand, pose);
+    };
+    let tracked = |f: &Fixture| {
+        fakexr::set_location_flags(
+            f.raw_session(),
+            LeftHand,
+            xr::SpaceLocationFlags::ORIENTATION_VALID
+                | xr::SpaceLocationFlags::ORIENTATION_TRACKED
+                | xr::SpaceLocationFlags::POSITION_VALID
+                | xr::SpaceLocationFlags::POSITION_TRACKED,
+        );
+        f.input.frame_start_update();
+        let pose = f.input.get_controller_pose(Hand::Left, origin).unwrap();
+        assert!(pose.bPoseIsValid);
+        pose
+    };
+    let lost = |f: &Fixture| {
+        fakexr::set_location_flags(f.raw_session(), LeftHand, xr::SpaceLocationFlags::EMPTY);
+        f.input.frame_start_update();
+        f.input.get_controller_pose(Hand::Left, origin).unwrap()
+    };
+
+    // Invalid by default
+    let f = Fixture::new();
+    setup(&f);
+    tracked(&f);
+    let pose = lost(&f);
+    assert!(!pose.bPoseIsValid);
+
+    let f = Fixture::new_with(|input| {
+        input.lost_tracking = [LostTracking::new(true), LostTracking::new(true)];
+    });
+    setup(&f);
+    let last = tracked(&f);
+    let pose = lost(&f);
+    assert!(pose.bPoseIsValid);
+    assert_eq!(
+        pose.eTrackingResult,
+        vr::ETrackingResult::Running_OutOfRange
+    );
+    assert_eq!(
+        pose.mDeviceToAbsoluteTracking.m,
+        last.mDeviceToAbsoluteTracking.m
+    );
+    assert_eq!(pose.vVelocity.v, [0.0; 3]);
+}
+
+#[test]
+fn switch_tracking_space() {
+    let f = Fixture::new();
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&SimpleController, LeftHand);
+    let session = f.raw_session();
+    let pose = xr::Posef {
+        position: xr::Vector3f {
+            x: 0.0,
+            y: 0.25,
+            z: -0.5,
+        },
+        orientation: xr::Quaternionf::IDENTITY,
+    };
+    fakexr::set_grip(session, LeftHand, pose);
+    fakexr::set_aim(session, LeftHand, pose);
+    fakexr::set_location_flags(
+        session,
+        LeftHand,
+        xr::SpaceLocationFlags::ORIENTATION_VALID
+            | xr::SpaceLocationFlags::ORIENTATION_TRACKED
+            | xr::SpaceLocationFlags::POSITION_VALID
+            | xr::SpaceLocationFlags::POSITION_TRACKED,
+    );
+
+    let heights = || {
+        f.input.frame_start_update();
+        let hmd = f.input.get_hmd_pose(None);
+        let controller = f.input.get_controller_pose(Hand::Left, None).unwrap();
+        assert!(hmd.bPoseIsValid);
+        assert!(controller.bPoseIsValid);
+        (
+            hmd.mDeviceToAbsoluteTracking.m[1][3],
+            controller.mDeviceToAbsoluteTracking.m[1][3],
+        )
+    };
+
+    let stage = fakexr::LOCAL_HEIGHT_ABOVE_STAGE;
+    for _ in 0..2 {
+        f.input
+            .openxr
+            .set_tracking_space(vr::ETrackingUniverseOrigin::Standing);
+        assert_eq!(heights(), (stage, stage + 0.25));
+
+        f.input
+            .openxr
+            .set_tracking_space(vr::ETrackingUniverseOrigin::Seated);
+        assert_eq!(heights(), (0.0, 0.25));
+    }
+
+    // There's no uncalibrated space, so it's the stage.
+    f.input
+        .openxr
+        .set_tracking_space(vr::ETrackingUniverseOrigin::RawAndUncalibrated);
+    assert_eq!(
+        f.input.openxr.get_tracking_space(),
+        vr::ETrackingUniverseOrigin::Standing
+    );
+    assert_eq!(heights(), (stage, stage + 0.25));
+
+    assert_eq!(f.raw_session(), session);
+}
+
+#[test]
+fn analog_binding_parameters() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let vec2act = f.get_action_handle(c"/actions/set1/in/vec2act");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    f.load_actions(c"actions_analog_params.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+
+    fakexr::set_action_state(
+        f.get_action::<xr::Vector2f>(vec2act),
+        fakexr::ActionState::Vector2(0.25, 0.75),
+        LeftHand,
+    );
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    for device in [0, left_hand] {
+        let mut data = vr::InputAnalogActionData_t::default();
+        assert_eq!(
+            f.input.GetAnalogActionData(
+                vec2act,
+                &mut data,
+                std::mem::size_of_val(&data) as u32,
+                device,
+            ),
+            vr::EVRInputError::None
+        );
+        assert!(data.bActive);
+        assert_eq!((data.x, data.y), (0.25, -0.75), "{device}");
+    }
+}
+
+#[test]
+fn analog_binding_parameters_per_hand() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let vec2act = f.get_action_handle(c"/actions/set1/in/vec2act");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    f.load_actions(c"actions_analog_params.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.set_interaction_profile(&Knuckles, RightHand);
+
+    let action = f.get_action::<xr::Vector2f>(vec2act);
+    fakexr::set_action_state(action, fakexr::ActionState::Vector2(0.25, 0.75), LeftHand);
+    fakexr::set_action_state(action, fakexr::ActionState::Vector2(0.5, 0.5), RightHand);
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    // Only the left hand's binding is inverted. Unrestricted reads get the left hand's state,
+    // since it's the most engaged.
+    for (device, expected) in [
+        (left_hand, (0.25, -0.75)),
+        (right_hand, (0.5, 0.5)),
+        (0, (0.25, -0.75)),
+    ] {
+        let mut data = vr::InputAnalogActionData_t::default();
+        assert_eq!(
+            f.input.GetAnalogActionData(
+                vec2act,
+                &mut data,
+                std::mem::size_of_val(&data) as u32,
+                device,
+            ),
+            vr::EVRInputError::None
+        );
+        assert!(data.bActive);
+        assert_eq!((data.x, data.y), expected, "{device}");
+    }
+}
+
+#[test]
+fn binding_variant() {
+    let f = Fixture::new();
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    let head = f.get_input_source_handle(c"/user/head");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.input.openxr.poll_events();
+    f.input.frame_start_update();
+    f.input.openxr.poll_events();
+
+    let get_variant = |handle, size: usize| {
+        let mut variant = vec![b'x' as std::ffi::c_char; size];
+        let err = f
+            .input
+            .GetBindingVariant(handle, variant.as_mut_ptr(), size as u32);
+        assert_eq!(err, vr::EVRInputError::None);
+        CStr::from_bytes_until_nul(unsafe {
+            std::slice::from_raw_parts(variant.as_ptr().cast(), size)
+        })
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string()
+    };
+
+    assert_eq!(get_variant(left_hand, 64), "knuckles");
+    // Truncated to fit, with room for the terminator.
+    assert_eq!(get_variant(left_hand, 5), "knuc");
+    assert_eq!(get_variant(left_hand, 1), "");
+
+    // No profile yet, or not a hand.
+    assert_eq!(get_variant(right_hand, 64), "");
+    assert_eq!(get_variant(head, 64), "");
+}
+
+#[test]
+fn origin_localized_name() {
+    let f = Fixture::new();
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    let head = f.get_input_source_handle(c"/user/head");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+    f.input.openxr.poll_events();
+    f.input.frame_start_update();
+    f.input.openxr.poll_events();
+
+    let get_name = |handle, size: usize, sections: i32| {
+        let mut name = vec![b'x' as std::ffi::c_char; size];
+        let err = f
+            .input
+            .GetOriginLocalizedName(handle, name.as_mut_ptr(), size as u32, sections);
+        assert_eq!(err, vr::EVRInputError::None);
+        CStr::from_bytes_until_nul(unsafe {
+            std::slice::from_raw_parts(name.as_ptr().cast(), size)
+        })
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string()
+    };
+
+    const ALL: i32 = -1;
+    const HAND: i32 = 0x01;
+    const CONTROLLER_TYPE: i32 = 0x02;
+    assert_eq!(get_name(left_hand, 64, ALL), "Left Hand Knuckles Left");
+    assert_eq!(get_name(left_hand, 64, HAND), "Left Hand");
+    assert_eq!(get_name(left_hand, 64, CONTROLLER_TYPE), "Knuckles Left");
+    // Truncated to fit, with room for the terminator.
+    assert_eq!(get_name(left_hand, 5, HAND), "Left");
+
+    // No profile yet, so only the hand is known.
+    assert_eq!(get_name(right_hand, 64, ALL), "Right Hand");
+    // Other devices fall back to their path.
+    assert_eq!(get_name(head, 64, ALL), "/user/head");
+
+    let mut name = [0; 64];
+    assert_eq!(
+        f.input
+            .GetOriginLocalizedName(12345, name.as_mut_ptr(), name.len() as u32, ALL),
+        vr::EVRInputError::InvalidHandle
+    );
+}
+
+#[test]
+fn bound_source_types() {
+    use super::source_type::SourceType;
+
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let vec1 = f.get_action_handle(c"/actions/set1/in/vec1act");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Touch, RightHand);
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    let sources = f.input.bound_sources(vec1, right_hand).unwrap();
+    assert_eq!(sources.len(), 1, "{sources:?}");
+    assert_eq!(sources[0].path, "/user/hand/right/input/trigger/value");
+    assert_eq!(sources[0].ty, Some(SourceType::Trigger));
+
+    let mut infos: [vr::InputBindingInfo_t; 2] = Default::default();
+    let mut count = 0;
+    assert_eq!(
+        f.input.GetActionBindingInfo(
+            vec1,
+            infos.as_mut_ptr(),
+            std::mem::size_of::<vr::InputBindingInfo_t>() as u32,
+            infos.len() as u32,
+            &mut count
+        ),
+        vr::EVRInputError::None
+    );
+    assert_eq!(count, 1);
+    let field = |field: &[std::ffi::c_char]| unsafe { CStr::from_ptr(field.as_ptr()) };
+    assert_eq!(field(&infos[0].rchDevicePathName), c"/user/hand/right");
+    assert_eq!(field(&infos[0].rchInputPathName), c"/input/trigger");
+    assert_eq!(field(&infos[0].rchSlotName), c"pull");
+    assert_eq!(field(&infos[0].rchInputSourceType), c"trigger");
+}
+
+#[test]
+fn binding_info_follows_runtime_bindings() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let vec1 = f.get_action_handle(c"/actions/set1/in/vec1act");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Touch, RightHand);
+    f.sync(vr::VRActiveActionSet_t {
+        ulActionSet: set1,
+        ..Default::default()
+    });
+
+    // Suggested on the trigger, but the user moved it to the grip in the runtime.
+    let action = f.get_action::<f32>(vec1);
+    fakexr::set_bound_sources(action, &["/user/hand/right/input/squeeze/value"]);
+
+    let mut info = vr::InputBindingInfo_t::default();
+    let mut count = 0;
+    assert_eq!(
+        f.input.GetActionBindingInfo(
+            vec1,
+            &mut info,
+            std::mem::size_of::<vr::InputBindingInfo_t>() as u32,
+            1,
+            &mut count
+        ),
+        vr::EVRInputError::None
+    );
+    assert_eq!(count, 1);
+    let field = |field: &[std::ffi::c_char]| unsafe { CStr::from_ptr(field.as_ptr()) };
+    assert_eq!(field(&info.rchDevicePathName), c"/user/hand/right");
+    assert_eq!(field(&info.rchInputPathName), c"/input/grip");
+    assert_eq!(field(&info.rchSlotName), c"pull");
+    assert_eq!(field(&info.rchInputSourceType), c"button");
+}
+
+#[test]
+fn digital_action_with_multiple_sources() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    f.load_actions(c"actions_multi_source.json");
+    f.set_interaction_profile(&Touch, LeftHand);
+
+    // X is bound directly, while the trigger's click is emulated from its value.
+    let button = f.get_action::<bool>(boolact);
+    let trigger = {
+        let data = f.input.openxr.session_data.get();
+        let actions = data.input_data.get_loaded_actions().unwrap();
+        let extra = actions.try_get_extra(boolact).unwrap();
+        extra.analog_action.as_ref().unwrap().as_raw()
+    };
+
+    let check = |x, trigger_value, state, changed, line| {
+        fakexr::set_action_state(button, fakexr::ActionState::Bool(x), LeftHand);
+        fakexr::set_action_state(trigger, fakexr::ActionState::Float(trigger_value), LeftHand);
+        f.sync(vr::VRActiveActionSet_t {
+            ulActionSet: set1,
+            ..Default::default()
+        });
+
+        let s = f.get_bool_state(boolact).unwrap();
+        assert!(s.bActive, "active failed (line {line})");
+        assert_eq!(s.bState, state, "state failed (line {line})");
+        assert_eq!(s.bChanged, changed, "changed failed (line {line})");
+        if state {
+            assert_eq!(s.activeOrigin, left_hand, "origin failed (line {line})");
+        }
+    };
+
+    check(false, 0.0, false, false, line!());
+    // Either source presses the action.
+    check(true, 0.0, true, true, line!());
+    check(false, 0.0, false, true, line!());
+    check(false, 1.0, true, true, line!());
+    // Pressing or releasing one source while the other is held isn't a change.
+    check(true, 1.0, true, false, line!());
+    check(true, 0.0, true, false, line!());
+    check(false, 0.0, false, true, line!());
+}
+
+#[test]
+fn digital_action_origin_from_binding_hand() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    let left_hand = f.get_input_source_handle(c"/user/hand/left");
+    let right_hand = f.get_input_source_handle(c"/user/hand/right");
+    f.load_actions(c"actions_multi_source.json");
+    f.set_interaction_profile(&Touch, LeftHand);
+    f.set_interaction_profile(&Touch, RightHand);
+
+    // X is bound on the left hand, and the trigger's click is emulated on the right hand.
+    let button = f.get_action::<bool>(boolact);
+    let trigger = {
+        let data = f.input.openxr.session_data.get();
+        let actions = data.input_data.get_loaded_actions().unwrap();
+        let extra = actions.try_get_extra(boolact).unwrap();
+        extra.analog_action.as_ref().unwrap().as_raw()
+    };
+
+    let check = |x, trigger_value, origin, line| {
+        fakexr::set_action_state(button, fakexr::ActionState::Bool(x), LeftHand);
+        fakexr::set_action_state(
+            trigger,
+            fakexr::ActionState::Float(trigger_value),
+            RightHand,
+        );
+        f.sync(vr::VRActiveActionSet_t {
+            ulActionSet: set1,
+            ..Default::default()
+        });
+
+        let s = f.get_bool_state(boolact).unwrap();
+        assert!(s.bState, "state failed (line {line})");
+        assert_eq!(s.activeOrigin, origin, "origin failed (line {line})");
+    };
+
+    check(false, 1.0, right_hand, line!());
+    // The hand that was pressed last is the origin.
+    check(true, 1.0, left_hand, line!());
+    check(false, 1.0, right_hand, line!());
+}
+
+#[test]
+fn legacy_input_until_manifest_loaded() {
+    let f = Fixture::new();
+    assert!(f.input.IsUsingLegacyInput());
+
+    f.load_actions(c"actions.json");
+    assert!(!f.input.IsUsingLegacyInput());
+}
+
+#[test]
+fn disabling_input_deactivates_game_actions() {
+    let f = Fixture::new();
+    let set1 = f.get_action_set_handle(c"/actions/set1");
+    let boolact = f.get_action_handle(c"/actions/set1/in/boolact");
+    f.load_actions(c"actions.json");
+    f.set_interaction_profile(&Knuckles, LeftHand);
+
+    fakexr::set_action_state(
+        f.get_action::<bool>(boolact),
+        fakexr::ActionState::Bool(true),
+        LeftHand,
+    );
+    let sync = || {
+        f.sync(vr::VRActiveActionSet_t {
+            ulActionSet: set1,
+            ..Default::default()
+        })
+    };
+    sync();
+    let state = f.get_bool_state(boolact).unwrap();
+    assert!(state.bActive);
+    assert!(state.bState);
+
+    f.input.set_input_enabled(false);
+    sync();
+    let state = f.get_bool_state(boolact).unwrap();
+    assert!(!state.bActive);
+    assert!(!state.bState);
+
+    // The controller is still tracked.
+    let get_pose = || {
+        f.input
+            .get_controller_pose(Hand::Left, Some(vr::ETrackingUniverseOrigin::Seated))
+            .expect("Controller should still be tracked")
+    };
+    let before = get_pose();
+    fakexr::set_grip(
+        f.raw_session(),
+        LeftHand,
+        xr::Posef {
+            position: xr::Vector3f {
+                x: 0.25,
+                y: 1.0,
+                z: -0.5,
+            },
+            orientation: xr::Quaternionf::IDENTITY,
+        },
+    );
+    f.input.frame_start_update();
+    sync();
+    let after = get_pose();
+    assert!(after.bPoseIsValid);
+    assert_ne!(
+        before.mDeviceToAbsoluteTracking.m,
+        after.mDeviceToAbsoluteTracking.m
+    );
+
+    f.input.set_input_enabled(true);
+    sync();
+    let state = f.get_bool_state(boolact).unwrap();
+    assert!(state.bActive);
+    assert!(state.bState);
+}
@@ -6,6 +6,8 @@ mod clientcore;
 mod compositor;
 mod graphics_backends;
 mod input;
+mod log_file;
+mod log_filter;
 mod misc_unknown;
 mod openxr_data;
 mod overlay;
@@ -22,8 +24,8 @@ use clientcore::ClientCore;
 use openvr as vr;
 use std::ffi::{c_char, c_void, CStr};
 use std::sync::{
-    atomic::{AtomicU32, AtomicU64, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    Arc, LazyLock,
 };
 
 macro_rules! warn_unimplemented {
@@ -58,6 +60,66 @@ use tracy_span;
 #[cfg(feature = "tracing")]
 tracy_client::register_demangler!();
 
+/// Wraps an OpenXR call, logging its result and how long it took to the `openxr_calls` target
+/// when OpenXR call logging is enabled.
+macro_rules! xr_call {
+    ($name:literal, $call:expr) => {{
+        crate::tracy_span!($name);
+        if crate::openxr_call_logging_enabled() {
+            let start = std::time::Instant::now();
+            let ret = $call;
+            crate::log_openxr_call($name, ret.as_ref().err(), start.elapsed());
+            ret
+        } else {
+            $call
+        }
+    }};
+}
+use xr_call;
+
+/// Returns true if the given environment variable is set to `1` or `true` (in any case).
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+/// Copies as much of `src` into `dst` as fits, truncating it to leave room for the NUL terminator.
+/// `dst` must not be empty.
+fn copy_truncated(dst: &mut [std::ffi::c_char], src: &[u8]) {
+    let len = src.len().min(dst.len() - 1);
+    for (dst, src) in dst.iter_mut().zip(&src[..len]) {
+        *dst = *src as std::ffi::c_char;
+    }
+    dst[len] = 0;
+}
+
+static OPENXR_CALL_LOGGING: LazyLock<AtomicBool> =
+    LazyLock::new(|| env_flag("XRIZER_LOG_OPENXR_CALLS").into());
+
+#[inline]
+fn openxr_call_logging_enabled() -> bool {
+    // Compiled out entirely if trace logs are statically disabled.
+    log::STATIC_MAX_LEVEL >= log::LevelFilter::Trace && OPENXR_CALL_LOGGING.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+thread_local! {
+    static LOGGED_OPENXR_CALLS: std::cell::RefCell<Vec<String>> = Default::default();
+}
+
+#[cfg(test)]
+fn take_logged_openxr_calls() -> Vec<String> {
+    LOGGED_OPENXR_CALLS.take()
+}
+
+#[cold]
+fn log_openxr_call(name: &str, err: Option<&openxr::sys::Result>, elapsed: std::time::Duration) {
+    let result = err.map_or(Ok(()), |e| Err(*e));
+    let line = format!("{name} -> {result:?} ({elapsed:?})");
+    log::trace!(target: "openxr_calls", "{line}");
+    #[cfg(test)]
+    LOGGED_OPENXR_CALLS.with_borrow_mut(|calls| calls.push(line));
+}
+
 macro_rules! atomic_float {
     ($name:ident, $float:ty, $atomic:ty) => {
         #[derive(Default)]
@@ -104,12 +166,15 @@ fn init_logging() {
     ONCE.call_once(|| {
         let mut builder = env_logger::Builder::new();
         #[allow(unused_mut)]
-        let mut startup_err: Option<String> = None;
+        let mut startup_errs: Vec<String> = Vec::new();
+
+        // safety: who cares lol
+        unsafe {
+            time::util::local_offset::set_soundness(time::util::local_offset::Soundness::Unsound)
+        };
 
         #[cfg(not(test))]
         {
-            use std::path::Path;
-
             struct ComboWriter(std::fs::File, std::io::Stderr);
 
             impl std::io::Write for ComboWriter {
@@ -124,29 +189,17 @@ fn init_logging() {
                 }
             }
 
-            let state_dir = std::env::var("XDG_STATE_HOME")
-                .or_else(|_| std::env::var("HOME").map(|h| h + "/.local/state"));
-
-            if let Ok(state) = state_dir {
-                let path = Path::new(&state).join("xrizer");
-                let mut setup = || {
-                    let path = path.join("xrizer.txt");
-                    match std::fs::File::create(path) {
-                        Ok(file) => {
-                            let writer = ComboWriter(file, std::io::stderr());
-                            builder.target(env_logger::Target::Pipe(Box::new(writer)));
-                        }
-                        Err(e) => startup_err = Some(format!("Failed to create log file: {e:?}")),
+            if let Some(dir) = log_file::log_dir() {
+                let keep = log_file::kept_files(|e| startup_errs.push(e));
+                let now = time::OffsetDateTime::now_local()
+                    .unwrap_or_else(|_| time::OffsetDateTime::now_utc());
+                match log_file::create(&dir, keep, now, |e| startup_errs.push(e)) {
+                    Ok((_, file)) => {
+                        let writer = ComboWriter(file, std::io::stderr());
+                        builder.target(env_logger::Target::Pipe(Box::new(writer)));
                     }
-                };
-
-                match std::fs::create_dir_all(&path) {
-                    Ok(_) => setup(),
-                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => setup(),
-                    err => {
-                        startup_err = Some(format!(
-                            "Failed to create log directory ({path:?}): {err:?}"
-                        ))
+                    Err(e) => {
+                        startup_errs.push(format!("Failed to create log file in {dir:?}: {e:?}"))
                     }
                 }
             }
@@ -160,14 +213,21 @@ fn init_logging() {
             }));
         }
 
-        // safety: who cares lol
-        unsafe {
-            time::util::local_offset::set_soundness(time::util::local_offset::Soundness::Unsound)
-        };
+        // Filtering is left to log_filter, so that it can be changed later.
+        let mut filter = String::from("info");
+        if OPENXR_CALL_LOGGING.load(Ordering::Relaxed) {
+            filter.push_str(",openxr_calls=trace");
+        }
+        if let Ok(env) = std::env::var("RUST_LOG") {
+            filter.push(',');
+            filter.push_str(&env);
+        }
 
+        if let Ok(style) = std::env::var("RUST_LOG_STYLE") {
+            builder.parse_write_style(&style);
+        }
         builder
-            .filter_level(log::LevelFilter::Info)
-            .parse_default_env()
+            .filter_level(log::LevelFilter::Trace)
             .is_test(cfg!(test))
             .format(|buf, record| {
                 use std::io::Write;
@@ -187,11 +247,11 @@ fn init_logging() {
                     write!(buf, " {}", path)?;
                 }
                 writeln!(buf, " {:?}] {}", std::thread::current().id(), record.args())
-            })
-            .init();
+            });
+        log_filter::init(builder.build(), &filter);
 
         log::info!("Initializing XRizer");
-        if let Some(err) = startup_err {
+        for err in startup_errs {
             log::warn!("{err}");
         }
     });
@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+
+const PREFIX: &str = "xrizer-";
+const EXTENSION: &str = ".txt";
+
+/// The directory logs are written to: `XRIZER_LOG_DIR` if it's set, otherwise `xrizer` in the XDG
+/// state directory.
+#[cfg(not(test))]
+pub fn log_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XRIZER_LOG_DIR").filter(|dir| !dir.is_empty()) {
+        return Some(dir.into());
+    }
+    std::env::var("XDG_STATE_HOME")
+        .or_else(|_| std::env::var("HOME").map(|h| h + "/.local/state"))
+        .ok()
+        .map(|state| Path::new(&state).join("xrizer"))
+}
+
+/// How many log files are kept, from `XRIZER_LOG_FILES`. Invalid values are passed to `invalid`
+/// and replaced with the default, since logging isn't set up yet to report them.
+#[cfg(not(test))]
+pub fn kept_files(invalid: impl FnOnce(String)) -> usize {
+    const DEFAULT: usize = 5;
+    let Ok(value) = std::env::var("XRIZER_LOG_FILES") else {
+        return DEFAULT;
+    };
+    match value.parse() {
+        Ok(count) if count > 0 => count,
+        _ => {
+            invalid(format!("Invalid XRIZER_LOG_FILES value {value:?}"));
+            DEFAULT
+        }
+    }
+}
+
+/// Creates a log file in `dir` named after `now`, and deletes the oldest log files so that at most
+/// `keep` are left, including the new one. `xrizer.txt` is pointed at the new file, so the latest
+/// log is easy to find.
+/// Old files that can't be deleted are passed to `warn` and left in place, since the new log is
+/// still usable.
+pub fn create(
+    dir: &Path,
+    keep: usize,
+    now: OffsetDateTime,
+    mut warn: impl FnMut(String),
+) -> io::Result<(PathBuf, File)> {
+    use time::macros::format_description;
+
+    std::fs::create_dir_all(dir)?;
+    // Timestamps sort in the same order as the files were created.
+    let timestamp = now
+        .format(format_description!(
+            "[year]-[month]-[day]T[hour]-[minute]-[second]"
+        ))
+        .map_err(io::Error::other)?;
+
+    // Several runs can start within the same second.
+    let mut counter = 0;
+    let (path, file) = loop {
+        let suffix = match counter {
+            0 => String::new(),
+            n => format!("-{n}"),
+        };
+        let path = dir.join(format!("{PREFIX}{timestamp}{suffix}{EXTENSION}"));
+        match File::options().write(true).create_new(true).open(&path) {
+            Ok(file) => break (path, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+            Err(e) => return Err(e),
+        }
+    };
+
+    prune(dir, keep, &path, &mut warn);
+    link_latest(dir, &path);
+    Ok((path, file))
+}
+
+/// Lists the log files in `dir`, oldest first.
+pub fn list(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(PREFIX) && name.ends_with(EXTENSION))
+        })
+        .collect();
+    files.sort_by_cached_key(|path| sort_key(path));
+    Ok(files)
+}
+
+/// Orders files from the same second by their counter, so `-10` comes after `-9`.
+fn sort_key(path: &Path) -> (String, u32) {
+    let name = path.file_stem().unwrap().to_string_lossy();
+    let name = name.strip_prefix(PREFIX).unwrap_or(&name);
+    // Timestamps don't contain a "-" after the seconds, so anything past them is the counter.
+    const TIMESTAMP_LEN: usize = "YYYY-MM-DDTHH-MM-SS".len();
+    match name
+        .get(TIMESTAMP_LEN..)
+        .and_then(|rest| rest.strip_prefix('-'))
+    {
+        Some(counter) => (
+            name[..TIMESTAMP_LEN].to_string(),
+            counter.parse().unwrap_or(0),
+        ),
+        None => (name.to_string(), 0),
+    }
+}
+
+/// Deletes the oldest log files other than `new`, leaving `keep` files in total. `new` isn't
+/// necessarily the last by name, since the clock can go back between runs (e.g. when DST ends).
+fn prune(dir: &Path, keep: usize, new: &Path, warn: &mut impl FnMut(String)) {
+    let mut files = match list(dir) {
+        Ok(files) => files,
+        Err(e) => {
+            warn(format!("Failed to list old log files in {dir:?}: {e:?}"));
+            return;
+        }
+    };
+    files.retain(|path| path != new);
+    let excess = files.len().saturating_sub(keep.saturating_sub(1));
+    for path in &files[..excess] {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn(format!("Failed to delete old log file {path:?}: {e:?}"));
+        }
+    }
+}
+
+fn link_latest(dir: &Path, path: &Path) {
+    let latest = dir.join("xrizer.txt");
+    let _ = std::fs::remove_file(&latest);
+    let _ = std::os::unix::fs::symlink(path.file_name().unwrap(), &latest);
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    #[test]
+    fn rotation() {
+        let dir = std::env::temp_dir().join(format!("xrizer-log-test-{}", std::process::id()));
+        let no_warnings = |e: String| panic!("unexpected warning: {e}");
+        let _ = std::fs::remove_dir_all(&dir);
+        let names = || {
+            super::list(&dir)
+                .unwrap()
+                .into_iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let (path, _) =
+            super::create(&dir, 3, datetime!(2024-05-01 10:00:00 UTC), no_warnings).unwrap();
+        assert_eq!(names(), ["xrizer-2024-05-01T10-00-00.txt"]);
+        assert_eq!(
+            std::fs::canonicalize(dir.join("xrizer.txt")).unwrap(),
+            std::fs::canonicalize(&path).unwrap()
+        );
+
+        super::create(&dir, 3, datetime!(2024-05-01 10:00:00 UTC), no_warnings).unwrap();
+        super::create(&dir, 3, datetime!(2024-05-02 09:30:00 UTC), no_warnings).unwrap();
+        assert_eq!(
+            names(),
+            [
+                "xrizer-2024-05-01T10-00-00.txt",
+                "xrizer-2024-05-01T10-00-00-1.txt",
+                "xrizer-2024-05-02T09-30-00.txt",
+            ]
+        );
+
+        // The oldest files are pruned beyond the limit.
+        super::create(&dir, 3, datetime!(2024-05-03 08:00:00 UTC), no_warnings).unwrap();
+        super::create(&dir, 3, datetime!(2024-05-04 08:00:00 UTC), no_warnings).unwrap();
+        assert_eq!(
+            names(),
+            [
+                "xrizer-2024-05-02T09-30-00.txt",
+                "xrizer-2024-05-03T08-00-00.txt",
+                "xrizer-2024-05-04T08-00-00.txt",
+            ]
+        );
+
+        // A file created after the clock went back sorts before the existing ones, but is kept.
+        let (path, _) =
+            super::create(&dir, 3, datetime!(2024-05-03 07:00:00 UTC), no_warnings).unwrap();
+        assert_eq!(
+            names(),
+            [
+                "xrizer-2024-05-03T07-00-00.txt",
+                "xrizer-2024-05-03T08-00-00.txt",
+                "xrizer-2024-05-04T08-00-00.txt",
+            ]
+        );
+        assert_eq!(
+            std::fs::canonicalize(dir.join("xrizer.txt")).unwrap(),
+            std::fs::canonicalize(&path).unwrap()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+use log::{Log, Metadata, Record};
+use std::sync::{OnceLock, RwLock};
+
+/// A parsed log filter, along with the spec it was parsed from.
+struct Filter {
+    spec: String,
+    inner: env_filter::Filter,
+}
+
+impl Filter {
+    /// Parses a filter in env_logger's `RUST_LOG` syntax (e.g. `info,xrizer::input=debug`).
+    fn parse(spec: &str) -> Result<Self, env_filter::ParseError> {
+        Ok(Self {
+            spec: spec.to_string(),
+            inner: env_filter::Builder::new().try_parse(spec)?.build(),
+        })
+    }
+}
+
+/// Wraps env_logger, which can't change its filter once it's installed.
+struct FilteredLogger {
+    inner: env_logger::Logger,
+    filter: RwLock<Filter>,
+}
+
+impl FilteredLogger {
+    /// Replaces the filter, returning false and keeping the current one if `spec` is invalid.
+    fn set_filter(&self, spec: &str) -> bool {
+        let filter = match Filter::parse(spec) {
+            Ok(filter) => filter,
+            Err(e) => {
+                log::warn!("Ignoring log filter {spec:?}: {e}");
+                return false;
+            }
+        };
+
+        log::info!("Setting log filter to {spec:?}");
+        let max_level = filter.inner.filter();
+        *self.filter.write().unwrap() = filter;
+        log::set_max_level(max_level);
+        true
+    }
+
+    fn spec(&self) -> String {
+        self.filter.read().unwrap().spec.clone()
+    }
+}
+
+impl Log for FilteredLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.read().unwrap().inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.filter.read().unwrap().inner.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+static LOGGER: OnceLock<FilteredLogger> = OnceLock::new();
+
+/// Installs `inner` as the logger, with its output filtered by `spec`. `inner` should let all
+/// records through.
+pub fn init(inner: env_logger::Logger, spec: &str) {
+    let (filter, error) = match Filter::parse(spec) {
+        Ok(filter) => (filter, None),
+        Err(e) => (Filter::parse("info").unwrap(), Some(e)),
+    };
+    let max_level = filter.inner.filter();
+    let logger = LOGGER.get_or_init(|| FilteredLogger {
+        inner,
+        filter: filter.into(),
+    });
+    log::set_logger(logger).expect("Logger already set");
+    log::set_max_level(max_level);
+
+    // Reported once the logger is installed, so it makes it to the log file.
+    if let Some(e) = error {
+        log::warn!("Ignoring log filter {spec:?}: {e}");
+    }
+}
+
+/// Replaces the current log filter. Returns false if the filter is invalid, in which case the
+/// current one is kept.
+pub fn set_filter(spec: &str) -> bool {
+    LOGGER.get().is_some_and(|logger| logger.set_filter(spec))
+}
+
+/// Returns the current log filter.
+pub fn filter() -> Option<String> {
+    LOGGER.get().map(FilteredLogger::spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, FilteredLogger};
+    use log::{Level, Log, Metadata};
+
+    fn enabled(logger: &FilteredLogger, target: &str, level: Level) -> bool {
+        logger.enabled(&Metadata::builder().target(target).level(level).build())
+    }
+
+    #[test]
+    fn per_target_directives() {
+        // Not installed, so the global logger is left alone.
+        let logger = FilteredLogger {
+            inner: env_logger::Builder::new().build(),
+            filter: Filter::parse("info, xrizer::input=debug,openxr_calls")
+                .unwrap()
+                .into(),
+        };
+        assert_eq!(logger.spec(), "info, xrizer::input=debug,openxr_calls");
+
+        assert!(enabled(&logger, "xrizer::compositor", Level::Info));
+        assert!(!enabled(&logger, "xrizer::compositor", Level::Debug));
+        assert!(enabled(&logger, "xrizer::input::legacy", Level::Debug));
+        assert!(!enabled(&logger, "xrizer::input", Level::Trace));
+        assert!(enabled(&logger, "openxr_calls", Level::Trace));
+
+        // Nothing is logged without a matching directive
+        let filter = Filter::parse("xrizer::input=debug").unwrap();
+        *logger.filter.write().unwrap() = filter;
+        assert!(!enabled(&logger, "xrizer::compositor", Level::Error));
+        assert!(enabled(&logger, "xrizer::input", Level::Debug));
+
+        assert!(Filter::parse("xrizer=loud").is_err());
+    }
+
+    #[test]
+    fn set_from_settings() {
+        use crate::{clientcore::Injector, openxr_data::RealOpenXrData, settings::Settings};
+        use openvr as vr;
+        use vr::IVRSettings003_Interface;
+
+        crate::init_logging();
+        let settings = Settings::new(RealOpenXrData::new_fake(), &Injector::default());
+        let get_filter = || {
+            let mut err = vr::EVRSettingsError::None;
+            let mut value = [0; 256];
+            settings.GetString(
+                c"xrizer".as_ptr(),
+                c"logFilter".as_ptr(),
+                value.as_mut_ptr(),
+                value.len() as u32,
+                &mut err,
+            );
+            assert_eq!(err, vr::EVRSettingsError::None);
+            unsafe { std::ffi::CStr::from_ptr(value.as_ptr()) }
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let set_filter = |filter: &std::ffi::CStr| {
+            let mut err = vr::EVRSettingsError::None;
+            settings.SetString(
+                c"xrizer".as_ptr(),
+                c"logFilter".as_ptr(),
+                filter.as_ptr(),
+                &mut err,
+            );
+            assert_eq!(err, vr::EVRSettingsError::None);
+        };
+        let original = get_filter();
+
+        set_filter(c"info");
+        assert!(!log::log_enabled!(target: "xrizer::input", Level::Debug));
+
+        set_filter(c"info,xrizer::input=debug");
+        assert_eq!(get_filter(), "info,xrizer::input=debug");
+        assert!(log::log_enabled!(target: "xrizer::input", Level::Debug));
+        assert!(!log::log_enabled!(target: "xrizer::compositor", Level::Debug));
+
+        // Invalid filters are ignored.
+        set_filter(c"xrizer=loud");
+        assert_eq!(get_filter(), "info,xrizer::input=debug");
+
+        super::set_filter(&original);
+    }
+}
@@ -1,18 +1,24 @@
+mod time_smoothing;
+
 use crate::{
     clientcore::{Injected, Injector},
     graphics_backends::{supported_apis_enum, GraphicsBackend, VulkanData},
     input::{InteractionProfile, Profiles},
+    xr_call, AtomicF64,
 };
 use derive_more::{Deref, From, TryInto};
 use glam::f32::{Quat, Vec3};
 use log::{info, warn};
 use openvr as vr;
 use openxr as xr;
+use std::collections::HashMap;
 use std::mem::ManuallyDrop;
 use std::sync::{
     atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
     Mutex, RwLock,
 };
+use std::time::Instant;
+use time_smoothing::TimeSmoothing;
 
 pub trait Compositor: vr::InterfaceImpl {
     fn post_session_restart(
@@ -35,9 +41,22 @@ pub struct OpenXrData<C: Compositor> {
     pub system_id: xr::SystemId,
     pub session_data: SessionReadGuard,
     pub display_time: AtomicXrTime,
+    /// Estimated runtime time at which the last xrWaitFrame returned, for when the current time
+    /// can't be asked for.
+    wait_time: AtomicXrTime,
+    /// The time poses are located at: the display time, with jitter smoothed out if enabled.
+    pub pose_time: AtomicXrTime,
+    time_smoothing: TimeSmoothing,
+    /// Base for all system times reported to the app.
+    pub clock: SystemClock,
     pub left_hand: HandInfo,
     pub right_hand: HandInfo,
     pub enabled_extensions: xr::ExtensionSet,
+    /// Set while xrizer ends the session itself, so the session stopping isn't taken as the
+    /// runtime asking the app to quit.
+    ending_session: AtomicBool,
+    /// Set when the runtime stops the session, until the app has been sent a quit event.
+    quit_requested: AtomicBool,
 
     /// should only be externally accessed for testing
     pub(crate) input: Injected<crate::input::Input<C>>,
@@ -86,11 +105,14 @@ impl<C: Compositor> OpenXrData<C> {
         exts.khr_vulkan_enable = supported_exts.khr_vulkan_enable;
         exts.khr_opengl_enable = supported_exts.khr_opengl_enable;
         exts.ext_hand_tracking = supported_exts.ext_hand_tracking;
+        exts.ext_hand_interaction = supported_exts.ext_hand_interaction;
         exts.khr_visibility_mask = supported_exts.khr_visibility_mask;
         exts.khr_composition_layer_cylinder = supported_exts.khr_composition_layer_cylinder;
         exts.khr_composition_layer_equirect2 = supported_exts.khr_composition_layer_equirect2;
         exts.khr_composition_layer_color_scale_bias =
             supported_exts.khr_composition_layer_color_scale_bias;
+        exts.fb_display_refresh_rate = supported_exts.fb_display_refresh_rate;
+        exts.khr_convert_timespec_time = supported_exts.khr_convert_timespec_time;
 
         let instance = entry
             .create_instance(
@@ -120,6 +142,14 @@ impl<C: Compositor> OpenXrData<C> {
 
         let left_hand = HandInfo::new(&instance, "/user/hand/left");
         let right_hand = HandInfo::new(&instance, "/user/hand/right");
+        if let Some(profile) = default_profile_from_env() {
+            info!(
+                "Assuming {} until the runtime reports an interaction profile",
+                profile.profile_path()
+            );
+            left_hand.assume_profile(&instance, profile);
+            right_hand.assume_profile(&instance, profile);
+        }
 
         Ok(Self {
             _entry: entry,
@@ -127,14 +157,27 @@ impl<C: Compositor> OpenXrData<C> {
             system_id,
             session_data,
             display_time: AtomicXrTime(1.into()),
+            pose_time: AtomicXrTime(1.into()),
+            wait_time: AtomicXrTime(0.into()),
+            time_smoothing: TimeSmoothing::from_env(),
+            clock: SystemClock::new(),
             left_hand,
             right_hand,
             enabled_extensions: exts,
+            ending_session: AtomicBool::new(false),
+            quit_requested: AtomicBool::new(false),
             input: injector.inject(),
             compositor: injector.inject(),
         })
     }
 
+    /// Sets up OpenXR on the fake runtime with nothing injected, for tests that don't need the
+    /// other interfaces.
+    #[cfg(test)]
+    pub fn new_fake() -> std::sync::Arc<Self> {
+        std::sync::Arc::new(Self::new(&Injector::default()).unwrap())
+    }
+
     pub fn poll_events(&self) {
         let mut buf = xr::EventDataBuffer::new();
         while let Some(event) = self.instance.poll_event(&mut buf).unwrap() {
@@ -142,6 +185,12 @@ impl<C: Compositor> OpenXrData<C> {
                 xr::Event::SessionStateChanged(event) => {
                     self.session_data.0.write().unwrap().state = event.state();
                     info!("OpenXR session state changed: {:?}", event.state());
+                    if event.state() == xr::SessionState::STOPPING
+                        && !self.ending_session.load(Ordering::Relaxed)
+                    {
+                        info!("Runtime requested exit, telling app to quit");
+                        self.quit_requested.store(true, Ordering::Relaxed);
+                    }
                 }
                 xr::Event::InteractionProfileChanged(_) => {
                     let session = self.session_data.get();
@@ -151,6 +200,12 @@ impl<C: Compositor> OpenXrData<C> {
                             .current_interaction_profile(info.subaction_path)
                             .unwrap();
 
+                        // Keep the assumed profile until the runtime reports a real one.
+                        if profile_path == xr::Path::NULL && info.assumed.load(Ordering::Relaxed) {
+                            continue;
+                        }
+                        info.assumed.store(false, Ordering::Relaxed);
+
                         info.profile_path.store(profile_path);
                         let profile = match profile_path {
                             xr::Path::NULL => {
@@ -208,8 +263,22 @@ impl<C: Compositor> OpenXrData<C> {
         *session_guard = ManuallyDrop::new(session);
     }
 
+    /// Both reference spaces are created with the session, so switching between them takes effect
+    /// from the next locate, without restarting the session.
     pub fn set_tracking_space(&self, space: vr::ETrackingUniverseOrigin) {
-        self.session_data.0.write().unwrap().current_origin = space;
+        // OpenXR has no uncalibrated space, the stage is the closest thing to it.
+        let space = match space {
+            vr::ETrackingUniverseOrigin::RawAndUncalibrated => {
+                vr::ETrackingUniverseOrigin::Standing
+            }
+            other => other,
+        };
+
+        let mut session_data = self.session_data.0.write().unwrap();
+        if session_data.current_origin != space {
+            info!("Switching tracking space to {space:?}");
+            session_data.current_origin = space;
+        }
     }
 
     pub fn get_tracking_space(&self) -> vr::ETrackingUniverseOrigin {
@@ -229,34 +298,20 @@ impl<C: Compositor> OpenXrData<C> {
         } = &mut **guard;
 
         let reset_space = |ref_space, adjusted_space: &mut xr::Space, ty| {
-            let xr::Posef {
-                position,
-                orientation,
-            } = view_space
-                .locate(ref_space, self.display_time.get())
+            let (orientation, position) = pose_to_glam(
+                xr_call!(
+                    "xrLocateSpace",
+                    view_space.locate(ref_space, self.display_time.get())
+                )
                 .unwrap()
-                .pose;
+                .pose,
+            );
 
             // Only set the rotation around the y axis
-            let (twist, _) = swing_twist_decomposition(
-                Quat::from_xyzw(orientation.x, orientation.y, orientation.z, orientation.w),
-                Vec3::Y,
-            )
-            .unwrap();
+            let (twist, _) = swing_twist_decomposition(orientation, Vec3::Y).unwrap();
 
             *adjusted_space = session
-                .create_reference_space(
-                    ty,
-                    xr::Posef {
-                        position,
-                        orientation: xr::Quaternionf {
-                            x: twist.x,
-                            y: twist.y,
-                            z: twist.z,
-                            w: twist.w,
-                        },
-                    },
-                )
+                .create_reference_space(ty, pose_from_glam(twist, position))
                 .unwrap();
         };
 
@@ -275,9 +330,68 @@ impl<C: Compositor> OpenXrData<C> {
         };
     }
 
+    /// Sets the predicted display time and period of the frame that was just waited on.
+    pub fn set_display_time(&self, time: xr::Time, period: xr::Duration) {
+        self.display_time.set(time);
+        self.pose_time.set(self.time_smoothing.smooth(time));
+        // xrWaitFrame returns about a display period before the frame is displayed.
+        self.wait_time
+            .set(xr::Time::from_nanos(time.as_nanos() - period.as_nanos()));
+        self.clock.record_wait();
+    }
+
+    /// The runtime's current time. Without XR_KHR_convert_timespec_time, this is estimated from
+    /// the time since the last xrWaitFrame.
+    pub fn now(&self) -> xr::Time {
+        if let Some(convert) = &self.instance.exts().khr_convert_timespec_time {
+            let mut timespec = libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            };
+            let mut time = xr::Time::from_nanos(0);
+            let result = unsafe {
+                libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut timespec);
+                (convert.convert_timespec_time_to_time)(
+                    self.instance.as_raw(),
+                    &timespec,
+                    &mut time,
+                )
+            };
+            if result == xr::sys::Result::SUCCESS {
+                return time;
+            }
+            crate::warn_once!("xrConvertTimespecTimeToTimeKHR failed: {:?}", result);
+        }
+        let since_wait = (self.clock.since_last_wait() * 1e9) as i64;
+        xr::Time::from_nanos(self.wait_time.get().as_nanos() + since_wait)
+    }
+
+    /// Returns true once for each time the runtime has stopped the session, so the app can be
+    /// sent a quit event.
+    pub fn take_quit_request(&self) -> bool {
+        self.quit_requested.swap(false, Ordering::Relaxed)
+    }
+
+    /// Ends the session after the app has acknowledged a quit. The frame controller is released
+    /// along with it, since no more frames can be submitted.
+    pub fn acknowledge_quit(&self) {
+        self.end_session();
+        let comp_data = std::mem::take(&mut self.session_data.0.write().unwrap().comp_data);
+        drop(comp_data);
+    }
+
     fn end_session(&self) {
-        self.session_data.get().session.request_exit().unwrap();
         let mut state = self.session_data.get().state;
+        // Already ended, e.g. by acknowledging a quit.
+        if state == xr::SessionState::EXITING {
+            return;
+        }
+
+        self.ending_session.store(true, Ordering::Relaxed);
+        // If the runtime already stopped the session, there's nothing left to request.
+        if state != xr::SessionState::STOPPING {
+            self.session_data.get().session.request_exit().unwrap();
+        }
         while state != xr::SessionState::STOPPING {
             self.poll_events();
             state = self.session_data.get().state;
@@ -287,6 +401,7 @@ impl<C: Compositor> OpenXrData<C> {
             self.poll_events();
             state = self.session_data.get().state;
         }
+        self.ending_session.store(false, Ordering::Relaxed);
     }
 }
 
@@ -304,6 +419,56 @@ impl AtomicXrTime {
     }
 }
 
+/// Monotonic clock anchored at instance creation. Frame timings and vsync times are all reported
+/// relative to it, so they can be compared with each other.
+pub struct SystemClock {
+    start: Instant,
+    last_vsync: AtomicF64,
+    vsync_count: AtomicU64,
+    last_wait: AtomicF64,
+}
+
+impl SystemClock {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            last_vsync: 0.0.into(),
+            vsync_count: 0.into(),
+            last_wait: 0.0.into(),
+        }
+    }
+
+    /// Seconds since the instance was created.
+    pub fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    /// Records a presented frame, which is the closest thing we have to a vsync, and returns its
+    /// system time.
+    pub fn record_vsync(&self) -> f64 {
+        let now = self.now();
+        self.last_vsync.store(now);
+        self.vsync_count.fetch_add(1, Ordering::Relaxed);
+        now
+    }
+
+    /// Returns the seconds since the last vsync, and the number of vsyncs so far.
+    pub fn since_last_vsync(&self) -> (f64, u64) {
+        let count = self.vsync_count.load(Ordering::Relaxed);
+        (self.now() - self.last_vsync.load(), count)
+    }
+
+    /// Records that xrWaitFrame just returned.
+    fn record_wait(&self) {
+        self.last_wait.store(self.now());
+    }
+
+    /// Returns the seconds since xrWaitFrame last returned.
+    fn since_last_wait(&self) -> f64 {
+        self.now() - self.last_wait.load()
+    }
+}
+
 pub struct SessionReadGuard(RwLock<ManuallyDrop<SessionData>>);
 impl SessionReadGuard {
     pub fn get(&self) -> std::sync::RwLockReadGuard<'_, ManuallyDrop<SessionData>> {
@@ -333,6 +498,13 @@ impl SessionCreateInfo {
     }
 }
 
+/// What a swapchain is used for - each purpose negotiates its format with the runtime separately.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SwapchainPurpose {
+    Scene,
+    Overlay,
+}
+
 pub struct SessionData {
     pub session: xr::Session<xr::AnyGraphics>,
     session_graphics: GraphicalSession,
@@ -347,6 +519,8 @@ pub struct SessionData {
     stage_space_reference: xr::Space,
     stage_space_adjusted: xr::Space,
     pub current_origin: vr::ETrackingUniverseOrigin,
+    /// The last format picked for each kind of swapchain, as returned by [`xr::Graphics::lower_format`].
+    negotiated_formats: Mutex<HashMap<SwapchainPurpose, i64>>,
 
     pub input_data: crate::input::InputSessionData,
     pub comp_data: crate::compositor::CompositorSessionData,
@@ -484,6 +658,7 @@ impl SessionData {
                 comp_data: Default::default(),
                 overlay_data: Default::default(),
                 current_origin,
+                negotiated_formats: Mutex::default(),
             },
             waiter,
             stream,
@@ -509,8 +684,14 @@ impl SessionData {
             .create_swapchain(info)
     }
 
-    pub fn check_format<G: GraphicsBackend>(&self, info: &mut xr::SwapchainCreateInfo<G::Api>)
-    where
+    /// Makes sure the swapchain format is supported by the runtime. Unsupported formats are
+    /// replaced with the format previously used for the same purpose if there is one, so that e.g.
+    /// an overlay can't change what format the scene falls back to.
+    pub fn check_format<G: GraphicsBackend>(
+        &self,
+        info: &mut xr::SwapchainCreateInfo<G::Api>,
+        purpose: SwapchainPurpose,
+    ) where
         for<'a> &'a GraphicalSession: TryInto<&'a Session<G::Api>, Error: std::fmt::Display>,
         <G::Api as xr::Graphics>::Format: PartialEq,
     {
@@ -524,15 +705,30 @@ impl SessionData {
             })
             .swapchain_formats;
 
+        let mut negotiated = self.negotiated_formats.lock().unwrap();
         if !formats.contains(&info.format) {
-            let new_format = formats[0];
+            let new_format = negotiated
+                .get(&purpose)
+                .map(|format| <G::Api as xr::Graphics>::raise_format(*format))
+                .filter(|format| formats.contains(format))
+                .unwrap_or(formats[0]);
             warn!(
-                "Requested to init swapchain with unsupported format {:?} - instead using {:?}",
+                "Requested to init {purpose:?} swapchain with unsupported format {:?} - instead using {:?}",
                 G::to_nice_format(info.format),
                 G::to_nice_format(new_format)
             );
             info.format = new_format;
         }
+        negotiated.insert(purpose, <G::Api as xr::Graphics>::lower_format(info.format));
+    }
+
+    #[cfg(test)]
+    pub fn negotiated_format(&self, purpose: SwapchainPurpose) -> Option<i64> {
+        self.negotiated_formats
+            .lock()
+            .unwrap()
+            .get(&purpose)
+            .copied()
     }
 
     pub fn tracking_space(&self) -> &xr::Space {
@@ -588,6 +784,8 @@ impl AtomicPath {
 pub struct HandInfo {
     path_name: &'static str,
     connected: AtomicBool,
+    /// Whether the profile is the configured default rather than one reported by the runtime.
+    assumed: AtomicBool,
     pub subaction_path: xr::Path,
     pub profile_path: AtomicPath,
     pub profile: Mutex<Option<&'static dyn InteractionProfile>>,
@@ -603,11 +801,44 @@ impl HandInfo {
         Self {
             path_name,
             connected: false.into(),
+            assumed: false.into(),
             subaction_path: instance.string_to_path(path_name).unwrap(),
             profile_path: AtomicPath(0.into()),
             profile: Mutex::default(),
         }
     }
+
+    /// Treats the hand as connected with the given profile until the runtime reports one.
+    pub fn assume_profile(
+        &self,
+        instance: &xr::Instance,
+        profile: &'static dyn InteractionProfile,
+    ) {
+        self.profile_path
+            .store(instance.string_to_path(profile.profile_path()).unwrap());
+        *self.profile.lock().unwrap() = Some(profile);
+        self.connected.store(true, Ordering::Relaxed);
+        self.assumed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Reads the profile to assume before the runtime reports one from
+/// `XRIZER_DEFAULT_INTERACTION_PROFILE`, given either as an OpenXR interaction profile path or
+/// an OpenVR controller type (e.g. `knuckles`).
+fn default_profile_from_env() -> Option<&'static dyn InteractionProfile> {
+    let value = std::env::var("XRIZER_DEFAULT_INTERACTION_PROFILE").ok()?;
+    let profiles = Profiles::get();
+    profiles
+        .profile_from_name(&value)
+        .or_else(|| {
+            profiles.profiles_iter().find(|profile| {
+                profile.properties().openvr_controller_type.to_bytes() == value.as_bytes()
+            })
+        })
+        .or_else(|| {
+            warn!("Unknown XRIZER_DEFAULT_INTERACTION_PROFILE value {value:?}");
+            None
+        })
 }
 
 #[repr(u32)]
@@ -629,6 +860,35 @@ impl TryFrom<vr::TrackedDeviceIndex_t> for Hand {
     }
 }
 
+/// Splits an OpenXR pose into its rotation and translation.
+pub fn pose_to_glam(pose: xr::Posef) -> (Quat, Vec3) {
+    let xr::Posef {
+        orientation,
+        position,
+    } = pose;
+    (
+        Quat::from_xyzw(orientation.x, orientation.y, orientation.z, orientation.w),
+        Vec3::new(position.x, position.y, position.z),
+    )
+}
+
+/// Builds an OpenXR pose from a rotation and translation.
+pub fn pose_from_glam(orientation: Quat, position: Vec3) -> xr::Posef {
+    xr::Posef {
+        orientation: xr::Quaternionf {
+            x: orientation.x,
+            y: orientation.y,
+            z: orientation.z,
+            w: orientation.w,
+        },
+        position: xr::Vector3f {
+            x: position.x,
+            y: position.y,
+            z: position.z,
+        },
+    }
+}
+
 /// Taken from: https://github.com/bitshifter/glam-rs/issues/536
 /// Decompose the rotation on to 2 parts.
 ///
@@ -662,3 +922,21 @@ fn swing_twist_decomposition(rotation: Quat, axis: Vec3) -> Option<(Quat, Quat)>
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use openxr as xr;
+
+    #[test]
+    fn enumerate_api_layers() {
+        let entry =
+            unsafe { xr::Entry::from_get_instance_proc_addr(fakexr::get_instance_proc_addr) }
+                .unwrap();
+        assert!(entry.enumerate_layers().unwrap().is_empty());
+
+        fakexr::add_api_layer(c"XR_APILAYER_xrizer_test");
+        let layers = entry.enumerate_layers().unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].layer_name, "XR_APILAYER_xrizer_test");
+    }
+}
@@ -0,0 +1,179 @@
+use log::{info, warn};
+use openxr as xr;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// How much of the difference between the extrapolated and predicted time is taken each frame, so
+/// the smoothed time doesn't drift away from the runtime's clock.
+const CORRECTION: f64 = 0.1;
+
+/// Smooths jitter out of the predicted display times poses are located at, for runtimes that
+/// deliver frames irregularly. Each frame's time is extrapolated from the last one by the median
+/// frame interval, and only nudged toward the runtime's prediction. Predictions more than a frame
+/// away from the extrapolated time (refresh rate switches, dropped frames) are taken as is.
+pub struct TimeSmoothing {
+    /// Number of recent frame intervals the median is taken over, or 0 if smoothing is disabled.
+    window: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    last_predicted: Option<i64>,
+    last_smoothed: i64,
+    intervals: VecDeque<i64>,
+}
+
+impl TimeSmoothing {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            state: Default::default(),
+        }
+    }
+
+    /// Reads the window from `XRIZER_POSE_TIME_SMOOTHING`, the number of frames to smooth over.
+    /// Smoothing is disabled if it's unset or 0.
+    pub fn from_env() -> Self {
+        let Ok(value) = std::env::var("XRIZER_POSE_TIME_SMOOTHING") else {
+            return Self::new(0);
+        };
+        let window = value.parse().unwrap_or_else(|_| {
+            warn!("Ignoring invalid XRIZER_POSE_TIME_SMOOTHING value {value:?}");
+            0
+        });
+        if window > 0 {
+            info!("Smoothing pose times over {window} frames");
+        }
+        Self::new(window)
+    }
+
+    /// Returns the time poses should be located at for a frame predicted to be displayed at
+    /// `predicted`.
+    pub fn smooth(&self, predicted: xr::Time) -> xr::Time {
+        if self.window == 0 {
+            return predicted;
+        }
+        let predicted = predicted.as_nanos();
+        let mut state = self.state.lock().unwrap();
+
+        let Some(last_predicted) = state.last_predicted.replace(predicted) else {
+            state.last_smoothed = predicted;
+            return xr::Time::from_nanos(predicted);
+        };
+
+        let interval = predicted - last_predicted;
+        if interval <= 0 {
+            // Time didn't move forward (e.g. a repeated frame), so neither do poses.
+            return xr::Time::from_nanos(state.last_smoothed);
+        }
+
+        // Until there are enough intervals for a reliable median, predictions are used as is.
+        let median = (state.intervals.len() == self.window).then(|| median(&state.intervals));
+        let smoothed = match median {
+            Some(median) => {
+                let extrapolated = state.last_smoothed + median;
+                let error = predicted - extrapolated;
+                if error.abs() > median {
+                    // Too far off to be jitter, so start over from here.
+                    state.intervals.clear();
+                    predicted
+                } else {
+                    extrapolated + (error as f64 * CORRECTION) as i64
+                }
+            }
+            None => predicted,
+        };
+
+        state.intervals.push_back(interval);
+        while state.intervals.len() > self.window {
+            state.intervals.pop_front();
+        }
+        state.last_smoothed = smoothed;
+        xr::Time::from_nanos(smoothed)
+    }
+}
+
+fn median(values: &VecDeque<i64>) -> i64 {
+    let mut sorted: Vec<i64> = values.iter().copied().collect();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimeSmoothing;
+    use openxr as xr;
+
+    const FRAME: i64 = 11_111_111;
+
+    fn deltas(times: &[i64]) -> Vec<f64> {
+        times.windows(2).map(|w| (w[1] - w[0]) as f64).collect()
+    }
+
+    fn variance(values: &[f64]) -> f64 {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+    }
+
+    #[test]
+    fn reduces_jitter() {
+        let smoothing = TimeSmoothing::new(9);
+        // Up to 2ms early or late, in a pattern that doesn't repeat every frame.
+        let jitter = [0, 2_000_000, -1_500_000, 500_000, -2_000_000, 1_000_000, 0];
+        let predicted: Vec<i64> = (0..200)
+            .map(|i| 1_000_000_000 + i * FRAME + jitter[i as usize % jitter.len()])
+            .collect();
+        let smoothed: Vec<i64> = predicted
+            .iter()
+            .map(|&t| smoothing.smooth(xr::Time::from_nanos(t)).as_nanos())
+            .collect();
+
+        // Skip the frames it takes to fill the window.
+        let before = variance(&deltas(&predicted[20..]));
+        let after = variance(&deltas(&smoothed[20..]));
+        assert!(after < before / 4.0, "{after} >= {before} / 4");
+
+        // Smoothed times stay close to the predicted ones.
+        for (predicted, smoothed) in predicted.iter().zip(&smoothed) {
+            assert!((predicted - smoothed).abs() < FRAME / 2);
+        }
+    }
+
+    #[test]
+    fn follows_large_changes() {
+        let smoothing = TimeSmoothing::new(9);
+        let mut time = 0;
+        for _ in 0..20 {
+            time += FRAME;
+            smoothing.smooth(xr::Time::from_nanos(time));
+        }
+
+        // A jump, e.g. after the app stalled, is followed immediately.
+        time += 100 * FRAME;
+        assert_eq!(
+            smoothing.smooth(xr::Time::from_nanos(time)).as_nanos(),
+            time
+        );
+
+        // So is a switch to a faster refresh rate, once the window has caught up.
+        let faster = 8_333_333;
+        let mut smoothed = 0;
+        for _ in 0..20 {
+            time += faster;
+            smoothed = smoothing.smooth(xr::Time::from_nanos(time)).as_nanos();
+        }
+        assert!((smoothed - time).abs() < 100_000, "{smoothed} != {time}");
+    }
+
+    #[test]
+    fn disabled() {
+        let smoothing = TimeSmoothing::new(0);
+        for time in [5, 20, 21, 40] {
+            assert_eq!(
+                smoothing.smooth(xr::Time::from_nanos(time)).as_nanos(),
+                time
+            );
+        }
+    }
+}
@@ -1,17 +1,27 @@
+mod follow;
+
 use crate::{
     compositor::{is_usable_swapchain, Compositor},
     graphics_backends::{supported_apis_enum, GraphicsBackend, SupportedBackend},
-    openxr_data::{GraphicalSession, OpenXrData, Session, SessionData},
+    openxr_data::{
+        pose_from_glam, pose_to_glam, GraphicalSession, Hand, OpenXrData, Session, SessionData,
+        SwapchainPurpose,
+    },
+    xr_call,
 };
-use glam::{vec3, Quat, Vec3};
-use log::{debug, trace};
+use follow::{FollowSpeeds, HmdFollow};
+use glam::Vec3;
+use log::{debug, trace, warn};
 use openvr as vr;
 use openxr as xr;
 use slotmap::{new_key_type, Key, KeyData, SecondaryMap, SlotMap};
 use std::f32::consts::{FRAC_1_SQRT_2, PI};
 use std::ffi::{c_char, c_void, CStr, CString};
 use std::sync::{Arc, Mutex, RwLock};
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Deref,
+};
 
 // OpenVR overlays are allowed to use ≥ 0
 pub const SKYBOX_Z_ORDER: i64 = -1;
@@ -25,6 +35,7 @@ pub struct OverlayMan {
     overlays: RwLock<SlotMap<OverlayKey, Overlay>>,
     key_to_overlay: RwLock<HashMap<CString, OverlayKey>>,
     skybox: RwLock<Vec<OverlayKey>>,
+    follow_speeds: FollowSpeeds,
 }
 
 impl OverlayMan {
@@ -35,10 +46,13 @@ impl OverlayMan {
             overlays: Default::default(),
             key_to_overlay: Default::default(),
             skybox: Default::default(),
+            follow_speeds: FollowSpeeds::from_env(),
         }
     }
 
-    pub fn set_skybox(&self, session: &SessionData, textures: &[vr::Texture_t]) {
+    /// Replaces the skybox with the given textures. Returns false, leaving no skybox, if any of
+    /// them has an unsupported type.
+    pub fn set_skybox(&self, session: &SessionData, textures: &[vr::Texture_t]) -> bool {
         // We don't yet follow HMD position, so the skybox needs to be
         // big enough so that the user never leaves it
         const SKYBOX_SIZE: f32 = 500.0;
@@ -47,27 +61,34 @@ impl OverlayMan {
 
         let mut overlays = self.overlays.write().unwrap();
         let mut skybox = self.skybox.write().unwrap();
+        let mut failed = false;
 
         match textures.len() {
             1..=2 => {
                 // only single equirect supported for now, ignore any 2nd one
                 let name = CString::new("__xrizer_skybox").unwrap();
                 let key = overlays.insert(Overlay::new(name.clone(), name));
+                skybox.push(key);
                 let overlay = overlays.get_mut(key).unwrap();
-                overlay.set_texture(key, session, *textures.first().unwrap());
+                failed = overlay
+                    .set_texture(key, session, *textures.first().unwrap())
+                    .is_err();
                 overlay.visible = true;
                 overlay.width = SKYBOX_SIZE; // for equirect this becomes radius
                 overlay.kind = OverlayKind::Sphere;
                 overlay.z_order = SKYBOX_Z_ORDER;
-                skybox.push(key);
             }
             6 => {
                 for (idx, texture) in textures.iter().enumerate() {
                     // 6 quads forming a cursed box
                     let name = CString::new(format!("__xrizer_skybox_{}", idx)).unwrap();
                     let key = overlays.insert(Overlay::new(name.clone(), name));
+                    skybox.push(key);
                     let overlay = overlays.get_mut(key).unwrap();
-                    overlay.set_texture(key, session, *texture);
+                    if overlay.set_texture(key, session, *texture).is_err() {
+                        failed = true;
+                        break;
+                    }
                     overlay.visible = true;
                     overlay.width = SKYBOX_SIZE * 2.0;
                     overlay.kind = OverlayKind::Quad;
@@ -105,12 +126,17 @@ impl OverlayMan {
                         vr::ETrackingUniverseOrigin::Standing,
                         QUAD_POSES[idx].into(),
                     ));
-
-                    skybox.push(key);
                 }
             }
             _ => unreachable!(),
         }
+
+        drop(overlays);
+        drop(skybox);
+        if failed {
+            self.clear_skybox();
+        }
+        !failed
     }
 
     pub fn clear_skybox(&self) {
@@ -120,18 +146,38 @@ impl OverlayMan {
         });
     }
 
+    /// Returns true if any dashboard overlay is currently shown.
+    pub fn is_dashboard_visible(&self) -> bool {
+        self.overlays
+            .read()
+            .unwrap()
+            .values()
+            .any(|overlay| overlay.dashboard && overlay.visible)
+    }
+
+    /// Returns true if the scene layer can be darkened (rather than hidden) while the dashboard is
+    /// visible.
+    pub fn can_dim_scene(&self) -> bool {
+        self.openxr
+            .enabled_extensions
+            .khr_composition_layer_color_scale_bias
+    }
+
     pub fn get_layers<'a, G: xr::Graphics>(
         &self,
         session: &'a SessionData,
         render_skybox: bool,
-    ) -> Vec<OverlayLayer<'a, G>>
+    ) -> OverlayLayers<'a, G>
     where
         for<'b> &'b AnySwapchainMap: TryInto<&'b SwapchainMap<G>, Error: std::fmt::Display>,
     {
         let mut overlays = self.overlays.write().unwrap();
         let swapchains = session.overlay_data.swapchains.lock().unwrap();
         let Some(swapchains) = swapchains.as_ref() else {
-            return Vec::new();
+            return OverlayLayers {
+                skybox: Vec::new(),
+                overlays: Vec::new(),
+            };
         };
         let swapchains: &SwapchainMap<G> = swapchains.try_into().unwrap_or_else(|e| {
             panic!(
@@ -140,6 +186,7 @@ impl OverlayMan {
             )
         });
 
+        let display_time = self.openxr.display_time.get();
         let mut layers = Vec::with_capacity(overlays.len());
         for (key, overlay) in overlays.iter_mut() {
             if !overlay.visible {
@@ -153,43 +200,47 @@ impl OverlayMan {
             };
 
             let SwapchainData { swapchain, .. } = swapchains.get(key).unwrap();
-            let space = session.get_space_for_origin(
-                overlay
-                    .transform
-                    .as_ref()
-                    .map(|(o, _)| *o)
-                    .unwrap_or(session.current_origin),
-            );
+            let (space, pose) = match overlay.hmd_transform {
+                Some(transform) => overlay.hmd_relative_pose(session, display_time, transform),
+                None => (
+                    session.get_space_for_origin(
+                        overlay
+                            .transform
+                            .as_ref()
+                            .map(|(o, _)| *o)
+                            .unwrap_or(session.current_origin),
+                    ),
+                    overlay
+                        .transform
+                        .as_ref()
+                        .map(|(_, t)| (*t).into())
+                        .unwrap_or(xr::Posef {
+                            position: xr::Vector3f {
+                                x: 0.0,
+                                y: 0.0,
+                                z: -0.5,
+                            },
+                            orientation: xr::Quaternionf::IDENTITY,
+                        }),
+                ),
+            };
 
             trace!("overlay rect: {:#?}", rect);
 
-            let pose = overlay
-                .transform
-                .as_ref()
-                .map(|(_, t)| (*t).into())
-                .unwrap_or(xr::Posef {
-                    position: xr::Vector3f {
-                        x: 0.0,
-                        y: 0.0,
-                        z: -0.5,
-                    },
-                    orientation: xr::Quaternionf::IDENTITY,
-                });
-
             macro_rules! layer_init {
-                ($ty:ident) => {{
+                ($ty:ident, $eye_visibility:expr, $rect:expr) => {{
                     $ty::new()
                         .space(space)
                         .layer_flags(
                             xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
                                 | xr::CompositionLayerFlags::UNPREMULTIPLIED_ALPHA,
                         )
-                        .eye_visibility(xr::EyeVisibility::BOTH)
+                        .eye_visibility($eye_visibility)
                         .sub_image(
                             xr::SwapchainSubImage::new()
                                 .image_array_index(vr::EVREye::Left as u32)
                                 .swapchain(swapchain)
-                                .image_rect(rect),
+                                .image_rect($rect),
                         )
                 }};
             }
@@ -211,87 +262,95 @@ impl OverlayMan {
                 }}
             }
 
-            match overlay.kind {
-                OverlayKind::Quad => {
-                    use xr::CompositionLayerQuad;
-                    let layer = layer_init!(CompositionLayerQuad)
-                        .pose(pose)
-                        .size(xr::Extent2Df {
-                            width: overlay.width,
-                            height: rect.extent.height as f32 * overlay.width
-                                / rect.extent.width as f32,
-                        });
-
-                    let layer = lifetime_extend!(CompositionLayerQuad, layer);
-                    let mut layer = OverlayLayer::from(OverlayLayerInner::Quad(layer));
-                    overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
-                    layers.push((overlay.z_order, layer));
-                }
-                // SetOverlayCurvature checks for khr_composition_layer_cylinder
-                OverlayKind::Curved { curvature } => {
-                    let radius = overlay.width / (2.0 * PI * curvature);
-                    let pos = vec3(pose.position.x, pose.position.y, pose.position.z);
-                    let rot = Quat::from_xyzw(
-                        pose.orientation.x,
-                        pose.orientation.y,
-                        pose.orientation.z,
-                        pose.orientation.w,
-                    );
-
-                    let center = pos + rot.mul_vec3(Vec3::Z * radius);
-                    let angle = 2.0 * (overlay.width / (2.0 * radius));
-
-                    use xr::CompositionLayerCylinderKHR;
-                    let layer = layer_init!(CompositionLayerCylinderKHR)
-                        .radius(radius)
-                        .central_angle(angle)
-                        .aspect_ratio(rect.extent.height as f32 / rect.extent.width as f32)
-                        .pose(xr::Posef {
-                            orientation: pose.orientation,
-                            position: xr::Vector3f {
-                                x: center.x,
-                                y: center.y,
-                                z: center.z,
-                            },
-                        });
-
-                    let layer = lifetime_extend!(CompositionLayerCylinderKHR, layer);
-                    let mut layer = OverlayLayer::from(OverlayLayerInner::Cylinder(layer));
-                    overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
-                    layers.push((overlay.z_order, layer));
-                }
-                // SetSkyboxOverride checks for khr_composition_layer_equirect2
-                OverlayKind::Sphere => {
-                    const HORIZONTAL_RAD: f32 = 2.0 * PI;
-                    const VERTICAL_RAD_HIGH: f32 = 0.5 * PI;
-                    const VERTICAL_RAD_LOW: f32 = -0.5 * PI;
-
-                    use xr::CompositionLayerEquirect2KHR;
-                    let layer = layer_init!(CompositionLayerEquirect2KHR)
-                        .radius(overlay.width)
-                        .central_horizontal_angle(HORIZONTAL_RAD)
-                        .upper_vertical_angle(VERTICAL_RAD_HIGH)
-                        .lower_vertical_angle(VERTICAL_RAD_LOW)
-                        .pose(pose);
-
-                    let layer = lifetime_extend!(CompositionLayerEquirect2KHR, layer);
-                    let mut layer = OverlayLayer::from(OverlayLayerInner::Equirect2(layer));
-                    overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
-                    layers.push((overlay.z_order, layer));
+            // Side by side overlays get a layer for each eye.
+            for (eye_visibility, rect) in overlay.eye_rects(rect) {
+                match overlay.kind {
+                    OverlayKind::Quad => {
+                        use xr::CompositionLayerQuad;
+                        let layer = layer_init!(CompositionLayerQuad, eye_visibility, rect)
+                            .pose(pose)
+                            .size(xr::Extent2Df {
+                                width: overlay.width,
+                                height: rect.extent.height as f32 * overlay.width
+                                    / rect.extent.width as f32,
+                            });
+
+                        let layer = lifetime_extend!(CompositionLayerQuad, layer);
+                        let mut layer = OverlayLayer::from(OverlayLayerInner::Quad(layer));
+                        overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
+                        layers.push(((overlay.dashboard_order(), overlay.z_order), layer));
+                    }
+                    // SetOverlayCurvature checks for khr_composition_layer_cylinder
+                    OverlayKind::Curved { curvature } => {
+                        let radius = overlay.width / (2.0 * PI * curvature);
+                        let (rot, pos) = pose_to_glam(pose);
+
+                        let center = pos + rot.mul_vec3(Vec3::Z * radius);
+                        let angle = 2.0 * (overlay.width / (2.0 * radius));
+
+                        use xr::CompositionLayerCylinderKHR;
+                        let layer = layer_init!(CompositionLayerCylinderKHR, eye_visibility, rect)
+                            .radius(radius)
+                            .central_angle(angle)
+                            .aspect_ratio(rect.extent.height as f32 / rect.extent.width as f32)
+                            .pose(pose_from_glam(rot, center));
+
+                        let layer = lifetime_extend!(CompositionLayerCylinderKHR, layer);
+                        let mut layer = OverlayLayer::from(OverlayLayerInner::Cylinder(layer));
+                        overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
+                        layers.push(((overlay.dashboard_order(), overlay.z_order), layer));
+                    }
+                    // SetSkyboxOverride checks for khr_composition_layer_equirect2
+                    OverlayKind::Sphere => {
+                        const HORIZONTAL_RAD: f32 = 2.0 * PI;
+                        const VERTICAL_RAD_HIGH: f32 = 0.5 * PI;
+                        const VERTICAL_RAD_LOW: f32 = -0.5 * PI;
+
+                        use xr::CompositionLayerEquirect2KHR;
+                        let layer = layer_init!(CompositionLayerEquirect2KHR, eye_visibility, rect)
+                            .radius(overlay.width)
+                            .central_horizontal_angle(HORIZONTAL_RAD)
+                            .upper_vertical_angle(VERTICAL_RAD_HIGH)
+                            .lower_vertical_angle(VERTICAL_RAD_LOW)
+                            .pose(pose);
+
+                        let layer = lifetime_extend!(CompositionLayerEquirect2KHR, layer);
+                        let mut layer = OverlayLayer::from(OverlayLayerInner::Equirect2(layer));
+                        overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
+                        layers.push(((overlay.dashboard_order(), overlay.z_order), layer));
+                    }
                 }
             }
         }
 
-        // Sort by z_order asc
+        // Sort around the dashboard, then by z_order asc
         layers.sort_by(|a, b| a.0.cmp(&b.0));
 
-        let sorted_layers: Vec<OverlayLayer<_>> = layers.into_iter().map(|(_, l)| l).collect();
+        let (skybox, overlays): (Vec<_>, Vec<_>) = layers
+            .into_iter()
+            .partition(|((_, z_order), _)| *z_order == SKYBOX_Z_ORDER);
+        let layers = OverlayLayers {
+            skybox: skybox.into_iter().map(|(_, l)| l).collect(),
+            overlays: overlays.into_iter().map(|(_, l)| l).collect(),
+        };
 
-        trace!("returning {} layers", sorted_layers.len());
-        sorted_layers
+        trace!(
+            "returning {} skybox layers and {} overlay layers",
+            layers.skybox.len(),
+            layers.overlays.len()
+        );
+        layers
     }
 }
 
+/// The layers OverlayMan contributes to a frame. The skybox goes behind the scene, the rest of the
+/// overlays in front of it.
+pub struct OverlayLayers<'a, G: xr::Graphics> {
+    pub skybox: Vec<OverlayLayer<'a, G>>,
+    /// Sorted back to front: behind the dashboard, the dashboard, then in front of it.
+    pub overlays: Vec<OverlayLayer<'a, G>>,
+}
+
 pub struct OverlayLayer<'a, G: xr::Graphics> {
     /// Only ever None during next_chain_insert
     layer: Option<OverlayLayerInner<'a, G>>,
@@ -412,6 +471,15 @@ enum OverlayKind {
     Sphere,
 }
 
+/// Where an overlay is composited relative to the dashboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum DashboardOrder {
+    Behind,
+    Dashboard,
+    /// Overlays shown in the dashboard (VisibleInDashboard) draw over it.
+    InFront,
+}
+
 struct Overlay {
     key: CString,
     name: CString,
@@ -419,12 +487,31 @@ struct Overlay {
     alpha: Option<f32>,
     width: f32,
     visible: bool,
+    /// Created via CreateDashboardOverlay
+    dashboard: bool,
     kind: OverlayKind,
     z_order: i64,
     bounds: vr::VRTextureBounds_t,
     transform: Option<(vr::ETrackingUniverseOrigin, vr::HmdMatrix34_t)>,
+    /// Transform relative to the HMD, set instead of `transform` by
+    /// SetOverlayTransformTrackedDeviceRelative.
+    hmd_transform: Option<vr::HmdMatrix34_t>,
+    /// Set if the overlay has a follow speed, to lag it behind head turns while it's HMD relative.
+    follow: Option<HmdFollow>,
     compositor: Option<SupportedBackend>,
     rect: Option<xr::Rect2Di>,
+    /// Set VROverlayFlags, each flag stored as its bit
+    flags: u32,
+    input_method: vr::VROverlayInputMethod,
+    /// Size of the overlay in mouse coordinates
+    mouse_scale: vr::HmdVector2_t,
+    events: VecDeque<OverlayEvent>,
+}
+
+struct OverlayEvent {
+    ty: vr::EVREventType,
+    index: vr::TrackedDeviceIndex_t,
+    data: vr::VREvent_Mouse_t,
 }
 
 impl Overlay {
@@ -435,6 +522,7 @@ impl Overlay {
             alpha: None,
             width: 1.0,
             visible: false,
+            dashboard: false,
             kind: OverlayKind::Quad,
             z_order: 0,
             bounds: vr::VRTextureBounds_t {
@@ -444,20 +532,202 @@ impl Overlay {
                 vMax: 1.0,
             },
             transform: None,
+            hmd_transform: None,
+            follow: None,
             compositor: None,
             rect: None,
+            flags: 0,
+            input_method: vr::VROverlayInputMethod::None,
+            mouse_scale: vr::HmdVector2_t { v: [1.0, 1.0] },
+            events: VecDeque::new(),
+        }
+    }
+
+    fn flag(&self, flag: vr::VROverlayFlags) -> bool {
+        self.flags & flag as u32 != 0
+    }
+
+    /// The part of the overlay's texture shown to each eye. Side by side overlays show each eye
+    /// one half of it.
+    fn eye_rects(&self, rect: xr::Rect2Di) -> Vec<(xr::EyeVisibility, xr::Rect2Di)> {
+        let crossed = self.flag(vr::VROverlayFlags::SideBySide_Crossed);
+        if !crossed && !self.flag(vr::VROverlayFlags::SideBySide_Parallel) {
+            return vec![(xr::EyeVisibility::BOTH, rect)];
+        }
+
+        let width = rect.extent.width / 2;
+        let half = |x| xr::Rect2Di {
+            offset: xr::Offset2Di {
+                x: rect.offset.x + x,
+                y: rect.offset.y,
+            },
+            extent: xr::Extent2Di {
+                width,
+                height: rect.extent.height,
+            },
+        };
+        // Crossed overlays have the left eye's image on the right.
+        let (left, right) = match crossed {
+            false => (half(0), half(width)),
+            true => (half(width), half(0)),
+        };
+        vec![
+            (xr::EyeVisibility::LEFT, left),
+            (xr::EyeVisibility::RIGHT, right),
+        ]
+    }
+
+    fn dashboard_order(&self) -> DashboardOrder {
+        if self.dashboard {
+            DashboardOrder::Dashboard
+        } else if self.flag(vr::VROverlayFlags::VisibleInDashboard) {
+            DashboardOrder::InFront
+        } else {
+            DashboardOrder::Behind
+        }
+    }
+
+    /// Height in meters, from the aspect ratio of the texture - or of the mouse scale, if no
+    /// texture has been set yet.
+    fn height(&self) -> f32 {
+        match self.rect {
+            Some(rect) if rect.extent.width > 0 => {
+                rect.extent.height as f32 * self.width / rect.extent.width as f32
+            }
+            _ => self.mouse_scale.v[1] * self.width / self.mouse_scale.v[0],
+        }
+    }
+
+    /// Where an HMD relative overlay is placed: locked to the view, or in the tracking space with
+    /// its orientation lagging behind the HMD's if it has a follow speed.
+    fn hmd_relative_pose<'a>(
+        &mut self,
+        session: &'a SessionData,
+        time: xr::Time,
+        transform: vr::HmdMatrix34_t,
+    ) -> (&'a xr::Space, xr::Posef) {
+        let relative: xr::Posef = transform.into();
+        let Some(follow) = self.follow.as_mut() else {
+            return (&session.view_space, relative);
+        };
+
+        let space = session.tracking_space();
+        let hmd = match xr_call!("xrLocateSpace", session.view_space.locate(space, time)) {
+            Ok(location)
+                if location.location_flags.contains(
+                    xr::SpaceLocationFlags::ORIENTATION_VALID
+                        | xr::SpaceLocationFlags::POSITION_VALID,
+                ) =>
+            {
+                location.pose
+            }
+            _ => return (&session.view_space, relative),
+        };
+
+        let (hmd_orientation, hmd_position) = pose_to_glam(hmd);
+        let (relative_orientation, relative_position) = pose_to_glam(relative);
+        let orientation = follow.update(time, hmd_orientation);
+        (
+            space,
+            pose_from_glam(
+                orientation * relative_orientation,
+                hmd_position + orientation * relative_position,
+            ),
+        )
+    }
+
+    /// Intersects a ray with the overlay. Only flat overlays with an absolute transform in the
+    /// same tracking universe as the ray can be hit.
+    fn intersect(
+        &self,
+        params: &vr::VROverlayIntersectionParams_t,
+    ) -> Option<vr::VROverlayIntersectionResults_t> {
+        if !matches!(self.kind, OverlayKind::Quad) {
+            return None;
+        }
+        let (origin, transform) = self.transform.as_ref()?;
+        if *origin != params.eOrigin {
+            return None;
+        }
+
+        let (rot, pos) = pose_to_glam((*transform).into());
+
+        // Work in overlay space, where the overlay lies on the z = 0 plane.
+        let source = rot.inverse() * (Vec3::from_array(params.vSource.v) - pos);
+        let direction = (rot.inverse() * Vec3::from_array(params.vDirection.v)).normalize_or_zero();
+        if direction.z.abs() < f32::EPSILON {
+            return None;
+        }
+        let distance = -source.z / direction.z;
+        if distance < 0.0 {
+            return None;
+        }
+
+        let hit = source + direction * distance;
+        // UVs are in GL space, the bottom left of the overlay is 0,0
+        let uvs = [hit.x / self.width + 0.5, hit.y / self.height() + 0.5];
+        if !uvs.iter().all(|c| (0.0..=1.0).contains(c)) {
+            return None;
+        }
+
+        Some(vr::VROverlayIntersectionResults_t {
+            vPoint: vr::HmdVector3_t {
+                v: (pos + rot * hit).to_array(),
+            },
+            vNormal: vr::HmdVector3_t {
+                v: (rot * Vec3::Z).to_array(),
+            },
+            vUVs: vr::HmdVector2_t { v: uvs },
+            fDistance: distance,
+        })
+    }
+
+    /// Queues a mouse move to the given UVs, scaled to mouse coordinates. Returns false if the
+    /// overlay doesn't take mouse input. A move that's still queued for the same device is
+    /// replaced, so the queue doesn't grow if the app never polls its events.
+    fn queue_mouse_move(
+        &mut self,
+        device: vr::TrackedDeviceIndex_t,
+        uvs: vr::HmdVector2_t,
+    ) -> bool {
+        if self.input_method != vr::VROverlayInputMethod::Mouse {
+            return false;
         }
+
+        let data = vr::VREvent_Mouse_t {
+            x: uvs.v[0] * self.mouse_scale.v[0],
+            y: uvs.v[1] * self.mouse_scale.v[1],
+            ..Default::default()
+        };
+        match self
+            .events
+            .iter_mut()
+            .find(|event| event.ty == vr::EVREventType::MouseMove && event.index == device)
+        {
+            Some(event) => event.data = data,
+            None => self.events.push_back(OverlayEvent {
+                ty: vr::EVREventType::MouseMove,
+                index: device,
+                data,
+            }),
+        }
+        true
     }
 
+    /// Copies the texture into the overlay's swapchain, failing if the texture type isn't supported.
     pub fn set_texture(
         &mut self,
         key: OverlayKey,
         session_data: &SessionData,
         texture: vr::Texture_t,
-    ) {
-        let backend = self
-            .compositor
-            .get_or_insert_with(|| SupportedBackend::new(&texture, self.bounds));
+    ) -> Result<(), vr::EVROverlayError> {
+        let backend = match &mut self.compositor {
+            Some(backend) => backend,
+            backend => backend.insert(
+                SupportedBackend::new(&texture, self.bounds)
+                    .ok_or(vr::EVROverlayError::InvalidTexture)?,
+            ),
+        };
 
         #[macros::any_graphics(SupportedBackend)]
         fn create_swapchain_map<G: GraphicsBackend>(_: &G) -> AnySwapchainMap
@@ -493,17 +763,40 @@ impl Overlay {
                 );
             });
             let b_texture = G::get_texture(&texture);
-            let tex_swapchain_info =
+            let protected = overlay.flag(vr::VROverlayFlags::ProtectedContent);
+            let mut tex_swapchain_info =
                 backend.swapchain_info_for_texture(b_texture, overlay.bounds, texture.eColorSpace);
+            if protected {
+                tex_swapchain_info.create_flags |= xr::SwapchainCreateFlags::PROTECTED_CONTENT;
+            }
             let mut create_swapchain = || {
                 let mut info = backend.swapchain_info_for_texture(
                     b_texture,
                     overlay.bounds,
                     texture.eColorSpace,
                 );
+                info.create_flags = tex_swapchain_info.create_flags;
                 let initial_format = info.format;
-                session_data.check_format::<G>(&mut info);
-                let swapchain = session_data.create_swapchain(&info).unwrap();
+                session_data.check_format::<G>(&mut info, SwapchainPurpose::Overlay);
+                let swapchain = session_data
+                    .create_swapchain(&info)
+                    .or_else(|err| {
+                        if !protected || err != xr::sys::Result::ERROR_FEATURE_UNSUPPORTED {
+                            return Err(err);
+                        }
+                        // Keep the protected flag in the stored info, so the swapchain isn't
+                        // recreated on every texture update.
+                        warn!(
+                            "Protected swapchains unsupported, overlay {:?} won't be protected",
+                            overlay.name
+                        );
+                        info.create_flags =
+                            info.create_flags & !xr::SwapchainCreateFlags::PROTECTED_CONTENT;
+                        let swapchain = session_data.create_swapchain(&info);
+                        info.create_flags |= xr::SwapchainCreateFlags::PROTECTED_CONTENT;
+                        swapchain
+                    })
+                    .unwrap();
                 let images = swapchain
                     .enumerate_images()
                     .expect("Couldn't enumerate swapchain images");
@@ -546,6 +839,7 @@ impl Overlay {
             extent,
             offset: xr::Offset2Di::default(),
         });
+        Ok(())
     }
 }
 
@@ -578,8 +872,11 @@ impl vr::IVROverlay027_Interface for OverlayMan {
             return vr::EVROverlayError::InvalidParameter;
         }
 
+        let mut overlay = Overlay::new(key.into(), name.into());
+        overlay.follow = self.follow_speeds.get(key).map(HmdFollow::new);
+
         let mut overlays = self.overlays.write().unwrap();
-        let ret_key = overlays.insert(Overlay::new(key.into(), name.into()));
+        let ret_key = overlays.insert(overlay);
         let mut key_to_overlay = self.key_to_overlay.write().unwrap();
         key_to_overlay.insert(key.into(), ret_key);
 
@@ -675,7 +972,9 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         } else {
             let texture = unsafe { texture.read() };
             let key = OverlayKey::from(KeyData::from_ffi(handle));
-            overlay.set_texture(key, &self.openxr.session_data.get(), texture);
+            if let Err(e) = overlay.set_texture(key, &self.openxr.session_data.get(), texture) {
+                return e;
+            }
             debug!("set overlay texture for {:?}", overlay.name);
             vr::EVROverlayError::None
         }
@@ -740,8 +1039,16 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     fn GetPrimaryDashboardDevice(&self) -> vr::TrackedDeviceIndex_t {
         todo!()
     }
-    fn ShowDashboard(&self, _: *const c_char) {
-        todo!()
+    fn ShowDashboard(&self, overlay_to_show: *const c_char) {
+        let mut overlays = self.overlays.write().unwrap();
+        let to_show =
+            (!overlay_to_show.is_null()).then(|| unsafe { CStr::from_ptr(overlay_to_show) });
+        for overlay in overlays.values_mut().filter(|o| o.dashboard) {
+            if to_show.is_none_or(|key| overlay.key.as_c_str() == key) {
+                debug!("showing dashboard overlay {:?}", overlay.name);
+                overlay.visible = true;
+            }
+        }
     }
     fn GetDashboardOverlaySceneProcess(
         &self,
@@ -757,20 +1064,44 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     ) -> vr::EVROverlayError {
         todo!()
     }
-    fn IsActiveDashboardOverlay(&self, _: vr::VROverlayHandle_t) -> bool {
-        todo!()
+    fn IsActiveDashboardOverlay(&self, handle: vr::VROverlayHandle_t) -> bool {
+        let overlays = self.overlays.read().unwrap();
+        overlays
+            .get(OverlayKey::from(KeyData::from_ffi(handle)))
+            .is_some_and(|overlay| overlay.dashboard && overlay.visible)
     }
     fn IsDashboardVisible(&self) -> bool {
-        false
+        self.is_dashboard_visible()
     }
     fn CreateDashboardOverlay(
         &self,
-        _: *const c_char,
-        _: *const c_char,
-        _: *mut vr::VROverlayHandle_t,
-        _: *mut vr::VROverlayHandle_t,
+        key: *const c_char,
+        name: *const c_char,
+        main_handle: *mut vr::VROverlayHandle_t,
+        thumbnail_handle: *mut vr::VROverlayHandle_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        if main_handle.is_null() || thumbnail_handle.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+
+        let ret = self.CreateOverlay(key, name, main_handle);
+        if ret != vr::EVROverlayError::None {
+            return ret;
+        }
+
+        let thumbnail_key = {
+            let mut key = unsafe { CStr::from_ptr(key) }.to_bytes().to_vec();
+            key.extend_from_slice(b".thumbnail");
+            CString::new(key).unwrap()
+        };
+        let ret = self.CreateOverlay(thumbnail_key.as_ptr(), name, thumbnail_handle);
+        if ret != vr::EVROverlayError::None {
+            return ret;
+        }
+
+        get_overlay!(self, unsafe { main_handle.read() }, mut overlay);
+        overlay.dashboard = true;
+        vr::EVROverlayError::None
     }
     fn GetOverlayTextureSize(
         &self,
@@ -861,47 +1192,115 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
     fn ComputeOverlayIntersection(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *const vr::VROverlayIntersectionParams_t,
-        _: *mut vr::VROverlayIntersectionResults_t,
+        handle: vr::VROverlayHandle_t,
+        params: *const vr::VROverlayIntersectionParams_t,
+        results: *mut vr::VROverlayIntersectionResults_t,
     ) -> bool {
-        todo!()
+        if params.is_null() || results.is_null() {
+            return false;
+        }
+        let overlays = self.overlays.read().unwrap();
+        let Some(overlay) = overlays.get(OverlayKey::from(KeyData::from_ffi(handle))) else {
+            return false;
+        };
+
+        match overlay.intersect(unsafe { &*params }) {
+            Some(hit) => {
+                unsafe { results.write(hit) };
+                true
+            }
+            None => false,
+        }
     }
     fn SetOverlayMouseScale(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *const vr::HmdVector2_t,
+        handle: vr::VROverlayHandle_t,
+        scale: *const vr::HmdVector2_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        if scale.is_null() {
+            vr::EVROverlayError::InvalidParameter
+        } else {
+            overlay.mouse_scale = unsafe { scale.read() };
+            debug!(
+                "set overlay {:?} mouse scale to {:?}",
+                overlay.name, overlay.mouse_scale.v
+            );
+            vr::EVROverlayError::None
+        }
     }
     fn GetOverlayMouseScale(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::HmdVector2_t,
+        handle: vr::VROverlayHandle_t,
+        scale: *mut vr::HmdVector2_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if scale.is_null() {
+            vr::EVROverlayError::InvalidParameter
+        } else {
+            unsafe { scale.write(overlay.mouse_scale) };
+            vr::EVROverlayError::None
+        }
     }
     fn SetOverlayInputMethod(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::VROverlayInputMethod,
+        handle: vr::VROverlayHandle_t,
+        method: vr::VROverlayInputMethod,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        debug!("set overlay {:?} input method to {method:?}", overlay.name);
+        overlay.input_method = method;
+        vr::EVROverlayError::None
     }
     fn GetOverlayInputMethod(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::VROverlayInputMethod,
+        handle: vr::VROverlayHandle_t,
+        method: *mut vr::VROverlayInputMethod,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if method.is_null() {
+            vr::EVROverlayError::InvalidParameter
+        } else {
+            unsafe { method.write(overlay.input_method) };
+            vr::EVROverlayError::None
+        }
     }
     fn PollNextOverlayEvent(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::VREvent_t,
-        _: u32,
+        handle: vr::VROverlayHandle_t,
+        out: *mut vr::VREvent_t,
+        size: u32,
     ) -> bool {
-        todo!()
+        if out.is_null() {
+            return false;
+        }
+        let mut overlays = self.overlays.write().unwrap();
+        let Some(overlay) = overlays.get_mut(OverlayKey::from(KeyData::from_ffi(handle))) else {
+            return false;
+        };
+
+        const MIN_MOUSE_EVENT_SIZE: usize = std::mem::offset_of!(vr::VREvent_t, data)
+            + std::mem::offset_of!(vr::VREvent_Mouse_t, button)
+            + std::mem::size_of::<u32>();
+        if (size as usize) < MIN_MOUSE_EVENT_SIZE {
+            warn!("PollNextOverlayEvent: Provided event struct size ({size}) is smaller than required ({MIN_MOUSE_EVENT_SIZE}).");
+            return false;
+        }
+
+        let Some(event) = overlay.events.pop_front() else {
+            return false;
+        };
+        // VREvent_t and VREvent_Mouse_t differ in size between OpenVR versions, so only write
+        // the fields every version has through raw pointers.
+        unsafe {
+            (&raw mut (*out).eventType).write(event.ty as u32);
+            (&raw mut (*out).trackedDeviceIndex).write(event.index);
+            (&raw mut (*out).eventAgeSeconds).write(0.0);
+            (&raw mut (*out).data.mouse.x).write(event.data.x);
+            (&raw mut (*out).data.mouse.y).write(event.data.y);
+            (&raw mut (*out).data.mouse.button).write(event.data.button);
+        }
+        true
     }
     fn WaitFrameSync(&self, _: u32) -> vr::EVROverlayError {
         todo!()
@@ -961,19 +1360,45 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
     fn GetOverlayTransformTrackedDeviceRelative(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::TrackedDeviceIndex_t,
-        _: *mut vr::HmdMatrix34_t,
+        handle: vr::VROverlayHandle_t,
+        device: *mut vr::TrackedDeviceIndex_t,
+        transform: *mut vr::HmdMatrix34_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if device.is_null() || transform.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        let Some(hmd_transform) = overlay.hmd_transform else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+
+        unsafe {
+            device.write(vr::k_unTrackedDeviceIndex_Hmd);
+            transform.write(hmd_transform);
+        }
+        vr::EVROverlayError::None
     }
     fn SetOverlayTransformTrackedDeviceRelative(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::TrackedDeviceIndex_t,
-        _: *const vr::HmdMatrix34_t,
+        handle: vr::VROverlayHandle_t,
+        device: vr::TrackedDeviceIndex_t,
+        transform: *const vr::HmdMatrix34_t,
     ) -> vr::EVROverlayError {
-        crate::warn_unimplemented!("SetOverlayTransformTrackedDeviceRelative");
+        get_overlay!(self, handle, mut overlay);
+        if transform.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        if device != vr::k_unTrackedDeviceIndex_Hmd {
+            crate::warn_unimplemented!("SetOverlayTransformTrackedDeviceRelative for controllers");
+            return vr::EVROverlayError::None;
+        }
+
+        overlay.transform = None;
+        overlay.hmd_transform = Some(unsafe { transform.read() });
+        debug!(
+            "set overlay transform relative to the HMD for {:?}",
+            overlay.name
+        );
         vr::EVROverlayError::None
     }
     fn GetOverlayTransformAbsolute(
@@ -995,6 +1420,7 @@ impl vr::IVROverlay027_Interface for OverlayMan {
             vr::EVROverlayError::InvalidParameter
         } else {
             overlay.transform = Some((origin, unsafe { transform.read() }));
+            overlay.hmd_transform = None;
             debug!(
                 "set overlay transform origin to {origin:?} for {:?}",
                 overlay.name
@@ -1165,19 +1591,31 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
     fn GetOverlayFlag(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::VROverlayFlags,
-        _: *mut bool,
+        handle: vr::VROverlayHandle_t,
+        flag: vr::VROverlayFlags,
+        enabled: *mut bool,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if enabled.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        unsafe { *enabled = overlay.flag(flag) };
+        vr::EVROverlayError::None
     }
     fn SetOverlayFlag(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::VROverlayFlags,
-        _: bool,
+        handle: vr::VROverlayHandle_t,
+        flag: vr::VROverlayFlags,
+        enabled: bool,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        if enabled {
+            overlay.flags |= flag as u32;
+        } else {
+            overlay.flags &= !(flag as u32);
+        }
+        debug!("overlay {:?} flag {flag:?} = {enabled}", overlay.name);
+        vr::EVROverlayError::None
     }
     fn GetOverlayRenderingPid(&self, _: vr::VROverlayHandle_t) -> u32 {
         todo!()
@@ -1393,9 +1831,225 @@ impl vr::IVROverlay018On019 for OverlayMan {
 impl vr::IVROverlay016On018 for OverlayMan {
     fn HandleControllerOverlayInteractionAsMouse(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::TrackedDeviceIndex_t,
+        handle: vr::VROverlayHandle_t,
+        device: vr::TrackedDeviceIndex_t,
     ) -> bool {
-        todo!()
+        let Ok(hand) = Hand::try_from(device) else {
+            return false;
+        };
+        let mut overlays = self.overlays.write().unwrap();
+        let Some(overlay) = overlays.get_mut(OverlayKey::from(KeyData::from_ffi(handle))) else {
+            return false;
+        };
+        if overlay.input_method != vr::VROverlayInputMethod::Mouse {
+            return false;
+        }
+        let Some((origin, _)) = overlay.transform else {
+            return false;
+        };
+
+        let Some(pose) = self
+            .openxr
+            .input
+            .get()
+            .and_then(|input| input.get_controller_pose(hand, Some(origin)))
+            .filter(|pose| pose.bPoseIsValid)
+        else {
+            return false;
+        };
+
+        // Controllers point down their -Z axis.
+        let m = pose.mDeviceToAbsoluteTracking.m;
+        let params = vr::VROverlayIntersectionParams_t {
+            vSource: vr::HmdVector3_t {
+                v: [m[0][3], m[1][3], m[2][3]],
+            },
+            vDirection: vr::HmdVector3_t {
+                v: [-m[0][2], -m[1][2], -m[2][2]],
+            },
+            eOrigin: origin,
+        };
+        overlay
+            .intersect(&params)
+            .is_some_and(|hit| overlay.queue_mouse_move(device, hit.vUVs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openxr_data::RealOpenXrData;
+    use glam::{vec3, Quat};
+    use vr::IVROverlay027_Interface;
+
+    #[test]
+    fn hmd_follow() {
+        let xr = RealOpenXrData::new_fake();
+        xr.set_tracking_space(vr::ETrackingUniverseOrigin::Seated);
+        let mut overlays = OverlayMan::new(xr.clone());
+        overlays.follow_speeds = FollowSpeeds::parse("hud=2").unwrap();
+
+        let mut handle = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"hud".as_ptr(), c"HUD".as_ptr(), &mut handle),
+            vr::EVROverlayError::None
+        );
+        let transform: vr::HmdMatrix34_t = xr::Posef {
+            orientation: xr::Quaternionf::IDENTITY,
+            position: xr::Vector3f {
+                x: 0.0,
+                y: 0.0,
+                z: -1.0,
+            },
+        }
+        .into();
+        assert_eq!(
+            overlays.SetOverlayTransformTrackedDeviceRelative(
+                handle,
+                vr::k_unTrackedDeviceIndex_Hmd,
+                &transform
+            ),
+            vr::EVROverlayError::None
+        );
+
+        let session = xr.session_data.get();
+        let mut overlays = overlays.overlays.write().unwrap();
+        let overlay = overlays
+            .get_mut(OverlayKey::from(KeyData::from_ffi(handle)))
+            .unwrap();
+        let mut pose_at = |seconds: f64| {
+            let time = xr::Time::from_nanos((seconds * 1e9) as i64);
+            let (space, pose) = overlay.hmd_relative_pose(&session, time, transform);
+            assert!(std::ptr::eq(space, session.tracking_space()));
+            pose_to_glam(pose)
+        };
+
+        let (orientation, position) = pose_at(1.0);
+        assert!(orientation.abs_diff_eq(Quat::IDENTITY, 1e-6));
+        assert!(position.abs_diff_eq(vec3(0.0, 0.0, -1.0), 1e-6));
+
+        // Half a second after the HMD turns, the overlay has closed 1 - e^-(2 * 0.5) of the turn.
+        let turned = Quat::from_rotation_y(PI / 2.0);
+        fakexr::set_view_pose(pose_from_glam(turned, Vec3::ZERO));
+        let (orientation, position) = pose_at(1.5);
+        let expected = Quat::from_rotation_y(PI / 2.0 * (1.0 - (-1.0f32).exp()));
+        assert!(orientation.angle_between(expected) < 1e-4, "{orientation}");
+        assert!(position.abs_diff_eq(expected * vec3(0.0, 0.0, -1.0), 1e-4));
+
+        // It catches up eventually.
+        let (orientation, _) = pose_at(10.0);
+        assert!(orientation.angle_between(turned) < 1e-3, "{orientation}");
+    }
+
+    #[test]
+    fn mouse_scale() {
+        let xr = RealOpenXrData::new_fake();
+        let overlays = OverlayMan::new(xr);
+
+        let mut handle = 0;
+        assert_eq!(
+            overlays.CreateOverlay(c"menu".as_ptr(), c"Menu".as_ptr(), &mut handle),
+            vr::EVROverlayError::None
+        );
+        let transform: vr::HmdMatrix34_t = xr::Posef {
+            orientation: xr::Quaternionf::IDENTITY,
+            position: xr::Vector3f {
+                x: 0.0,
+                y: 0.0,
+                z: -1.0,
+            },
+        }
+        .into();
+        assert_eq!(
+            overlays.SetOverlayTransformAbsolute(
+                handle,
+                vr::ETrackingUniverseOrigin::Standing,
+                &transform
+            ),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(
+            overlays.SetOverlayWidthInMeters(handle, 2.0),
+            vr::EVROverlayError::None
+        );
+        // No texture is set, so the overlay is 2m x 1m from the mouse scale's aspect ratio.
+        let scale = vr::HmdVector2_t { v: [200.0, 100.0] };
+        assert_eq!(
+            overlays.SetOverlayMouseScale(handle, &scale),
+            vr::EVROverlayError::None
+        );
+        let mut scale = vr::HmdVector2_t::default();
+        assert_eq!(
+            overlays.GetOverlayMouseScale(handle, &mut scale),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(scale.v, [200.0, 100.0]);
+
+        let params = vr::VROverlayIntersectionParams_t {
+            vSource: vr::HmdVector3_t { v: [0.0; 3] },
+            vDirection: vr::HmdVector3_t {
+                v: [0.5, 0.25, -1.0],
+            },
+            eOrigin: vr::ETrackingUniverseOrigin::Standing,
+        };
+        let mut results = vr::VROverlayIntersectionResults_t::default();
+        assert!(overlays.ComputeOverlayIntersection(handle, &params, &mut results));
+        let assert_near = |actual: &[f32], expected: &[f32]| {
+            assert!(
+                actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(a, e)| (a - e).abs() < 1e-5),
+                "{actual:?} != {expected:?}"
+            );
+        };
+        assert_near(&results.vUVs.v, &[0.75, 0.75]);
+        assert_near(&results.vPoint.v, &[0.5, 0.25, -1.0]);
+        assert_near(&results.vNormal.v, &[0.0, 0.0, 1.0]);
+
+        let send_mouse_move = || {
+            let mut overlays = overlays.overlays.write().unwrap();
+            let overlay = overlays
+                .get_mut(OverlayKey::from(KeyData::from_ffi(handle)))
+                .unwrap();
+            overlay.queue_mouse_move(Hand::Right as u32, results.vUVs)
+        };
+        let mut event = vr::VREvent_t::default();
+        let poll = |event: &mut vr::VREvent_t| {
+            overlays.PollNextOverlayEvent(
+                handle,
+                event,
+                std::mem::size_of::<vr::VREvent_t>() as u32,
+            )
+        };
+
+        // Overlays don't receive mouse events by default.
+        let mut method = vr::VROverlayInputMethod::Mouse;
+        assert_eq!(
+            overlays.GetOverlayInputMethod(handle, &mut method),
+            vr::EVROverlayError::None
+        );
+        assert_eq!(method, vr::VROverlayInputMethod::None);
+        assert!(!send_mouse_move());
+        assert!(!poll(&mut event));
+
+        assert_eq!(
+            overlays.SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+            vr::EVROverlayError::None
+        );
+        assert!(send_mouse_move());
+        assert!(poll(&mut event));
+        assert_eq!(event.eventType, vr::EVREventType::MouseMove as u32);
+        assert_eq!(event.trackedDeviceIndex, Hand::Right as u32);
+        let mouse = unsafe { event.data.mouse };
+        assert_near(&[mouse.x, mouse.y], &[150.0, 75.0]);
+        assert!(!poll(&mut event));
+
+        // Moves that haven't been polled yet are coalesced.
+        assert!(send_mouse_move());
+        assert!(send_mouse_move());
+        assert!(poll(&mut event));
+        assert_eq!(event.eventType, vr::EVREventType::MouseMove as u32);
+        assert!(!poll(&mut event));
     }
 }
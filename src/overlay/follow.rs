@@ -0,0 +1,96 @@
+use glam::Quat;
+use log::warn;
+use openxr as xr;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+
+/// Follow speeds for HMD relative overlays, from `XRIZER_OVERLAY_FOLLOW_SPEED`: comma separated
+/// `<overlay key>=<speed>` entries, with a lone speed applying to every other overlay.
+#[derive(Default)]
+pub(super) struct FollowSpeeds {
+    default: Option<f32>,
+    per_overlay: HashMap<CString, f32>,
+}
+
+impl FollowSpeeds {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let parse_speed = |speed: &str| match speed.trim().parse::<f32>() {
+            Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+            _ => Err(format!("invalid speed {speed:?}")),
+        };
+
+        let mut speeds = Self::default();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((key, speed)) => {
+                    let key = CString::new(key.trim()).map_err(|e| e.to_string())?;
+                    speeds.per_overlay.insert(key, parse_speed(speed)?);
+                }
+                None => speeds.default = Some(parse_speed(entry)?),
+            }
+        }
+        Ok(speeds)
+    }
+
+    pub fn from_env() -> Self {
+        let Ok(value) = std::env::var("XRIZER_OVERLAY_FOLLOW_SPEED") else {
+            return Self::default();
+        };
+        Self::parse(&value).unwrap_or_else(|e| {
+            warn!("Ignoring XRIZER_OVERLAY_FOLLOW_SPEED value {value:?}: {e}");
+            Self::default()
+        })
+    }
+
+    pub fn get(&self, key: &CStr) -> Option<f32> {
+        self.per_overlay.get(key).copied().or(self.default)
+    }
+}
+
+/// Lags an HMD relative overlay's orientation behind the HMD's, so it lazily follows head turns
+/// instead of being locked to them.
+pub(super) struct HmdFollow {
+    /// Each second, the overlay closes all but e^-speed of the rotation between it and the HMD.
+    speed: f32,
+    last: Option<(xr::Time, Quat)>,
+}
+
+impl HmdFollow {
+    pub fn new(speed: f32) -> Self {
+        Self { speed, last: None }
+    }
+
+    /// Moves the overlay toward the HMD's orientation at `time`, and returns its new orientation.
+    pub fn update(&mut self, time: xr::Time, hmd: Quat) -> Quat {
+        let (time, orientation) = match self.last {
+            // The first frame starts out at the HMD's orientation.
+            None => (time, hmd),
+            Some((last_time, last)) if time > last_time => {
+                let seconds = (time.as_nanos() - last_time.as_nanos()) as f32 / 1e9;
+                let t = 1.0 - (-self.speed * seconds).exp();
+                (time, last.slerp(hmd, t).normalize())
+            }
+            Some(last) => last,
+        };
+        self.last = Some((time, orientation));
+        orientation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FollowSpeeds;
+
+    #[test]
+    fn parse_speeds() {
+        let speeds = FollowSpeeds::parse("2, system.hud=4.5").unwrap();
+        assert_eq!(speeds.get(c"system.hud"), Some(4.5));
+        assert_eq!(speeds.get(c"other"), Some(2.0));
+
+        let speeds = FollowSpeeds::parse("system.hud=4").unwrap();
+        assert_eq!(speeds.get(c"other"), None);
+
+        assert!(FollowSpeeds::parse("hud=fast").is_err());
+        assert!(FollowSpeeds::parse("-1").is_err());
+    }
+}
@@ -1,53 +1,230 @@
+use log::{debug, warn};
 use openvr as vr;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Mutex,
+};
 
 #[derive(Default, macros::InterfaceImpl)]
 #[interface = "IVRScreenshots"]
 #[versions(001)]
 pub struct Screenshots {
     vtables: Vtables,
+    next_handle: AtomicU32,
+    screenshots: Mutex<HashMap<vr::ScreenshotHandle_t, Screenshot>>,
+    /// Screenshot waiting for the next submitted frame.
+    pending: Mutex<Option<PendingCapture>>,
+}
+
+#[derive(Clone)]
+struct Screenshot {
+    ty: vr::EVRScreenshotType,
+    preview_path: PathBuf,
+    vr_path: Option<PathBuf>,
+}
+
+struct PendingCapture {
+    handle: vr::ScreenshotHandle_t,
+    screenshot: Screenshot,
+    eyes: [Option<EyeImage>; 2],
+}
+
+/// Tightly packed RGBA8 pixels of one submitted eye, top row first.
+pub struct EyeImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Screenshots {
+    /// Whether the given eye of the frame currently being submitted should be read back.
+    pub fn wants_eye(&self, eye: vr::EVREye) -> bool {
+        self.pending.lock().unwrap().as_ref().is_some_and(|p| {
+            p.eyes[eye as usize].is_none()
+                && (eye == vr::EVREye::Left || p.screenshot.ty == vr::EVRScreenshotType::Stereo)
+        })
+    }
+
+    /// Called by the compositor with the contents of a submitted eye. Once all the eyes needed by
+    /// the pending screenshot are available, its images are written out.
+    pub fn capture_eye(&self, eye: vr::EVREye, image: EyeImage) {
+        let mut pending = self.pending.lock().unwrap();
+        let Some(capture) = pending.as_mut() else {
+            return;
+        };
+        capture.eyes[eye as usize] = Some(image);
+
+        let [Some(left), right] = &capture.eyes else {
+            return;
+        };
+        let screenshot = &capture.screenshot;
+        let result = match (screenshot.ty, right) {
+            (vr::EVRScreenshotType::Stereo, None) => return,
+            (vr::EVRScreenshotType::Stereo, Some(right)) => {
+                write_tga(&screenshot.preview_path, left).and_then(|_| {
+                    let vr_path = screenshot.vr_path.as_ref().unwrap();
+                    write_tga(vr_path, &side_by_side(left, right))
+                })
+            }
+            _ => write_tga(&screenshot.preview_path, left),
+        };
+
+        match result {
+            Ok(()) => debug!(
+                "Wrote {:?} screenshot {} to {:?}",
+                screenshot.ty, capture.handle, screenshot.preview_path
+            ),
+            Err(e) => warn!("Failed to write screenshot {}: {e}", capture.handle),
+        }
+        *pending = None;
+    }
+}
+
+/// Places the two eyes next to each other, left eye on the left.
+fn side_by_side(left: &EyeImage, right: &EyeImage) -> EyeImage {
+    let width = left.width + right.width;
+    let height = left.height.max(right.height);
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    for (row, out) in pixels.chunks_exact_mut(width as usize * 4).enumerate() {
+        let (out_left, out_right) = out.split_at_mut(left.width as usize * 4);
+        for (image, out) in [(left, out_left), (right, out_right)] {
+            let stride = image.width as usize * 4;
+            if let Some(src) = image.pixels.get(row * stride..(row + 1) * stride) {
+                out.copy_from_slice(src);
+            }
+        }
+    }
+
+    EyeImage {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Writes an uncompressed 32 bit TGA. Screenshots are rare enough that the file size doesn't
+/// matter much, and the format needs no encoder.
+fn write_tga(path: &Path, image: &EyeImage) -> std::io::Result<()> {
+    let (Ok(width), Ok(height)) = (u16::try_from(image.width), u16::try_from(image.height)) else {
+        return Err(std::io::Error::other("Image is too large for TGA"));
+    };
+
+    let mut tga = Vec::with_capacity(18 + image.pixels.len());
+    // No image ID or color map, uncompressed true color
+    tga.extend_from_slice(&[0, 0, 2]);
+    // Color map specification, X and Y origin
+    tga.extend_from_slice(&[0; 9]);
+    tga.extend_from_slice(&width.to_le_bytes());
+    tga.extend_from_slice(&height.to_le_bytes());
+    // 32 bits per pixel, 8 of them alpha, top row first
+    tga.extend_from_slice(&[32, 0x28]);
+    for pixel in image.pixels.chunks_exact(4) {
+        tga.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::File::create(path)?.write_all(&tga)
+}
+
+/// The destination names are passed without an extension.
+fn tga_path(name: *const c_char) -> Option<PathBuf> {
+    let name = unsafe { name.as_ref() }.map(|n| unsafe { CStr::from_ptr(n) })?;
+    let mut path = PathBuf::from(name.to_string_lossy().into_owned());
+    path.set_extension("tga");
+    Some(path)
 }
 
 impl vr::IVRScreenshots001_Interface for Screenshots {
     fn SubmitScreenshot(
         &self,
-        _: vr::ScreenshotHandle_t,
-        _: vr::EVRScreenshotType,
-        _: *const std::os::raw::c_char,
-        _: *const std::os::raw::c_char,
+        handle: vr::ScreenshotHandle_t,
+        ty: vr::EVRScreenshotType,
+        _: *const c_char,
+        _: *const c_char,
     ) -> vr::EVRScreenshotError {
-        todo!()
+        debug!("App submitted {ty:?} screenshot (handle {handle})");
+        vr::EVRScreenshotError::None
     }
     fn TakeStereoScreenshot(
         &self,
-        _: *mut vr::ScreenshotHandle_t,
-        _: *const std::os::raw::c_char,
-        _: *const std::os::raw::c_char,
+        handle: *mut vr::ScreenshotHandle_t,
+        preview_name: *const c_char,
+        vr_name: *const c_char,
     ) -> vr::EVRScreenshotError {
-        todo!()
+        self.RequestScreenshot(handle, vr::EVRScreenshotType::Stereo, preview_name, vr_name)
     }
     fn UpdateScreenshotProgress(
         &self,
-        _: vr::ScreenshotHandle_t,
+        handle: vr::ScreenshotHandle_t,
         _: f32,
     ) -> vr::EVRScreenshotError {
-        todo!()
+        if self.screenshots.lock().unwrap().contains_key(&handle) {
+            vr::EVRScreenshotError::None
+        } else {
+            vr::EVRScreenshotError::NotFound
+        }
     }
     fn GetScreenshotPropertyFilename(
         &self,
-        _: vr::ScreenshotHandle_t,
-        _: vr::EVRScreenshotPropertyFilenames,
-        _: *mut std::os::raw::c_char,
-        _: u32,
-        _: *mut vr::EVRScreenshotError,
+        handle: vr::ScreenshotHandle_t,
+        filename_type: vr::EVRScreenshotPropertyFilenames,
+        buf: *mut c_char,
+        buf_size: u32,
+        error: *mut vr::EVRScreenshotError,
     ) -> u32 {
-        todo!()
+        let set_error = |e| {
+            if let Some(error) = unsafe { error.as_mut() } {
+                *error = e;
+            }
+        };
+
+        let screenshots = self.screenshots.lock().unwrap();
+        let Some(screenshot) = screenshots.get(&handle) else {
+            set_error(vr::EVRScreenshotError::NotFound);
+            return 0;
+        };
+        let path = match filename_type {
+            vr::EVRScreenshotPropertyFilenames::Preview => Some(&screenshot.preview_path),
+            vr::EVRScreenshotPropertyFilenames::VR => screenshot.vr_path.as_ref(),
+        };
+        let Some(path) = path else {
+            set_error(vr::EVRScreenshotError::NotFound);
+            return 0;
+        };
+
+        let path = CString::new(path.to_string_lossy().into_owned()).unwrap();
+        let data = path.as_bytes_with_nul();
+        if buf.is_null() || (buf_size as usize) < data.len() {
+            set_error(vr::EVRScreenshotError::BufferTooSmall);
+        } else {
+            let buf = unsafe { std::slice::from_raw_parts_mut(buf.cast(), data.len()) };
+            buf.copy_from_slice(data);
+            set_error(vr::EVRScreenshotError::None);
+        }
+        data.len() as u32
     }
     fn GetScreenshotPropertyType(
         &self,
-        _: vr::ScreenshotHandle_t,
-        _: *mut vr::EVRScreenshotError,
+        handle: vr::ScreenshotHandle_t,
+        error: *mut vr::EVRScreenshotError,
     ) -> vr::EVRScreenshotType {
-        todo!()
+        let (ty, err) = match self.screenshots.lock().unwrap().get(&handle) {
+            Some(screenshot) => (screenshot.ty, vr::EVRScreenshotError::None),
+            None => (
+                vr::EVRScreenshotType::None,
+                vr::EVRScreenshotError::NotFound,
+            ),
+        };
+        if let Some(error) = unsafe { error.as_mut() } {
+            *error = err;
+        }
+        ty
     }
     fn HookScreenshot(
         &self,
@@ -58,11 +235,57 @@ impl vr::IVRScreenshots001_Interface for Screenshots {
     }
     fn RequestScreenshot(
         &self,
-        _: *mut vr::ScreenshotHandle_t,
-        _: vr::EVRScreenshotType,
-        _: *const std::os::raw::c_char,
-        _: *const std::os::raw::c_char,
+        handle: *mut vr::ScreenshotHandle_t,
+        ty: vr::EVRScreenshotType,
+        preview_name: *const c_char,
+        vr_name: *const c_char,
     ) -> vr::EVRScreenshotError {
-        todo!()
+        let Some(handle) = (unsafe { handle.as_mut() }) else {
+            return vr::EVRScreenshotError::RequestFailed;
+        };
+        *handle = vr::k_unScreenshotHandleInvalid;
+
+        // The VR image is ignored for mono screenshots.
+        let (preview_path, vr_path) = match ty {
+            vr::EVRScreenshotType::Mono => (tga_path(preview_name), None),
+            vr::EVRScreenshotType::Stereo => {
+                let vr_path = tga_path(vr_name);
+                if vr_path.is_none() {
+                    return vr::EVRScreenshotError::RequestFailed;
+                }
+                (tga_path(preview_name), vr_path)
+            }
+            other => {
+                warn!("Unsupported screenshot type requested: {other:?}");
+                return vr::EVRScreenshotError::RequestFailed;
+            }
+        };
+        let Some(preview_path) = preview_path else {
+            return vr::EVRScreenshotError::RequestFailed;
+        };
+
+        let mut pending = self.pending.lock().unwrap();
+        if pending.is_some() {
+            return vr::EVRScreenshotError::ScreenshotAlreadyInProgress;
+        }
+
+        let screenshot = Screenshot {
+            ty,
+            preview_path,
+            vr_path,
+        };
+        let new_handle = self.next_handle.fetch_add(1, Ordering::Relaxed) + 1;
+        self.screenshots
+            .lock()
+            .unwrap()
+            .insert(new_handle, screenshot.clone());
+        *pending = Some(PendingCapture {
+            handle: new_handle,
+            screenshot,
+            eyes: Default::default(),
+        });
+        *handle = new_handle;
+
+        vr::EVRScreenshotError::None
     }
 }
@@ -1,14 +1,45 @@
+use crate::{
+    clientcore::{Injected, Injector},
+    compositor::Compositor,
+    input::Input,
+    openxr_data::RealOpenXrData,
+};
 use log::debug;
 use openvr as vr;
 use openvr::EVRSettingsError;
 use std::ffi::CStr;
 use std::os::raw::c_char;
+use std::sync::Arc;
 
-#[derive(Default, macros::InterfaceImpl)]
+/// Settings section for xrizer's own settings.
+const XRIZER_SECTION: &str = "xrizer";
+/// The log filter, in `RUST_LOG` syntax. Changes take effect immediately.
+const LOG_FILTER_KEY: &str = "logFilter";
+/// Whether the game receives input. While false, all of its actions read inactive, but devices are
+/// still tracked.
+const INPUT_ENABLED_KEY: &str = "inputEnabled";
+
+#[derive(macros::InterfaceImpl)]
 #[interface = "IVRSettings"]
 #[versions(003)]
 pub struct Settings {
     vtables: Vtables,
+    openxr: Arc<RealOpenXrData>,
+    input: Injected<Input<Compositor>>,
+}
+
+impl Settings {
+    pub fn new(openxr: Arc<RealOpenXrData>, injector: &Injector) -> Self {
+        Self {
+            vtables: Default::default(),
+            openxr,
+            input: injector.inject(),
+        }
+    }
+
+    fn input(&self) -> Arc<Input<Compositor>> {
+        self.input.force(|_| Input::new(self.openxr.clone()))
+    }
 }
 
 impl vr::IVRSettings003_Interface for Settings {
@@ -37,6 +68,9 @@ impl vr::IVRSettings003_Interface for Settings {
         let section = unsafe { CStr::from_ptr(section) }.to_string_lossy();
         let key = unsafe { CStr::from_ptr(settings_key) }.to_string_lossy();
         debug!("Setting bool on {section}/{key} to {value}");
+        if section == XRIZER_SECTION && key == INPUT_ENABLED_KEY {
+            self.input().set_input_enabled(value);
+        }
         unsafe {
             *error = EVRSettingsError::None;
         }
@@ -83,6 +117,9 @@ impl vr::IVRSettings003_Interface for Settings {
         let key = unsafe { CStr::from_ptr(settings_key) }.to_string_lossy();
         let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
         debug!("Setting string on {section}/{key} to {value}");
+        if section == XRIZER_SECTION && key == LOG_FILTER_KEY {
+            crate::log_filter::set_filter(&value);
+        }
         unsafe {
             *error = EVRSettingsError::None;
         }
@@ -100,7 +137,7 @@ impl vr::IVRSettings003_Interface for Settings {
             *error = EVRSettingsError::None;
         }
         debug!("Getting bool on {section}/{key}");
-        false
+        section == XRIZER_SECTION && key == INPUT_ENABLED_KEY && self.input().input_enabled()
     }
 
     fn GetInt32(
@@ -146,12 +183,15 @@ impl vr::IVRSettings003_Interface for Settings {
         unsafe {
             *error = EVRSettingsError::None;
         }
-        if value_len > 0 {
-            unsafe {
-                *value = 0;
-            }
-        }
         debug!("Getting string on {section}/{key}");
+        let string = (section == XRIZER_SECTION && key == LOG_FILTER_KEY)
+            .then(crate::log_filter::filter)
+            .flatten()
+            .unwrap_or_default();
+        if !value.is_null() && value_len > 0 {
+            let dst = unsafe { std::slice::from_raw_parts_mut(value, value_len as usize) };
+            crate::copy_truncated(dst, string.as_bytes());
+        }
     }
 
     fn RemoveSection(&self, section: *const c_char, error: *mut EVRSettingsError) {
@@ -5,15 +5,29 @@ use crate::{
     tracy_span,
 };
 use glam::{Mat3, Quat, Vec3};
-use log::{debug, trace, warn};
+use log::{debug, info, trace, warn};
 use openvr as vr;
 use openxr as xr;
 use std::ffi::CStr;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
+    Arc, Mutex, Once,
 };
 
+/// Reported when the runtime doesn't support XR_FB_display_refresh_rate.
+const DEFAULT_DISPLAY_FREQUENCY: f32 = 90.0;
+/// Roughly what SteamVR reports for common headsets.
+const DEFAULT_VSYNC_TO_PHOTONS: f32 = 0.011;
+/// Reported as the HMD's Prop_DriverVersion_String.
+const DRIVER_VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+        Ok(version) => version,
+        Err(_) => panic!("Invalid crate version"),
+    };
+/// Default bounds for the near and far planes passed to GetProjectionMatrix, in meters.
+const DEFAULT_MIN_NEAR_Z: f32 = 0.001;
+const DEFAULT_MAX_FAR_Z: f32 = 100_000.0;
+
 #[derive(Default)]
 struct ConnectedHands {
     left: AtomicBool,
@@ -75,6 +89,18 @@ pub struct System {
     input: Injected<Input<crate::compositor::Compositor>>,
     vtables: Vtables,
     last_connected_hands: ConnectedHands,
+    /// The interaction profile of each hand as of the last event sent about it, so that games can
+    /// be told when a connected controller changes type.
+    last_hand_profiles: Mutex<[xr::Path; 2]>,
+    swap_hands: AtomicBool,
+    /// Whether projection matrices map the near plane to the far end of the depth range.
+    reverse_z: AtomicBool,
+    /// Whether projection matrices use OpenGL's `[-1, 1]` depth range instead of SteamVR's `[0, 1]`.
+    opengl_depth_range: bool,
+    /// Reported as Prop_SecondsFromVsyncToPhotons_Float.
+    vsync_to_photons: f32,
+    depth_clamp: DepthClamp,
+    render_scale: RenderScale,
     views: Mutex<ViewCache>,
 }
 
@@ -89,14 +115,87 @@ impl System {
             input: injector.inject(),
             vtables: Default::default(),
             last_connected_hands: Default::default(),
+            last_hand_profiles: Mutex::default(),
             views: Mutex::default(),
+            swap_hands: crate::env_flag("XRIZER_SWAP_HANDS").into(),
+            reverse_z: crate::env_flag("XRIZER_REVERSE_Z").into(),
+            opengl_depth_range: crate::env_flag("XRIZER_OPENGL_DEPTH_RANGE"),
+            vsync_to_photons: vsync_to_photons_from_env(),
+            depth_clamp: DepthClamp::from_env(),
+            render_scale: RenderScale::from_env(),
+        }
+    }
+
+    /// The HMD's refresh rate in Hz, from XR_FB_display_refresh_rate if available.
+    fn display_frequency(&self) -> f32 {
+        if !self.openxr.enabled_extensions.fb_display_refresh_rate {
+            return DEFAULT_DISPLAY_FREQUENCY;
+        }
+
+        self.openxr
+            .session_data
+            .get()
+            .session
+            .current_display_refresh_rate()
+            .inspect_err(|e| warn!("Failed to get display refresh rate: {e}"))
+            .ok()
+            // Runtimes may report 0 if they don't know the refresh rate.
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(DEFAULT_DISPLAY_FREQUENCY)
+    }
+
+    /// Maps a hand to the controller role reported to the app, taking hand swapping into account.
+    fn role_for_hand(&self, hand: Hand) -> vr::ETrackedControllerRole {
+        match (hand, self.swap_hands.load(Ordering::Relaxed)) {
+            (Hand::Left, false) | (Hand::Right, true) => vr::ETrackedControllerRole::LeftHand,
+            (Hand::Right, false) | (Hand::Left, true) => vr::ETrackedControllerRole::RightHand,
         }
     }
 
+    /// Checks that a tracked property can be requested from a device with a getter for the
+    /// given type.
+    fn check_tracked_property(
+        &self,
+        device_index: vr::TrackedDeviceIndex_t,
+        prop: vr::ETrackedDeviceProperty,
+        ty: vr::TrackedPropertyType,
+    ) -> Result<(), vr::ETrackedPropertyError> {
+        if !vr::IVRSystem022_Interface::IsTrackedDeviceConnected(self, device_index) {
+            return Err(vr::ETrackedPropertyError::InvalidDevice);
+        }
+        if prop.value_type() != Some(ty) {
+            return Err(vr::ETrackedPropertyError::WrongDataType);
+        }
+        Ok(())
+    }
+
+    fn tracked_property<T: Default>(
+        &self,
+        device_index: vr::TrackedDeviceIndex_t,
+        prop: vr::ETrackedDeviceProperty,
+        ty: vr::TrackedPropertyType,
+        error: *mut vr::ETrackedPropertyError,
+        get: impl FnOnce() -> Option<T>,
+    ) -> T {
+        let value = self
+            .check_tracked_property(device_index, prop, ty)
+            .and_then(|()| get().ok_or(vr::ETrackedPropertyError::UnknownProperty));
+
+        set_property_error(
+            error,
+            value
+                .as_ref()
+                .err()
+                .copied()
+                .unwrap_or(vr::ETrackedPropertyError::Success),
+        );
+        value.unwrap_or_default()
+    }
+
     pub fn reset_views(&self) {
         std::mem::take(&mut *self.views.lock().unwrap());
         let session = self.openxr.session_data.get();
-        let display_time = self.openxr.display_time.get();
+        let display_time = self.openxr.pose_time.get();
         let mut views = self.views.lock().unwrap();
         views.get_views(&session, display_time, xr::ReferenceSpaceType::VIEW);
         views.get_views(
@@ -106,11 +205,180 @@ impl System {
         );
     }
 
+    /// Whether the session is running but another app (e.g. the runtime's dashboard) has input
+    /// focus.
+    fn is_unfocused(&self) -> bool {
+        matches!(
+            self.openxr.session_data.get().state,
+            xr::SessionState::SYNCHRONIZED | xr::SessionState::VISIBLE
+        )
+    }
+
     pub fn get_views(&self, ty: xr::ReferenceSpaceType) -> ViewData {
         tracy_span!();
         let session = self.openxr.session_data.get();
         let mut views = self.views.lock().unwrap();
-        views.get_views(&session, self.openxr.display_time.get(), ty)
+        views.get_views(&session, self.openxr.pose_time.get(), ty)
+    }
+}
+
+/// Clip space depth range of a graphics API.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum DepthRange {
+    /// Direct3D and Vulkan
+    ZeroToOne,
+    /// OpenGL
+    NegativeOneToOne,
+}
+
+/// Bounds for the near and far planes apps ask for projection matrices with, since extreme planes
+/// (e.g. a near plane of 0) make for degenerate matrices.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct DepthClamp {
+    min_near_z: f32,
+    max_far_z: f32,
+}
+
+impl DepthClamp {
+    /// Reads the bounds from `XRIZER_MIN_NEAR_Z` and `XRIZER_MAX_FAR_Z`.
+    fn from_env() -> Self {
+        let min_near_z =
+            f32_from_env("XRIZER_MIN_NEAR_Z", |v| v > 0.0).unwrap_or(DEFAULT_MIN_NEAR_Z);
+        let max_far_z = f32_from_env("XRIZER_MAX_FAR_Z", |v| v > min_near_z)
+            .unwrap_or(DEFAULT_MAX_FAR_Z.max(min_near_z * 2.0));
+        Self {
+            min_near_z,
+            max_far_z,
+        }
+    }
+
+    /// Clamps the near and far planes to the bounds. Planes that end up equal are replaced with
+    /// the whole range, since there's no depth range between them.
+    fn apply(&self, near_z: f32, far_z: f32) -> (f32, f32) {
+        let clamp = |z: f32| {
+            if z.is_nan() {
+                self.min_near_z
+            } else {
+                z.clamp(self.min_near_z, self.max_far_z)
+            }
+        };
+        let (near, far) = match (clamp(near_z), clamp(far_z)) {
+            (near, far) if near == far => (self.min_near_z, self.max_far_z),
+            planes => planes,
+        };
+
+        if (near, far) != (near_z, far_z) {
+            static LOG_CLAMPED: Once = Once::new();
+            LOG_CLAMPED.call_once(|| {
+                warn!(
+                    "Clamping projection planes {near_z}..{far_z} to {near}..{far} \
+                     (further clamping won't be logged)"
+                );
+            });
+        }
+        (near, far)
+    }
+}
+
+/// Scale factors for the recommended render target size, so games render at a higher (or lower)
+/// resolution than the runtime recommends.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct RenderScale {
+    width: f32,
+    height: f32,
+}
+
+impl RenderScale {
+    /// Reads the scale from `XRIZER_RENDER_SCALE`, which applies to both dimensions, and
+    /// `XRIZER_RENDER_SCALE_WIDTH` and `XRIZER_RENDER_SCALE_HEIGHT`, which override it for one.
+    fn from_env() -> Self {
+        let scale = f32_from_env("XRIZER_RENDER_SCALE", |v| v > 0.0).unwrap_or(1.0);
+        let width = f32_from_env("XRIZER_RENDER_SCALE_WIDTH", |v| v > 0.0).unwrap_or(scale);
+        let height = f32_from_env("XRIZER_RENDER_SCALE_HEIGHT", |v| v > 0.0).unwrap_or(scale);
+        if (width, height) != (1.0, 1.0) {
+            info!("Scaling render target size by {width}x{height}");
+        }
+        Self { width, height }
+    }
+
+    /// Scales the size the runtime recommends for a view, without going past its maximum size.
+    fn apply(&self, view: &xr::ViewConfigurationView) -> (u32, u32) {
+        let scale = |size: u32, scale: f32, max: u32| {
+            ((size as f32 * scale).round() as u32).clamp(1, max.max(1))
+        };
+        (
+            scale(
+                view.recommended_image_rect_width,
+                self.width,
+                view.max_image_rect_width,
+            ),
+            scale(
+                view.recommended_image_rect_height,
+                self.height,
+                view.max_image_rect_height,
+            ),
+        )
+    }
+}
+
+/// Builds a projection matrix from the tangents of the view's half angles (left, right, up, down),
+/// mapping the near plane to the start of the depth range (or the end, with reverse Z).
+fn projection_matrix(
+    [left, right, up, down]: [f32; 4],
+    near_z: f32,
+    far_z: f32,
+    depth_range: DepthRange,
+    reverse_z: bool,
+) -> vr::HmdMatrix44_t {
+    let idx = 1.0 / (right - left);
+    let idy = 1.0 / (up - down);
+    let idz = 1.0 / (far_z - near_z);
+    let sx = right + left;
+    let sy = up + down;
+
+    let (z_scale, z_offset) = match depth_range {
+        DepthRange::ZeroToOne => (-far_z * idz, -far_z * near_z * idz),
+        DepthRange::NegativeOneToOne => (-(far_z + near_z) * idz, -2.0 * far_z * near_z * idz),
+    };
+    let (z_scale, z_offset) = match (depth_range, reverse_z) {
+        (_, false) => (z_scale, z_offset),
+        // 1 - z
+        (DepthRange::ZeroToOne, true) => (-1.0 - z_scale, -z_offset),
+        // -z
+        (DepthRange::NegativeOneToOne, true) => (-z_scale, -z_offset),
+    };
+
+    vr::HmdMatrix44_t {
+        m: [
+            [2.0 * idx, 0.0, sx * idx, 0.0],
+            [0.0, 2.0 * idy, sy * idy, 0.0],
+            [0.0, 0.0, z_scale, z_offset],
+            [0.0, 0.0, -1.0, 0.0],
+        ],
+    }
+}
+
+/// Reads a finite float from the environment variable `name`, warning if it's set but invalid.
+fn f32_from_env(name: &str, valid: impl Fn(f32) -> bool) -> Option<f32> {
+    let value = std::env::var(name).ok()?;
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite() && valid(*v))
+        .or_else(|| {
+            warn!("Invalid {name} value {value:?}");
+            None
+        })
+}
+
+/// Reads the vsync to photons latency (in seconds) from `XRIZER_VSYNC_TO_PHOTONS`.
+fn vsync_to_photons_from_env() -> f32 {
+    f32_from_env("XRIZER_VSYNC_TO_PHOTONS", |v| v >= 0.0).unwrap_or(DEFAULT_VSYNC_TO_PHOTONS)
+}
+
+fn set_property_error(error: *mut vr::ETrackedPropertyError, value: vr::ETrackedPropertyError) {
+    if let Some(error) = unsafe { error.as_mut() } {
+        *error = value;
     }
 }
 
@@ -124,13 +392,14 @@ impl vr::IVRSystem022_Interface for System {
                 xr::ViewConfigurationType::PRIMARY_STEREO,
             )
             .unwrap();
+        let (recommended_width, recommended_height) = self.render_scale.apply(&views[0]);
 
         if !width.is_null() {
-            unsafe { *width = views[0].recommended_image_rect_width };
+            unsafe { *width = recommended_width };
         }
 
         if !height.is_null() {
-            unsafe { *height = views[0].recommended_image_rect_height };
+            unsafe { *height = recommended_height };
         }
     }
     fn GetProjectionMatrix(&self, eye: vr::EVREye, near_z: f32, far_z: f32) -> vr::HmdMatrix44_t {
@@ -138,20 +407,19 @@ impl vr::IVRSystem022_Interface for System {
         let [mut left, mut right, mut up, mut down] = [0.0; 4];
         self.GetProjectionRaw(eye, &mut left, &mut right, &mut down, &mut up);
 
-        let idx = 1.0 / (right - left);
-        let idy = 1.0 / (up - down);
-        let idz = 1.0 / (far_z - near_z);
-        let sx = right + left;
-        let sy = up + down;
-
-        vr::HmdMatrix44_t {
-            m: [
-                [2.0 * idx, 0.0, sx * idx, 0.0],
-                [0.0, 2.0 * idy, sy * idy, 0.0],
-                [0.0, 0.0, -far_z * idz, -far_z * near_z * idz],
-                [0.0, 0.0, -1.0, 0.0],
-            ],
-        }
+        let depth_range = if self.opengl_depth_range {
+            DepthRange::NegativeOneToOne
+        } else {
+            DepthRange::ZeroToOne
+        };
+        let (near_z, far_z) = self.depth_clamp.apply(near_z, far_z);
+        projection_matrix(
+            [left, right, up, down],
+            near_z,
+            far_z,
+            depth_range,
+            self.reverse_z.load(Ordering::Relaxed),
+        )
     }
     fn GetProjectionRaw(
         &self,
@@ -179,12 +447,21 @@ impl vr::IVRSystem022_Interface for System {
     fn ComputeDistortion(
         &self,
         _: vr::EVREye,
-        _: f32,
-        _: f32,
-        _: *mut vr::DistortionCoordinates_t,
+        u: f32,
+        v: f32,
+        coordinates: *mut vr::DistortionCoordinates_t,
     ) -> bool {
-        crate::warn_unimplemented!("ComputeDistortion");
-        false
+        let Some(coordinates) = (unsafe { coordinates.as_mut() }) else {
+            return false;
+        };
+        // The runtime applies lens distortion to the submitted frames, so the game's own mesh
+        // should leave them as is.
+        *coordinates = vr::DistortionCoordinates_t {
+            rfRed: [u, v],
+            rfGreen: [u, v],
+            rfBlue: [u, v],
+        };
+        true
     }
     fn GetEyeToHeadTransform(&self, eye: vr::EVREye) -> vr::HmdMatrix34_t {
         let views = self.get_views(xr::ReferenceSpaceType::VIEW).views;
@@ -210,8 +487,19 @@ impl vr::IVRSystem022_Interface for System {
             }
         }
     }
-    fn GetTimeSinceLastVsync(&self, _: *mut f32, _: *mut u64) -> bool {
-        todo!()
+    fn GetTimeSinceLastVsync(
+        &self,
+        seconds_since_last_vsync: *mut f32,
+        frame_counter: *mut u64,
+    ) -> bool {
+        let (seconds, count) = self.openxr.clock.since_last_vsync();
+        if let Some(seconds_since_last_vsync) = unsafe { seconds_since_last_vsync.as_mut() } {
+            *seconds_since_last_vsync = seconds as f32;
+        }
+        if let Some(frame_counter) = unsafe { frame_counter.as_mut() } {
+            *frame_counter = count;
+        }
+        true
     }
     fn GetRuntimeVersion(&self) -> *const std::os::raw::c_char {
         static VERSION: &CStr = c"2.5.1";
@@ -221,22 +509,23 @@ impl vr::IVRSystem022_Interface for System {
         todo!()
     }
     fn AcknowledgeQuit_Exiting(&self) {
-        todo!()
+        info!("App acknowledged quit, ending session");
+        self.openxr.acknowledge_quit();
     }
     fn PerformFirmwareUpdate(&self, _: vr::TrackedDeviceIndex_t) -> vr::EVRFirmwareError {
         todo!()
     }
     fn ShouldApplicationReduceRenderingWork(&self) -> bool {
-        false
+        self.is_unfocused()
     }
     fn ShouldApplicationPause(&self) -> bool {
-        false
+        self.is_unfocused()
     }
     fn IsSteamVRDrawingControllers(&self) -> bool {
         todo!()
     }
     fn IsInputAvailable(&self) -> bool {
-        true
+        self.openxr.session_data.get().state == xr::SessionState::FOCUSED
     }
     fn GetControllerAxisTypeNameFromEnum(
         &self,
@@ -247,8 +536,15 @@ impl vr::IVRSystem022_Interface for System {
     fn GetButtonIdNameFromEnum(&self, _: vr::EVRButtonId) -> *const std::os::raw::c_char {
         todo!()
     }
-    fn TriggerHapticPulse(&self, _: vr::TrackedDeviceIndex_t, _: u32, _: std::os::raw::c_ushort) {
-        crate::warn_unimplemented!("TriggerHapticPulse");
+    fn TriggerHapticPulse(
+        &self,
+        device_index: vr::TrackedDeviceIndex_t,
+        _axis_id: u32,
+        duration_micros: std::os::raw::c_ushort,
+    ) {
+        self.input
+            .force(|_| Input::new(self.openxr.clone()))
+            .trigger_legacy_haptic_pulse(device_index, duration_micros);
     }
     fn GetControllerStateWithPose(
         &self,
@@ -357,18 +653,32 @@ impl vr::IVRSystem022_Interface for System {
         size: u32,
         pose: *mut vr::TrackedDevicePose_t,
     ) -> bool {
-        for (current, prev, hand) in [
+        let write_pose = |hand: Hand| {
+            if !pose.is_null() {
+                unsafe {
+                    pose.write(
+                        self.input
+                            .force(|_| Input::new(self.openxr.clone()))
+                            .get_controller_pose(hand, Some(origin))
+                            .unwrap_or_default(),
+                    );
+                }
+            }
+        };
+
+        for (info, prev, hand) in [
             (
-                self.openxr.left_hand.connected(),
+                &self.openxr.left_hand,
                 &self.last_connected_hands.left,
                 Hand::Left,
             ),
             (
-                self.openxr.right_hand.connected(),
+                &self.openxr.right_hand,
                 &self.last_connected_hands.right,
                 Hand::Right,
             ),
         ] {
+            let current = info.connected();
             if prev
                 .compare_exchange(!current, current, Ordering::Relaxed, Ordering::Relaxed)
                 .is_ok()
@@ -377,6 +687,9 @@ impl vr::IVRSystem022_Interface for System {
                     "sending {hand:?} {}connected",
                     if current { "" } else { "not " }
                 );
+                // Activation already tells games to look at the new controller.
+                self.last_hand_profiles.lock().unwrap()[hand as usize - 1] =
+                    info.profile_path.load();
 
                 // Since the VREvent_t struct can be a variable size, it seems a little dangerous to
                 // create a reference to it, so we'll just operate through pointers.
@@ -390,19 +703,68 @@ impl vr::IVRSystem022_Interface for System {
 
                     (&raw mut (*event).trackedDeviceIndex).write(hand as u32);
                     (&raw mut (*event).eventAgeSeconds).write(0.0);
-                    if !pose.is_null() {
-                        pose.write(
-                            self.input
-                                .force(|_| Input::new(self.openxr.clone()))
-                                .get_controller_pose(hand, Some(origin))
-                                .unwrap_or_default(),
-                        );
-                    }
                 }
+                write_pose(hand);
                 return true;
             }
         }
 
+        for (info, hand) in [
+            (&self.openxr.left_hand, Hand::Left),
+            (&self.openxr.right_hand, Hand::Right),
+        ] {
+            let profile = info.profile_path.load();
+            let mut last_profiles = self.last_hand_profiles.lock().unwrap();
+            let last = &mut last_profiles[hand as usize - 1];
+            if !info.connected() || *last == profile {
+                continue;
+            }
+
+            // The change is left pending if it can't be delivered.
+            const PROPERTY_EVENT_SIZE: usize = std::mem::offset_of!(vr::VREvent_t, data)
+                + std::mem::size_of::<vr::VREvent_Property_t>();
+            if (size as usize) < PROPERTY_EVENT_SIZE {
+                crate::warn_once!(
+                    "Event struct size ({}) is too small for property events",
+                    size
+                );
+                continue;
+            }
+            *last = profile;
+            drop(last_profiles);
+
+            // Games that only pick controller types when a device is activated won't notice the
+            // change, but the controller type is the property they'd re-query.
+            debug!("sending {hand:?} controller type changed");
+            unsafe {
+                (&raw mut (*event).eventType).write(vr::EVREventType::PropertyChanged as u32);
+                (&raw mut (*event).trackedDeviceIndex).write(hand as u32);
+                (&raw mut (*event).eventAgeSeconds).write(0.0);
+                (&raw mut (*event).data.property).write(vr::VREvent_Property_t {
+                    // Property containers only exist on the driver side (IVRProperties), which
+                    // xrizer doesn't implement, so there's no handle for the device to report.
+                    // Games identify it by trackedDeviceIndex instead.
+                    container: vr::k_ulInvalidPropertyContainer,
+                    prop: vr::ETrackedDeviceProperty::ControllerType_String,
+                });
+            }
+            write_pose(hand);
+            return true;
+        }
+
+        if self.openxr.take_quit_request() {
+            debug!("sending quit");
+            unsafe {
+                (&raw mut (*event).eventType).write(vr::EVREventType::Quit as u32);
+                (&raw mut (*event).trackedDeviceIndex).write(vr::k_unTrackedDeviceIndexInvalid);
+                (&raw mut (*event).eventAgeSeconds).write(0.0);
+                if !pose.is_null() {
+                    pose.write(Default::default());
+                }
+            }
+            return true;
+        }
+
         self.input.get().is_some_and(|input| {
             let got_event = input.get_next_event(size, event);
             if got_event && !pose.is_null() {
@@ -444,16 +806,13 @@ impl vr::IVRSystem022_Interface for System {
     ) -> u32 {
         debug!(target: log_tags::TRACKED_PROP, "requesting string property: {prop:?} ({device_index})");
 
-        if !self.IsTrackedDeviceConnected(device_index) {
-            if let Some(error) = unsafe { error.as_mut() } {
-                *error = vr::ETrackedPropertyError::InvalidDevice;
-            }
+        if let Err(e) =
+            self.check_tracked_property(device_index, prop, vr::TrackedPropertyType::String)
+        {
+            set_property_error(error, e);
             return 0;
         }
-
-        if let Some(error) = unsafe { error.as_mut() } {
-            *error = vr::ETrackedPropertyError::Success;
-        }
+        set_property_error(error, vr::ETrackedPropertyError::Success);
 
         let buf = if !value.is_null() && size > 0 {
             unsafe { std::slice::from_raw_parts_mut(value, size as usize) }
@@ -469,6 +828,10 @@ impl vr::IVRSystem022_Interface for System {
                 vr::ETrackedDeviceProperty::SerialNumber_String
                 | vr::ETrackedDeviceProperty::ManufacturerName_String
                 | vr::ETrackedDeviceProperty::ControllerType_String => Some(c"<unknown>"),
+                // Controllers report the tracking system of their profile instead, since games
+                // match on it to identify them.
+                vr::ETrackedDeviceProperty::TrackingSystemName_String => Some(c"xrizer"),
+                vr::ETrackedDeviceProperty::DriverVersion_String => Some(DRIVER_VERSION),
                 _ => None,
             },
             x if Hand::try_from(x).is_ok() => self.input.get().and_then(|i| {
@@ -478,18 +841,14 @@ impl vr::IVRSystem022_Interface for System {
         };
 
         let Some(data) = data else {
-            if let Some(error) = unsafe { error.as_mut() } {
-                *error = vr::ETrackedPropertyError::UnknownProperty;
-            }
+            set_property_error(error, vr::ETrackedPropertyError::UnknownProperty);
             return 0;
         };
 
         let data =
             unsafe { std::slice::from_raw_parts(data.as_ptr(), data.to_bytes_with_nul().len()) };
         if buf.len() < data.len() {
-            if let Some(error) = unsafe { error.as_mut() } {
-                *error = vr::ETrackedPropertyError::BufferTooSmall;
-            }
+            set_property_error(error, vr::ETrackedPropertyError::BufferTooSmall);
         } else {
             buf[0..data.len()].copy_from_slice(data);
         }
@@ -498,22 +857,36 @@ impl vr::IVRSystem022_Interface for System {
     }
     fn GetArrayTrackedDeviceProperty(
         &self,
-        _: vr::TrackedDeviceIndex_t,
-        _: vr::ETrackedDeviceProperty,
+        device_index: vr::TrackedDeviceIndex_t,
+        prop: vr::ETrackedDeviceProperty,
         _: vr::PropertyTypeTag_t,
         _: *mut std::os::raw::c_void,
         _: u32,
-        _: *mut vr::ETrackedPropertyError,
+        err: *mut vr::ETrackedPropertyError,
     ) -> u32 {
-        todo!()
+        debug!(target: log_tags::TRACKED_PROP, "requesting array property: {prop:?} ({device_index})");
+        self.tracked_property(
+            device_index,
+            prop,
+            vr::TrackedPropertyType::Array,
+            err,
+            || None,
+        )
     }
     fn GetMatrix34TrackedDeviceProperty(
         &self,
-        _: vr::TrackedDeviceIndex_t,
-        _: vr::ETrackedDeviceProperty,
-        _: *mut vr::ETrackedPropertyError,
+        device_index: vr::TrackedDeviceIndex_t,
+        prop: vr::ETrackedDeviceProperty,
+        err: *mut vr::ETrackedPropertyError,
     ) -> vr::HmdMatrix34_t {
-        todo!()
+        debug!(target: log_tags::TRACKED_PROP, "requesting matrix34 property: {prop:?} ({device_index})");
+        self.tracked_property(
+            device_index,
+            prop,
+            vr::TrackedPropertyType::Matrix34,
+            err,
+            || None,
+        )
     }
     fn GetUint64TrackedDeviceProperty(
         &self,
@@ -522,27 +895,18 @@ impl vr::IVRSystem022_Interface for System {
         err: *mut vr::ETrackedPropertyError,
     ) -> u64 {
         debug!(target: log_tags::TRACKED_PROP, "requesting uint64 property: {prop:?} ({device_index})");
-        if !self.IsTrackedDeviceConnected(device_index) {
-            if let Some(err) = unsafe { err.as_mut() } {
-                *err = vr::ETrackedPropertyError::InvalidDevice;
-            }
-        }
-        if let Some(err) = unsafe { err.as_mut() } {
-            *err = vr::ETrackedPropertyError::UnknownProperty;
-        }
-
-        match device_index {
-            x if Hand::try_from(x).is_ok() => self.input.get().and_then(|input| {
-                input.get_controller_uint_tracked_property(Hand::try_from(x).unwrap(), prop)
-            }),
-            _ => None,
-        }
-        .unwrap_or_else(|| {
-            if let Some(err) = unsafe { err.as_mut() } {
-                *err = vr::ETrackedPropertyError::UnknownProperty;
-            }
-            0
-        })
+        self.tracked_property(
+            device_index,
+            prop,
+            vr::TrackedPropertyType::Uint64,
+            err,
+            || {
+                let hand = Hand::try_from(device_index).ok()?;
+                self.input
+                    .get()
+                    .and_then(|input| input.get_controller_uint_tracked_property(hand, prop))
+            },
+        )
     }
     fn GetInt32TrackedDeviceProperty(
         &self,
@@ -551,27 +915,18 @@ impl vr::IVRSystem022_Interface for System {
         err: *mut vr::ETrackedPropertyError,
     ) -> i32 {
         debug!(target: log_tags::TRACKED_PROP, "requesting int32 property: {prop:?} ({device_index})");
-        if !self.IsTrackedDeviceConnected(device_index) {
-            if let Some(err) = unsafe { err.as_mut() } {
-                *err = vr::ETrackedPropertyError::InvalidDevice;
-            }
-        }
-
-        if let Some(err) = unsafe { err.as_mut() } {
-            *err = vr::ETrackedPropertyError::Success;
-        }
-        match device_index {
-            x if Hand::try_from(x).is_ok() => self.input.get().and_then(|input| {
-                input.get_controller_int_tracked_property(Hand::try_from(x).unwrap(), prop)
-            }),
-            _ => None,
-        }
-        .unwrap_or_else(|| {
-            if let Some(err) = unsafe { err.as_mut() } {
-                *err = vr::ETrackedPropertyError::UnknownProperty;
-            }
-            0
-        })
+        self.tracked_property(
+            device_index,
+            prop,
+            vr::TrackedPropertyType::Int32,
+            err,
+            || {
+                let hand = Hand::try_from(device_index).ok()?;
+                self.input
+                    .get()
+                    .and_then(|input| input.get_controller_int_tracked_property(hand, prop))
+            },
+        )
     }
     fn GetFloatTrackedDeviceProperty(
         &self,
@@ -580,26 +935,31 @@ impl vr::IVRSystem022_Interface for System {
         error: *mut vr::ETrackedPropertyError,
     ) -> f32 {
         debug!(target: log_tags::TRACKED_PROP, "requesting float property: {prop:?} ({device_index})");
-        if device_index != vr::k_unTrackedDeviceIndex_Hmd {
-            if let Some(error) = unsafe { error.as_mut() } {
-                *error = vr::ETrackedPropertyError::UnknownProperty;
-            }
-            return 0.0;
-        }
+        self.tracked_property(
+            device_index,
+            prop,
+            vr::TrackedPropertyType::Float,
+            error,
+            || {
+                if device_index != vr::k_unTrackedDeviceIndex_Hmd {
+                    return None;
+                }
 
-        match prop {
-            vr::ETrackedDeviceProperty::UserIpdMeters_Float => {
-                let views = self.get_views(xr::ReferenceSpaceType::VIEW).views;
-                views[1].pose.position.x - views[0].pose.position.x
-            }
-            vr::ETrackedDeviceProperty::DisplayFrequency_Float => 90.0,
-            _ => {
-                if let Some(error) = unsafe { error.as_mut() } {
-                    *error = vr::ETrackedPropertyError::UnknownProperty;
+                match prop {
+                    vr::ETrackedDeviceProperty::UserIpdMeters_Float => {
+                        let views = self.get_views(xr::ReferenceSpaceType::VIEW).views;
+                        Some(views[1].pose.position.x - views[0].pose.position.x)
+                    }
+                    vr::ETrackedDeviceProperty::DisplayFrequency_Float => {
+                        Some(self.display_frequency())
+                    }
+                    vr::ETrackedDeviceProperty::SecondsFromVsyncToPhotons_Float => {
+                        Some(self.vsync_to_photons)
+                    }
+                    _ => None,
                 }
-                0.0
-            }
-        }
+            },
+        )
     }
     fn GetBoolTrackedDeviceProperty(
         &self,
@@ -608,10 +968,13 @@ impl vr::IVRSystem022_Interface for System {
         err: *mut vr::ETrackedPropertyError,
     ) -> bool {
         debug!(target: log_tags::TRACKED_PROP, "requesting bool property: {prop:?} ({device_index})");
-        if let Some(err) = unsafe { err.as_mut() } {
-            *err = vr::ETrackedPropertyError::UnknownProperty;
-        }
-        false
+        self.tracked_property(
+            device_index,
+            prop,
+            vr::TrackedPropertyType::Bool,
+            err,
+            || None,
+        )
     }
 
     fn IsTrackedDeviceConnected(&self, device_index: vr::TrackedDeviceIndex_t) -> bool {
@@ -642,34 +1005,30 @@ impl vr::IVRSystem022_Interface for System {
         &self,
         index: vr::TrackedDeviceIndex_t,
     ) -> vr::ETrackedControllerRole {
-        match index {
-            x if Hand::try_from(x).is_ok() => match Hand::try_from(x).unwrap() {
-                Hand::Left => vr::ETrackedControllerRole::LeftHand,
-                Hand::Right => vr::ETrackedControllerRole::RightHand,
-            },
-            _ => vr::ETrackedControllerRole::Invalid,
+        match Hand::try_from(index) {
+            Ok(hand) => self.role_for_hand(hand),
+            Err(_) => vr::ETrackedControllerRole::Invalid,
         }
     }
     fn GetTrackedDeviceIndexForControllerRole(
         &self,
         role: vr::ETrackedControllerRole,
     ) -> vr::TrackedDeviceIndex_t {
-        match role {
-            vr::ETrackedControllerRole::LeftHand => {
-                if self.openxr.left_hand.connected() {
-                    Hand::Left as u32
-                } else {
-                    vr::k_unTrackedDeviceIndexInvalid
-                }
-            }
-            vr::ETrackedControllerRole::RightHand => {
-                if self.openxr.right_hand.connected() {
-                    Hand::Right as u32
-                } else {
-                    vr::k_unTrackedDeviceIndexInvalid
-                }
-            }
-            _ => vr::k_unTrackedDeviceIndexInvalid,
+        let Some(hand) = [Hand::Left, Hand::Right]
+            .into_iter()
+            .find(|hand| self.role_for_hand(*hand) == role)
+        else {
+            return vr::k_unTrackedDeviceIndexInvalid;
+        };
+
+        let connected = match hand {
+            Hand::Left => self.openxr.left_hand.connected(),
+            Hand::Right => self.openxr.right_hand.connected(),
+        };
+        if connected {
+            hand as u32
+        } else {
+            vr::k_unTrackedDeviceIndexInvalid
         }
     }
     fn ApplyTransform(
@@ -718,6 +1077,12 @@ impl vr::IVRSystem022_Interface for System {
         pose_array: *mut vr::TrackedDevicePose_t,
         pose_count: u32,
     ) {
+        if pose_array.is_null() || pose_count == 0 {
+            return;
+        }
+        // The poses are predicted for the frame's display time rather than the requested time:
+        // without XR_KHR_convert_timespec_time there's no way to turn "seconds from now" into an
+        // OpenXR time.
         self.input
             .force(|_| Input::new(self.openxr.clone()))
             .get_poses(
@@ -823,11 +1188,14 @@ mod tests {
     use std::ffi::CStr;
     use vr::IVRSystem022_Interface;
 
+    fn new_system() -> System {
+        let xr = RealOpenXrData::new_fake();
+        System::new(xr, &Injector::default())
+    }
+
     #[test]
     fn unity_required_properties() {
-        let xr = Arc::new(RealOpenXrData::new(&Injector::default()).unwrap());
-        let injector = Injector::default();
-        let system = System::new(xr, &injector);
+        let system = new_system();
 
         let test_prop = |property| {
             let mut err = vr::ETrackedPropertyError::Success;
@@ -861,4 +1229,397 @@ mod tests {
         test_prop(vr::ETrackedDeviceProperty::ManufacturerName_String);
         test_prop(vr::ETrackedDeviceProperty::ControllerType_String);
     }
+
+    #[test]
+    fn input_focus() {
+        let system = new_system();
+        let xr = system.openxr.clone();
+        let set_state = |state| {
+            fakexr::set_session_state(xr.session_data.get().session.as_raw(), state);
+            xr.poll_events();
+        };
+
+        set_state(xr::SessionState::FOCUSED);
+        assert!(system.IsInputAvailable());
+        assert!(!system.ShouldApplicationPause());
+        assert!(!system.ShouldApplicationReduceRenderingWork());
+
+        // Dashboard opened
+        set_state(xr::SessionState::VISIBLE);
+        assert!(!system.IsInputAvailable());
+        assert!(system.ShouldApplicationPause());
+        assert!(system.ShouldApplicationReduceRenderingWork());
+
+        set_state(xr::SessionState::FOCUSED);
+        assert!(system.IsInputAvailable());
+        assert!(!system.ShouldApplicationPause());
+    }
+
+    /// Returns the normalized device depth of a point at the given view space depth.
+    fn ndc_depth(m: &vr::HmdMatrix44_t, z: f32) -> f32 {
+        (m.m[2][2] * z + m.m[2][3]) / (m.m[3][2] * z)
+    }
+
+    #[test]
+    fn hmd_identity() {
+        let system = new_system();
+
+        let string_prop = |prop| {
+            let mut err = vr::ETrackedPropertyError::Success;
+            let mut buf = [0; 64];
+            system.GetStringTrackedDeviceProperty(
+                vr::k_unTrackedDeviceIndex_Hmd,
+                prop,
+                buf.as_mut_ptr(),
+                buf.len() as u32,
+                &mut err,
+            );
+            assert_eq!(err, vr::ETrackedPropertyError::Success, "{prop:?}");
+            unsafe { CStr::from_ptr(buf.as_ptr()) }
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            string_prop(vr::ETrackedDeviceProperty::TrackingSystemName_String),
+            "xrizer"
+        );
+        assert_eq!(
+            string_prop(vr::ETrackedDeviceProperty::DriverVersion_String),
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    #[test]
+    fn projection_depth_range() {
+        let system = new_system();
+        let (near, far) = (0.1, 100.0);
+
+        // SteamVR's depth range is used unless OpenGL's is opted into.
+        let m = system.GetProjectionMatrix(vr::EVREye::Left, near, far);
+        assert!(ndc_depth(&m, -near).abs() < 1e-5);
+        assert!((ndc_depth(&m, -far) - 1.0).abs() < 1e-5);
+
+        system.reverse_z.store(true, Ordering::Relaxed);
+        let m = system.GetProjectionMatrix(vr::EVREye::Left, near, far);
+        assert!((ndc_depth(&m, -near) - 1.0).abs() < 1e-5);
+        assert!(ndc_depth(&m, -far).abs() < 1e-5);
+    }
+
+    #[test]
+    fn projection_plane_clamping() {
+        let system = new_system();
+        assert_eq!(
+            system.depth_clamp,
+            DepthClamp {
+                min_near_z: DEFAULT_MIN_NEAR_Z,
+                max_far_z: DEFAULT_MAX_FAR_Z
+            }
+        );
+
+        let m = system.GetProjectionMatrix(vr::EVREye::Left, 0.0, 100.0);
+        assert!(m.m.iter().flatten().all(|v| v.is_finite()), "{:?}", m.m);
+        assert!(ndc_depth(&m, -DEFAULT_MIN_NEAR_Z).abs() < 1e-5);
+        assert!((ndc_depth(&m, -100.0) - 1.0).abs() < 1e-5);
+
+        let clamp = system.depth_clamp;
+        assert_eq!(clamp.apply(0.1, 100.0), (0.1, 100.0));
+        assert_eq!(clamp.apply(-1.0, f32::INFINITY), (0.001, 100_000.0));
+        assert_eq!(clamp.apply(f32::NAN, 10.0), (0.001, 10.0));
+        // No depth range between the planes
+        assert_eq!(clamp.apply(1.0, 1.0), (0.001, 100_000.0));
+    }
+
+    #[test]
+    fn render_target_scale() {
+        let mut system = new_system();
+        let size = |system: &System| {
+            let (mut width, mut height) = (0, 0);
+            system.GetRecommendedRenderTargetSize(&mut width, &mut height);
+            (width, height)
+        };
+
+        let (width, height) = size(&system);
+        assert!(width > 0 && height > 0);
+
+        system.render_scale = RenderScale {
+            width: 1.5,
+            height: 1.0,
+        };
+        let (scaled_width, scaled_height) = size(&system);
+        assert_eq!(scaled_width, (width as f32 * 1.5).round() as u32);
+        assert_eq!(scaled_height, height);
+    }
+
+    #[test]
+    fn distortion_is_identity() {
+        let system = new_system();
+
+        for (u, v) in [(0.0, 0.0), (0.25, 0.75), (0.5, 0.5), (1.0, 1.0)] {
+            let mut coords = vr::DistortionCoordinates_t::default();
+            assert!(system.ComputeDistortion(vr::EVREye::Right, u, v, &mut coords));
+            assert_eq!(coords.rfRed, [u, v]);
+            assert_eq!(coords.rfGreen, [u, v]);
+            assert_eq!(coords.rfBlue, [u, v]);
+        }
+        assert!(!system.ComputeDistortion(vr::EVREye::Left, 0.5, 0.5, std::ptr::null_mut()));
+    }
+
+    #[test]
+    fn opengl_projection_depth_range() {
+        let mut system = new_system();
+        system.opengl_depth_range = true;
+        let (near, far) = (0.1, 100.0);
+
+        let m = system.GetProjectionMatrix(vr::EVREye::Left, near, far);
+        assert!((ndc_depth(&m, -near) + 1.0).abs() < 1e-5);
+        assert!((ndc_depth(&m, -far) - 1.0).abs() < 1e-5);
+
+        system.reverse_z.store(true, Ordering::Relaxed);
+        let m = system.GetProjectionMatrix(vr::EVREye::Left, near, far);
+        assert!((ndc_depth(&m, -near) - 1.0).abs() < 1e-5);
+        assert!((ndc_depth(&m, -far) + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn controller_roles() {
+        let system = new_system();
+        system.swap_hands.store(false, Ordering::Relaxed);
+
+        let check_roles = |left, right| {
+            assert_eq!(
+                system.GetControllerRoleForTrackedDeviceIndex(Hand::Left as u32),
+                left
+            );
+            assert_eq!(
+                system.GetControllerRoleForTrackedDeviceIndex(Hand::Right as u32),
+                right
+            );
+            assert_eq!(
+                system.GetControllerRoleForTrackedDeviceIndex(vr::k_unTrackedDeviceIndex_Hmd),
+                vr::ETrackedControllerRole::Invalid
+            );
+            assert_eq!(
+                system.GetControllerRoleForTrackedDeviceIndex(vr::k_unTrackedDeviceIndexInvalid),
+                vr::ETrackedControllerRole::Invalid
+            );
+        };
+
+        check_roles(
+            vr::ETrackedControllerRole::LeftHand,
+            vr::ETrackedControllerRole::RightHand,
+        );
+
+        system.swap_hands.store(true, Ordering::Relaxed);
+        check_roles(
+            vr::ETrackedControllerRole::RightHand,
+            vr::ETrackedControllerRole::LeftHand,
+        );
+    }
+
+    #[test]
+    fn display_timing_properties() {
+        let system = new_system();
+        let xr = system.openxr.clone();
+
+        let float_prop = |prop| {
+            let mut err = vr::ETrackedPropertyError::Success;
+            let value = system.GetFloatTrackedDeviceProperty(
+                vr::k_unTrackedDeviceIndex_Hmd,
+                prop,
+                &mut err,
+            );
+            assert_eq!(err, vr::ETrackedPropertyError::Success, "{prop:?}");
+            value
+        };
+
+        assert_eq!(
+            float_prop(vr::ETrackedDeviceProperty::DisplayFrequency_Float),
+            90.0
+        );
+        let vsync_to_photons =
+            float_prop(vr::ETrackedDeviceProperty::SecondsFromVsyncToPhotons_Float);
+        assert!(
+            vsync_to_photons > 0.0 && vsync_to_photons < 0.1,
+            "{vsync_to_photons}"
+        );
+
+        fakexr::set_display_refresh_rate(xr.session_data.get().session.as_raw(), 120.0);
+        assert_eq!(
+            float_prop(vr::ETrackedDeviceProperty::DisplayFrequency_Float),
+            120.0
+        );
+    }
+
+    #[test]
+    fn tracked_property_errors() {
+        let system = new_system();
+
+        let float_prop = |device, prop| {
+            let mut err = vr::ETrackedPropertyError::Success;
+            let value = system.GetFloatTrackedDeviceProperty(device, prop, &mut err);
+            (value, err)
+        };
+
+        let hmd = vr::k_unTrackedDeviceIndex_Hmd;
+        assert_eq!(
+            float_prop(hmd, vr::ETrackedDeviceProperty::DisplayFrequency_Float),
+            (90.0, vr::ETrackedPropertyError::Success)
+        );
+        assert_eq!(
+            float_prop(hmd, vr::ETrackedDeviceProperty::DisplayMCOffset_Float).1,
+            vr::ETrackedPropertyError::UnknownProperty
+        );
+        assert_eq!(
+            float_prop(hmd, vr::ETrackedDeviceProperty::SerialNumber_String).1,
+            vr::ETrackedPropertyError::WrongDataType
+        );
+        assert_eq!(
+            float_prop(
+                vr::k_unTrackedDeviceIndexInvalid,
+                vr::ETrackedDeviceProperty::DisplayFrequency_Float
+            )
+            .1,
+            vr::ETrackedPropertyError::InvalidDevice
+        );
+
+        // Controllers aren't connected yet
+        let mut err = vr::ETrackedPropertyError::Success;
+        system.GetInt32TrackedDeviceProperty(
+            Hand::Left as u32,
+            vr::ETrackedDeviceProperty::Axis0Type_Int32,
+            &mut err,
+        );
+        assert_eq!(err, vr::ETrackedPropertyError::InvalidDevice);
+
+        system.GetBoolTrackedDeviceProperty(
+            hmd,
+            vr::ETrackedDeviceProperty::DeviceIsWireless_Bool,
+            &mut err,
+        );
+        assert_eq!(err, vr::ETrackedPropertyError::UnknownProperty);
+
+        // A null error pointer is allowed
+        system.GetUint64TrackedDeviceProperty(
+            hmd,
+            vr::ETrackedDeviceProperty::SupportedButtons_Uint64,
+            std::ptr::null_mut(),
+        );
+    }
+
+    #[test]
+    fn device_poses_for_all_indices() {
+        let system = new_system();
+
+        // Start from garbage, as an app's uninitialized array might.
+        let garbage = vr::TrackedDevicePose_t {
+            bPoseIsValid: true,
+            bDeviceIsConnected: true,
+            eTrackingResult: vr::ETrackingResult::Running_OK,
+            mDeviceToAbsoluteTracking: vr::HmdMatrix34_t {
+                m: [[f32::NAN; 4]; 3],
+            },
+            ..Default::default()
+        };
+        let mut poses = [garbage; 16];
+        system.GetDeviceToAbsoluteTrackingPose(
+            vr::ETrackingUniverseOrigin::Seated,
+            0.0,
+            poses.as_mut_ptr(),
+            poses.len() as u32,
+        );
+
+        // No controllers are set up, so only the HMD has a pose.
+        for (index, pose) in poses.iter().enumerate().skip(1) {
+            assert!(!pose.bPoseIsValid, "{index}");
+            assert!(!pose.bDeviceIsConnected, "{index}");
+            assert_eq!(
+                pose.eTrackingResult,
+                vr::ETrackingResult::Uninitialized,
+                "{index}"
+            );
+            assert_eq!(pose.mDeviceToAbsoluteTracking.m, [[0.0; 4]; 3], "{index}");
+        }
+
+        // Empty arrays are left alone.
+        system.GetDeviceToAbsoluteTrackingPose(
+            vr::ETrackingUniverseOrigin::Standing,
+            0.0,
+            std::ptr::null_mut(),
+            0,
+        );
+    }
+
+    #[test]
+    fn interaction_profile_change_events() {
+        let system = new_system();
+        let xr = system.openxr.clone();
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of::<vr::VREvent_t>() as u32;
+
+        let set_profile = |profile| {
+            let data = xr.session_data.get();
+            let profile = xr.instance.string_to_path(profile).unwrap();
+            fakexr::set_interaction_profile(
+                data.session.as_raw(),
+                fakexr::UserPath::LeftHand,
+                profile,
+            );
+            data.session.sync_actions(&[]).unwrap();
+            drop(data);
+            xr.poll_events();
+        };
+        xr.session_data
+            .get()
+            .session
+            .attach_action_sets(&[])
+            .unwrap();
+
+        // A new controller is only announced once.
+        set_profile("/interaction_profiles/valve/index_controller");
+        assert!(system.PollNextEvent(&mut event, size));
+        assert_eq!(
+            event.eventType,
+            vr::EVREventType::TrackedDeviceActivated as u32
+        );
+        assert_eq!(event.trackedDeviceIndex, Hand::Left as u32);
+        assert!(!system.PollNextEvent(&mut event, size));
+
+        set_profile("/interaction_profiles/oculus/touch_controller");
+        assert!(system.PollNextEvent(&mut event, size));
+        assert_eq!(event.eventType, vr::EVREventType::PropertyChanged as u32);
+        assert_eq!(event.trackedDeviceIndex, Hand::Left as u32);
+        let property = unsafe { event.data.property };
+        assert_eq!(
+            property.prop,
+            vr::ETrackedDeviceProperty::ControllerType_String
+        );
+        assert!(!system.PollNextEvent(&mut event, size));
+    }
+
+    #[test]
+    fn acknowledge_quit() {
+        let system = new_system();
+        let xr = system.openxr.clone();
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of::<vr::VREvent_t>() as u32;
+        assert!(!system.PollNextEvent(&mut event, size));
+
+        // The runtime stops the session, e.g. when the user quits from its dashboard.
+        let session = xr.session_data.get().session.as_raw();
+        fakexr::set_session_state(session, xr::SessionState::STOPPING);
+        xr.poll_events();
+        assert!(system.PollNextEvent(&mut event, size));
+        assert_eq!(event.eventType, vr::EVREventType::Quit as u32);
+        assert!(!system.PollNextEvent(&mut event, size));
+
+        system.AcknowledgeQuit_Exiting();
+        assert_eq!(xr.session_data.get().state, xr::SessionState::EXITING);
+        assert!(!system.PollNextEvent(&mut event, size));
+
+        // Tearing down doesn't try to end the session again.
+        drop(system);
+        drop(Arc::into_inner(xr).unwrap());
+    }
 }